@@ -6,6 +6,8 @@ use std::env;
 pub struct Config {
     pub server: ServerConfig,
     pub scylla: ScyllaConfig,
+    /// Time allowed to complete in-flight requests after SIGTERM/SIGINT
+    pub shutdown_timeout_secs: u64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -35,6 +37,10 @@ impl Config {
             keyspace: env::var("SCYLLA_KEYSPACE").unwrap_or_else(|_| "orion_cdr".to_string()),
         };
 
-        Ok(Config { server, scylla })
+        let shutdown_timeout_secs = env::var("SHUTDOWN_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "25".to_string())
+            .parse()?;
+
+        Ok(Config { server, scylla, shutdown_timeout_secs })
     }
 }
@@ -80,6 +80,19 @@ impl ApiClient {
         let health = response.json::<HealthStatus>().await?;
         Ok(health)
     }
+
+    /// Normalize sample CDRs on orion-normalization, without publishing
+    pub async fn mapping_dry_run(&self, request: &MappingDryRun) -> Result<serde_json::Value> {
+        let url = format!("{}/mappings/dry-run", self.base_url);
+        let response = self.client.post(&url).json(request).send().await?;
+
+        let status = response.status();
+        let body = response.json::<serde_json::Value>().await?;
+        if !status.is_success() {
+            anyhow::bail!("Mapping dry-run failed: {} {}", status, body["error"].as_str().unwrap_or_default());
+        }
+        Ok(body)
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -92,6 +105,15 @@ pub struct SearchFilters {
     pub limit: Option<usize>,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct MappingDryRun {
+    pub country: String,
+    pub source_system: Option<String>,
+    /// Mappings file content, the deployed profiles when absent
+    pub mappings: Option<String>,
+    pub records: Vec<serde_json::Value>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct HealthStatus {
     pub status: String,
@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use comfy_table::{presets::UTF8_FULL, Cell, CellAlignment, Color, Table};
+use serde_json::Value;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use crate::{Cli, MappingCommands, OutputFormat, api::{ApiClient, MappingDryRun}};
+
+pub async fn execute(action: MappingCommands, cli: &Cli) -> Result<()> {
+    match action {
+        MappingCommands::DryRun {
+            files,
+            country,
+            source_system,
+            profiles,
+            normalization_url,
+            offline,
+            normalization_bin,
+        } => {
+            let mut records = Vec::new();
+            for file in &files {
+                let content = std::fs::read_to_string(file)
+                    .with_context(|| format!("Failed to read {}", file))?;
+                records.extend(read_samples(&content).with_context(|| format!("Invalid samples in {}", file))?);
+            }
+            let mappings = profiles
+                .map(|path| std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path)))
+                .transpose()?;
+
+            let request = MappingDryRun { country, source_system, mappings, records };
+            let result = if offline {
+                dry_run_offline(&normalization_bin, &request).await?
+            } else {
+                ApiClient::new(normalization_url).mapping_dry_run(&request).await?
+            };
+
+            if matches!(cli.format, OutputFormat::Json) {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            } else {
+                show_dry_run(&result);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Dry-run with a local orion-normalization binary, reading its mappings
+/// and reference data from the same environment variables as the service
+async fn dry_run_offline(binary: &str, request: &MappingDryRun) -> Result<Value> {
+    let mut child = Command::new(binary)
+        .arg("dry-run")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", binary))?;
+
+    let mut stdin = child.stdin.take().context("No stdin for orion-normalization")?;
+    stdin.write_all(&serde_json::to_vec(request)?).await?;
+    drop(stdin);
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        anyhow::bail!("Mapping dry-run failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    serde_json::from_slice(&output.stdout).context("Invalid dry-run report")
+}
+
+/// Raw CDRs of a sample file: a JSON object, a JSON array or JSON lines
+fn read_samples(content: &str) -> Result<Vec<Value>> {
+    match serde_json::from_str(content) {
+        Ok(Value::Array(records)) => Ok(records),
+        Ok(record @ Value::Object(_)) => Ok(vec![record]),
+        Ok(_) => anyhow::bail!("Expected a CDR object or an array of CDRs"),
+        Err(_) => content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| serde_json::from_str(line).with_context(|| format!("Line {}", index + 1)))
+            .collect(),
+    }
+}
+
+fn show_dry_run(result: &Value) {
+    let profile = result["profile"].as_str().unwrap_or("?");
+    println!("{}", "╔═══════════════════════════════════════════════════════════════════╗".bright_magenta());
+    println!("{}", format!("║  🧪 Mapping dry-run: profile {:<37}║", profile).bright_magenta());
+    println!("{}", "╚═══════════════════════════════════════════════════════════════════╝".bright_magenta());
+    println!();
+
+    let results = result["results"].as_array().map(Vec::as_slice).unwrap_or_default();
+    for (index, result) in results.iter().enumerate() {
+        let Some(cdr) = result["cdr"].as_object() else {
+            println!("  {} {}", format!("Record {}:", index + 1).bright_red().bold(), result["error"].to_string().bright_red());
+            continue;
+        };
+
+        println!("{}\n", format!("  📄 Record {}", index + 1).bright_cyan().bold());
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .set_header(vec![
+                Cell::new("Field").fg(Color::Cyan).set_alignment(CellAlignment::Left),
+                Cell::new("Value").fg(Color::Cyan).set_alignment(CellAlignment::Left),
+            ]);
+        for (field, value) in cdr.iter().filter(|(_, value)| !value.is_null()) {
+            let value = value.as_str().map_or_else(|| value.to_string(), str::to_string);
+            table.add_row(vec![Cell::new(field).fg(Color::Yellow), Cell::new(value).fg(Color::White)]);
+        }
+        println!("{}", table);
+        for violation in result["violations"].as_array().map(Vec::as_slice).unwrap_or_default() {
+            let line = format!(
+                "  ⚠️  {} ({}): {}",
+                violation["field"].as_str().unwrap_or("?"),
+                violation["severity"].as_str().unwrap_or("?"),
+                violation["message"].as_str().unwrap_or_default()
+            );
+            println!("{}", line.bright_yellow());
+        }
+        println!();
+    }
+    println!("  {} record(s) normalized, nothing published", results.len().to_string().bright_white().bold());
+}
@@ -5,3 +5,4 @@ pub mod logs;
 pub mod fraud;
 pub mod kafka;
 pub mod scylla;
+pub mod mapping;
@@ -103,6 +103,12 @@ enum Commands {
         #[command(subcommand)]
         action: ScyllaCommands,
     },
+
+    /// Normalization mapping profiles
+    Mapping {
+        #[command(subcommand)]
+        action: MappingCommands,
+    },
 }
 
 #[derive(Subcommand, Clone)]
@@ -233,6 +239,41 @@ enum ScyllaCommands {
     },
 }
 
+#[derive(Subcommand, Clone)]
+enum MappingCommands {
+    /// Normalize sample CDRs with the mapping profiles, without publishing
+    DryRun {
+        /// Sample files: a CDR object, an array of CDRs or JSON lines
+        #[arg(required = true)]
+        files: Vec<String>,
+
+        /// Country of the samples (FR, TN, FN, CH)
+        #[arg(long)]
+        country: String,
+
+        /// Source system of the samples
+        #[arg(long)]
+        source_system: Option<String>,
+
+        /// Mappings file to try instead of the deployed one
+        #[arg(long)]
+        profiles: Option<String>,
+
+        /// orion-normalization endpoint URL
+        #[arg(long, default_value = "http://localhost:8083")]
+        normalization_url: String,
+
+        /// Run the orion-normalization binary locally instead of calling
+        /// the service
+        #[arg(long)]
+        offline: bool,
+
+        /// orion-normalization binary used with --offline
+        #[arg(long, default_value = "orion-normalization")]
+        normalization_bin: String,
+    },
+}
+
 #[derive(Clone, clap::ValueEnum)]
 enum DashboardMode {
     Compact,
@@ -301,6 +342,7 @@ async fn main() -> Result<()> {
         Commands::Fraud { action } => commands::fraud::execute(action.clone(), &cli).await?,
         Commands::Kafka { action } => commands::kafka::execute(action.clone(), &cli).await?,
         Commands::Scylla { action } => commands::scylla::execute(action.clone(), &cli).await?,
+        Commands::Mapping { action } => commands::mapping::execute(action.clone(), &cli).await?,
     }
 
     Ok(())
@@ -4,6 +4,8 @@ use std::env;
 pub struct Config {
     pub kafka: KafkaConfig,
     pub server: ServerConfig,
+    /// Time allowed to drain in-flight work after SIGTERM/SIGINT
+    pub shutdown_timeout_secs: u64,
     pub enrichment: EnrichmentConfig,
 }
 
@@ -13,6 +15,30 @@ pub struct KafkaConfig {
     pub input_topic: String,
     pub output_topic: String,
     pub consumer_group: String,
+    pub delivery_mode: DeliveryMode,
+    pub commit_batch_size: usize,
+    pub commit_interval_ms: u64,
+    pub transactional_id: String,
+    pub transaction_timeout_ms: u64,
+}
+
+/// Delivery guarantee for consumed CDRs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryMode {
+    /// Offsets are committed in batches once the downstream produce is acknowledged
+    AtLeastOnce,
+    /// Offsets are committed atomically with the produced records
+    ExactlyOnce,
+}
+
+impl DeliveryMode {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "at_least_once" | "at-least-once" => Ok(Self::AtLeastOnce),
+            "exactly_once" | "exactly-once" => Ok(Self::ExactlyOnce),
+            other => anyhow::bail!("Invalid KAFKA_DELIVERY_MODE: {}", other),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -28,6 +54,10 @@ pub struct EnrichmentConfig {
     pub fraud_agent_url: String,
     pub enable_network_data: bool,
     pub enable_client_data: bool,
+    pub enable_rating: bool,
+    /// Tariff file or directory of tariff files, the bundled tariffs when
+    /// not set
+    pub tariffs_path: Option<String>,
 }
 
 impl Config {
@@ -44,12 +74,42 @@ impl Config {
         let consumer_group = env::var("KAFKA_CONSUMER_GROUP")
             .unwrap_or_else(|_| "orion-enrichment".to_string());
         
+        let delivery_mode = DeliveryMode::parse(
+            &env::var("KAFKA_DELIVERY_MODE").unwrap_or_else(|_| "at_least_once".to_string()),
+        )?;
+
+        let commit_batch_size = env::var("KAFKA_COMMIT_BATCH_SIZE")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<usize>()?;
+
+        let commit_interval_ms = env::var("KAFKA_COMMIT_INTERVAL_MS")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse::<u64>()?;
+
+        // Must be unique per running instance: a new producer with the same
+        // id fences the previous one
+        let transactional_id = env::var("KAFKA_TRANSACTIONAL_ID").unwrap_or_else(|_| {
+            format!(
+                "{}-{}",
+                consumer_group,
+                env::var("HOSTNAME").unwrap_or_else(|_| "0".to_string())
+            )
+        });
+
+        let transaction_timeout_ms = env::var("KAFKA_TRANSACTION_TIMEOUT_MS")
+            .unwrap_or_else(|_| "60000".to_string())
+            .parse::<u64>()?;
+        
         let server_host = env::var("SERVER_HOST")
             .unwrap_or_else(|_| "0.0.0.0".to_string());
         
         let server_port = env::var("SERVER_PORT")
             .unwrap_or_else(|_| "8084".to_string())
             .parse::<u16>()?;
+
+        let shutdown_timeout_secs = env::var("SHUTDOWN_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "25".to_string())
+            .parse::<u64>()?;
         
         let enable_fraud_detection = env::var("ENABLE_FRAUD_DETECTION")
             .unwrap_or_else(|_| "false".to_string())
@@ -68,6 +128,15 @@ impl Config {
             .unwrap_or_else(|_| "true".to_string())
             .parse::<bool>()
             .unwrap_or(true);
+
+        let enable_rating = env::var("ENABLE_RATING")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse::<bool>()
+            .unwrap_or(true);
+
+        let tariffs_path = env::var("ENRICHMENT_TARIFFS_PATH")
+            .ok()
+            .filter(|path| !path.is_empty());
         
         Ok(Config {
             kafka: KafkaConfig {
@@ -75,16 +144,24 @@ impl Config {
                 input_topic,
                 output_topic,
                 consumer_group,
+                delivery_mode,
+                commit_batch_size,
+                commit_interval_ms,
+                transactional_id,
+                transaction_timeout_ms,
             },
             server: ServerConfig {
                 host: server_host,
                 port: server_port,
             },
+            shutdown_timeout_secs,
             enrichment: EnrichmentConfig {
                 enable_fraud_detection,
                 fraud_agent_url,
                 enable_network_data,
                 enable_client_data,
+                enable_rating,
+                tariffs_path,
             },
         })
     }
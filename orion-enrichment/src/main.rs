@@ -6,8 +6,10 @@ mod service;
 use axum::{Router, routing::get};
 use config::Config;
 use metrics_exporter_prometheus::PrometheusBuilder;
+use orion_shutdown::Shutdown;
 use service::KafkaConsumerService;
 use std::net::SocketAddr;
+use std::time::Duration;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
@@ -26,12 +28,14 @@ async fn main() -> anyhow::Result<()> {
     // Load configuration
     let config = Config::from_env()?;
     tracing::info!(
-        "Configuration loaded - Server: {}:{}, Fraud detection: {}, Network data: {}, Client data: {}",
+        "Configuration loaded - Server: {}:{}, Fraud detection: {}, Network data: {}, Client data: {}, Rating: {}, Delivery: {:?}",
         config.server.host,
         config.server.port,
         config.enrichment.enable_fraud_detection,
         config.enrichment.enable_network_data,
         config.enrichment.enable_client_data,
+        config.enrichment.enable_rating,
+        config.kafka.delivery_mode,
     );
 
     // Initialize metrics
@@ -44,9 +48,11 @@ async fn main() -> anyhow::Result<()> {
     let kafka_service = KafkaConsumerService::new(&config.kafka, config.enrichment.clone())?;
     tracing::info!("Kafka consumer service initialized");
 
-    // Spawn Kafka consumer
-    tokio::spawn(async move {
-        if let Err(e) = kafka_service.run().await {
+    // Spawn Kafka consumer, stopped by SIGTERM/SIGINT
+    let shutdown = Shutdown::on_signal();
+    let consumer_shutdown = shutdown.clone();
+    let consumer_handle = tokio::spawn(async move {
+        if let Err(e) = kafka_service.run(consumer_shutdown).await {
             tracing::error!("Kafka consumer error: {}", e);
         }
     });
@@ -63,7 +69,17 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Starting HTTP server on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    let mut server_shutdown = shutdown.clone();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move { server_shutdown.requested().await })
+        .await?;
+
+    // Let the consumer finish its message and commit, within the deadline
+    let timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    if tokio::time::timeout(timeout, consumer_handle).await.is_err() {
+        tracing::warn!("Shutdown deadline of {:?} exceeded, exiting with work in flight", timeout);
+    }
+    tracing::info!("orion-enrichment service stopped");
 
     Ok(())
 }
@@ -16,6 +16,16 @@ pub fn init_metrics() {
         "Total number of fraud cases detected"
     );
     
+    describe_counter!(
+        "orion_enrichment_rated_total",
+        "Total number of CDRs rated, by tariff version"
+    );
+
+    describe_counter!(
+        "orion_enrichment_unrated_total",
+        "Total number of CDRs priced by no tariff plan"
+    );
+    
     describe_histogram!(
         "orion_enrichment_latency_seconds",
         "Enrichment latency in seconds"
@@ -30,6 +40,14 @@ pub fn increment_fraud_detected_total() {
     counter!("orion_enrichment_fraud_detected_total").increment(1);
 }
 
+pub fn increment_rated_total(version: &str) {
+    counter!("orion_enrichment_rated_total", "version" => version.to_string()).increment(1);
+}
+
+pub fn increment_unrated_total() {
+    counter!("orion_enrichment_unrated_total").increment(1);
+}
+
 pub fn record_latency(duration: f64) {
     histogram!("orion_enrichment_latency_seconds").record(duration);
 }
@@ -1,19 +1,36 @@
 use crate::config::EnrichmentConfig;
 use crate::metrics;
 use crate::service::model::*;
+use crate::service::rating::Tariffs;
 use chrono::{Datelike, Utc};
+use std::path::Path;
 use std::time::Instant;
 
 pub struct Enricher {
     config: EnrichmentConfig,
+    tariffs: Tariffs,
 }
 
 impl Enricher {
-    pub fn new(config: EnrichmentConfig) -> Self {
-        Self { config }
+    pub fn new(config: EnrichmentConfig, tariffs: Tariffs) -> Self {
+        Self { config, tariffs }
     }
 
-    pub async fn enrich(&self, unified: UnifiedCDR) -> anyhow::Result<EnrichedCDR> {
+    /// Enricher with the tariffs deployed at `tariffs_path`, else the
+    /// bundled ones
+    pub fn from_config(config: EnrichmentConfig) -> anyhow::Result<Self> {
+        let tariffs = match &config.tariffs_path {
+            Some(path) => {
+                let tariffs = Tariffs::from_path(Path::new(path))?;
+                tracing::info!("Loaded tariff versions {:?} from {}", tariffs.versions(), path);
+                tariffs
+            }
+            None => Tariffs::bundled(),
+        };
+        Ok(Self::new(config, tariffs))
+    }
+
+    pub async fn enrich(&self, mut unified: UnifiedCDR) -> anyhow::Result<EnrichedCDR> {
         let start = Instant::now();
         metrics::increment_messages_total();
 
@@ -45,11 +62,19 @@ impl Enricher {
             None
         };
 
+        // Rating, unless already rated upstream (e.g. by the billing mediation)
+        let rating_info = if self.config.enable_rating && unified.rated_amount.is_none() {
+            self.rate(&mut unified, client_info.as_ref())
+        } else {
+            None
+        };
+
         let enriched = EnrichedCDR {
             unified,
             fraud_info,
             network_info,
             client_info,
+            rating_info,
             enrichment_timestamp: Utc::now().to_rfc3339(),
             enrichment_version: "v1.0.0".to_string(),
         };
@@ -58,6 +83,24 @@ impl Enricher {
         Ok(enriched)
     }
 
+    /// Rate the CDR by the tariff plan of the subscriber's segment, setting
+    /// its amount and currency
+    fn rate(&self, cdr: &mut UnifiedCDR, client_info: Option<&ClientInfo>) -> Option<RatingInfo> {
+        let segment = client_info.map(|client| client.subscriber_segment.as_str());
+        match self.tariffs.rate(cdr, segment) {
+            Some(rating) => {
+                metrics::increment_rated_total(&rating.info.rating_version);
+                cdr.rated_amount = Some(rating.amount);
+                cdr.currency = Some(rating.currency);
+                Some(rating.info)
+            }
+            None => {
+                metrics::increment_unrated_total();
+                None
+            }
+        }
+    }
+
     /// Simple rule-based fraud detection (placeholder for ML model)
     async fn detect_fraud(&self, cdr: &UnifiedCDR) -> FraudInfo {
         let mut fraud_score: f64 = 0.0;
@@ -75,7 +118,8 @@ impl Enricher {
         if cdr.is_roaming {
             // Check if roaming in high-risk country (simplified)
             if let Some(ref visited) = cdr.visited_country {
-                if !["FR", "TN", "FN", "CH", "DE", "ES", "IT"].contains(&visited.as_str()) {
+                // Derived visited countries are ISO codes: FI for Finland
+                if !["FR", "TN", "FN", "FI", "CH", "DE", "ES", "IT"].contains(&visited.as_str()) {
                     fraud_score += 0.4;
                     reasons.push("suspicious_roaming_country".to_string());
                 }
@@ -95,11 +139,9 @@ impl Enricher {
         }
 
         // Rule 4: International calls (high risk)
-        if let Some(ref call_type) = cdr.call_type {
-            if call_type == "international" {
-                fraud_score += 0.2;
-                reasons.push("international_call".to_string());
-            }
+        if cdr.call_type == Some(CallType::International) {
+            fraud_score += 0.2;
+            reasons.push("international_call".to_string());
         }
 
         // Determine risk level
@@ -170,6 +212,7 @@ impl Enricher {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use orion_model::{testing, EventType, ServiceType};
 
     #[tokio::test]
     async fn test_fraud_detection_high_risk() {
@@ -178,44 +221,19 @@ mod tests {
             fraud_agent_url: "http://localhost:50051".to_string(),
             enable_network_data: false,
             enable_client_data: false,
+            enable_rating: false,
+            tariffs_path: None,
         };
         
-        let enricher = Enricher::new(config);
+        let enricher = Enricher::new(config, Tariffs::bundled());
         
-        let cdr = UnifiedCDR {
-            cdr_id: "test-123".to_string(),
-            session_id: None,
-            imsi: "208150123456789".to_string(),
-            msisdn: "+33612345678".to_string(),
-            imei: None,
-            event_type: "data".to_string(),
-            service_type: "roaming".to_string(),
-            start_timestamp: Utc::now(),
-            end_timestamp: None,
-            duration_seconds: Some(10000), // Very long
-            country_code: "FR".to_string(),
-            mcc: Some("208".to_string()),
-            mnc: Some("15".to_string()),
-            lac: None,
-            cell_id: None,
-            calling_number: None,
-            called_number: None,
-            call_type: None,
-            bytes_uploaded: Some(15_000_000_000), // 15GB
-            bytes_downloaded: Some(5_000_000_000),
-            apn: None,
-            sms_type: None,
-            message_length: None,
-            is_roaming: true,
-            visited_country: Some("XX".to_string()), // Suspicious country
-            visited_network: None,
-            charging_id: None,
-            rated_amount: None,
-            currency: None,
-            normalization_timestamp: Utc::now().to_rfc3339(),
-            source_system: "test".to_string(),
-            raw_data_hash: "abc123".to_string(),
-        };
+        let mut cdr = testing::unified_cdr("test-123", EventType::Data);
+        cdr.service_type = ServiceType::Roaming;
+        cdr.duration_seconds = Some(10000); // Very long
+        cdr.bytes_uploaded = Some(15_000_000_000); // 15GB
+        cdr.bytes_downloaded = Some(5_000_000_000);
+        cdr.is_roaming = true;
+        cdr.visited_country = Some("XX".to_string()); // Suspicious country
 
         let fraud_info = enricher.detect_fraud(&cdr).await;
         assert_eq!(fraud_info.risk_level, "high");
@@ -229,44 +247,18 @@ mod tests {
             fraud_agent_url: "http://localhost:50051".to_string(),
             enable_network_data: true,
             enable_client_data: true,
+            enable_rating: true,
+            tariffs_path: None,
         };
         
-        let enricher = Enricher::new(config);
+        let enricher = Enricher::new(config, Tariffs::bundled());
         
-        let cdr = UnifiedCDR {
-            cdr_id: "test-456".to_string(),
-            session_id: None,
-            imsi: "208150123456789".to_string(),
-            msisdn: "+33612345678".to_string(),
-            imei: None,
-            event_type: "voice".to_string(),
-            service_type: "standard".to_string(),
-            start_timestamp: Utc::now(),
-            end_timestamp: None,
-            duration_seconds: Some(120),
-            country_code: "FR".to_string(),
-            mcc: Some("208".to_string()),
-            mnc: Some("15".to_string()),
-            lac: None,
-            cell_id: Some("12345".to_string()),
-            calling_number: Some("+33612345678".to_string()),
-            called_number: Some("+33698765432".to_string()),
-            call_type: Some("mobile".to_string()),
-            bytes_uploaded: None,
-            bytes_downloaded: None,
-            apn: None,
-            sms_type: None,
-            message_length: None,
-            is_roaming: false,
-            visited_country: None,
-            visited_network: None,
-            charging_id: None,
-            rated_amount: None,
-            currency: None,
-            normalization_timestamp: Utc::now().to_rfc3339(),
-            source_system: "test".to_string(),
-            raw_data_hash: "def456".to_string(),
-        };
+        let mut cdr = testing::unified_cdr("test-456", EventType::Voice);
+        cdr.duration_seconds = Some(120);
+        cdr.cell_id = Some("12345".to_string());
+        cdr.calling_number = Some("+33612345678".to_string());
+        cdr.called_number = Some("+33698765432".to_string());
+        cdr.call_type = Some(CallType::Mobile);
 
         let enriched = enricher.enrich(cdr).await.unwrap();
         
@@ -276,5 +268,12 @@ mod tests {
         
         let network = enriched.network_info.unwrap();
         assert_eq!(network.network_name, "Orange France");
+
+        // Subscriber of the Free network (208-15): free national calls
+        let rating = enriched.rating_info.unwrap();
+        assert_eq!(rating.tariff_plan, "fr-free");
+        assert_eq!(rating.tariff_class, "voice_national");
+        assert_eq!(enriched.unified.rated_amount, Some(0.0));
+        assert_eq!(enriched.unified.currency.as_deref(), Some("EUR"));
     }
 }
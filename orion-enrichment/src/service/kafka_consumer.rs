@@ -1,87 +1,269 @@
 use rdkafka::config::ClientConfig;
 use rdkafka::consumer::{Consumer, StreamConsumer};
-use rdkafka::message::Message;
-use crate::config::{KafkaConfig, EnrichmentConfig};
+use rdkafka::message::{BorrowedMessage, Message};
+use rdkafka::Offset;
+use crate::config::{DeliveryMode, KafkaConfig, EnrichmentConfig};
 use crate::service::model::UnifiedCDR;
 use crate::service::enricher::Enricher;
 use crate::service::kafka_producer::KafkaProducerService;
-use tracing::{info, error};
+use orion_kafka::{OffsetCommitter, TransactionBatch};
+use orion_shutdown::Shutdown;
+use std::time::Duration;
+use tracing::{debug, info, error};
+
+/// Time given to the producer to deliver queued records on shutdown
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct KafkaConsumerService {
     consumer: StreamConsumer,
     enricher: Enricher,
     producer: KafkaProducerService,
+    delivery_mode: DeliveryMode,
+    commit_batch_size: usize,
+    commit_interval: Duration,
 }
 
 impl KafkaConsumerService {
     pub fn new(kafka_config: &KafkaConfig, enrichment_config: EnrichmentConfig) -> anyhow::Result<Self> {
+        // Offsets are stored and committed manually once the output is acknowledged
         let consumer: StreamConsumer = ClientConfig::new()
             .set("bootstrap.servers", &kafka_config.brokers)
             .set("group.id", &kafka_config.consumer_group)
-            .set("enable.auto.commit", "true")
+            .set("enable.auto.commit", "false")
+            .set("enable.auto.offset.store", "false")
+            .set("isolation.level", "read_committed")
             .set("auto.offset.reset", "earliest")
             .create()?;
 
         consumer.subscribe(&[&kafka_config.input_topic])?;
 
-        let producer = KafkaProducerService::new(
-            &kafka_config.brokers,
-            kafka_config.output_topic.clone(),
-        )?;
+        let producer = KafkaProducerService::new(kafka_config)?;
 
         Ok(Self {
             consumer,
-            enricher: Enricher::new(enrichment_config),
+            enricher: Enricher::from_config(enrichment_config)?,
             producer,
+            delivery_mode: kafka_config.delivery_mode,
+            commit_batch_size: kafka_config.commit_batch_size,
+            commit_interval: Duration::from_millis(kafka_config.commit_interval_ms),
         })
     }
 
-    pub async fn run(self) -> anyhow::Result<()> {
-        info!("Kafka consumer service started");
+    /// Consume until shutdown is requested. The message in hand is
+    /// finished, then its offsets (or open transaction) are committed.
+    pub async fn run(self, shutdown: Shutdown) -> anyhow::Result<()> {
+        info!("Kafka consumer service started ({:?})", self.delivery_mode);
 
+        match self.delivery_mode {
+            DeliveryMode::AtLeastOnce => {
+                let committer = OffsetCommitter::new(self.commit_batch_size, self.commit_interval);
+                self.run_at_least_once(committer, shutdown).await
+            }
+            DeliveryMode::ExactlyOnce => {
+                let batch = TransactionBatch::new(self.commit_batch_size, self.commit_interval);
+                self.run_exactly_once(batch, shutdown).await
+            }
+        }
+    }
+
+    async fn run_at_least_once(
+        &self,
+        mut committer: OffsetCommitter,
+        mut shutdown: Shutdown,
+    ) -> anyhow::Result<()> {
         loop {
-            match self.consumer.recv().await {
-                Ok(message) => {
-                    if let Some(payload) = message.payload() {
-                        match serde_json::from_slice::<UnifiedCDR>(payload) {
-                            Ok(unified_cdr) => {
-                                info!(
-                                    "Received CDR {} (event: {}, country: {})",
-                                    unified_cdr.cdr_id,
-                                    unified_cdr.event_type,
-                                    unified_cdr.country_code
-                                );
-                                
-                                match self.enricher.enrich(unified_cdr).await {
-                                    Ok(enriched_cdr) => {
-                                        if let Some(ref fraud) = enriched_cdr.fraud_info {
-                                            info!(
-                                                "Fraud check: score={:.2}, risk={}",
-                                                fraud.fraud_score,
-                                                fraud.risk_level
-                                            );
-                                        }
-                                        
-                                        if let Err(e) = self.producer.send(&enriched_cdr).await {
-                                            error!("Failed to send enriched CDR: {}", e);
-                                        }
-                                    }
-                                    Err(e) => {
-                                        error!("Failed to enrich CDR: {}", e);
-                                    }
-                                }
+            let received = tokio::select! {
+                biased;
+                _ = shutdown.requested() => break,
+                received = tokio::time::timeout(committer.interval(), self.consumer.recv()) => received,
+            };
+            match received {
+                // Idle: flush offsets acknowledged since the last commit
+                Err(_) => {
+                    if let Err(e) = committer.commit(&self.consumer) {
+                        error!("Failed to commit offsets: {:?}", e);
+                    }
+                }
+                Ok(Ok(message)) => {
+                    match self.handle_message(&message).await {
+                        Ok(()) => {
+                            if let Err(e) = committer.ack(
+                                &self.consumer,
+                                message.topic(),
+                                message.partition(),
+                                message.offset(),
+                            ) {
+                                error!("Failed to store offset: {:?}", e);
                             }
-                            Err(e) => {
-                                error!("Failed to deserialize UnifiedCDR: {}", e);
+                        }
+                        Err(e) => {
+                            // Output not acknowledged: rewind so the message is redelivered
+                            error!("Failed to send enriched CDR, retrying: {}", e);
+                            tokio::time::sleep(Duration::from_secs(1)).await;
+                            self.rewind(message.topic(), message.partition(), message.offset());
+                        }
+                    }
+                }
+                Ok(Err(e)) => {
+                    error!("Kafka consumer error: {:?}", e);
+                    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                }
+            }
+        }
+
+        info!("Kafka consumer stopped, committing final offsets");
+        if let Err(e) = committer.commit_sync(&self.consumer) {
+            error!("Failed to commit final offsets: {:?}", e);
+        }
+        if let Err(e) = self.producer.flush(FLUSH_TIMEOUT) {
+            error!("Failed to flush producer: {:?}", e);
+        }
+        Ok(())
+    }
+
+    async fn run_exactly_once(
+        &self,
+        mut batch: TransactionBatch,
+        mut shutdown: Shutdown,
+    ) -> anyhow::Result<()> {
+        loop {
+            let received = tokio::select! {
+                biased;
+                _ = shutdown.requested() => break,
+                received = tokio::time::timeout(batch.interval(), self.consumer.recv()) => received,
+            };
+            match received {
+                // Idle: commit the transaction opened before the pause
+                Err(_) => {
+                    if batch.is_open() {
+                        self.commit_transaction(&mut batch).await?;
+                    }
+                }
+                Ok(Ok(message)) => {
+                    if !batch.is_open() {
+                        if let Err(e) = self.producer.begin_transaction() {
+                            error!("Failed to begin transaction: {:?}", e);
+                            tokio::time::sleep(Duration::from_secs(1)).await;
+                            self.rewind(message.topic(), message.partition(), message.offset());
+                            continue;
+                        }
+                        batch.open();
+                    }
+                    batch.record(message.topic(), message.partition(), message.offset());
+
+                    match self.handle_message(&message).await {
+                        Ok(()) => {
+                            if batch.should_commit() {
+                                self.commit_transaction(&mut batch).await?;
                             }
                         }
+                        Err(e) => {
+                            error!("Failed to send enriched CDR, aborting transaction: {}", e);
+                            self.abort_transaction(&mut batch).await?;
+                        }
                     }
                 }
-                Err(e) => {
+                Ok(Err(e)) => {
                     error!("Kafka consumer error: {:?}", e);
                     tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
                 }
             }
         }
+
+        info!("Kafka consumer stopped, committing the open transaction");
+        if batch.is_open() {
+            self.commit_transaction(&mut batch).await?;
+        }
+        Ok(())
+    }
+
+    /// Commit the outputs of the batch together with its input offsets.
+    /// A failed commit aborts the transaction so that the batch is replayed.
+    async fn commit_transaction(&self, batch: &mut TransactionBatch) -> anyhow::Result<()> {
+        let result = batch
+            .commit_offsets()
+            .map_err(anyhow::Error::from)
+            .and_then(|offsets| self.producer.commit_transaction(&self.consumer, &offsets));
+
+        match result {
+            Ok(()) => {
+                debug!("Committed transaction of {} messages", batch.messages());
+                batch.reset();
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to commit transaction: {}", e);
+                self.abort_transaction(batch).await
+            }
+        }
+    }
+
+    /// Abort the open transaction and rewind the consumer to replay it.
+    /// Fails only when the producer can no longer be used (e.g. fenced by
+    /// another instance with the same transactional id).
+    async fn abort_transaction(&self, batch: &mut TransactionBatch) -> anyhow::Result<()> {
+        self.producer
+            .abort_transaction()
+            .map_err(|e| anyhow::anyhow!("Failed to abort transaction: {:?}", e))?;
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        for (topic, partition, offset) in batch.rewind_offsets() {
+            self.rewind(topic, partition, offset);
+        }
+        batch.reset();
+        Ok(())
+    }
+
+    fn rewind(&self, topic: &str, partition: i32, offset: i64) {
+        if let Err(e) = self.consumer.seek(
+            topic,
+            partition,
+            Offset::Offset(offset),
+            Duration::from_secs(5),
+        ) {
+            error!("Failed to rewind partition {}: {:?}", partition, e);
+        }
+    }
+
+    /// Enrich one message and publish the result. Returns an error only
+    /// when the output could not be published; undecodable or unenrichable
+    /// messages are logged and considered handled.
+    async fn handle_message(&self, message: &BorrowedMessage<'_>) -> anyhow::Result<()> {
+        let Some(payload) = message.payload() else {
+            return Ok(());
+        };
+
+        let unified_cdr = match orion_model::from_slice::<UnifiedCDR>(payload) {
+            Ok(cdr) => cdr,
+            Err(e) => {
+                error!("Failed to deserialize UnifiedCDR: {}", e);
+                return Ok(());
+            }
+        };
+
+        info!(
+            "Received CDR {} (event: {}, country: {})",
+            unified_cdr.cdr_id,
+            unified_cdr.event_type.as_str(),
+            unified_cdr.country_code
+        );
+
+        match self.enricher.enrich(unified_cdr).await {
+            Ok(enriched_cdr) => {
+                if let Some(ref fraud) = enriched_cdr.fraud_info {
+                    info!(
+                        "Fraud check: score={:.2}, risk={}",
+                        fraud.fraud_score,
+                        fraud.risk_level
+                    );
+                }
+
+                self.producer.send(&enriched_cdr).await
+            }
+            Err(e) => {
+                error!("Failed to enrich CDR: {}", e);
+                Ok(())
+            }
+        }
     }
 }
@@ -2,5 +2,6 @@ mod kafka_consumer;
 mod kafka_producer;
 mod model;
 mod enricher;
+mod rating;
 
 pub use kafka_consumer::KafkaConsumerService;
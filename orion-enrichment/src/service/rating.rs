@@ -0,0 +1,801 @@
+//! Rating of the CDRs by tariff plans.
+//!
+//! Tariffs are versioned files, each in effect from its date until the
+//! next version. A CDR is rated with the version in effect on its start
+//! date, by the plan of its country, home network and subscriber segment.
+//! The format is documented in `config/tariffs/2026-01.yaml`.
+
+use crate::service::model::{CallType, RatingInfo, UnifiedCDR};
+use anyhow::Context;
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use orion_model::{EventType, RoamingType, SmsType};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Tariffs shipped with the service, used when none are deployed
+const BUNDLED_TARIFFS: &str = include_str!("../../config/tariffs/2026-01.yaml");
+
+/// Zone of the calls to the CDR's country, and of SMS and data at home
+const NATIONAL_ZONE: &str = "national";
+/// Zone of the calls to premium numbers
+const PREMIUM_ZONE: &str = "premium";
+/// Zone of the international calls to a destination of no zone
+const WORLD_ZONE: &str = "world";
+/// Tariff class zone of the free events (emergency, toll-free, received SMS)
+const FREE_ZONE: &str = "free";
+/// Prefix of the zones priced abroad
+const ROAMING_PREFIX: &str = "roaming_";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TariffFile {
+    version: String,
+    effective_from: NaiveDate,
+    #[serde(default)]
+    destination_zones: HashMap<String, Vec<String>>,
+    plans: Vec<PlanSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlanSpec {
+    name: String,
+    country: String,
+    #[serde(default)]
+    networks: Vec<String>,
+    segment: Option<String>,
+    currency: String,
+    peak: Option<PeakSpec>,
+    #[serde(default)]
+    roam_like_at_home: Vec<String>,
+    voice: Option<VoiceSpec>,
+    sms: Option<SmsSpec>,
+    data: Option<DataSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PeakSpec {
+    days: Vec<String>,
+    start: String,
+    end: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VoiceSpec {
+    rounding: Rounding,
+    prices: HashMap<String, Price>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SmsSpec {
+    prices: HashMap<String, Price>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DataSpec {
+    bundle_kb: u64,
+    prices: HashMap<String, Price>,
+}
+
+/// Billing increment of the calls
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Rounding {
+    Second,
+    /// Every started minute is billed
+    Minute,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+enum Price {
+    Flat(f64),
+    Period(PeriodPrice),
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PeriodPrice {
+    peak: f64,
+    off_peak: f64,
+}
+
+/// Tariff versions, by effective date
+pub struct Tariffs {
+    versions: Vec<TariffVersion>,
+}
+
+struct TariffVersion {
+    version: String,
+    effective_from: NaiveDate,
+    /// Destination zone by E.164 prefix, longest prefix first
+    destinations: Vec<(String, String)>,
+    plans: Vec<Plan>,
+}
+
+struct Plan {
+    name: String,
+    country: String,
+    /// Home networks, keyed `mcc-mnc`; any network when empty
+    networks: HashSet<String>,
+    segment: Option<String>,
+    currency: String,
+    peak: Option<Peak>,
+    roam_like_at_home: HashSet<String>,
+    voice: Option<(Rounding, HashMap<String, Price>)>,
+    sms: Option<HashMap<String, Price>>,
+    /// Bundle size in bytes and prices
+    data: Option<(i64, HashMap<String, Price>)>,
+}
+
+/// Peak hours, in the local time of the subscriber
+struct Peak {
+    days: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+/// Rating of a CDR
+#[derive(Debug, Clone)]
+pub struct Rating {
+    pub amount: f64,
+    pub currency: String,
+    pub info: RatingInfo,
+}
+
+impl Tariffs {
+    /// A tariff file, or a directory of tariff files (`.yaml`), one per
+    /// version
+    pub fn from_path(path: &Path) -> anyhow::Result<Self> {
+        let mut files = Vec::new();
+        if path.is_dir() {
+            let entries = std::fs::read_dir(path)
+                .with_context(|| format!("Failed to read tariffs directory {}", path.display()))?;
+            for entry in entries {
+                let file = entry?.path();
+                if matches!(file.extension().and_then(|e| e.to_str()), Some("yaml" | "yml")) {
+                    files.push(file);
+                }
+            }
+            files.sort();
+            if files.is_empty() {
+                anyhow::bail!("No tariff file in {}", path.display());
+            }
+        } else {
+            files.push(path.to_path_buf());
+        }
+
+        let mut contents = Vec::new();
+        for file in &files {
+            let content = std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read tariff file {}", file.display()))?;
+            let tariff: TariffFile = serde_yaml::from_str(&content)
+                .with_context(|| format!("Invalid tariff file {}", file.display()))?;
+            contents.push(tariff);
+        }
+        Self::compile(contents)
+    }
+
+    pub fn from_yaml(contents: &[&str]) -> anyhow::Result<Self> {
+        let files = contents
+            .iter()
+            .map(|content| serde_yaml::from_str(content))
+            .collect::<Result<Vec<TariffFile>, _>>()?;
+        Self::compile(files)
+    }
+
+    pub fn bundled() -> Self {
+        Self::from_yaml(&[BUNDLED_TARIFFS]).expect("bundled tariffs are valid")
+    }
+
+    /// Versions loaded, oldest first
+    pub fn versions(&self) -> Vec<&str> {
+        self.versions.iter().map(|v| v.version.as_str()).collect()
+    }
+
+    fn compile(files: Vec<TariffFile>) -> anyhow::Result<Self> {
+        let mut versions = Vec::new();
+        let mut names = HashSet::new();
+        for file in files {
+            let version = file.version.clone();
+            if !names.insert(version.clone()) {
+                anyhow::bail!("Duplicate tariff version {}", version);
+            }
+            versions.push(
+                TariffVersion::compile(file).with_context(|| format!("Tariff version {}", version))?,
+            );
+        }
+        versions.sort_by_key(|v| v.effective_from);
+        for pair in versions.windows(2) {
+            if pair[0].effective_from == pair[1].effective_from {
+                anyhow::bail!(
+                    "Tariff versions {} and {} are in effect from the same date",
+                    pair[0].version,
+                    pair[1].version
+                );
+            }
+        }
+        Ok(Self { versions })
+    }
+
+    /// Rating of a CDR of a subscriber of `segment`, `None` when no tariff
+    /// prices it: no version in effect, inbound roaming, no plan of the
+    /// country, event or zone not priced, or usage not known
+    pub fn rate(&self, cdr: &UnifiedCDR, segment: Option<&str>) -> Option<Rating> {
+        if cdr.roaming_type == Some(RoamingType::InboundInternational) {
+            return None;
+        }
+        let start = cdr.start_timestamp.date_naive();
+        let version = self.versions.iter().rev().find(|v| v.effective_from <= start)?;
+        let plan = version.plan(cdr, segment)?;
+        let (zone, units, price) = plan.usage(version, cdr)?;
+
+        let (unit_price, period) = match price {
+            None => (0.0, None),
+            Some(Price::Flat(price)) => (price, None),
+            Some(Price::Period(price)) => {
+                let local = cdr
+                    .local_start_timestamp
+                    .unwrap_or_else(|| cdr.start_timestamp.fixed_offset());
+                if plan.is_peak(local.weekday(), local.time()) {
+                    (price.peak, Some("peak"))
+                } else {
+                    (price.off_peak, Some("off_peak"))
+                }
+            }
+        };
+
+        let amount = match cdr.event_type {
+            // Prices of the calls are per minute
+            EventType::Voice => unit_price * units as f64 / 60.0,
+            _ => unit_price * units as f64,
+        };
+
+        let event = match cdr.event_type {
+            EventType::Voice => "voice",
+            EventType::Sms => "sms",
+            EventType::Data => "data",
+            EventType::Unknown => return None,
+        };
+        let tariff_class = match period {
+            Some(period) => format!("{}_{}_{}", event, zone, period),
+            None => format!("{}_{}", event, zone),
+        };
+
+        Some(Rating {
+            amount: round_to_minor_unit(amount, &plan.currency),
+            currency: plan.currency.clone(),
+            info: RatingInfo {
+                tariff_plan: plan.name.clone(),
+                tariff_class,
+                rating_version: version.version.clone(),
+                billed_units: units,
+            },
+        })
+    }
+}
+
+impl TariffVersion {
+    fn compile(file: TariffFile) -> anyhow::Result<Self> {
+        let mut destinations = Vec::new();
+        let mut prefixes = HashSet::new();
+        for (zone, zone_prefixes) in &file.destination_zones {
+            if [NATIONAL_ZONE, PREMIUM_ZONE, WORLD_ZONE, FREE_ZONE].contains(&zone.as_str())
+                || zone.starts_with(ROAMING_PREFIX)
+            {
+                anyhow::bail!("Destination zone {} is a reserved name", zone);
+            }
+            for prefix in zone_prefixes {
+                let digits = prefix.strip_prefix('+').unwrap_or_default();
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    anyhow::bail!("Zone {}: invalid prefix {}, expected +<digits>", zone, prefix);
+                }
+                if !prefixes.insert(prefix.clone()) {
+                    anyhow::bail!("Prefix {} is in several destination zones", prefix);
+                }
+                destinations.push((prefix.clone(), zone.clone()));
+            }
+        }
+        destinations.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+
+        let mut names = HashSet::new();
+        let mut plans = Vec::new();
+        for spec in file.plans {
+            if !names.insert(spec.name.clone()) {
+                anyhow::bail!("Duplicate tariff plan {}", spec.name);
+            }
+            let name = spec.name.clone();
+            let plan = Plan::compile(spec, &file.destination_zones)
+                .with_context(|| format!("Tariff plan {}", name))?;
+            // Equally specific plans would tie in `plan`
+            if let Some(other) = plans.iter().find(|other: &&Plan| plan.overlaps(other)) {
+                anyhow::bail!(
+                    "Tariff plans {} and {} match the same subscribers",
+                    other.name,
+                    plan.name
+                );
+            }
+            plans.push(plan);
+        }
+
+        Ok(Self {
+            version: file.version,
+            effective_from: file.effective_from,
+            destinations,
+            plans,
+        })
+    }
+
+    /// Most specific plan of the CDR's country: matching its home network,
+    /// then its segment, else the country default
+    fn plan(&self, cdr: &UnifiedCDR, segment: Option<&str>) -> Option<&Plan> {
+        let network = match (&cdr.mcc, &cdr.mnc) {
+            (Some(mcc), Some(mnc)) => Some(format!("{}-{}", mcc, mnc)),
+            _ => None,
+        };
+
+        let mut selected: Option<(&Plan, u8)> = None;
+        for plan in &self.plans {
+            if !plan.country.eq_ignore_ascii_case(&cdr.country_code) {
+                continue;
+            }
+            let mut score = 0;
+            if !plan.networks.is_empty() {
+                match &network {
+                    Some(network) if plan.networks.contains(network) => score += 2,
+                    _ => continue,
+                }
+            }
+            if let Some(plan_segment) = &plan.segment {
+                if segment != Some(plan_segment.as_str()) {
+                    continue;
+                }
+                score += 1;
+            }
+            match selected {
+                Some((_, best)) if best >= score => {}
+                _ => selected = Some((plan, score)),
+            }
+        }
+        selected.map(|(plan, _)| plan)
+    }
+
+    /// Destination zone of an international number
+    fn destination(&self, number: &str) -> &str {
+        let number = match number.strip_prefix("00") {
+            Some(rest) => format!("+{}", rest),
+            None => number.to_string(),
+        };
+        self.destinations
+            .iter()
+            .find(|(prefix, _)| number.starts_with(prefix.as_str()))
+            .map_or(WORLD_ZONE, |(_, zone)| zone.as_str())
+    }
+}
+
+impl Plan {
+    fn compile(spec: PlanSpec, destination_zones: &HashMap<String, Vec<String>>) -> anyhow::Result<Self> {
+        if spec.currency.len() != 3 || !spec.currency.bytes().all(|b| b.is_ascii_uppercase()) {
+            anyhow::bail!("Invalid currency {}, expected an ISO 4217 code", spec.currency);
+        }
+
+        let mut networks = HashSet::new();
+        for network in &spec.networks {
+            let valid = network.split_once('-').is_some_and(|(mcc, mnc)| {
+                mcc.len() == 3
+                    && (2..=3).contains(&mnc.len())
+                    && mcc.bytes().chain(mnc.bytes()).all(|b| b.is_ascii_digit())
+            });
+            if !valid {
+                anyhow::bail!("Invalid network {}, expected mcc-mnc", network);
+            }
+            networks.insert(network.clone());
+        }
+
+        let peak = spec.peak.map(Peak::compile).transpose()?;
+
+        let check = |event: &str, prices: &HashMap<String, Price>| -> anyhow::Result<()> {
+            for (zone, price) in prices {
+                let known = [NATIONAL_ZONE, PREMIUM_ZONE, WORLD_ZONE].contains(&zone.as_str())
+                    || zone.starts_with(ROAMING_PREFIX)
+                    || destination_zones.contains_key(zone);
+                if !known {
+                    anyhow::bail!("{} price of unknown zone {}", event, zone);
+                }
+                let (low, by_period) = match price {
+                    Price::Flat(price) => (*price, false),
+                    Price::Period(price) => (price.peak.min(price.off_peak), true),
+                };
+                if low < 0.0 || low.is_nan() {
+                    anyhow::bail!("{} price of zone {} is negative", event, zone);
+                }
+                if by_period && peak.is_none() {
+                    anyhow::bail!("{} price of zone {} is by period, but the plan has no peak hours", event, zone);
+                }
+            }
+            Ok(())
+        };
+
+        let voice = match spec.voice {
+            Some(voice) => {
+                check("Voice", &voice.prices)?;
+                Some((voice.rounding, voice.prices))
+            }
+            None => None,
+        };
+        let sms = match spec.sms {
+            Some(sms) => {
+                check("SMS", &sms.prices)?;
+                Some(sms.prices)
+            }
+            None => None,
+        };
+        let data = match spec.data {
+            Some(data) => {
+                check("Data", &data.prices)?;
+                let bundle = data
+                    .bundle_kb
+                    .checked_mul(1024)
+                    .and_then(|bytes| i64::try_from(bytes).ok())
+                    .filter(|bytes| *bytes > 0);
+                let Some(bundle) = bundle else {
+                    anyhow::bail!("Data bundle_kb must be positive and fit in bytes");
+                };
+                Some((bundle, data.prices))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            name: spec.name,
+            country: spec.country.to_uppercase(),
+            networks,
+            segment: spec.segment,
+            currency: spec.currency,
+            peak,
+            roam_like_at_home: spec.roam_like_at_home.into_iter().collect(),
+            voice,
+            sms,
+            data,
+        })
+    }
+
+    /// Whether both plans select the same subscribers with the same score:
+    /// same country and segment, and both of any network or sharing one
+    fn overlaps(&self, other: &Plan) -> bool {
+        self.country == other.country
+            && self.segment == other.segment
+            && self.networks.is_empty() == other.networks.is_empty()
+            && (self.networks.is_empty() || !self.networks.is_disjoint(&other.networks))
+    }
+
+    /// Zone, billed units and price of a CDR; the price is `None` for free
+    /// events. `None` when not priced by the plan, or when the billed units
+    /// overflow.
+    fn usage(&self, version: &TariffVersion, cdr: &UnifiedCDR) -> Option<(String, i64, Option<Price>)> {
+        // Abroad, out of the zones rated as at home
+        let roaming = match cdr.roaming_type {
+            Some(RoamingType::OutboundInternational) => {
+                let zone = cdr.roaming_zone.as_deref().unwrap_or(WORLD_ZONE);
+                (!self.roam_like_at_home.contains(zone)).then(|| format!("{}{}", ROAMING_PREFIX, zone))
+            }
+            _ => None,
+        };
+
+        match cdr.event_type {
+            EventType::Voice => {
+                let (rounding, prices) = self.voice.as_ref()?;
+                let duration = cdr.duration_seconds.filter(|d| *d >= 0)?;
+                let units = match rounding {
+                    Rounding::Second => duration,
+                    Rounding::Minute => duration.checked_add(59)? / 60 * 60,
+                };
+                if matches!(cdr.call_type, Some(CallType::Emergency | CallType::TollFree)) {
+                    return Some((FREE_ZONE.to_string(), units, None));
+                }
+                let zone = roaming.unwrap_or_else(|| {
+                    match cdr.call_type {
+                        Some(CallType::Premium) => PREMIUM_ZONE,
+                        Some(CallType::International) => cdr
+                            .called_number
+                            .as_deref()
+                            .map_or(WORLD_ZONE, |number| version.destination(number)),
+                        _ => NATIONAL_ZONE,
+                    }
+                    .to_string()
+                });
+                let price = *prices.get(&zone)?;
+                Some((zone, units, Some(price)))
+            }
+            EventType::Sms => {
+                let prices = self.sms.as_ref()?;
+                if cdr.sms_type == Some(SmsType::MtSms) {
+                    return Some((FREE_ZONE.to_string(), 1, None));
+                }
+                let zone = roaming.unwrap_or_else(|| NATIONAL_ZONE.to_string());
+                let price = *prices.get(&zone)?;
+                Some((zone, 1, Some(price)))
+            }
+            EventType::Data => {
+                let (bundle, prices) = self.data.as_ref()?;
+                if cdr.bytes_uploaded.is_none() && cdr.bytes_downloaded.is_none() {
+                    return None;
+                }
+                let bytes = cdr
+                    .bytes_uploaded
+                    .unwrap_or(0)
+                    .checked_add(cdr.bytes_downloaded.unwrap_or(0))?
+                    .max(0);
+                let units = bytes.checked_add(bundle - 1)? / bundle;
+                let zone = roaming.unwrap_or_else(|| NATIONAL_ZONE.to_string());
+                let price = *prices.get(&zone)?;
+                Some((zone, units, Some(price)))
+            }
+            EventType::Unknown => None,
+        }
+    }
+
+    fn is_peak(&self, day: Weekday, time: NaiveTime) -> bool {
+        let Some(peak) = &self.peak else {
+            return false;
+        };
+        if !peak.days.contains(&day) {
+            return false;
+        }
+        if peak.start <= peak.end {
+            peak.start <= time && time < peak.end
+        } else {
+            // Peak hours over midnight
+            time >= peak.start || time < peak.end
+        }
+    }
+}
+
+impl Peak {
+    fn compile(spec: PeakSpec) -> anyhow::Result<Self> {
+        let days = spec
+            .days
+            .iter()
+            .map(|day| day.parse::<Weekday>().map_err(|_| anyhow::anyhow!("Invalid peak day {}", day)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let time = |value: &str| {
+            NaiveTime::parse_from_str(value, "%H:%M")
+                .with_context(|| format!("Invalid peak time {}, expected HH:MM", value))
+        };
+        Ok(Self {
+            days,
+            start: time(&spec.start)?,
+            end: time(&spec.end)?,
+        })
+    }
+}
+
+/// Amount rounded to the minor unit of its currency (ISO 4217)
+fn round_to_minor_unit(amount: f64, currency: &str) -> f64 {
+    let decimals = match currency {
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        "JPY" | "KRW" | "ISK" | "CLP" | "VND" | "XAF" | "XOF" => 0,
+        _ => 2,
+    };
+    let factor = 10f64.powi(decimals);
+    (amount * factor).round() / factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use orion_model::testing;
+
+    fn cdr(country: &str, imsi: &str, event_type: EventType) -> UnifiedCDR {
+        // Tuesday 10:00 UTC
+        let mut cdr = testing::unified_cdr("rating-1", event_type);
+        cdr.imsi = imsi.to_string();
+        cdr.country_code = country.to_string();
+        cdr.mcc = Some(imsi[0..3].to_string());
+        cdr.mnc = Some(imsi[3..5].to_string());
+        cdr
+    }
+
+    fn call(country: &str, imsi: &str, seconds: i64, call_type: CallType, called: &str) -> UnifiedCDR {
+        let mut cdr = cdr(country, imsi, EventType::Voice);
+        cdr.duration_seconds = Some(seconds);
+        cdr.call_type = Some(call_type);
+        cdr.called_number = Some(called.to_string());
+        cdr
+    }
+
+    #[test]
+    fn test_voice_rounding_and_peak_hours() {
+        let tariffs = Tariffs::bundled();
+
+        // Per second, peak: 0.10 EUR/min
+        let mut voice = call("FR", "208010123456789", 90, CallType::Mobile, "+33698765432");
+        let rating = tariffs.rate(&voice, Some("individual")).unwrap();
+        assert_eq!(rating.amount, 0.15);
+        assert_eq!(rating.currency, "EUR");
+        assert_eq!(rating.info.tariff_plan, "fr-individual");
+        assert_eq!(rating.info.tariff_class, "voice_national_peak");
+        assert_eq!(rating.info.billed_units, 90);
+        assert_eq!(rating.info.rating_version, "2026-01");
+
+        // Off-peak in the local time of the subscriber: 21:30 in Paris
+        voice.duration_seconds = Some(120);
+        voice.local_start_timestamp = Some(
+            chrono::FixedOffset::east_opt(3600).unwrap().with_ymd_and_hms(2026, 3, 10, 21, 30, 0).unwrap(),
+        );
+        let rating = tariffs.rate(&voice, None).unwrap();
+        assert_eq!(rating.info.tariff_class, "voice_national_off_peak");
+        assert_eq!(rating.amount, 0.1);
+
+        // Per started minute, in TND with 3 decimals: 61s billed 120s
+        let voice = call("TN", "605020123456789", 61, CallType::Mobile, "+21698765432");
+        let rating = tariffs.rate(&voice, None).unwrap();
+        assert_eq!(rating.info.billed_units, 120);
+        assert_eq!(rating.amount, 0.24);
+        assert_eq!(rating.currency, "TND");
+    }
+
+    #[test]
+    fn test_destination_zones_and_free_calls() {
+        let tariffs = Tariffs::bundled();
+
+        let voice = call("FR", "208010123456789", 60, CallType::International, "+21698765432");
+        let rating = tariffs.rate(&voice, None).unwrap();
+        assert_eq!(rating.info.tariff_class, "voice_maghreb");
+        assert_eq!(rating.amount, 0.45);
+
+        // Longest prefix: +358 (Finland, eu) rather than +35 of no zone
+        let voice = call("FR", "208010123456789", 60, CallType::International, "00358401234567");
+        assert_eq!(tariffs.rate(&voice, None).unwrap().info.tariff_class, "voice_eu");
+
+        let voice = call("FR", "208010123456789", 60, CallType::International, "+15551234567");
+        assert_eq!(tariffs.rate(&voice, None).unwrap().info.tariff_class, "voice_world");
+
+        let voice = call("FR", "208010123456789", 600, CallType::Emergency, "112");
+        let rating = tariffs.rate(&voice, None).unwrap();
+        assert_eq!(rating.info.tariff_class, "voice_free");
+        assert_eq!(rating.amount, 0.0);
+    }
+
+    #[test]
+    fn test_plan_by_network_and_segment() {
+        let tariffs = Tariffs::bundled();
+        let voice = call("FR", "208150123456789", 60, CallType::Mobile, "+33698765432");
+
+        // Free network plan before the business segment plan
+        assert_eq!(tariffs.rate(&voice, Some("business")).unwrap().info.tariff_plan, "fr-free");
+
+        let voice = call("FR", "208010123456789", 60, CallType::Mobile, "+33698765432");
+        assert_eq!(tariffs.rate(&voice, Some("business")).unwrap().info.tariff_plan, "fr-business");
+        assert_eq!(tariffs.rate(&voice, Some("individual")).unwrap().info.tariff_plan, "fr-individual");
+
+        // No plan of the country
+        let voice = call("DE", "262010123456789", 60, CallType::Mobile, "+49151234567");
+        assert!(tariffs.rate(&voice, None).is_none());
+    }
+
+    #[test]
+    fn test_roaming_sms_and_data_bundles() {
+        let tariffs = Tariffs::bundled();
+
+        // 2.5 MB of data: 3 bundles of 1 MB
+        let mut data = cdr("FR", "208010123456789", EventType::Data);
+        data.bytes_uploaded = Some(500 * 1024);
+        data.bytes_downloaded = Some(2 * 1024 * 1024);
+        let rating = tariffs.rate(&data, None).unwrap();
+        assert_eq!(rating.info.tariff_class, "data_national");
+        assert_eq!(rating.info.billed_units, 3);
+        assert_eq!(rating.amount, 0.03);
+
+        // Roam like at home in the EU, not elsewhere
+        data.roaming_type = Some(RoamingType::OutboundInternational);
+        data.roaming_zone = Some("eu".to_string());
+        assert_eq!(tariffs.rate(&data, None).unwrap().info.tariff_class, "data_national");
+        data.roaming_zone = Some("world".to_string());
+        let rating = tariffs.rate(&data, None).unwrap();
+        assert_eq!(rating.info.tariff_class, "data_roaming_world");
+        assert_eq!(rating.amount, 15.0);
+
+        let mut sms = cdr("CH", "228010123456789", EventType::Sms);
+        sms.sms_type = Some(SmsType::MoSms);
+        sms.roaming_type = Some(RoamingType::OutboundInternational);
+        sms.roaming_zone = Some("world".to_string());
+        let rating = tariffs.rate(&sms, None).unwrap();
+        assert_eq!(rating.info.tariff_class, "sms_roaming_world");
+        assert_eq!((rating.amount, rating.currency.as_str()), (0.6, "CHF"));
+
+        sms.sms_type = Some(SmsType::MtSms);
+        assert_eq!(tariffs.rate(&sms, None).unwrap().amount, 0.0);
+
+        // Foreign subscribers are rated by their home operator
+        sms.roaming_type = Some(RoamingType::InboundInternational);
+        assert!(tariffs.rate(&sms, None).is_none());
+    }
+
+    #[test]
+    fn test_overflowing_usage_is_unrated() {
+        let tariffs = Tariffs::bundled();
+
+        let voice = call("TN", "605020123456789", i64::MAX, CallType::Mobile, "+21698765432");
+        assert!(tariffs.rate(&voice, None).is_none());
+
+        let mut data = cdr("FR", "208010123456789", EventType::Data);
+        data.bytes_uploaded = Some(i64::MAX);
+        data.bytes_downloaded = Some(1);
+        assert!(tariffs.rate(&data, None).is_none());
+
+        data.bytes_downloaded = None;
+        assert!(tariffs.rate(&data, None).is_none());
+    }
+
+    #[test]
+    fn test_version_in_effect_at_start() {
+        let next = BUNDLED_TARIFFS
+            .replace("version: \"2026-01\"", "version: \"2026-07\"")
+            .replace("effective_from: 2026-01-01", "effective_from: 2026-07-01")
+            .replace("national: { peak: 0.10, off_peak: 0.05 }", "national: { peak: 0.12, off_peak: 0.05 }");
+        let tariffs = Tariffs::from_yaml(&[&next, BUNDLED_TARIFFS]).unwrap();
+        assert_eq!(tariffs.versions(), vec!["2026-01", "2026-07"]);
+
+        let mut voice = call("FR", "208010123456789", 60, CallType::Mobile, "+33698765432");
+        assert_eq!(tariffs.rate(&voice, None).unwrap().info.rating_version, "2026-01");
+
+        voice.start_timestamp = Utc.with_ymd_and_hms(2026, 7, 7, 10, 0, 0).unwrap();
+        let rating = tariffs.rate(&voice, None).unwrap();
+        assert_eq!(rating.info.rating_version, "2026-07");
+        assert_eq!(rating.amount, 0.12);
+
+        // Before the first version
+        voice.start_timestamp = Utc.with_ymd_and_hms(2025, 12, 31, 10, 0, 0).unwrap();
+        assert!(tariffs.rate(&voice, None).is_none());
+    }
+
+    #[test]
+    fn test_tariffs_reject_invalid_files() {
+        let file = |plan: &str| format!("version: v1\neffective_from: 2026-01-01\nplans:\n  - {}\n", plan);
+        let valid = file("{name: a, country: FR, currency: EUR, sms: {prices: {national: 0.1}}}");
+        assert!(Tariffs::from_yaml(&[&valid]).is_ok());
+        assert!(Tariffs::from_yaml(&[&valid, &valid]).is_err());
+
+        // Plans selected with the same score
+        let plans = |a: &str, b: &str| {
+            let yaml = format!(
+                "version: v1\neffective_from: 2026-01-01\nplans:\n  - {{name: a, country: FR, currency: EUR, {}}}\n  - {{name: b, country: fr, currency: EUR, {}}}\n",
+                a, b
+            );
+            Tariffs::from_yaml(&[&yaml])
+        };
+        assert!(plans("segment: pro", "segment: pro").is_err());
+        assert!(plans("networks: [208-01, 208-10]", "networks: [208-10]").is_err());
+        assert!(plans("sms: {prices: {national: 0.1}}", "sms: {prices: {national: 0.2}}").is_err());
+        assert!(plans("segment: pro", "segment: individual").is_ok());
+        assert!(plans("networks: [208-01]", "networks: [208-10]").is_ok());
+        assert!(plans("networks: [208-01]", "networks: [208-01], segment: pro").is_ok());
+
+        for plan in [
+            "{name: a, country: FR, currency: euro}",
+            "{name: a, country: FR, currency: EUR, networks: [20801]}",
+            "{name: a, country: FR, currency: EUR, sms: {prices: {national: -0.1}}}",
+            "{name: a, country: FR, currency: EUR, sms: {prices: {nationl: 0.1}}}",
+            "{name: a, country: FR, currency: EUR, sms: {prices: {national: {peak: 0.2, off_peak: 0.1}}}}",
+            "{name: a, country: FR, currency: EUR, data: {bundle_kb: 0, prices: {national: 0.1}}}",
+            "{name: a, country: FR, currency: EUR, data: {bundle_kb: 18446744073709551615, prices: {national: 0.1}}}",
+            "{name: a, country: FR, currency: EUR, peak: {days: [mon], start: '8h', end: '20:00'}}",
+        ] {
+            assert!(Tariffs::from_yaml(&[&file(plan)]).is_err(), "{}", plan);
+        }
+    }
+
+    #[test]
+    fn test_round_to_minor_unit() {
+        assert_eq!(round_to_minor_unit(0.1234, "EUR"), 0.12);
+        assert_eq!(round_to_minor_unit(0.12351, "TND"), 0.124);
+        assert_eq!(round_to_minor_unit(12.6, "JPY"), 13.0);
+    }
+}
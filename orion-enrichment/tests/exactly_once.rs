@@ -0,0 +1,73 @@
+//! Exactly-once integration tests, run by the shared `orion_kafka::testing`
+//! scenarios against a single-node Kafka broker in a container. They need a
+//! Docker daemon:
+//!
+//! ```bash
+//! cargo test --test exactly_once -- --ignored
+//! ```
+
+use orion_kafka::testing::{self, Service};
+
+/// 20 unified voice CDRs
+fn service() -> Service {
+    let inputs = (0..20)
+        .map(|i| {
+            serde_json::json!({
+                "cdr_id": format!("cdr-{}", i),
+                "session_id": null,
+                "imsi": format!("2081501234{:05}", i),
+                "msisdn": "+33612345678",
+                "imei": null,
+                "event_type": "voice",
+                "service_type": "voice",
+                "start_timestamp": "2026-01-29T10:00:00Z",
+                "end_timestamp": null,
+                "duration_seconds": 60,
+                "country_code": "FR",
+                "mcc": "208",
+                "mnc": "15",
+                "lac": null,
+                "cell_id": null,
+                "calling_number": "+33612345678",
+                "called_number": "+33698765432",
+                "call_type": "national",
+                "bytes_uploaded": null,
+                "bytes_downloaded": null,
+                "apn": null,
+                "sms_type": null,
+                "message_length": null,
+                "is_roaming": false,
+                "visited_country": null,
+                "visited_network": null,
+                "charging_id": null,
+                "rated_amount": null,
+                "currency": null,
+                "normalization_timestamp": "2026-01-29T10:00:02Z",
+                "source_system": "orion-ingestion",
+                "raw_data_hash": "a1b2c3d4e5f6",
+            })
+            .to_string()
+        })
+        .collect();
+
+    Service {
+        binary: env!("CARGO_BIN_EXE_orion-enrichment"),
+        name: "orion-enrichment-it",
+        env: vec![("KAFKA_OUTPUT_TOPIC", "cdr.stored")],
+        input_topic: "cdr.enriched",
+        inputs,
+        outputs: vec![("cdr.stored", 20)],
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires a Docker daemon"]
+async fn test_restart_does_not_duplicate_outputs() {
+    testing::restart_does_not_duplicate_outputs(&service()).await;
+}
+
+#[tokio::test]
+#[ignore = "requires a Docker daemon"]
+async fn test_crash_mid_transaction_replays_without_duplicates() {
+    testing::crash_mid_transaction_replays_without_duplicates(&service()).await;
+}
@@ -0,0 +1,143 @@
+//! Sustained throughput benchmark of orion-ingestion against a local broker.
+//!
+//! Produces `BENCH_MESSAGES` JSON CDRs on the source topic as fast as the
+//! broker accepts them, and counts the CDRs of this run coming out of the
+//! output topic. Throughput is reported every few seconds and once all
+//! CDRs went through the running service.
+//!
+//! ```bash
+//! docker-compose up -d kafka
+//! KAFKA_WORKERS=8 cargo run --release &
+//! BENCH_MESSAGES=2000000 cargo run --release --example throughput
+//! ```
+
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::message::Message;
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use rdkafka::{Offset, TopicPartitionList};
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn env_or(name: &str, default: &str) -> String {
+    env::var(name).unwrap_or_else(|_| default.to_string())
+}
+
+fn rate(count: u64, elapsed: Duration) -> f64 {
+    count as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let brokers = env_or("BENCH_BROKERS", "localhost:9092");
+    let source_topic = env_or("BENCH_TOPIC", "cdr.raw.FR");
+    let output_topic = env_or("BENCH_OUTPUT_TOPIC", "cdr.validated");
+    let messages: u64 = env_or("BENCH_MESSAGES", "1000000").parse()?;
+    let timeout = Duration::from_secs(env_or("BENCH_TIMEOUT_SECS", "600").parse()?);
+    let run_id = format!("bench-{}", chrono::Utc::now().timestamp_millis());
+
+    // Read the output topic from its current end, so only this run is counted
+    let consumer: StreamConsumer = ClientConfig::new()
+        .set("bootstrap.servers", &brokers)
+        .set("group.id", &run_id)
+        .set("enable.auto.commit", "false")
+        .create()?;
+    let metadata = consumer.fetch_metadata(Some(&output_topic), Duration::from_secs(10))?;
+    let mut assignment = TopicPartitionList::new();
+    for topic in metadata.topics() {
+        for partition in topic.partitions() {
+            assignment.add_partition_offset(&output_topic, partition.id(), Offset::End)?;
+        }
+    }
+    consumer.assign(&assignment)?;
+
+    let received = Arc::new(AtomicU64::new(0));
+    let counter = Arc::clone(&received);
+    let marker = run_id.clone();
+    tokio::spawn(async move {
+        while let Ok(message) = consumer.recv().await {
+            let ours = message
+                .payload()
+                .is_some_and(|payload| payload.windows(marker.len()).any(|w| w == marker.as_bytes()));
+            if ours {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    });
+
+    println!("📊 orion-ingestion throughput benchmark");
+    println!("  Run:     {}", run_id);
+    println!("  Source:  {} → {}", source_topic, output_topic);
+    println!("  CDRs:    {}", messages);
+
+    let producer: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", &brokers)
+        .set("linger.ms", "5")
+        .set("compression.type", "lz4")
+        .create()?;
+
+    let start = Instant::now();
+    let mut last_report = Instant::now();
+    for i in 0..messages {
+        let imsi = format!("20815{:010}", i % 1_000_000);
+        let payload = format!(
+            r#"{{"imsi":"{}","msisdn":"+336{:08}","event_type":"data","bytes_up":1024,"bytes_down":4096,"bench_run":"{}","seq":{}}}"#,
+            imsi,
+            i % 100_000_000,
+            run_id,
+            i
+        );
+        loop {
+            let record = FutureRecord::to(&source_topic).key(&imsi).payload(&payload);
+            match producer.send_result(record) {
+                // Acknowledgements are not awaited: the output topic is the measure
+                Ok(_) => break,
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), _)) => {
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+                Err((e, _)) => anyhow::bail!("Failed to produce: {:?}", e),
+            }
+        }
+
+        if last_report.elapsed() >= Duration::from_secs(5) {
+            let done = received.load(Ordering::Relaxed);
+            println!(
+                "  produced {:>10} ({:>9.0}/s) | ingested {:>10} ({:>9.0}/s)",
+                i + 1,
+                rate(i + 1, start.elapsed()),
+                done,
+                rate(done, start.elapsed())
+            );
+            last_report = Instant::now();
+        }
+    }
+    producer.flush(Duration::from_secs(30))?;
+    let produced_in = start.elapsed();
+    println!("  ✓ {} CDRs produced in {:.1?} ({:.0}/s)", messages, produced_in, rate(messages, produced_in));
+
+    loop {
+        let done = received.load(Ordering::Relaxed);
+        if done >= messages {
+            break;
+        }
+        if start.elapsed() > timeout {
+            anyhow::bail!("Timed out after {:?} with {}/{} CDRs ingested", timeout, done, messages);
+        }
+        if last_report.elapsed() >= Duration::from_secs(5) {
+            println!("  ingested {:>10} ({:>9.0}/s)", done, rate(done, start.elapsed()));
+            last_report = Instant::now();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let elapsed = start.elapsed();
+    let per_second = rate(messages, elapsed);
+    println!();
+    println!("=== Sustained throughput ===");
+    println!("  {} CDRs in {:.1?}", messages, elapsed);
+    println!("  {:.0} CDR/s — {:.2}M CDR/min", per_second, per_second * 60.0 / 1_000_000.0);
+    Ok(())
+}
@@ -3,8 +3,17 @@ use std::env;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
+    pub sources: Vec<SourceKind>,
     pub kafka: KafkaConfig,
+    pub file: FileSourceConfig,
+    pub http_ingest: HttpIngestConfig,
     pub server: ServerConfig,
+    /// JSON file describing the delimited (CSV) formats of each topic
+    pub delimited_formats_path: String,
+    /// JSON routing table (topic -> country, source system, format)
+    pub routing_table_path: String,
+    /// Time allowed to drain in-flight work after SIGTERM/SIGINT
+    pub shutdown_timeout_secs: u64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -13,6 +22,96 @@ pub struct KafkaConfig {
     pub group_id: String,
     pub topics: Vec<String>,
     pub auto_offset_reset: String,
+    pub delivery_mode: DeliveryMode,
+    pub commit_batch_size: usize,
+    pub commit_interval_ms: u64,
+    pub output_topic: String,
+    pub dlt_topic: String,
+    pub quarantine_topic: String,
+    pub max_message_bytes: usize,
+    pub producer_max_in_flight: u32,
+    pub producer_max_retries: u32,
+    pub producer_timeout_ms: u64,
+    pub producer_linger_ms: u64,
+    /// Parallel workers; each source partition is handled by one of them
+    pub workers: usize,
+    /// Messages queued per worker before polling pauses
+    pub worker_queue_size: usize,
+    /// CDRs a worker publishes before waiting for acknowledgements
+    pub worker_max_pending: usize,
+    /// Headers carried from the source message into the CDR and its output record
+    pub trace_id_header: String,
+    pub producer_id_header: String,
+}
+
+/// Delivery guarantee for consumed CDRs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryMode {
+    /// Offsets are committed in batches once the downstream produce is acknowledged
+    AtLeastOnce,
+    /// Offsets are committed atomically with the produced records
+    ExactlyOnce,
+}
+
+impl DeliveryMode {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "at_least_once" | "at-least-once" => Ok(Self::AtLeastOnce),
+            "exactly_once" | "exactly-once" => Ok(Self::ExactlyOnce),
+            other => anyhow::bail!("Invalid KAFKA_DELIVERY_MODE: {}", other),
+        }
+    }
+}
+
+/// Where raw CDRs are read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    /// `cdr.raw.*` Kafka topics
+    Kafka,
+    /// Files dropped in a local directory
+    File,
+}
+
+impl SourceKind {
+    /// Parse a comma-separated list such as `kafka,file`
+    pub fn parse_list(value: &str) -> anyhow::Result<Vec<Self>> {
+        let mut sources = Vec::new();
+        for name in value.split(',').map(|s| s.trim().to_lowercase()) {
+            let source = match name.as_str() {
+                "kafka" => Self::Kafka,
+                "file" => Self::File,
+                other => anyhow::bail!("Invalid INGESTION_SOURCES entry: {}", other),
+            };
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+        Ok(sources)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileSourceConfig {
+    /// Files are dropped in `<input_dir>/<topic>/`
+    pub input_dir: String,
+    pub archive_dir: String,
+    pub quarantine_dir: String,
+    pub checkpoint_path: String,
+    pub poll_interval_ms: u64,
+    /// Largest file, before and after decompression
+    pub max_file_bytes: usize,
+}
+
+/// Limits of `POST /ingest/{country}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpIngestConfig {
+    /// Request body limit, also applied to decompressed gzip bodies
+    pub max_body_bytes: usize,
+    pub max_records: usize,
+    /// Records accepted but not yet published
+    pub queue_size: usize,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -23,12 +122,134 @@ pub struct ServerConfig {
 
 impl Config {
     pub fn from_env() -> anyhow::Result<Self> {
+        let sources = SourceKind::parse_list(
+            &env::var("INGESTION_SOURCES").unwrap_or_else(|_| "kafka".to_string()),
+        )?;
+
         let kafka_brokers = env::var("KAFKA_BROKERS")
             .unwrap_or_else(|_| "localhost:9092".to_string());
         
         let kafka_topics = env::var("KAFKA_TOPICS")
             .unwrap_or_else(|_| "cdr.raw.FR,cdr.raw.TN,cdr.raw.FN,cdr.raw.CH".to_string());
         
+        let output_topic = env::var("KAFKA_OUTPUT_TOPIC")
+            .unwrap_or_else(|_| "cdr.validated".to_string());
+
+        let dlt_topic = env::var("KAFKA_DLT_TOPIC")
+            .unwrap_or_else(|_| "cdr.raw.dlt".to_string());
+
+        let quarantine_topic = env::var("KAFKA_QUARANTINE_TOPIC")
+            .unwrap_or_else(|_| "cdr.raw.quarantine".to_string());
+
+        let max_message_bytes = env::var("KAFKA_MAX_MESSAGE_BYTES")
+            .unwrap_or_else(|_| "1048576".to_string())
+            .parse()
+            .unwrap_or(1_048_576);
+
+        let delivery_mode = DeliveryMode::parse(
+            &env::var("KAFKA_DELIVERY_MODE").unwrap_or_else(|_| "at_least_once".to_string()),
+        )?;
+        if delivery_mode == DeliveryMode::ExactlyOnce {
+            anyhow::bail!("KAFKA_DELIVERY_MODE=exactly_once is not supported by orion-ingestion");
+        }
+
+        let commit_batch_size = env::var("KAFKA_COMMIT_BATCH_SIZE")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse()
+            .unwrap_or(100);
+
+        let commit_interval_ms = env::var("KAFKA_COMMIT_INTERVAL_MS")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse()
+            .unwrap_or(1000);
+
+        let producer_max_in_flight = env::var("KAFKA_PRODUCER_MAX_IN_FLIGHT")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .unwrap_or(5);
+
+        let producer_max_retries = env::var("KAFKA_PRODUCER_MAX_RETRIES")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse()
+            .unwrap_or(3);
+
+        let producer_timeout_ms = env::var("KAFKA_PRODUCER_TIMEOUT_MS")
+            .unwrap_or_else(|_| "5000".to_string())
+            .parse()
+            .unwrap_or(5000);
+
+        let producer_linger_ms = env::var("KAFKA_PRODUCER_LINGER_MS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .unwrap_or(5);
+
+        let workers = env::var("KAFKA_WORKERS")
+            .unwrap_or_else(|_| "4".to_string())
+            .parse()
+            .unwrap_or(4);
+
+        let worker_queue_size = env::var("KAFKA_WORKER_QUEUE_SIZE")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse()
+            .unwrap_or(1000);
+
+        let worker_max_pending = env::var("KAFKA_WORKER_MAX_PENDING")
+            .unwrap_or_else(|_| "500".to_string())
+            .parse()
+            .unwrap_or(500);
+
+        let trace_id_header = env::var("KAFKA_TRACE_ID_HEADER")
+            .unwrap_or_else(|_| "traceparent".to_string());
+
+        let producer_id_header = env::var("KAFKA_PRODUCER_ID_HEADER")
+            .unwrap_or_else(|_| "x-producer-id".to_string());
+
+        let delimited_formats_path = env::var("DELIMITED_FORMATS_PATH")
+            .unwrap_or_else(|_| "formats/delimited.json".to_string());
+
+        let file_input_dir = env::var("FILE_SOURCE_INPUT_DIR")
+            .unwrap_or_else(|_| "/var/lib/orion/inbox".to_string());
+
+        let file_archive_dir = env::var("FILE_SOURCE_ARCHIVE_DIR")
+            .unwrap_or_else(|_| "/var/lib/orion/archive".to_string());
+
+        let file_quarantine_dir = env::var("FILE_SOURCE_QUARANTINE_DIR")
+            .unwrap_or_else(|_| "/var/lib/orion/quarantine".to_string());
+
+        let file_checkpoint_path = env::var("FILE_SOURCE_CHECKPOINT_PATH")
+            .unwrap_or_else(|_| "/var/lib/orion/file-checkpoint.json".to_string());
+
+        let file_poll_interval_ms = env::var("FILE_SOURCE_POLL_INTERVAL_MS")
+            .unwrap_or_else(|_| "5000".to_string())
+            .parse()
+            .unwrap_or(5000);
+
+        let file_max_bytes = env::var("FILE_SOURCE_MAX_FILE_BYTES")
+            .unwrap_or_else(|_| "268435456".to_string())
+            .parse()
+            .unwrap_or(268_435_456);
+
+        let routing_table_path = env::var("ROUTING_TABLE_PATH")
+            .unwrap_or_else(|_| "config/routing.json".to_string());
+
+        let http_max_body_bytes = env::var("HTTP_INGEST_MAX_BODY_BYTES")
+            .unwrap_or_else(|_| "10485760".to_string())
+            .parse()
+            .unwrap_or(10_485_760);
+
+        let http_max_records = env::var("HTTP_INGEST_MAX_RECORDS")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse()
+            .unwrap_or(1000);
+
+        let http_queue_size = env::var("HTTP_INGEST_QUEUE_SIZE")
+            .unwrap_or_else(|_| "10000".to_string())
+            .parse()
+            .unwrap_or(10_000);
+        if http_max_records > http_queue_size {
+            anyhow::bail!("HTTP_INGEST_MAX_RECORDS must not exceed HTTP_INGEST_QUEUE_SIZE");
+        }
+
         let server_host = env::var("SERVER_HOST")
             .unwrap_or_else(|_| "0.0.0.0".to_string());
         
@@ -37,17 +258,55 @@ impl Config {
             .parse()
             .unwrap_or(8081);
 
+        let shutdown_timeout_secs = env::var("SHUTDOWN_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "25".to_string())
+            .parse()
+            .unwrap_or(25);
+
         Ok(Config {
+            sources,
             kafka: KafkaConfig {
                 brokers: kafka_brokers,
                 group_id: "orion-ingestion-group".to_string(),
                 topics: kafka_topics.split(',').map(|s| s.trim().to_string()).collect(),
                 auto_offset_reset: "earliest".to_string(),
+                delivery_mode,
+                commit_batch_size,
+                commit_interval_ms,
+                output_topic,
+                dlt_topic,
+                quarantine_topic,
+                max_message_bytes,
+                producer_max_in_flight,
+                producer_max_retries,
+                producer_timeout_ms,
+                producer_linger_ms,
+                workers,
+                worker_queue_size,
+                worker_max_pending,
+                trace_id_header,
+                producer_id_header,
+            },
+            file: FileSourceConfig {
+                input_dir: file_input_dir,
+                archive_dir: file_archive_dir,
+                quarantine_dir: file_quarantine_dir,
+                checkpoint_path: file_checkpoint_path,
+                poll_interval_ms: file_poll_interval_ms,
+                max_file_bytes: file_max_bytes,
+            },
+            http_ingest: HttpIngestConfig {
+                max_body_bytes: http_max_body_bytes,
+                max_records: http_max_records,
+                queue_size: http_queue_size,
             },
             server: ServerConfig {
                 host: server_host,
                 port: server_port,
             },
+            delimited_formats_path,
+            routing_table_path,
+            shutdown_timeout_secs,
         })
     }
 }
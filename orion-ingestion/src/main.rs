@@ -4,18 +4,28 @@ mod routes;
 mod service;
 
 use axum::{
-    routing::get,
+    extract::DefaultBodyLimit,
+    routing::{get, post},
     Router,
 };
 use metrics_exporter_prometheus::PrometheusBuilder;
+use orion_kafka::OffsetCommitter;
+use orion_shutdown::Shutdown;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::task::JoinSet;
 use tower_http::trace::TraceLayer;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::config::Config;
-use crate::service::{KafkaConsumerService, ProcessedCDR};
+use crate::config::{Config, SourceKind};
+use crate::service::{
+    DeadLetterReplayer, DelimitedFormats, FileSource, HttpIngestor, KafkaConsumerService,
+    KafkaProducerService, KafkaWorker, MessageProcessor, ProcessedCDR, RoutingTable,
+    SourceOffset, WorkerPool,
+};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -33,6 +43,10 @@ async fn main() -> anyhow::Result<()> {
     // Load configuration
     let config = Config::from_env()?;
     info!("Configuration loaded: {:?}", config);
+    info!("Sources: {:?}", config.sources);
+    info!("Delivery mode: {:?}", config.kafka.delivery_mode);
+
+    let shutdown = Shutdown::on_signal();
 
     // Initialize metrics
     let prometheus_handle = PrometheusBuilder::new()
@@ -40,36 +54,188 @@ async fn main() -> anyhow::Result<()> {
     metrics::init_metrics();
     info!("✅ Metrics initialized");
 
-    // Create output channel for processed CDR
-    let (output_tx, mut output_rx) = mpsc::channel::<ProcessedCDR>(1000);
+    // Load delimited formats: without them, non-JSON text is forwarded as-is
+    let formats = if std::path::Path::new(&config.delimited_formats_path).exists() {
+        let formats = DelimitedFormats::from_json(&config.delimited_formats_path)?;
+        info!(
+            "✅ Delimited formats loaded from {} ({} topics)",
+            config.delimited_formats_path,
+            formats.len()
+        );
+        formats
+    } else {
+        warn!("No delimited formats file at {}", config.delimited_formats_path);
+        DelimitedFormats::default()
+    };
 
-    // Initialize Kafka consumer
-    let kafka_service = KafkaConsumerService::new(&config.kafka, output_tx)?;
-    info!("✅ Kafka consumer initialized");
+    // Load the routing table, falling back to the one shipped with the service
+    let routing = if std::path::Path::new(&config.routing_table_path).exists() {
+        RoutingTable::from_json(&config.routing_table_path)?
+    } else {
+        warn!("No routing table at {}, using the bundled one", config.routing_table_path);
+        RoutingTable::bundled()
+    };
+    info!("✅ Routing table loaded ({} routes)", routing.len());
 
-    // Spawn Kafka consumer task
-    let kafka_handle = tokio::spawn(async move {
-        if let Err(e) = kafka_service.run().await {
-            error!("Kafka consumer error: {:?}", e);
-        }
+    let processor = Arc::new(MessageProcessor::new(
+        config.kafka.max_message_bytes,
+        formats,
+        routing,
+    ));
+
+    // Initialize Kafka producer
+    let kafka_producer = Arc::new(KafkaProducerService::new(&config.kafka)?);
+    info!(
+        "✅ Kafka producer initialized (output topic: {}, dead-letter topic: {}, quarantine topic: {})",
+        config.kafka.output_topic, config.kafka.dlt_topic, config.kafka.quarantine_topic
+    );
+
+    let replay_state = Arc::new(routes::ReplayState {
+        producer: Arc::clone(&kafka_producer),
+        replayer: DeadLetterReplayer::new(
+            &config.kafka.brokers,
+            &config.kafka.dlt_topic,
+            &format!("{}-dlt-replay", config.kafka.group_id),
+        ),
+    });
+
+    let quarantine_replay_state = Arc::new(routes::ReplayState {
+        producer: Arc::clone(&kafka_producer),
+        replayer: DeadLetterReplayer::new(
+            &config.kafka.brokers,
+            &config.kafka.quarantine_topic,
+            &format!("{}-quarantine-replay", config.kafka.group_id),
+        ),
     });
 
-    // Spawn output handler (for now, just log)
-    // In production, this would publish to the next Kafka topic
-    let output_handle = tokio::spawn(async move {
-        while let Some(processed_cdr) = output_rx.recv().await {
-            info!(
-                "📤 Processed CDR ready for validation - Country: {}, Topic: {}",
-                processed_cdr.country,
-                processed_cdr.source_topic
-            );
-            // TODO: Publish to Kafka topic "cdr.validated"
+    let mut sources = JoinSet::new();
+
+    if config.sources.contains(&SourceKind::Kafka) {
+        // Offsets of published CDRs, acknowledged by the workers
+        let (offsets_tx, mut offsets_rx) = mpsc::channel::<SourceOffset>(
+            config.kafka.workers * config.kafka.worker_max_pending.max(1),
+        );
+
+        // Partition-aware worker pool: partitions are processed concurrently,
+        // messages of one partition in order
+        let pool = WorkerPool::new(
+            config.kafka.workers,
+            config.kafka.worker_queue_size,
+            |_, queue| {
+                KafkaWorker {
+                    processor: Arc::clone(&processor),
+                    producer: Arc::clone(&kafka_producer),
+                    offsets: offsets_tx.clone(),
+                    max_pending: config.kafka.worker_max_pending,
+                    trace_id_header: config.kafka.trace_id_header.clone(),
+                    producer_id_header: config.kafka.producer_id_header.clone(),
+                }
+                .run(queue)
+            },
+        );
+        drop(offsets_tx);
+
+        // Initialize Kafka consumer
+        let kafka_service = KafkaConsumerService::new(&config.kafka, pool)?;
+        let consumer = kafka_service.consumer();
+        info!("✅ Kafka consumer initialized ({} workers)", config.kafka.workers);
+
+        // Spawn Kafka consumer task
+        let consumer_shutdown = shutdown.clone();
+        sources.spawn(async move {
+            if let Err(e) = kafka_service.run(consumer_shutdown).await {
+                error!("Kafka consumer error: {:?}", e);
+            }
+            info!("Kafka consumer task exited");
+        });
+
+        // Spawn offset committer: acknowledged offsets are stored and
+        // committed in batches (at-least-once)
+        let mut committer = OffsetCommitter::new(
+            config.kafka.commit_batch_size,
+            Duration::from_millis(config.kafka.commit_interval_ms),
+        );
+        sources.spawn(async move {
+            loop {
+                let source = match tokio::time::timeout(committer.interval(), offsets_rx.recv()).await {
+                    Ok(Some(source)) => source,
+                    // Workers drained: commit what they acknowledged last
+                    Ok(None) => {
+                        if let Err(e) = committer.commit_sync(consumer.as_ref()) {
+                            error!("Failed to commit final offsets: {:?}", e);
+                        }
+                        break;
+                    }
+                    // Idle: flush offsets acknowledged since the last commit
+                    Err(_) => {
+                        if let Err(e) = committer.commit(consumer.as_ref()) {
+                            error!("Failed to commit offsets: {:?}", e);
+                        }
+                        continue;
+                    }
+                };
+
+                if let Err(e) = committer.ack(
+                    consumer.as_ref(),
+                    &source.topic,
+                    source.partition,
+                    source.offset,
+                ) {
+                    error!("Failed to store offset: {:?}", e);
+                }
+            }
+            info!("Offset committer task exited");
+        });
+    }
+
+    // HTTP push ingestion: accepted records are queued for a dedicated publisher
+    let (http_tx, mut http_rx) = mpsc::channel::<ProcessedCDR>(config.http_ingest.queue_size);
+    let ingestor = Arc::new(HttpIngestor::new(&config.http_ingest, Arc::clone(&processor), http_tx));
+    let http_producer = Arc::clone(&kafka_producer);
+    sources.spawn(async move {
+        while let Some(processed_cdr) = http_rx.recv().await {
+            while let Err(e) = http_producer.send(&processed_cdr).await {
+                error!(
+                    "Failed to publish pushed CDR (country: {}), retrying: {}",
+                    processed_cdr.country, e
+                );
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
         }
+        info!("HTTP publisher task exited");
     });
 
+    if config.sources.contains(&SourceKind::File) {
+        let file_source = FileSource::new(
+            &config.file,
+            Arc::clone(&processor),
+            Arc::clone(&kafka_producer),
+        )?;
+        info!("✅ File source initialized");
+
+        let file_shutdown = shutdown.clone();
+        sources.spawn(async move {
+            if let Err(e) = file_source.run(file_shutdown).await {
+                error!("File source error: {:?}", e);
+            }
+            info!("File source task exited");
+        });
+    }
+
     // Build HTTP server with routes
     let app = Router::new()
         .route("/health", get(routes::health))
+        .route("/dlt/replay", post(routes::replay_dead_letters).with_state(replay_state))
+        .route(
+            "/quarantine/replay",
+            post(routes::replay_dead_letters).with_state(quarantine_replay_state),
+        )
+        .route(
+            "/ingest/:country",
+            post(routes::ingest)
+                .layer(DefaultBodyLimit::max(ingestor.max_body_bytes()))
+                .with_state(ingestor),
+        )
         .route("/metrics", get(move || async move {
             prometheus_handle.render()
         }))
@@ -83,24 +249,54 @@ async fn main() -> anyhow::Result<()> {
     info!("🌐 HTTP server listening on {}", addr);
     info!("  - Health: http://{}/health", addr);
     info!("  - Metrics: http://{}/metrics", addr);
+    info!("  - Ingest: http://{}/ingest/{{country}}", addr);
 
-    // Start HTTP server
+    // Start HTTP server: on shutdown, in-flight requests are completed and
+    // dropping the router closes the HTTP publisher queue
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    let server_handle = tokio::spawn(async move {
-        if let Err(e) = axum::serve(listener, app).await {
+    let mut server_shutdown = shutdown.clone();
+    let mut server_handle = tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app)
+            .with_graceful_shutdown(async move { server_shutdown.requested().await })
+            .await
+        {
             error!("HTTP server error: {:?}", e);
         }
     });
 
     info!("✅ ORION Ingestion Service fully operational");
 
-    // Wait for all tasks
+    // Run until a shutdown signal, or until a task stops on its own
+    let mut signal = shutdown.clone();
     tokio::select! {
-        _ = kafka_handle => error!("Kafka consumer task exited"),
-        _ = output_handle => error!("Output handler task exited"),
-        _ = server_handle => error!("HTTP server task exited"),
+        _ = signal.requested() => {}
+        Some(_) = sources.join_next() => {
+            error!("A source task exited unexpectedly");
+            return Ok(());
+        }
+        _ = &mut server_handle => {
+            error!("HTTP server task exited");
+            return Ok(());
+        }
+    }
+
+    // Drain: sources stop taking work and hand over what they hold, then
+    // queued messages are flushed, all within the shutdown deadline
+    let timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    let deadline = tokio::time::Instant::now() + timeout;
+    let drained = tokio::time::timeout_at(deadline, async {
+        let _ = server_handle.await;
+        while sources.join_next().await.is_some() {}
+    })
+    .await;
+    if drained.is_err() {
+        warn!("Shutdown deadline of {:?} exceeded, exiting with work in flight", timeout);
+    }
+    let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+    if let Err(e) = kafka_producer.flush(remaining) {
+        warn!("Failed to flush Kafka producer: {:?}", e);
     }
 
+    info!("👋 ORION Ingestion Service stopped");
     Ok(())
 }
-
@@ -13,6 +13,54 @@ pub fn init_metrics() {
         "orion_ingestion_bytes_total",
         "Total bytes ingested"
     );
+    describe_counter!(
+        "orion_ingestion_published_total",
+        "Total number of CDRs published to the output topic"
+    );
+    describe_counter!(
+        "orion_ingestion_publish_errors_total",
+        "Total number of CDRs that could not be published"
+    );
+    describe_counter!(
+        "orion_ingestion_publish_retries_total",
+        "Total number of publish retries"
+    );
+    describe_counter!(
+        "orion_ingestion_dead_letters_total",
+        "Total number of messages sent to the dead-letter topic, by reason"
+    );
+    describe_counter!(
+        "orion_ingestion_dead_letters_replayed_total",
+        "Total number of dead-lettered messages replayed to their source topic"
+    );
+    describe_counter!(
+        "orion_ingestion_files_total",
+        "Total number of files ingested by the file source"
+    );
+    describe_counter!(
+        "orion_ingestion_file_records_total",
+        "Total number of records read from ingested files"
+    );
+    describe_counter!(
+        "orion_ingestion_files_quarantined_total",
+        "Total number of files moved to quarantine"
+    );
+    describe_counter!(
+        "orion_ingestion_http_records_total",
+        "Total number of records pushed over HTTP, by status"
+    );
+    describe_counter!(
+        "orion_ingestion_http_throttled_total",
+        "Total number of HTTP batches refused because the output queue was full"
+    );
+    describe_counter!(
+        "orion_ingestion_decompressed_total",
+        "Total number of compressed payloads inflated, by codec"
+    );
+    describe_counter!(
+        "orion_ingestion_worker_queue_full_total",
+        "Total number of times the consumer waited for a full worker queue"
+    );
     describe_histogram!(
         "orion_ingestion_latency_seconds",
         "Ingestion processing latency in seconds"
@@ -34,3 +82,49 @@ pub fn record_bytes(bytes: u64) {
 pub fn record_latency(duration_secs: f64) {
     histogram!("orion_ingestion_latency_seconds").record(duration_secs);
 }
+
+pub fn record_published() {
+    counter!("orion_ingestion_published_total").increment(1);
+}
+
+pub fn record_publish_error() {
+    counter!("orion_ingestion_publish_errors_total").increment(1);
+}
+
+pub fn record_publish_retry() {
+    counter!("orion_ingestion_publish_retries_total").increment(1);
+}
+
+pub fn record_dead_letter(reason: &'static str) {
+    counter!("orion_ingestion_dead_letters_total", "reason" => reason).increment(1);
+}
+
+pub fn record_dead_letter_replayed() {
+    counter!("orion_ingestion_dead_letters_replayed_total").increment(1);
+}
+
+pub fn record_file(records: u64) {
+    counter!("orion_ingestion_files_total").increment(1);
+    counter!("orion_ingestion_file_records_total").increment(records);
+}
+
+pub fn record_file_quarantined() {
+    counter!("orion_ingestion_files_quarantined_total").increment(1);
+}
+
+pub fn record_http_records(accepted: u64, rejected: u64) {
+    counter!("orion_ingestion_http_records_total", "status" => "accepted").increment(accepted);
+    counter!("orion_ingestion_http_records_total", "status" => "rejected").increment(rejected);
+}
+
+pub fn record_http_throttled() {
+    counter!("orion_ingestion_http_throttled_total").increment(1);
+}
+
+pub fn record_decompressed(codec: &'static str) {
+    counter!("orion_ingestion_decompressed_total", "codec" => codec).increment(1);
+}
+
+pub fn record_worker_queue_full() {
+    counter!("orion_ingestion_worker_queue_full_total").increment(1);
+}
@@ -1,7 +1,28 @@
 use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
     response::{IntoResponse, Response},
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
+    Json,
 };
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::service::{DeadLetterReplayer, HttpIngestor, IngestError, KafkaProducerService};
+
+/// Records replayed per call when `max` is not given
+const DEFAULT_REPLAY_MAX: usize = 10_000;
+
+pub struct ReplayState {
+    pub producer: Arc<KafkaProducerService>,
+    pub replayer: DeadLetterReplayer,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReplayParams {
+    pub max: Option<usize>,
+}
 
 /// Health check endpoint
 /// Returns 200 OK with "OK" body
@@ -17,3 +38,52 @@ pub async fn metrics_handler() -> Response {
     // But we keep it here for completeness
     (StatusCode::OK, "Metrics endpoint").into_response()
 }
+
+/// Dead-letter replay endpoint
+/// Re-publishes pending dead-lettered messages to their source topic
+pub async fn replay_dead_letters(
+    State(state): State<Arc<ReplayState>>,
+    Query(params): Query<ReplayParams>,
+) -> Response {
+    let max = params.max.unwrap_or(DEFAULT_REPLAY_MAX);
+    match state
+        .replayer
+        .replay(&state.producer, max, Duration::from_secs(10))
+        .await
+    {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Push ingestion endpoint
+/// Accepts a single CDR, an NDJSON batch (`Content-Type: application/x-ndjson`)
+/// or a gzip body, and returns the outcome of each record
+pub async fn ingest(
+    State(ingestor): State<Arc<HttpIngestor>>,
+    Path(country): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    let gzip = headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("gzip"));
+
+    match ingestor.ingest(&country, content_type, gzip, &body).await {
+        Ok(report) => (StatusCode::ACCEPTED, Json(report)).into_response(),
+        Err(e @ IngestError::Busy) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, "1")],
+            e.to_string(),
+        )
+            .into_response(),
+        Err(e @ IngestError::TooLarge(_)) => {
+            (StatusCode::PAYLOAD_TOO_LARGE, e.to_string()).into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
@@ -0,0 +1,429 @@
+//! Decoder for 3GPP TS 32.298 charging records encoded in BER.
+//!
+//! Each supported `CallEventRecord` alternative is mapped to the flat JSON
+//! payload understood by orion-validation and orion-normalization
+//! (`imsi`, `msisdn`, `event_type`, `timestamp`, ...). Fields absent from
+//! the record are omitted; unknown fields are ignored.
+
+use crate::service::ber::{self, BerError, TagClass, Tlv};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Asn1Error {
+    #[error("malformed BER: {0}")]
+    Ber(#[from] BerError),
+    #[error("unsupported record [{0}]")]
+    UnsupportedRecord(u32),
+    #[error("{0} bytes after the end of the record")]
+    TrailingData(usize),
+}
+
+/// `CallEventRecord` alternatives decoded by orion-ingestion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Asn1RecordType {
+    #[serde(rename = "MOCallRecord")]
+    MoCall,
+    #[serde(rename = "MTCallRecord")]
+    MtCall,
+    #[serde(rename = "SMSMORecord")]
+    SmsMo,
+    #[serde(rename = "SMSMTRecord")]
+    SmsMt,
+    #[serde(rename = "PGWRecord")]
+    Pgw,
+}
+
+impl Asn1RecordType {
+    /// Alternative of a `CallEventRecord` CHOICE tag. roamingRecord [2],
+    /// written by the GMSC for a call forwarded to a roaming number, is
+    /// not a subscriber event and is rejected like the other alternatives.
+    fn from_tag(tag: u32) -> Option<Self> {
+        match tag {
+            0 => Some(Self::MoCall),
+            1 => Some(Self::MtCall),
+            6 => Some(Self::SmsMo),
+            7 => Some(Self::SmsMt),
+            79 => Some(Self::Pgw),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MoCall => "MOCallRecord",
+            Self::MtCall => "MTCallRecord",
+            Self::SmsMo => "SMSMORecord",
+            Self::SmsMt => "SMSMTRecord",
+            Self::Pgw => "PGWRecord",
+        }
+    }
+}
+
+/// Cheap check used to route binary payloads to the decoder: every
+/// `CallEventRecord` alternative is a context-specific constructed tag
+pub fn looks_like_ber(payload: &[u8]) -> bool {
+    matches!(payload.first(), Some(0xA0..=0xBF))
+}
+
+/// Split a CDR file into its concatenated records. Filler bytes (0x00 or
+/// 0xFF) padding blocks between records are skipped.
+pub fn split_records(data: &[u8]) -> Result<Vec<&[u8]>, Asn1Error> {
+    let mut records = Vec::new();
+    let mut cursor = data;
+    loop {
+        let start = cursor.iter().position(|&b| b != 0x00 && b != 0xFF);
+        let Some(start) = start else {
+            return Ok(records);
+        };
+        cursor = &cursor[start..];
+        let (_, rest) = Tlv::parse(cursor)?;
+        records.push(&cursor[..cursor.len() - rest.len()]);
+        cursor = rest;
+    }
+}
+
+/// Decode one BER-encoded `CallEventRecord`
+pub fn decode(payload: &[u8]) -> Result<(Asn1RecordType, Value), Asn1Error> {
+    let (record, rest) = Tlv::parse(payload)?;
+    if !rest.is_empty() {
+        return Err(Asn1Error::TrailingData(rest.len()));
+    }
+    let record_type = match (record.class, record.constructed) {
+        (TagClass::ContextSpecific, true) => Asn1RecordType::from_tag(record.tag),
+        _ => None,
+    }
+    .ok_or(Asn1Error::UnsupportedRecord(record.tag))?;
+
+    let fields = Fields::new(&record)?;
+    let mut out = Map::new();
+    out.insert("record_type".to_string(), record_type.as_str().into());
+
+    match record_type {
+        Asn1RecordType::MoCall => {
+            out.insert("event_type".to_string(), "voice".into());
+            fields.tbcd(&mut out, "imsi", 1)?;
+            fields.tbcd(&mut out, "imei", 2)?;
+            fields.address(&mut out, "msisdn", 3)?;
+            fields.address(&mut out, "calling_number", 4)?;
+            fields.address(&mut out, "called_number", 5)?;
+            fields.location(&mut out, 12)?;
+            fields.first_timestamp(&mut out, "timestamp", &[23, 22])?;
+            fields.first_timestamp(&mut out, "end_timestamp", &[24])?;
+            fields.integer(&mut out, "duration", 25)?;
+            fields.integer(&mut out, "cause_for_termination", 30)?;
+            if !out.contains_key("calling_number") {
+                if let Some(msisdn) = out.get("msisdn").cloned() {
+                    out.insert("calling_number".to_string(), msisdn);
+                }
+            }
+        }
+        Asn1RecordType::MtCall => {
+            out.insert("event_type".to_string(), "voice".into());
+            fields.tbcd(&mut out, "imsi", 1)?;
+            fields.tbcd(&mut out, "imei", 2)?;
+            fields.address(&mut out, "msisdn", 3)?;
+            fields.address(&mut out, "calling_number", 4)?;
+            fields.location(&mut out, 9)?;
+            fields.first_timestamp(&mut out, "timestamp", &[20, 19])?;
+            fields.first_timestamp(&mut out, "end_timestamp", &[21])?;
+            fields.integer(&mut out, "duration", 22)?;
+            fields.integer(&mut out, "cause_for_termination", 27)?;
+            if let Some(msisdn) = out.get("msisdn").cloned() {
+                out.insert("called_number".to_string(), msisdn);
+            }
+        }
+        Asn1RecordType::SmsMo => {
+            out.insert("event_type".to_string(), "sms".into());
+            out.insert("sms_type".to_string(), "mo".into());
+            fields.tbcd(&mut out, "imsi", 1)?;
+            fields.tbcd(&mut out, "imei", 2)?;
+            fields.address(&mut out, "msisdn", 3)?;
+            fields.address(&mut out, "service_centre", 5)?;
+            fields.location(&mut out, 7)?;
+            fields.first_timestamp(&mut out, "timestamp", &[9])?;
+            if let Some(value) = fields.get(12) {
+                out.insert("called_number".to_string(), ber::decode_tp_address(value)?.into());
+            }
+            if let Some(msisdn) = out.get("msisdn").cloned() {
+                out.insert("calling_number".to_string(), msisdn);
+            }
+        }
+        Asn1RecordType::SmsMt => {
+            out.insert("event_type".to_string(), "sms".into());
+            out.insert("sms_type".to_string(), "mt".into());
+            fields.address(&mut out, "service_centre", 1)?;
+            fields.tbcd(&mut out, "imsi", 3)?;
+            fields.tbcd(&mut out, "imei", 4)?;
+            fields.address(&mut out, "msisdn", 5)?;
+            fields.location(&mut out, 7)?;
+            fields.first_timestamp(&mut out, "timestamp", &[8])?;
+            if let Some(msisdn) = out.get("msisdn").cloned() {
+                out.insert("called_number".to_string(), msisdn);
+            }
+        }
+        Asn1RecordType::Pgw => {
+            out.insert("event_type".to_string(), "data".into());
+            fields.tbcd(&mut out, "imsi", 3)?;
+            fields.integer(&mut out, "charging_id", 5)?;
+            if let Some(value) = fields.get(7) {
+                out.insert("apn".to_string(), String::from_utf8_lossy(value).into());
+            }
+            fields.first_timestamp(&mut out, "timestamp", &[13])?;
+            fields.integer(&mut out, "duration", 14)?;
+            fields.integer(&mut out, "cause_for_record_closing", 15)?;
+            fields.integer(&mut out, "record_sequence_number", 17)?;
+            fields.address(&mut out, "msisdn", 22)?;
+            fields.tbcd(&mut out, "imei", 29)?;
+            fields.integer(&mut out, "rat_type", 30)?;
+            if let Some(volumes) = fields.constructed(12) {
+                let (uplink, downlink) = traffic_volumes(volumes)?;
+                out.insert("bytes_uploaded".to_string(), uplink.into());
+                out.insert("bytes_downloaded".to_string(), downlink.into());
+            }
+        }
+    }
+
+    Ok((record_type, Value::Object(out)))
+}
+
+/// Sum of uplink [3] and downlink [4] volumes over all
+/// `ChangeOfCharCondition` containers
+fn traffic_volumes(list: &Tlv<'_>) -> Result<(i64, i64), BerError> {
+    let (mut uplink, mut downlink) = (0i64, 0i64);
+    for container in list.children()? {
+        for field in container.children()? {
+            let total = match field.tag {
+                3 => &mut uplink,
+                4 => &mut downlink,
+                _ => continue,
+            };
+            *total = total
+                .checked_add(ber::decode_integer(field.value)?)
+                .ok_or(BerError::InvalidValue("traffic volume"))?;
+        }
+    }
+    Ok((uplink, downlink))
+}
+
+/// Top-level fields of a record, indexed by context tag
+struct Fields<'a> {
+    by_tag: HashMap<u32, Tlv<'a>>,
+}
+
+impl<'a> Fields<'a> {
+    fn new(record: &Tlv<'a>) -> Result<Self, BerError> {
+        let mut by_tag = HashMap::new();
+        for field in record.children()? {
+            if field.class == TagClass::ContextSpecific {
+                by_tag.entry(field.tag).or_insert(field);
+            }
+        }
+        Ok(Self { by_tag })
+    }
+
+    fn get(&self, tag: u32) -> Option<&'a [u8]> {
+        self.by_tag
+            .get(&tag)
+            .filter(|field| !field.constructed)
+            .map(|field| field.value)
+    }
+
+    fn constructed(&self, tag: u32) -> Option<&Tlv<'a>> {
+        self.by_tag.get(&tag).filter(|field| field.constructed)
+    }
+
+    fn tbcd(&self, out: &mut Map<String, Value>, key: &str, tag: u32) -> Result<(), BerError> {
+        if let Some(value) = self.get(tag) {
+            out.insert(key.to_string(), ber::decode_tbcd(value)?.into());
+        }
+        Ok(())
+    }
+
+    fn address(&self, out: &mut Map<String, Value>, key: &str, tag: u32) -> Result<(), BerError> {
+        if let Some(value) = self.get(tag) {
+            out.insert(key.to_string(), ber::decode_address_string(value)?.into());
+        }
+        Ok(())
+    }
+
+    fn integer(&self, out: &mut Map<String, Value>, key: &str, tag: u32) -> Result<(), BerError> {
+        if let Some(value) = self.get(tag) {
+            out.insert(key.to_string(), ber::decode_integer(value)?.into());
+        }
+        Ok(())
+    }
+
+    /// First present timestamp among `tags`, as RFC 3339
+    fn first_timestamp(
+        &self,
+        out: &mut Map<String, Value>,
+        key: &str,
+        tags: &[u32],
+    ) -> Result<(), BerError> {
+        if let Some(value) = tags.iter().find_map(|tag| self.get(*tag)) {
+            out.insert(key.to_string(), ber::decode_timestamp(value)?.to_rfc3339().into());
+        }
+        Ok(())
+    }
+
+    /// `LocationAreaAndCell`: locationAreaCode [0] and cellId [1], two octets each
+    fn location(&self, out: &mut Map<String, Value>, tag: u32) -> Result<(), BerError> {
+        let Some(location) = self.constructed(tag) else {
+            return Ok(());
+        };
+        for field in location.children()? {
+            let key = match field.tag {
+                0 => "lac",
+                1 => "cell_id",
+                _ => continue,
+            };
+            let value = field.value.iter().fold(0u32, |acc, &b| (acc << 8) | u32::from(b));
+            out.insert(key.to_string(), value.to_string().into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MO_CALL: &[u8] = include_bytes!("../../tests/fixtures/asn1/mo_call_record.ber");
+    const MT_CALL: &[u8] = include_bytes!("../../tests/fixtures/asn1/mt_call_record.ber");
+    const SMS_MO: &[u8] = include_bytes!("../../tests/fixtures/asn1/sms_mo_record.ber");
+    const PGW: &[u8] = include_bytes!("../../tests/fixtures/asn1/pgw_record.ber");
+    const PGW_PARTIAL: &[u8] = include_bytes!("../../tests/fixtures/asn1/pgw_partial_record.ber");
+
+    #[test]
+    fn test_decode_mo_call_record() {
+        let (record_type, cdr) = decode(MO_CALL).unwrap();
+
+        assert_eq!(record_type, Asn1RecordType::MoCall);
+        assert_eq!(cdr["record_type"], "MOCallRecord");
+        assert_eq!(cdr["event_type"], "voice");
+        assert_eq!(cdr["imsi"], "208150123456789");
+        assert_eq!(cdr["imei"], "352099001761481");
+        assert_eq!(cdr["msisdn"], "+33612345678");
+        assert_eq!(cdr["calling_number"], "+33612345678");
+        assert_eq!(cdr["called_number"], "+33698765432");
+        assert_eq!(cdr["timestamp"], "2026-01-29T10:15:30+01:00");
+        assert_eq!(cdr["end_timestamp"], "2026-01-29T10:17:45+01:00");
+        assert_eq!(cdr["duration"], 135);
+        assert_eq!(cdr["lac"], "4097");
+        assert_eq!(cdr["cell_id"], "12345");
+    }
+
+    #[test]
+    fn test_decode_mt_call_record() {
+        let (record_type, cdr) = decode(MT_CALL).unwrap();
+
+        assert_eq!(record_type, Asn1RecordType::MtCall);
+        assert_eq!(cdr["imsi"], "605011234567890");
+        assert_eq!(cdr["msisdn"], "+21698123456");
+        assert_eq!(cdr["called_number"], "+21698123456");
+        assert_eq!(cdr["calling_number"], "+33612345678");
+        assert_eq!(cdr["duration"], 62);
+    }
+
+    #[test]
+    fn test_decode_sms_mo_record() {
+        let (record_type, cdr) = decode(SMS_MO).unwrap();
+
+        assert_eq!(record_type, Asn1RecordType::SmsMo);
+        assert_eq!(cdr["event_type"], "sms");
+        assert_eq!(cdr["sms_type"], "mo");
+        assert_eq!(cdr["service_centre"], "+33609001390");
+        assert_eq!(cdr["called_number"], "+41791234567");
+        assert_eq!(cdr["timestamp"], "2026-01-29T09:00:05+01:00");
+    }
+
+    #[test]
+    fn test_decode_pgw_record() {
+        let (record_type, cdr) = decode(PGW).unwrap();
+
+        assert_eq!(record_type, Asn1RecordType::Pgw);
+        assert_eq!(cdr["event_type"], "data");
+        assert_eq!(cdr["imsi"], "244911234567890");
+        assert_eq!(cdr["msisdn"], "+358401234567");
+        assert_eq!(cdr["charging_id"], 305419896);
+        assert_eq!(cdr["apn"], "internet");
+        assert_eq!(cdr["duration"], 3600);
+        // Two traffic volume containers
+        assert_eq!(cdr["bytes_uploaded"], 1_500_000 + 250_000);
+        assert_eq!(cdr["bytes_downloaded"], 20_000_000 + 4_000_000);
+    }
+
+    #[test]
+    fn test_decode_pgw_partial_record() {
+        let (record_type, cdr) = decode(PGW_PARTIAL).unwrap();
+
+        assert_eq!(record_type, Asn1RecordType::Pgw);
+        assert_eq!(cdr["charging_id"], 305419896);
+        // timeLimit
+        assert_eq!(cdr["cause_for_record_closing"], 17);
+        assert_eq!(cdr["record_sequence_number"], 2);
+        assert_eq!(cdr["bytes_uploaded"], 800_000);
+        assert_eq!(cdr["bytes_downloaded"], 9_000_000);
+
+        // The first record of the fixtures carries no sequence number
+        let (_, cdr) = decode(PGW).unwrap();
+        assert_eq!(cdr["cause_for_record_closing"], 16);
+        assert!(cdr.get("record_sequence_number").is_none());
+    }
+
+    #[test]
+    fn test_traffic_volume_overflow_is_rejected() {
+        // PGWRecord [79] { listOfTrafficVolumes [12] { { [3] i64::MAX, [3] i64::MAX } } }
+        let max = [0x83, 0x08, 0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        let mut container = vec![0x30, 20];
+        container.extend(max);
+        container.extend(max);
+        let mut volumes = vec![0xAC, container.len() as u8];
+        volumes.extend(container);
+        let mut record = vec![0xBF, 0x4F, volumes.len() as u8];
+        record.extend(volumes);
+
+        assert_eq!(
+            decode(&record),
+            Err(Asn1Error::Ber(BerError::InvalidValue("traffic volume")))
+        );
+    }
+
+    #[test]
+    fn test_split_records() {
+        let mut file = MO_CALL.to_vec();
+        file.extend_from_slice(MT_CALL);
+        file.extend_from_slice(&[0xFF; 16]);
+        file.extend_from_slice(PGW);
+        file.extend_from_slice(&[0x00; 8]);
+
+        let records = split_records(&file).unwrap();
+
+        assert_eq!(records, vec![MO_CALL, MT_CALL, PGW]);
+    }
+
+    #[test]
+    fn test_call_record_tags_follow_ts_32_298() {
+        assert_eq!(MO_CALL[0], 0xA0);
+        assert_eq!(MT_CALL[0], 0xA1);
+        // moCallRecord [0], mtCallRecord [1]
+        assert_eq!(decode(&[0xA0, 0x00]).unwrap().0, Asn1RecordType::MoCall);
+        assert_eq!(decode(&[0xA1, 0x00]).unwrap().0, Asn1RecordType::MtCall);
+        // roamingRecord [2] is not an MT call
+        assert_eq!(decode(&[0xA2, 0x00]), Err(Asn1Error::UnsupportedRecord(2)));
+    }
+
+    #[test]
+    fn test_decode_rejects_unsupported_and_truncated() {
+        // [10] (ssActionRecord) is not decoded
+        assert_eq!(decode(&[0xAA, 0x00]), Err(Asn1Error::UnsupportedRecord(10)));
+        assert_eq!(
+            decode(&MO_CALL[..MO_CALL.len() - 4]),
+            Err(Asn1Error::Ber(BerError::Truncated))
+        );
+        assert!(looks_like_ber(PGW));
+        assert!(!looks_like_ber(b"{\"imsi\": \"1\"}"));
+    }
+}
@@ -0,0 +1,288 @@
+//! Minimal BER (X.690) reader used by the 3GPP CDR decoder.
+//!
+//! Only what TS 32.298 records need: tag/length/value parsing with
+//! multi-byte tags and indefinite lengths, plus the primitive encodings
+//! used for subscriber identities and timestamps.
+
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone};
+
+/// Most indefinite-length elements nested in one another. TS 32.298
+/// records nest a few levels; the bound keeps a crafted payload from
+/// exhausting the stack.
+pub const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BerError {
+    #[error("unexpected end of data")]
+    Truncated,
+    #[error("unsupported length encoding")]
+    InvalidLength,
+    #[error("tag number too large")]
+    TagTooLarge,
+    #[error("elements nested deeper than {MAX_DEPTH} levels")]
+    TooDeep,
+    #[error("invalid {0}")]
+    InvalidValue(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagClass {
+    Universal,
+    Application,
+    ContextSpecific,
+    Private,
+}
+
+/// One decoded tag-length-value element
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tlv<'a> {
+    pub class: TagClass,
+    pub constructed: bool,
+    pub tag: u32,
+    pub value: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    /// Parse one element, returning it with the remaining input
+    pub fn parse(input: &'a [u8]) -> Result<(Tlv<'a>, &'a [u8]), BerError> {
+        Self::parse_nested(input, 0)
+    }
+
+    /// Parse an element nested in `depth` indefinite-length elements
+    fn parse_nested(input: &'a [u8], depth: usize) -> Result<(Tlv<'a>, &'a [u8]), BerError> {
+        let (&first, mut rest) = input.split_first().ok_or(BerError::Truncated)?;
+
+        let class = match first >> 6 {
+            0 => TagClass::Universal,
+            1 => TagClass::Application,
+            2 => TagClass::ContextSpecific,
+            _ => TagClass::Private,
+        };
+        let constructed = first & 0x20 != 0;
+
+        let mut tag = u32::from(first & 0x1F);
+        if tag == 0x1F {
+            // High tag number form: base-128, high bit set on all but the last byte
+            tag = 0;
+            loop {
+                let (&byte, next) = rest.split_first().ok_or(BerError::Truncated)?;
+                rest = next;
+                if tag > (u32::MAX >> 7) {
+                    return Err(BerError::TagTooLarge);
+                }
+                tag = (tag << 7) | u32::from(byte & 0x7F);
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+        }
+
+        let (&len_byte, mut rest) = rest.split_first().ok_or(BerError::Truncated)?;
+        if len_byte == 0x80 {
+            // Indefinite length: contents end with an end-of-contents marker
+            if !constructed {
+                return Err(BerError::InvalidLength);
+            }
+            if depth >= MAX_DEPTH {
+                return Err(BerError::TooDeep);
+            }
+            let mut cursor = rest;
+            loop {
+                if cursor.starts_with(&[0x00, 0x00]) {
+                    let consumed = rest.len() - cursor.len();
+                    let value = &rest[..consumed];
+                    return Ok((Tlv { class, constructed, tag, value }, &cursor[2..]));
+                }
+                let (_, next) = Tlv::parse_nested(cursor, depth + 1)?;
+                cursor = next;
+            }
+        }
+
+        let length = if len_byte & 0x80 == 0 {
+            usize::from(len_byte)
+        } else {
+            let count = usize::from(len_byte & 0x7F);
+            if count > std::mem::size_of::<usize>() || rest.len() < count {
+                return Err(BerError::InvalidLength);
+            }
+            let length = rest[..count]
+                .iter()
+                .fold(0usize, |acc, &b| (acc << 8) | usize::from(b));
+            rest = &rest[count..];
+            length
+        };
+
+        if rest.len() < length {
+            return Err(BerError::Truncated);
+        }
+        let (value, rest) = rest.split_at(length);
+        Ok((Tlv { class, constructed, tag, value }, rest))
+    }
+
+    /// Child elements of a constructed value
+    pub fn children(&self) -> Result<Vec<Tlv<'a>>, BerError> {
+        let mut children = Vec::new();
+        let mut cursor = self.value;
+        while !cursor.is_empty() {
+            let (child, next) = Tlv::parse(cursor)?;
+            children.push(child);
+            cursor = next;
+        }
+        Ok(children)
+    }
+}
+
+/// Big-endian two's complement INTEGER
+pub fn decode_integer(bytes: &[u8]) -> Result<i64, BerError> {
+    if bytes.is_empty() || bytes.len() > 8 {
+        return Err(BerError::InvalidValue("integer"));
+    }
+    let negative = bytes[0] & 0x80 != 0;
+    let init: i64 = if negative { -1 } else { 0 };
+    Ok(bytes.iter().fold(init, |acc, &b| (acc << 8) | i64::from(b)))
+}
+
+/// TBCD-STRING (TS 29.002): two digits per octet, low nibble first,
+/// 0xF as filler
+pub fn decode_tbcd(bytes: &[u8]) -> Result<String, BerError> {
+    let mut digits = String::with_capacity(bytes.len() * 2);
+    for &byte in bytes {
+        for nibble in [byte & 0x0F, byte >> 4] {
+            match nibble {
+                0..=9 => digits.push(char::from(b'0' + nibble)),
+                0x0F => {}
+                _ => return Err(BerError::InvalidValue("TBCD digit")),
+            }
+        }
+    }
+    Ok(digits)
+}
+
+/// AddressString (TS 29.002): a TON/NPI octet followed by TBCD digits.
+/// International numbers are returned with a leading `+`.
+pub fn decode_address_string(bytes: &[u8]) -> Result<String, BerError> {
+    let (&ton_npi, digits) = bytes
+        .split_first()
+        .ok_or(BerError::InvalidValue("address string"))?;
+    let digits = decode_tbcd(digits)?;
+    if (ton_npi >> 4) & 0x07 == 0x01 {
+        Ok(format!("+{}", digits))
+    } else {
+        Ok(digits)
+    }
+}
+
+/// TP address (TS 23.040): digit count, TON/NPI octet, then TBCD digits
+pub fn decode_tp_address(bytes: &[u8]) -> Result<String, BerError> {
+    let (&count, address) = bytes
+        .split_first()
+        .ok_or(BerError::InvalidValue("TP address"))?;
+    let mut number = decode_address_string(address)?;
+    let prefix = usize::from(number.starts_with('+'));
+    number.truncate(prefix + usize::from(count));
+    Ok(number)
+}
+
+/// TimeStamp (TS 32.298): `YYMMDDhhmmssShhmm`, BCD digits except the
+/// ASCII sign of the UTC offset
+pub fn decode_timestamp(bytes: &[u8]) -> Result<DateTime<FixedOffset>, BerError> {
+    const INVALID: BerError = BerError::InvalidValue("timestamp");
+
+    if bytes.len() != 9 {
+        return Err(INVALID);
+    }
+    let bcd = |b: u8| -> Result<u32, BerError> {
+        let (high, low) = (b >> 4, b & 0x0F);
+        if high > 9 || low > 9 {
+            return Err(INVALID);
+        }
+        Ok(u32::from(high) * 10 + u32::from(low))
+    };
+
+    let year = 2000 + bcd(bytes[0])? as i32;
+    let offset_secs = (bcd(bytes[7])? * 3600 + bcd(bytes[8])? * 60) as i32;
+    let offset = match bytes[6] {
+        b'+' => FixedOffset::east_opt(offset_secs),
+        b'-' => FixedOffset::west_opt(offset_secs),
+        _ => None,
+    }
+    .ok_or(INVALID)?;
+
+    let (hour, minute, second) = (bcd(bytes[3])?, bcd(bytes[4])?, bcd(bytes[5])?);
+    let local = NaiveDate::from_ymd_opt(year, bcd(bytes[1])?, bcd(bytes[2])?)
+        .and_then(|date| date.and_hms_opt(hour, minute, second))
+        .ok_or(INVALID)?;
+    offset.from_local_datetime(&local).single().ok_or(INVALID)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_definite_and_indefinite_length() {
+        // [1] { [0] 0x01, [3] 0x02 0x03 } with indefinite length
+        let data = [0xA1, 0x80, 0x80, 0x01, 0x01, 0x83, 0x02, 0x02, 0x03, 0x00, 0x00, 0xFF];
+        let (tlv, rest) = Tlv::parse(&data).unwrap();
+        assert_eq!(tlv.class, TagClass::ContextSpecific);
+        assert!(tlv.constructed);
+        assert_eq!(tlv.tag, 1);
+        assert_eq!(rest, &[0xFF]);
+
+        let children = tlv.children().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[1].tag, 3);
+        assert_eq!(children[1].value, &[0x02, 0x03]);
+    }
+
+    #[test]
+    fn test_parse_high_tag_number() {
+        // [79] constructed, long-form length
+        let data = [0xBF, 0x4F, 0x81, 0x01, 0x00];
+        let (tlv, _) = Tlv::parse(&data).unwrap();
+        assert_eq!(tlv.tag, 79);
+        assert_eq!(tlv.value.len(), 1);
+
+        assert_eq!(Tlv::parse(&[0x80, 0x05, 0x01]), Err(BerError::Truncated));
+    }
+
+    #[test]
+    fn test_nesting_depth_is_bounded() {
+        let nested = |depth: usize| {
+            let mut data = [0xA0, 0x80].repeat(depth);
+            data.extend([0x00, 0x00].repeat(depth));
+            data
+        };
+        assert!(Tlv::parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(Tlv::parse(&nested(MAX_DEPTH + 1)), Err(BerError::TooDeep));
+
+        // 1 MB of nested openings fails instead of overflowing the stack
+        assert_eq!(Tlv::parse(&[0xA0, 0x80].repeat(512 * 1024)), Err(BerError::TooDeep));
+    }
+
+    #[test]
+    fn test_decode_identities() {
+        assert_eq!(
+            decode_tbcd(&[0x02, 0x18, 0x05, 0x21, 0x43, 0x65, 0x87, 0xF9]).unwrap(),
+            "208150123456789"
+        );
+        assert_eq!(
+            decode_address_string(&[0x91, 0x33, 0x16, 0x32, 0x54, 0x76, 0xF8]).unwrap(),
+            "+33612345678"
+        );
+        assert_eq!(
+            decode_tp_address(&[0x0B, 0x91, 0x33, 0x16, 0x32, 0x54, 0x76, 0xF8]).unwrap(),
+            "+33612345678"
+        );
+        assert_eq!(decode_integer(&[0x00, 0xFF]).unwrap(), 255);
+        assert_eq!(decode_integer(&[0xFF]).unwrap(), -1);
+    }
+
+    #[test]
+    fn test_decode_timestamp() {
+        let ts = decode_timestamp(&[0x26, 0x01, 0x29, 0x10, 0x15, 0x30, b'+', 0x01, 0x00]).unwrap();
+        assert_eq!(ts.to_rfc3339(), "2026-01-29T10:15:30+01:00");
+
+        assert!(decode_timestamp(&[0x26, 0x13, 0x29, 0x10, 0x15, 0x30, b'+', 0x01, 0x00]).is_err());
+    }
+}
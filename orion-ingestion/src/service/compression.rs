@@ -0,0 +1,129 @@
+//! Compressed payloads.
+//!
+//! Some producers compress each value instead of relying on Kafka batch
+//! compression, and mediation platforms drop compressed files. Gzip, zstd
+//! and LZ4 frames are recognised by their magic number and inflated before
+//! parsing; anything else is passed through.
+
+use std::io::Read;
+
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+const LZ4_MAGIC: &[u8] = &[0x04, 0x22, 0x4D, 0x18];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Gzip,
+    Zstd,
+    Lz4,
+}
+
+impl Codec {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Codec::Gzip => "gzip",
+            Codec::Zstd => "zstd",
+            Codec::Lz4 => "lz4",
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DecompressError {
+    #[error("decompressed size exceeds the {0} bytes limit")]
+    TooLarge(usize),
+    #[error("invalid {codec} data: {source}")]
+    Invalid {
+        codec: &'static str,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Codec of compressed data, `None` for uncompressed data
+pub fn detect(data: &[u8]) -> Option<Codec> {
+    if data.starts_with(GZIP_MAGIC) {
+        Some(Codec::Gzip)
+    } else if data.starts_with(ZSTD_MAGIC) {
+        Some(Codec::Zstd)
+    } else if data.starts_with(LZ4_MAGIC) {
+        Some(Codec::Lz4)
+    } else {
+        None
+    }
+}
+
+/// Inflate `data`, refusing to grow past `limit` bytes when one is given
+/// so that a small compressed payload cannot exhaust memory
+pub fn decompress(codec: Codec, data: &[u8], limit: Option<usize>) -> Result<Vec<u8>, DecompressError> {
+    let reader: Box<dyn Read + '_> = match codec {
+        Codec::Gzip => Box::new(flate2::read::MultiGzDecoder::new(data)),
+        Codec::Zstd => Box::new(
+            zstd::stream::read::Decoder::new(data)
+                .map_err(|source| DecompressError::Invalid { codec: codec.as_str(), source })?,
+        ),
+        Codec::Lz4 => Box::new(lz4_flex::frame::FrameDecoder::new(data)),
+    };
+
+    let mut out = Vec::new();
+    reader
+        .take(limit.map_or(u64::MAX, |limit| limit as u64 + 1))
+        .read_to_end(&mut out)
+        .map_err(|source| DecompressError::Invalid { codec: codec.as_str(), source })?;
+
+    match limit {
+        Some(limit) if out.len() > limit => Err(DecompressError::TooLarge(limit)),
+        _ => Ok(out),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const PLAIN: &[u8] = br#"{"imsi":"208150123456789","msisdn":"+33612345678"}"#;
+
+    fn compress(codec: Codec, data: &[u8]) -> Vec<u8> {
+        match codec {
+            Codec::Gzip => {
+                let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                gzip.write_all(data).unwrap();
+                gzip.finish().unwrap()
+            }
+            Codec::Zstd => zstd::stream::encode_all(data, 3).unwrap(),
+            Codec::Lz4 => {
+                let mut lz4 = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                lz4.write_all(data).unwrap();
+                lz4.finish().unwrap()
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for codec in [Codec::Gzip, Codec::Zstd, Codec::Lz4] {
+            let compressed = compress(codec, PLAIN);
+            assert_eq!(detect(&compressed), Some(codec));
+            assert_eq!(decompress(codec, &compressed, Some(PLAIN.len())).unwrap(), PLAIN);
+        }
+        assert_eq!(detect(PLAIN), None);
+    }
+
+    #[test]
+    fn test_limits_and_corruption() {
+        let bomb = compress(Codec::Gzip, &[b' '; 4096]);
+        assert!(matches!(
+            decompress(Codec::Gzip, &bomb, Some(1024)),
+            Err(DecompressError::TooLarge(1024))
+        ));
+        assert_eq!(decompress(Codec::Gzip, &bomb, None).unwrap().len(), 4096);
+
+        let mut truncated = compress(Codec::Lz4, PLAIN);
+        truncated.truncate(truncated.len() / 2);
+        assert!(matches!(
+            decompress(Codec::Lz4, &truncated, None),
+            Err(DecompressError::Invalid { codec: "lz4", .. })
+        ));
+    }
+}
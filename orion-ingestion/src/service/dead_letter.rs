@@ -0,0 +1,226 @@
+use crate::service::kafka_producer::KafkaProducerService;
+use crate::service::processor::ProcessError;
+use chrono::{DateTime, Utc};
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::{Header, Headers, Message, OwnedHeaders};
+use serde::Serialize;
+use std::time::Duration;
+use tracing::{info, warn};
+
+pub const HEADER_SOURCE_TOPIC: &str = "x-orion-source-topic";
+pub const HEADER_SOURCE_PARTITION: &str = "x-orion-source-partition";
+pub const HEADER_SOURCE_OFFSET: &str = "x-orion-source-offset";
+pub const HEADER_ERROR_CLASS: &str = "x-orion-error-class";
+pub const HEADER_ERROR_MESSAGE: &str = "x-orion-error-message";
+pub const HEADER_TIMESTAMP: &str = "x-orion-dead-letter-timestamp";
+
+/// Headers added to a dead letter, removed again on replay
+const DEAD_LETTER_HEADERS: [&str; 6] = [
+    HEADER_SOURCE_TOPIC,
+    HEADER_SOURCE_PARTITION,
+    HEADER_SOURCE_OFFSET,
+    HEADER_ERROR_CLASS,
+    HEADER_ERROR_MESSAGE,
+    HEADER_TIMESTAMP,
+];
+
+/// A raw message that could not be processed. The original key, headers
+/// and bytes are kept untouched so that the message can be replayed as-is.
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    pub key: Option<Vec<u8>>,
+    pub original_headers: Vec<(String, Option<Vec<u8>>)>,
+    pub payload: Vec<u8>,
+    pub error: ProcessError,
+}
+
+impl DeadLetter {
+    /// Original headers of a message, such as the country override or the
+    /// trace id
+    pub fn copy_headers<H: Headers>(headers: &H) -> Vec<(String, Option<Vec<u8>>)> {
+        headers
+            .iter()
+            .map(|header| (header.key.to_string(), header.value.map(|value| value.to_vec())))
+            .collect()
+    }
+
+    /// Original headers of the message, followed by headers describing
+    /// where it came from and why it failed
+    pub fn headers(
+        &self,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        timestamp: DateTime<Utc>,
+    ) -> OwnedHeaders {
+        let mut headers = OwnedHeaders::new();
+        for (key, value) in &self.original_headers {
+            if !DEAD_LETTER_HEADERS.contains(&key.as_str()) {
+                headers = headers.insert(Header { key, value: value.as_deref() });
+            }
+        }
+        headers
+            .insert(Header { key: HEADER_SOURCE_TOPIC, value: Some(topic) })
+            .insert(Header { key: HEADER_SOURCE_PARTITION, value: Some(&partition.to_string()) })
+            .insert(Header { key: HEADER_SOURCE_OFFSET, value: Some(&offset.to_string()) })
+            .insert(Header { key: HEADER_ERROR_CLASS, value: Some(self.error.class()) })
+            .insert(Header { key: HEADER_ERROR_MESSAGE, value: Some(&self.error.to_string()) })
+            .insert(Header { key: HEADER_TIMESTAMP, value: Some(&timestamp.to_rfc3339()) })
+    }
+}
+
+/// Headers of a dead letter without those added by `DeadLetter::headers`,
+/// to replay the message as it was received
+pub fn original_headers<H: Headers>(headers: &H) -> OwnedHeaders {
+    headers
+        .iter()
+        .filter(|header| !DEAD_LETTER_HEADERS.contains(&header.key))
+        .fold(OwnedHeaders::new(), |restored, header| {
+            restored.insert(Header { key: header.key, value: header.value })
+        })
+}
+
+/// Value of a UTF-8 header, if present
+pub fn header_value<H: Headers>(headers: &H, key: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|header| header.key == key)
+        .and_then(|header| header.value)
+        .map(|value| String::from_utf8_lossy(value).into_owned())
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ReplayReport {
+    pub replayed: usize,
+    pub skipped: usize,
+}
+
+/// Re-publishes dead-lettered messages to their source topic once the
+/// cause has been fixed. Progress is tracked by a dedicated consumer group,
+/// so each record is replayed once; records failing again are
+/// dead-lettered again with new offsets.
+pub struct DeadLetterReplayer {
+    brokers: String,
+    topic: String,
+    group_id: String,
+}
+
+impl DeadLetterReplayer {
+    pub fn new(brokers: &str, topic: &str, group_id: &str) -> Self {
+        Self {
+            brokers: brokers.to_string(),
+            topic: topic.to_string(),
+            group_id: group_id.to_string(),
+        }
+    }
+
+    /// Replay up to `max` pending records, stopping when the dead-letter
+    /// topic has been idle for `idle_timeout`
+    pub async fn replay(
+        &self,
+        producer: &KafkaProducerService,
+        max: usize,
+        idle_timeout: Duration,
+    ) -> anyhow::Result<ReplayReport> {
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &self.brokers)
+            .set("group.id", &self.group_id)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()?;
+        consumer.subscribe(&[&self.topic])?;
+
+        let mut report = ReplayReport::default();
+        while report.replayed + report.skipped < max {
+            let message = match tokio::time::timeout(idle_timeout, consumer.recv()).await {
+                Ok(message) => message?,
+                Err(_) => break,
+            };
+
+            let source_topic = message
+                .headers()
+                .and_then(|headers| header_value(headers, HEADER_SOURCE_TOPIC));
+
+            match source_topic {
+                Some(source_topic) => {
+                    let headers = message.headers().map(original_headers);
+                    producer
+                        .replay(&source_topic, message.key(), headers, message.payload().unwrap_or_default())
+                        .await?;
+                    report.replayed += 1;
+                }
+                None => {
+                    warn!(
+                        "Dead letter at {}/{} has no source topic, skipping",
+                        message.partition(),
+                        message.offset()
+                    );
+                    report.skipped += 1;
+                }
+            }
+
+            consumer.commit_message(&message, CommitMode::Sync)?;
+        }
+
+        info!(
+            "Dead-letter replay finished: {} replayed, {} skipped",
+            report.replayed, report.skipped
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dead_letter_headers() {
+        let letter = DeadLetter {
+            key: None,
+            original_headers: Vec::new(),
+            payload: vec![0xFF, 0xFE],
+            error: ProcessError::NonUtf8("invalid utf-8 sequence".to_string()),
+        };
+        let timestamp = "2026-01-29T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let headers = letter.headers("cdr.raw.FR", 3, 42, timestamp);
+
+        assert_eq!(header_value(&headers, HEADER_SOURCE_TOPIC).as_deref(), Some("cdr.raw.FR"));
+        assert_eq!(header_value(&headers, HEADER_SOURCE_PARTITION).as_deref(), Some("3"));
+        assert_eq!(header_value(&headers, HEADER_SOURCE_OFFSET).as_deref(), Some("42"));
+        assert_eq!(header_value(&headers, HEADER_ERROR_CLASS).as_deref(), Some("non_utf8"));
+        assert_eq!(
+            header_value(&headers, HEADER_TIMESTAMP).as_deref(),
+            Some("2026-01-29T10:00:00+00:00")
+        );
+        assert_eq!(header_value(&headers, "x-unknown"), None);
+    }
+
+    #[test]
+    fn test_original_headers_survive_replay() {
+        let received = OwnedHeaders::new()
+            .insert(Header { key: "x-orion-country", value: Some("TN") })
+            .insert(Header { key: "traceparent", value: Some("00-abc-def-01") })
+            .insert(Header { key: "x-empty", value: None::<&[u8]> });
+        let letter = DeadLetter {
+            key: None,
+            original_headers: DeadLetter::copy_headers(&received),
+            payload: b"{}".to_vec(),
+            error: ProcessError::NonUtf8("invalid utf-8 sequence".to_string()),
+        };
+        let timestamp = "2026-01-29T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let dead_letter = letter.headers("cdr.raw.FR", 3, 42, timestamp);
+        assert_eq!(header_value(&dead_letter, "x-orion-country").as_deref(), Some("TN"));
+        assert_eq!(header_value(&dead_letter, HEADER_ERROR_CLASS).as_deref(), Some("non_utf8"));
+
+        let replayed = original_headers(&dead_letter);
+        assert_eq!(replayed.count(), 3);
+        assert_eq!(header_value(&replayed, "x-orion-country").as_deref(), Some("TN"));
+        assert_eq!(header_value(&replayed, "traceparent").as_deref(), Some("00-abc-def-01"));
+        assert!(replayed.iter().any(|header| header.key == "x-empty" && header.value.is_none()));
+        assert_eq!(header_value(&replayed, HEADER_SOURCE_TOPIC), None);
+    }
+}
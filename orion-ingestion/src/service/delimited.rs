@@ -0,0 +1,340 @@
+//! Delimited (CSV-like) CDR formats.
+//!
+//! Legacy feeds publish one record per line, with a configurable
+//! separator and quote character, and either a header row or positional
+//! columns. Each format is attached to one or more topics and maps its
+//! columns to the canonical JSON field names used downstream.
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DelimitedError {
+    #[error("unterminated quoted value")]
+    UnterminatedQuote,
+    #[error("expected {expected} columns, found {found}")]
+    ColumnCount { expected: usize, found: usize },
+    #[error("invalid {kind} value {value:?} for field {field}")]
+    InvalidValue { field: String, kind: &'static str, value: String },
+    #[error("expected {expected} line(s), found {found}")]
+    LineCount { expected: usize, found: usize },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnType {
+    #[default]
+    String,
+    Integer,
+    Float,
+    Boolean,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ColumnMapping {
+    /// Header name in header mode, defaults to `field`.
+    /// Ignored in positional mode, where the column order is used.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Canonical field name in the JSON payload
+    pub field: String,
+    #[serde(default, rename = "type")]
+    pub kind: ColumnType,
+}
+
+impl ColumnMapping {
+    fn source(&self) -> &str {
+        self.source.as_deref().unwrap_or(&self.field)
+    }
+
+    /// Typed JSON value, `None` for empty columns
+    fn convert(&self, raw: &str) -> Result<Option<Value>, DelimitedError> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return Ok(None);
+        }
+        let invalid = |kind| DelimitedError::InvalidValue {
+            field: self.field.clone(),
+            kind,
+            value: raw.to_string(),
+        };
+        let value = match self.kind {
+            ColumnType::String => Value::from(raw),
+            ColumnType::Integer => Value::from(raw.parse::<i64>().map_err(|_| invalid("integer"))?),
+            ColumnType::Float => Value::from(raw.parse::<f64>().map_err(|_| invalid("float"))?),
+            ColumnType::Boolean => match raw.to_lowercase().as_str() {
+                "true" | "1" | "y" | "yes" => Value::Bool(true),
+                "false" | "0" | "n" | "no" => Value::Bool(false),
+                _ => return Err(invalid("boolean")),
+            },
+        };
+        Ok(Some(value))
+    }
+}
+
+fn default_separator() -> char {
+    ';'
+}
+
+fn default_quote() -> char {
+    '"'
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DelimitedFormat {
+    pub name: String,
+    pub topics: Vec<String>,
+    #[serde(default = "default_separator")]
+    pub separator: char,
+    #[serde(default = "default_quote")]
+    pub quote: char,
+    /// The payload starts with a header row naming the columns
+    #[serde(default)]
+    pub has_header: bool,
+    pub columns: Vec<ColumnMapping>,
+}
+
+impl DelimitedFormat {
+    /// Split one line into values. A quoted value may contain the separator;
+    /// a doubled quote inside it stands for a literal quote.
+    pub fn split(&self, line: &str) -> Result<Vec<String>, DelimitedError> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let mut values = Vec::new();
+        let mut current = String::new();
+        let mut quoted = false;
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if quoted {
+                if c == self.quote {
+                    if chars.peek() == Some(&self.quote) {
+                        current.push(c);
+                        chars.next();
+                    } else {
+                        quoted = false;
+                    }
+                } else {
+                    current.push(c);
+                }
+            } else if c == self.quote {
+                quoted = true;
+            } else if c == self.separator {
+                values.push(std::mem::take(&mut current));
+            } else {
+                current.push(c);
+            }
+        }
+
+        if quoted {
+            return Err(DelimitedError::UnterminatedQuote);
+        }
+        values.push(current);
+        Ok(values)
+    }
+
+    /// Column names of a header row
+    pub fn header(&self, line: &str) -> Result<Vec<String>, DelimitedError> {
+        Ok(self.split(line)?.into_iter().map(|name| name.trim().to_string()).collect())
+    }
+
+    /// Map one data line to a JSON object. With a header, mapped columns are
+    /// renamed and typed and the others are kept as strings under their header
+    /// name. Without one, values are taken in column order and trailing
+    /// columns may be omitted.
+    pub fn record(&self, header: Option<&[String]>, line: &str) -> Result<Value, DelimitedError> {
+        let values = self.split(line)?;
+        let mut record = Map::new();
+
+        match header {
+            Some(header) => {
+                if values.len() != header.len() {
+                    return Err(DelimitedError::ColumnCount {
+                        expected: header.len(),
+                        found: values.len(),
+                    });
+                }
+                for (name, raw) in header.iter().zip(&values) {
+                    match self.columns.iter().find(|column| column.source() == name) {
+                        Some(column) => {
+                            if let Some(value) = column.convert(raw)? {
+                                record.insert(column.field.clone(), value);
+                            }
+                        }
+                        None if !raw.trim().is_empty() => {
+                            record.insert(name.clone(), Value::from(raw.trim()));
+                        }
+                        None => {}
+                    }
+                }
+            }
+            None => {
+                if values.len() > self.columns.len() {
+                    return Err(DelimitedError::ColumnCount {
+                        expected: self.columns.len(),
+                        found: values.len(),
+                    });
+                }
+                for (column, raw) in self.columns.iter().zip(&values) {
+                    if let Some(value) = column.convert(raw)? {
+                        record.insert(column.field.clone(), value);
+                    }
+                }
+            }
+        }
+
+        Ok(Value::Object(record))
+    }
+
+    /// Parse a single-record payload: one data line, preceded by the header
+    /// row when the format has one
+    pub fn parse(&self, text: &str) -> Result<Value, DelimitedError> {
+        let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+        let expected = if self.has_header { 2 } else { 1 };
+        if lines.len() != expected {
+            return Err(DelimitedError::LineCount { expected, found: lines.len() });
+        }
+
+        if self.has_header {
+            let header = self.header(lines[0])?;
+            self.record(Some(&header), lines[1])
+        } else {
+            self.record(None, lines[0])
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FormatsFile {
+    formats: Vec<DelimitedFormat>,
+}
+
+/// Delimited formats indexed by topic
+#[derive(Debug, Clone, Default)]
+pub struct DelimitedFormats {
+    by_topic: HashMap<String, Arc<DelimitedFormat>>,
+}
+
+impl DelimitedFormats {
+    /// Load formats from a JSON file
+    pub fn from_json(path: &str) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> anyhow::Result<Self> {
+        let file: FormatsFile = serde_json::from_str(contents)?;
+        let mut by_topic = HashMap::new();
+        for format in file.formats {
+            if format.separator == format.quote {
+                anyhow::bail!("Format {}: separator and quote must differ", format.name);
+            }
+            let format = Arc::new(format);
+            for topic in &format.topics {
+                if by_topic.insert(topic.clone(), Arc::clone(&format)).is_some() {
+                    anyhow::bail!("Topic {} is mapped to several delimited formats", topic);
+                }
+            }
+        }
+        Ok(Self { by_topic })
+    }
+
+    pub fn for_topic(&self, topic: &str) -> Option<&DelimitedFormat> {
+        self.by_topic.get(topic).map(|format| format.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_topic.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUNDLED: &str = include_str!("../../formats/delimited.json");
+
+    fn format(has_header: bool) -> DelimitedFormat {
+        serde_json::from_value(serde_json::json!({
+            "name": "test",
+            "topics": ["cdr.raw.TEST"],
+            "has_header": has_header,
+            "columns": [
+                {"field": "imsi"},
+                {"field": "msisdn"},
+                {"field": "event_type"},
+                {"source": "bytes_up", "field": "bytes_uploaded", "type": "integer"},
+                {"source": "roaming", "field": "is_roaming", "type": "boolean"}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_split_with_quotes() {
+        let format = format(false);
+        assert_eq!(
+            format.split(r#"a;"b;c";"say ""hi""";"#).unwrap(),
+            vec!["a", "b;c", r#"say "hi""#, ""]
+        );
+        assert_eq!(format.split(r#"a;"b"#), Err(DelimitedError::UnterminatedQuote));
+    }
+
+    #[test]
+    fn test_positional_record() {
+        let record = format(false).parse("208150123456789;+33612345678;data;1024\r\n").unwrap();
+
+        assert_eq!(
+            record,
+            serde_json::json!({
+                "imsi": "208150123456789",
+                "msisdn": "+33612345678",
+                "event_type": "data",
+                "bytes_uploaded": 1024
+            })
+        );
+        assert_eq!(
+            format(false).parse("1;2;3;4;5;6"),
+            Err(DelimitedError::ColumnCount { expected: 5, found: 6 })
+        );
+    }
+
+    #[test]
+    fn test_header_record() {
+        let text = "msisdn;imsi;roaming;cell\n+33612345678;208150123456789;Y;A1\n";
+        let record = format(true).parse(text).unwrap();
+
+        assert_eq!(record["imsi"], "208150123456789");
+        assert_eq!(record["is_roaming"], true);
+        // Unmapped columns are kept under their header name
+        assert_eq!(record["cell"], "A1");
+
+        assert_eq!(
+            format(true).parse("imsi;bytes_up\n208150123456789;lots"),
+            Err(DelimitedError::InvalidValue {
+                field: "bytes_uploaded".to_string(),
+                kind: "integer",
+                value: "lots".to_string(),
+            })
+        );
+        assert_eq!(
+            format(true).parse("208150123456789;+33612345678"),
+            Err(DelimitedError::LineCount { expected: 2, found: 1 })
+        );
+    }
+
+    #[test]
+    fn test_bundled_formats() {
+        let formats = DelimitedFormats::parse(BUNDLED).unwrap();
+
+        for topic in ["cdr.raw.FR", "cdr.raw.TN", "cdr.raw.FN", "cdr.raw.CH"] {
+            assert!(formats.for_topic(topic).is_some(), "no format for {}", topic);
+        }
+
+        let ch = formats.for_topic("cdr.raw.CH").unwrap();
+        let record = ch.parse("208150123456789|+221712345678|voice|120|0|0").unwrap();
+        assert_eq!(record["event_type"], "voice");
+        assert_eq!(record["duration"], 120);
+    }
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointEntry {
+    pub size: u64,
+    pub records: usize,
+    pub completed_at: String,
+}
+
+/// Files of the input directory that have been fully published.
+///
+/// A file is recorded once all its records are acknowledged, before it is
+/// moved to the archive, so a restart between the two steps does not
+/// ingest it again. Entries are keyed by `<topic>/<file name>` and dropped
+/// once the file has left the input directory.
+#[derive(Debug, Default)]
+pub struct FileCheckpoint {
+    path: PathBuf,
+    files: HashMap<String, CheckpointEntry>,
+}
+
+impl FileCheckpoint {
+    /// Load the checkpoint, starting empty if the file does not exist yet
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let files = match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path: PathBuf::from(path), files })
+    }
+
+    /// Whether this file (same name and size) was already published
+    pub fn is_done(&self, key: &str, size: u64) -> bool {
+        self.files.get(key).is_some_and(|entry| entry.size == size)
+    }
+
+    pub fn complete(&mut self, key: &str, size: u64, records: usize) -> anyhow::Result<()> {
+        self.files.insert(
+            key.to_string(),
+            CheckpointEntry {
+                size,
+                records,
+                completed_at: chrono::Utc::now().to_rfc3339(),
+            },
+        );
+        self.save()
+    }
+
+    /// Forget files no longer present in the input directory
+    pub fn retain(&mut self, present: &HashSet<String>) -> anyhow::Result<()> {
+        let before = self.files.len();
+        self.files.retain(|key, _| present.contains(key));
+        if self.files.len() != before {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Write to a temporary file then rename, so a crash never leaves a
+    /// truncated checkpoint
+    fn save(&self) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = tmp_path(&self.path);
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&self.files)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_survives_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state/checkpoint.json");
+        let path = path.to_str().unwrap();
+
+        let mut checkpoint = FileCheckpoint::load(path).unwrap();
+        assert!(!checkpoint.is_done("cdr.raw.FR/a.csv", 10));
+        checkpoint.complete("cdr.raw.FR/a.csv", 10, 3).unwrap();
+        checkpoint.complete("cdr.raw.FR/b.csv", 20, 5).unwrap();
+
+        let mut checkpoint = FileCheckpoint::load(path).unwrap();
+        assert!(checkpoint.is_done("cdr.raw.FR/a.csv", 10));
+        // Same name, different content
+        assert!(!checkpoint.is_done("cdr.raw.FR/a.csv", 11));
+
+        checkpoint
+            .retain(&HashSet::from(["cdr.raw.FR/b.csv".to_string()]))
+            .unwrap();
+        let checkpoint = FileCheckpoint::load(path).unwrap();
+        assert!(!checkpoint.is_done("cdr.raw.FR/a.csv", 10));
+        assert!(checkpoint.is_done("cdr.raw.FR/b.csv", 20));
+    }
+}
@@ -0,0 +1,341 @@
+use crate::config::FileSourceConfig;
+use crate::metrics;
+use crate::service::asn1_decoder;
+use crate::service::compression;
+use crate::service::delimited::DelimitedFormat;
+use crate::service::file_checkpoint::FileCheckpoint;
+use crate::service::{DeadLetter, KafkaProducerService, MessageProcessor};
+use orion_shutdown::Shutdown;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::{debug, error, info, warn};
+
+/// Batch ingestion of CDR files dropped by mediation platforms.
+///
+/// Files are expected in `<input_dir>/<topic>/`, the directory name giving
+/// the topic whose country and delimited format apply. A file is picked up
+/// once its size and modification time are stable across two scans; its
+/// records go through the same `MessageProcessor` as Kafka messages. Once
+/// published the file is checkpointed and archived; a file that cannot be
+/// read or split, or larger than the limit once decompressed, is moved to
+/// quarantine.
+pub struct FileSource {
+    config: FileSourceConfig,
+    processor: Arc<MessageProcessor>,
+    producer: Arc<KafkaProducerService>,
+    checkpoint: FileCheckpoint,
+    /// Size and modification time seen at the previous scan
+    pending: HashMap<PathBuf, (u64, SystemTime)>,
+}
+
+impl FileSource {
+    pub fn new(
+        config: &FileSourceConfig,
+        processor: Arc<MessageProcessor>,
+        producer: Arc<KafkaProducerService>,
+    ) -> anyhow::Result<Self> {
+        for dir in [&config.input_dir, &config.archive_dir, &config.quarantine_dir] {
+            std::fs::create_dir_all(dir)?;
+        }
+        let checkpoint = FileCheckpoint::load(&config.checkpoint_path)?;
+        info!("Watching {} for CDR files", config.input_dir);
+
+        Ok(Self {
+            config: config.clone(),
+            processor,
+            producer,
+            checkpoint,
+            pending: HashMap::new(),
+        })
+    }
+
+    /// Scan the input directory until shutdown. A scan in progress is
+    /// finished, so no file is left half-published.
+    pub async fn run(mut self, mut shutdown: Shutdown) -> anyhow::Result<()> {
+        let mut interval =
+            tokio::time::interval(Duration::from_millis(self.config.poll_interval_ms));
+        loop {
+            tokio::select! {
+                biased;
+                _ = shutdown.requested() => break,
+                _ = interval.tick() => {}
+            }
+            if let Err(e) = self.scan().await {
+                error!("File source scan failed: {:?}", e);
+            }
+        }
+        info!("File source stopped");
+        Ok(())
+    }
+
+    async fn scan(&mut self) -> anyhow::Result<()> {
+        let mut present = HashSet::new();
+        let mut seen = HashMap::new();
+
+        for (topic, path) in list_files(Path::new(&self.config.input_dir))? {
+            let metadata = match std::fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                // Moved away since the listing
+                Err(_) => continue,
+            };
+            let size = metadata.len();
+            let modified = metadata.modified()?;
+            let key = format!("{}/{}", topic, file_name(&path));
+            present.insert(key.clone());
+
+            if self.checkpoint.is_done(&key, size) {
+                // Published but not archived before a restart
+                self.archive(&topic, &path);
+                continue;
+            }
+
+            // Wait until the writer is done with the file
+            let stable = self.pending.get(&path) == Some(&(size, modified));
+            seen.insert(path.clone(), (size, modified));
+            if !stable {
+                continue;
+            }
+
+            match self.ingest_file(&topic, &path).await {
+                Ok(records) => {
+                    self.checkpoint.complete(&key, size, records)?;
+                    self.archive(&topic, &path);
+                    seen.remove(&path);
+                }
+                Err(reason) => {
+                    warn!("Quarantining {}: {}", path.display(), reason);
+                    self.quarantine(&topic, &path, &reason);
+                    seen.remove(&path);
+                }
+            }
+        }
+
+        self.pending = seen;
+        self.checkpoint.retain(&present)
+    }
+
+    /// Publish every record of a file. Returns the number of records, or
+    /// the reason the file must be quarantined.
+    async fn ingest_file(&self, topic: &str, path: &Path) -> Result<usize, String> {
+        if self.processor.route(topic).is_none() {
+            return Err(format!("no route for topic {}", topic));
+        }
+        info!("Ingesting file {} for topic {}", path.display(), topic);
+
+        let format = self.processor.delimited_format(topic).cloned();
+        let file = path.to_path_buf();
+        let max_bytes = self.config.max_file_bytes;
+        let records = tokio::task::spawn_blocking(move || {
+            let size = std::fs::metadata(&file).map_err(|e| format!("read failed: {}", e))?.len();
+            if size > max_bytes as u64 {
+                return Err(format!("file size {} exceeds the {} bytes limit", size, max_bytes));
+            }
+            let data = std::fs::read(&file).map_err(|e| format!("read failed: {}", e))?;
+            let data = decompress(data, max_bytes).map_err(|e| format!("decompression failed: {}", e))?;
+            split_records(&data, format.as_ref())
+        })
+        .await
+        .map_err(|e| format!("reader task failed: {}", e))??;
+
+        for (index, payload) in records.iter().enumerate() {
+            match self.processor.process(payload, topic, None).await {
+                Ok(processed) => {
+                    while let Err(e) = self.producer.send(&processed).await {
+                        error!("Failed to publish CDR from {}, retrying: {}", path.display(), e);
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                }
+                Err(error) => {
+                    // No partition for files: the record index stands for the offset
+                    let letter = DeadLetter { key: None, original_headers: Vec::new(), payload: payload.clone(), error };
+                    while let Err(e) = self
+                        .producer
+                        .send_dead_letter(&letter, topic, -1, index as i64)
+                        .await
+                    {
+                        error!("Failed to publish dead letter from {}, retrying: {}", path.display(), e);
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                }
+            }
+        }
+
+        debug!("Published {} records from {}", records.len(), path.display());
+        metrics::record_file(records.len() as u64);
+        Ok(records.len())
+    }
+
+    fn archive(&self, topic: &str, path: &Path) {
+        let target = Path::new(&self.config.archive_dir).join(topic);
+        if let Err(e) = move_file(path, &target) {
+            error!("Failed to archive {}: {}", path.display(), e);
+        }
+    }
+
+    fn quarantine(&self, topic: &str, path: &Path, reason: &str) {
+        metrics::record_file_quarantined();
+        let target = Path::new(&self.config.quarantine_dir).join(topic);
+        match move_file(path, &target) {
+            Ok(moved) => {
+                let mut note = moved.into_os_string();
+                note.push(".error");
+                if let Err(e) = std::fs::write(&note, reason) {
+                    error!("Failed to write quarantine reason for {}: {}", path.display(), e);
+                }
+            }
+            Err(e) => error!("Failed to quarantine {}: {}", path.display(), e),
+        }
+    }
+}
+
+/// Regular files of each topic directory, skipping hidden files and
+/// transfers still in progress (`.tmp`, `.part`)
+fn list_files(input_dir: &Path) -> std::io::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    for topic_dir in std::fs::read_dir(input_dir)? {
+        let topic_dir = topic_dir?.path();
+        if !topic_dir.is_dir() {
+            continue;
+        }
+        let topic = file_name(&topic_dir);
+        for entry in std::fs::read_dir(&topic_dir)? {
+            let path = entry?.path();
+            let name = file_name(&path);
+            if !path.is_file()
+                || name.starts_with('.')
+                || name.ends_with(".tmp")
+                || name.ends_with(".part")
+            {
+                continue;
+            }
+            files.push((topic.clone(), path));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Move a file into `dir`, falling back to copy and delete across filesystems
+fn move_file(path: &Path, dir: &Path) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let target = dir.join(path.file_name().unwrap_or_default());
+    if std::fs::rename(path, &target).is_err() {
+        std::fs::copy(path, &target)?;
+        std::fs::remove_file(path)?;
+    }
+    Ok(target)
+}
+
+/// Compressed files are recognised by their magic number; they are not
+/// inflated past `max_bytes`
+fn decompress(data: Vec<u8>, max_bytes: usize) -> Result<Vec<u8>, compression::DecompressError> {
+    match compression::detect(&data) {
+        Some(codec) => compression::decompress(codec, &data, Some(max_bytes)),
+        None => Ok(data),
+    }
+}
+
+/// Split file contents into message payloads: concatenated BER records, or
+/// one line per record. With a header row, the header is repeated in front
+/// of each line so that records parse exactly like Kafka messages.
+fn split_records(data: &[u8], format: Option<&DelimitedFormat>) -> Result<Vec<Vec<u8>>, String> {
+    if asn1_decoder::looks_like_ber(data) {
+        return asn1_decoder::split_records(data)
+            .map(|records| records.into_iter().map(|r| r.to_vec()).collect())
+            .map_err(|e| e.to_string());
+    }
+
+    let text = std::str::from_utf8(data).map_err(|e| format!("not valid UTF-8: {}", e))?;
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+
+    match format.filter(|format| format.has_header) {
+        Some(_) => {
+            let Some(header) = lines.next() else {
+                return Ok(Vec::new());
+            };
+            Ok(lines
+                .map(|line| format!("{}\n{}", header, line).into_bytes())
+                .collect())
+        }
+        None => Ok(lines.map(|line| line.as_bytes().to_vec()).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_decompress() {
+        let plain = b"208150123456789;+33612345678;data\n".to_vec();
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&plain).unwrap();
+        assert_eq!(decompress(gzip.finish().unwrap(), 1024).unwrap(), plain);
+
+        let zstd = zstd::stream::encode_all(plain.as_slice(), 3).unwrap();
+        assert_eq!(decompress(zstd, 1024).unwrap(), plain);
+
+        let mut lz4 = lz4_flex::frame::FrameEncoder::new(Vec::new());
+        lz4.write_all(&plain).unwrap();
+        assert_eq!(decompress(lz4.finish().unwrap(), 1024).unwrap(), plain);
+
+        assert_eq!(decompress(plain.clone(), 1024).unwrap(), plain);
+
+        // A bomb stops at the limit
+        let bomb = zstd::stream::encode_all(vec![0u8; 1 << 20].as_slice(), 3).unwrap();
+        assert!(matches!(
+            decompress(bomb, 64 * 1024),
+            Err(compression::DecompressError::TooLarge(65536))
+        ));
+    }
+
+    #[test]
+    fn test_split_records() {
+        let format: DelimitedFormat = serde_json::from_value(serde_json::json!({
+            "name": "fn-csv",
+            "topics": ["cdr.raw.FN"],
+            "has_header": true,
+            "columns": [{"field": "imsi"}]
+        }))
+        .unwrap();
+        let data = b"imsi;msisdn\n244911234567890;+358401234567\n\n244911234567891;+358401234568\n";
+
+        let records = split_records(data, Some(&format)).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1], b"imsi;msisdn\n244911234567891;+358401234568");
+
+        let records = split_records(data, None).unwrap();
+        assert_eq!(records.len(), 3);
+
+        let ber = include_bytes!("../../tests/fixtures/asn1/pgw_record.ber");
+        assert_eq!(split_records(ber, None).unwrap(), vec![ber.to_vec()]);
+    }
+
+    #[test]
+    fn test_list_and_move_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let inbox = dir.path().join("inbox");
+        std::fs::create_dir_all(inbox.join("cdr.raw.FR")).unwrap();
+        std::fs::write(inbox.join("cdr.raw.FR/a.csv"), "x").unwrap();
+        std::fs::write(inbox.join("cdr.raw.FR/b.csv.part"), "x").unwrap();
+        std::fs::write(inbox.join("cdr.raw.FR/.hidden"), "x").unwrap();
+        std::fs::write(inbox.join("stray.csv"), "x").unwrap();
+
+        let files = list_files(&inbox).unwrap();
+        assert_eq!(files, vec![("cdr.raw.FR".to_string(), inbox.join("cdr.raw.FR/a.csv"))]);
+
+        let archived = move_file(&files[0].1, &dir.path().join("archive/cdr.raw.FR")).unwrap();
+        assert!(archived.exists());
+        assert!(!files[0].1.exists());
+    }
+}
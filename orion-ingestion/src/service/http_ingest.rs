@@ -0,0 +1,243 @@
+use crate::config::HttpIngestConfig;
+use crate::metrics;
+use crate::service::compression::{self, Codec, DecompressError};
+use crate::service::{MessageProcessor, ProcessedCDR};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IngestError {
+    #[error("invalid country code: {0}")]
+    InvalidCountry(String),
+    #[error("country {0} is not in the routing table")]
+    UnknownCountry(String),
+    #[error("body exceeds the {0} bytes limit")]
+    TooLarge(usize),
+    #[error("batch of {found} records exceeds the {max} records limit")]
+    TooManyRecords { found: usize, max: usize },
+    #[error("invalid gzip body: {0}")]
+    InvalidGzip(String),
+    #[error("output queue is full, retry later")]
+    Busy,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordStatus {
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecordResult {
+    pub index: usize,
+    pub status: RecordStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_class: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct IngestReport {
+    pub accepted: usize,
+    pub rejected: usize,
+    pub results: Vec<RecordResult>,
+}
+
+/// CDRs pushed over HTTP by partners and test harnesses.
+///
+/// Records go through the same `MessageProcessor` as Kafka messages of
+/// `cdr.raw.{country}`. Accepted records are queued for publication; when
+/// the queue cannot take the whole batch, nothing is queued and the caller
+/// is asked to retry.
+pub struct HttpIngestor {
+    processor: Arc<MessageProcessor>,
+    output_tx: mpsc::Sender<ProcessedCDR>,
+    max_body_bytes: usize,
+    max_records: usize,
+}
+
+impl HttpIngestor {
+    pub fn new(
+        config: &HttpIngestConfig,
+        processor: Arc<MessageProcessor>,
+        output_tx: mpsc::Sender<ProcessedCDR>,
+    ) -> Self {
+        Self {
+            processor,
+            output_tx,
+            max_body_bytes: config.max_body_bytes,
+            max_records: config.max_records,
+        }
+    }
+
+    pub fn max_body_bytes(&self) -> usize {
+        self.max_body_bytes
+    }
+
+    pub async fn ingest(
+        &self,
+        country: &str,
+        content_type: Option<&str>,
+        gzip: bool,
+        body: &[u8],
+    ) -> Result<IngestReport, IngestError> {
+        if !(2..=3).contains(&country.len()) || !country.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(IngestError::InvalidCountry(country.to_string()));
+        }
+        let country = country.to_uppercase();
+        let topic = format!("cdr.raw.{}", country);
+        // Same routes as Kafka: a country without one is not ingested
+        let route = self
+            .processor
+            .route(&topic)
+            .ok_or_else(|| IngestError::UnknownCountry(country.clone()))?;
+
+        let body = if gzip || compression::detect(body) == Some(Codec::Gzip) {
+            gunzip(body, self.max_body_bytes)?
+        } else {
+            body.to_vec()
+        };
+        let records = split_body(&body, content_type);
+        if records.len() > self.max_records {
+            return Err(IngestError::TooManyRecords { found: records.len(), max: self.max_records });
+        }
+
+        let mut report = IngestReport::default();
+        let mut accepted = Vec::new();
+        for (index, record) in records.into_iter().enumerate() {
+            match self.processor.process_with_route(record, &topic, route.clone()).await {
+                Ok(processed) => {
+                    accepted.push(processed);
+                    report.results.push(RecordResult {
+                        index,
+                        status: RecordStatus::Accepted,
+                        error_class: None,
+                        error: None,
+                    });
+                }
+                Err(e) => report.results.push(RecordResult {
+                    index,
+                    status: RecordStatus::Rejected,
+                    error_class: Some(e.class()),
+                    error: Some(e.to_string()),
+                }),
+            }
+        }
+
+        if !accepted.is_empty() {
+            let permits = self.output_tx.try_reserve_many(accepted.len()).map_err(|_| {
+                metrics::record_http_throttled();
+                IngestError::Busy
+            })?;
+            for (permit, cdr) in permits.zip(accepted) {
+                permit.send(cdr);
+            }
+        }
+
+        report.accepted = report.results.iter().filter(|r| r.status == RecordStatus::Accepted).count();
+        report.rejected = report.results.len() - report.accepted;
+        metrics::record_http_records(report.accepted as u64, report.rejected as u64);
+        Ok(report)
+    }
+}
+
+/// Decompress a gzip body without letting it grow past `max` bytes
+fn gunzip(body: &[u8], max: usize) -> Result<Vec<u8>, IngestError> {
+    compression::decompress(Codec::Gzip, body, Some(max)).map_err(|e| match e {
+        DecompressError::TooLarge(max) => IngestError::TooLarge(max),
+        DecompressError::Invalid { source, .. } => IngestError::InvalidGzip(source.to_string()),
+    })
+}
+
+/// NDJSON bodies carry one record per line, any other body is one record
+fn split_body<'a>(body: &'a [u8], content_type: Option<&str>) -> Vec<&'a [u8]> {
+    let ndjson = content_type
+        .map(|ct| ct.split(';').next().unwrap_or_default().trim())
+        .is_some_and(|ct| matches!(ct, "application/x-ndjson" | "application/jsonl"));
+
+    if ndjson {
+        body.split(|&b| b == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+            .collect()
+    } else {
+        vec![body]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::{DelimitedFormats, RoutingTable};
+    use std::io::Write;
+
+    fn ingestor(queue: usize) -> (HttpIngestor, mpsc::Receiver<ProcessedCDR>) {
+        let (tx, rx) = mpsc::channel(queue);
+        let config = HttpIngestConfig { max_body_bytes: 1024, max_records: 3, queue_size: queue };
+        let processor = Arc::new(MessageProcessor::new(256, DelimitedFormats::default(), RoutingTable::bundled()));
+        (HttpIngestor::new(&config, processor, tx), rx)
+    }
+
+    #[tokio::test]
+    async fn test_ingest_ndjson_batch() {
+        let (ingestor, mut rx) = ingestor(10);
+        let body = b"{\"imsi\":\"208150123456789\"}\n\n\xFF\xFE\n{\"imsi\":\"208150123456780\"}\r\n";
+
+        let report = ingestor.ingest("fr", Some("application/x-ndjson"), false, body).await.unwrap();
+
+        assert_eq!((report.accepted, report.rejected), (2, 1));
+        assert_eq!(report.results[1].error_class, Some("non_utf8"));
+        assert_eq!(rx.recv().await.unwrap().country, "FR");
+        assert_eq!(rx.recv().await.unwrap().source_topic, "cdr.raw.FR");
+    }
+
+    #[tokio::test]
+    async fn test_ingest_gzip_single_record() {
+        let (ingestor, mut rx) = ingestor(10);
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(br#"{"imsi":"605011234567890"}"#).unwrap();
+
+        let report = ingestor
+            .ingest("TN", Some("application/json"), true, &gzip.finish().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(report.accepted, 1);
+        assert!(rx.recv().await.unwrap().raw_payload.contains("605011234567890"));
+    }
+
+    #[tokio::test]
+    async fn test_ingest_limits_and_backpressure() {
+        let (ingestor, _rx) = ingestor(1);
+        let batch = b"{}\n{}\n{}\n{}\n";
+        assert_eq!(
+            ingestor.ingest("FR", Some("application/x-ndjson"), false, batch).await.unwrap_err(),
+            IngestError::TooManyRecords { found: 4, max: 3 }
+        );
+        assert_eq!(
+            ingestor.ingest("FRANCE", None, false, b"{}").await.unwrap_err(),
+            IngestError::InvalidCountry("FRANCE".to_string())
+        );
+        assert_eq!(
+            ingestor.ingest("de", None, false, b"{}").await.unwrap_err(),
+            IngestError::UnknownCountry("DE".to_string())
+        );
+
+        // Queue of one: a batch of two is refused as a whole
+        let batch = b"{\"imsi\":\"1\"}\n{\"imsi\":\"2\"}\n";
+        assert_eq!(
+            ingestor.ingest("FR", Some("application/x-ndjson"), false, batch).await.unwrap_err(),
+            IngestError::Busy
+        );
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&[b' '; 2048]).unwrap();
+        assert_eq!(
+            ingestor.ingest("FR", None, true, &gzip.finish().unwrap()).await.unwrap_err(),
+            IngestError::TooLarge(1024)
+        );
+    }
+}
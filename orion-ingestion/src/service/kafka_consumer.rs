@@ -1,31 +1,44 @@
 use crate::config::KafkaConfig;
-use crate::service::{MessageProcessor, ProcessedCDR};
+use crate::service::dead_letter::header_value;
+use crate::service::model::KafkaOrigin;
+use crate::service::worker_pool::WorkerPool;
+use crate::service::{DeadLetter, KafkaProducerService, MessageProcessor, ProcessedCDR};
+use orion_shutdown::Shutdown;
 use rdkafka::config::ClientConfig;
 use rdkafka::consumer::{Consumer, StreamConsumer};
-use rdkafka::message::Message;
+use rdkafka::message::{Message, OwnedMessage};
 use rdkafka::error::KafkaError;
-use tokio::sync::mpsc;
-use tracing::{info, error, warn};
+use rdkafka::producer::DeliveryFuture;
+use std::collections::{HashSet, VecDeque};
+use tokio::sync::mpsc::{self, error::TryRecvError};
+use tracing::{debug, info, error, warn};
+use std::sync::Arc;
 use std::time::Duration;
 
+/// Source offset of a message whose CDR (or dead letter) has been
+/// published. Offsets are only committed once acknowledged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceOffset {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+}
+
 pub struct KafkaConsumerService {
-    consumer: StreamConsumer,
-    processor: MessageProcessor,
-    output_tx: mpsc::Sender<ProcessedCDR>,
+    consumer: Arc<StreamConsumer>,
+    pool: WorkerPool<OwnedMessage>,
 }
 
 impl KafkaConsumerService {
-    pub fn new(
-        config: &KafkaConfig,
-        output_tx: mpsc::Sender<ProcessedCDR>,
-    ) -> Result<Self, KafkaError> {
+    pub fn new(config: &KafkaConfig, pool: WorkerPool<OwnedMessage>) -> Result<Self, KafkaError> {
         info!("Initializing Kafka consumer with brokers: {}", config.brokers);
         info!("Topics: {:?}", config.topics);
 
         let consumer: StreamConsumer = ClientConfig::new()
             .set("bootstrap.servers", &config.brokers)
             .set("group.id", &config.group_id)
-            .set("enable.auto.commit", "true")
+            .set("enable.auto.commit", "false")
+            .set("enable.auto.offset.store", "false")
             .set("auto.offset.reset", &config.auto_offset_reset)
             .set("session.timeout.ms", "6000")
             .set("enable.partition.eof", "false")
@@ -38,41 +51,40 @@ impl KafkaConsumerService {
         info!("Successfully subscribed to topics: {:?}", topics);
 
         Ok(Self {
-            consumer,
-            processor: MessageProcessor::new(),
-            output_tx,
+            consumer: Arc::new(consumer),
+            pool,
         })
     }
 
+    /// Shared handle used by the offset committer
+    pub fn consumer(&self) -> Arc<StreamConsumer> {
+        Arc::clone(&self.consumer)
+    }
+
     /// Run the consumer loop
-    /// This is the main processing loop that consumes messages from Kafka
-    pub async fn run(&self) -> anyhow::Result<()> {
-        info!("Starting Kafka consumer loop...");
+    /// Messages are handed to the worker owning their partition; polling
+    /// pauses while that worker's queue is full. On shutdown, polling stops
+    /// and the workers drain their queues before this returns.
+    pub async fn run(self, mut shutdown: Shutdown) -> anyhow::Result<()> {
+        info!("Starting Kafka consumer loop ({} workers)...", self.pool.len());
 
         loop {
-            match self.consumer.recv().await {
+            let received = tokio::select! {
+                biased;
+                _ = shutdown.requested() => break,
+                received = self.consumer.recv() => received,
+            };
+            match received {
                 Ok(message) => {
-                    let topic = message.topic();
-                    let partition = message.partition();
-                    let offset = message.offset();
-
-                    info!(
+                    debug!(
                         "Received message from topic: {}, partition: {}, offset: {}",
-                        topic, partition, offset
+                        message.topic(), message.partition(), message.offset()
                     );
 
-                    if let Some(payload) = message.payload() {
-                        // Process the message
-                        if let Some(processed) = self.processor.process(payload, topic).await {
-                            // Send to output channel (for next service: validation)
-                            if let Err(e) = self.output_tx.send(processed).await {
-                                error!("Failed to send processed CDR to output channel: {:?}", e);
-                            }
-                        } else {
-                            warn!("Failed to process message from topic: {}", topic);
-                        }
-                    } else {
-                        warn!("Received message with no payload");
+                    let message = message.detach();
+                    let (topic, partition) = (message.topic().to_string(), message.partition());
+                    if self.pool.dispatch(&topic, partition, message).await.is_err() {
+                        anyhow::bail!("Worker of {}/{} has stopped", topic, partition);
                     }
                 }
                 Err(e) => {
@@ -82,5 +94,186 @@ impl KafkaConsumerService {
                 }
             }
         }
+
+        info!("Kafka consumer stopped polling, draining workers...");
+        self.pool.close().await;
+        Ok(())
+    }
+}
+
+/// A CDR handed to the producer, waiting for its acknowledgement
+struct Pending {
+    source: SourceOffset,
+    cdr: ProcessedCDR,
+    delivery: DeliveryFuture,
+}
+
+/// Processes the messages of the partitions assigned to one worker.
+///
+/// Up to `max_pending` CDRs are published without waiting for each
+/// acknowledgement. Deliveries are settled oldest first and their offsets
+/// acknowledged in that order, so offsets of a partition are always
+/// committed in sequence. After a failed delivery, the CDRs of its source
+/// partition are published again in order.
+pub struct KafkaWorker {
+    pub processor: Arc<MessageProcessor>,
+    pub producer: Arc<KafkaProducerService>,
+    pub offsets: mpsc::Sender<SourceOffset>,
+    pub max_pending: usize,
+    pub trace_id_header: String,
+    pub producer_id_header: String,
+}
+
+impl KafkaWorker {
+    pub async fn run(self, mut queue: mpsc::Receiver<OwnedMessage>) {
+        let mut pending = VecDeque::new();
+        loop {
+            let message = match queue.try_recv() {
+                Ok(message) => message,
+                Err(TryRecvError::Empty) => {
+                    // Idle: settle what is in flight before waiting
+                    self.settle(&mut pending, 0).await;
+                    match queue.recv().await {
+                        Some(message) => message,
+                        None => break,
+                    }
+                }
+                Err(TryRecvError::Disconnected) => break,
+            };
+            self.handle(message, &mut pending).await;
+        }
+        self.settle(&mut pending, 0).await;
+    }
+
+    async fn handle(&self, message: OwnedMessage, pending: &mut VecDeque<Pending>) {
+        let source = SourceOffset {
+            topic: message.topic().to_string(),
+            partition: message.partition(),
+            offset: message.offset(),
+        };
+
+        match self.process(&message).await {
+            Ok(cdr) => {
+                if pending.len() >= self.max_pending {
+                    self.settle(pending, self.max_pending.saturating_sub(1)).await;
+                }
+                match self.producer.enqueue(&cdr) {
+                    Ok(Some(delivery)) => pending.push_back(Pending { source, cdr, delivery }),
+                    // Producer queue full or enqueue error: publish synchronously
+                    // once everything before it is settled
+                    other => {
+                        if let Err(e) = other {
+                            warn!("Failed to enqueue CDR from {}: {}", source.topic, e);
+                        }
+                        self.settle(pending, 0).await;
+                        self.publish(&cdr).await;
+                        self.acknowledge(source).await;
+                    }
+                }
+            }
+            Err(letter) => {
+                // Dead letters are rare: settle first to keep offsets in order
+                self.settle(pending, 0).await;
+                while let Err(e) = self
+                    .producer
+                    .send_dead_letter(&letter, &source.topic, source.partition, source.offset)
+                    .await
+                {
+                    error!("Failed to publish dead letter from {}, retrying: {}", source.topic, e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+                self.acknowledge(source).await;
+            }
+        }
+    }
+
+    async fn process(&self, message: &OwnedMessage) -> Result<ProcessedCDR, DeadLetter> {
+        let topic = message.topic();
+        let payload = message.payload().unwrap_or_default();
+        let header = |name: &str| message.headers().and_then(|headers| header_value(headers, name));
+
+        let country = header(self.processor.country_header());
+        match self.processor.process(payload, topic, country.as_deref()).await {
+            Ok(cdr) => Ok(cdr.with_origin(KafkaOrigin {
+                partition: message.partition(),
+                offset: message.offset(),
+                key: message.key().map(|key| String::from_utf8_lossy(key).into_owned()),
+                timestamp: message
+                    .timestamp()
+                    .to_millis()
+                    .and_then(chrono::DateTime::from_timestamp_millis)
+                    .map(|timestamp| timestamp.to_rfc3339()),
+                trace_id: header(&self.trace_id_header),
+                producer_id: header(&self.producer_id_header),
+            })),
+            Err(error) => {
+                warn!("Failed to process message from topic {}: {}", topic, error);
+                Err(DeadLetter {
+                    key: message.key().map(|key| key.to_vec()),
+                    original_headers: message.headers().map(DeadLetter::copy_headers).unwrap_or_default(),
+                    payload: payload.to_vec(),
+                    error,
+                })
+            }
+        }
+    }
+
+    /// Wait for pending deliveries, oldest first, until at most `keep`
+    /// remain, and acknowledge their offsets
+    async fn settle(&self, pending: &mut VecDeque<Pending>, keep: usize) {
+        while pending.len() > keep {
+            let Some(Pending { source, cdr, delivery }) = pending.pop_front() else { break };
+            if self.producer.confirm(delivery).await {
+                self.acknowledge(source).await;
+            } else {
+                self.republish(source, cdr, pending).await;
+            }
+        }
+    }
+
+    /// Publish again a CDR whose delivery failed. The CDRs enqueued behind
+    /// it may already be published: every pending delivery is settled
+    /// first, then the CDRs of a source partition from its first failure on
+    /// are published again, delivered or not, so that the last copies keep
+    /// the order of the partition.
+    async fn republish(&self, source: SourceOffset, cdr: ProcessedCDR, pending: &mut VecDeque<Pending>) {
+        warn!(
+            "Delivery of CDR from {}/{}/{} failed, publishing its partition again from there",
+            source.topic, source.partition, source.offset
+        );
+        let mut settled = vec![(source, cdr, false)];
+        while let Some(entry) = pending.pop_front() {
+            let delivered = self.producer.confirm(entry.delivery).await;
+            settled.push((entry.source, entry.cdr, delivered));
+        }
+
+        let mut broken = HashSet::new();
+        for (source, cdr, delivered) in settled {
+            let partition = (source.topic.clone(), source.partition);
+            if !delivered {
+                broken.insert(partition.clone());
+            }
+            if broken.contains(&partition) {
+                self.publish(&cdr).await;
+            }
+            self.acknowledge(source).await;
+        }
+    }
+
+    /// Keep retrying: an offset must not be committed before its CDR is published
+    async fn publish(&self, cdr: &ProcessedCDR) {
+        while let Err(e) = self.producer.send(cdr).await {
+            error!(
+                "Failed to publish CDR from {} (country: {}), retrying: {}",
+                cdr.source_topic, cdr.country, e
+            );
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    async fn acknowledge(&self, source: SourceOffset) {
+        if let Err(e) = self.offsets.send(source).await {
+            error!("Failed to acknowledge offset: {:?}", e);
+        }
     }
 }
@@ -0,0 +1,217 @@
+use crate::config::KafkaConfig;
+use crate::metrics;
+use crate::service::dead_letter::{
+    DeadLetter, HEADER_SOURCE_OFFSET, HEADER_SOURCE_PARTITION, HEADER_SOURCE_TOPIC,
+};
+use crate::service::processor::ProcessError;
+use crate::service::ProcessedCDR;
+use rdkafka::config::ClientConfig;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{DeliveryFuture, FutureProducer, FutureRecord, Producer};
+use rdkafka::util::Timeout;
+use std::time::Duration;
+use tracing::{debug, error, warn};
+
+pub struct KafkaProducerService {
+    producer: FutureProducer,
+    output_topic: String,
+    dlt_topic: String,
+    quarantine_topic: String,
+    max_retries: u32,
+    timeout: Duration,
+    trace_id_header: String,
+    producer_id_header: String,
+}
+
+impl KafkaProducerService {
+    pub fn new(config: &KafkaConfig) -> anyhow::Result<Self> {
+        // Idempotent producer: acks from all replicas, bounded in-flight
+        // requests so retries cannot reorder records of a subscriber
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("acks", "all")
+            .set("enable.idempotence", "true")
+            .set(
+                "max.in.flight.requests.per.connection",
+                config.producer_max_in_flight.to_string(),
+            )
+            .set("message.timeout.ms", config.producer_timeout_ms.to_string())
+            .set("linger.ms", config.producer_linger_ms.to_string())
+            .create()?;
+
+        Ok(Self {
+            producer,
+            output_topic: config.output_topic.clone(),
+            dlt_topic: config.dlt_topic.clone(),
+            quarantine_topic: config.quarantine_topic.clone(),
+            max_retries: config.producer_max_retries,
+            timeout: Duration::from_millis(config.producer_timeout_ms),
+            trace_id_header: config.trace_id_header.clone(),
+            producer_id_header: config.producer_id_header.clone(),
+        })
+    }
+
+    /// Headers of an output record: source coordinates, and the trace and
+    /// producer ids of the source message so downstream stages keep them
+    fn output_headers(&self, cdr: &ProcessedCDR) -> OwnedHeaders {
+        let mut headers = OwnedHeaders::new()
+            .insert(Header { key: HEADER_SOURCE_TOPIC, value: Some(&cdr.source_topic) });
+        if let Some(ref origin) = cdr.origin {
+            headers = headers
+                .insert(Header { key: HEADER_SOURCE_PARTITION, value: Some(&origin.partition.to_string()) })
+                .insert(Header { key: HEADER_SOURCE_OFFSET, value: Some(&origin.offset.to_string()) });
+            if let Some(ref trace_id) = origin.trace_id {
+                headers = headers.insert(Header { key: &self.trace_id_header, value: Some(trace_id) });
+            }
+            if let Some(ref producer_id) = origin.producer_id {
+                headers = headers.insert(Header { key: &self.producer_id_header, value: Some(producer_id) });
+            }
+        }
+        headers
+    }
+
+    /// Publish a processed CDR and wait for the broker acknowledgement.
+    /// Records are keyed by subscriber so that all CDRs of one IMSI land
+    /// on the same partition.
+    pub async fn send(&self, cdr: &ProcessedCDR) -> anyhow::Result<()> {
+        let payload = orion_model::to_json(cdr)?;
+        let key = cdr.subscriber_key();
+        let headers = self.output_headers(cdr);
+
+        let mut attempts = 0;
+        loop {
+            let mut record = FutureRecord::to(&self.output_topic)
+                .payload(&payload)
+                .headers(headers.clone());
+            if let Some(ref key) = key {
+                record = record.key(key);
+            }
+
+            match self.producer.send(record, Timeout::After(self.timeout)).await {
+                Ok((partition, offset)) => {
+                    debug!(
+                        "Published CDR from {} to {} (partition: {}, offset: {})",
+                        cdr.source_topic, self.output_topic, partition, offset
+                    );
+                    metrics::record_published();
+                    return Ok(());
+                }
+                Err((e, _)) => {
+                    attempts += 1;
+                    if attempts > self.max_retries {
+                        error!("Failed to publish CDR after {} attempts: {:?}", attempts, e);
+                        metrics::record_publish_error();
+                        return Err(anyhow::anyhow!("Kafka send error: {:?}", e));
+                    }
+                    metrics::record_publish_retry();
+                    warn!("Retry {}/{} after publish error: {:?}", attempts, self.max_retries, e);
+                    tokio::time::sleep(Duration::from_millis(100 * 2_u64.pow(attempts))).await;
+                }
+            }
+        }
+    }
+
+    /// Hand a CDR to the producer without waiting for its acknowledgement.
+    /// Returns `None` when the producer queue is full.
+    pub fn enqueue(&self, cdr: &ProcessedCDR) -> anyhow::Result<Option<DeliveryFuture>> {
+        let payload = orion_model::to_json(cdr)?;
+        let key = cdr.subscriber_key();
+        let mut record = FutureRecord::to(&self.output_topic)
+            .payload(&payload)
+            .headers(self.output_headers(cdr));
+        if let Some(ref key) = key {
+            record = record.key(key);
+        }
+
+        match self.producer.send_result(record) {
+            Ok(delivery) => Ok(Some(delivery)),
+            Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), _)) => Ok(None),
+            Err((e, _)) => Err(anyhow::anyhow!("Kafka send error: {:?}", e)),
+        }
+    }
+
+    /// Wait for the acknowledgement of an enqueued CDR. A failed delivery
+    /// has to be sent again with `send`.
+    pub async fn confirm(&self, delivery: DeliveryFuture) -> bool {
+        match delivery.await {
+            Ok(Ok(_)) => {
+                metrics::record_published();
+                true
+            }
+            Ok(Err((e, _))) => {
+                warn!("Delivery of enqueued CDR failed: {:?}", e);
+                metrics::record_publish_retry();
+                false
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Send an unprocessable message to the dead-letter topic, keeping its
+    /// original key, headers and bytes. Messages from unrouted topics go to the
+    /// quarantine topic instead.
+    pub async fn send_dead_letter(
+        &self,
+        letter: &DeadLetter,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+    ) -> anyhow::Result<()> {
+        let target = match letter.error {
+            ProcessError::UnknownTopic(_) => &self.quarantine_topic,
+            _ => &self.dlt_topic,
+        };
+        let headers = letter.headers(topic, partition, offset, chrono::Utc::now());
+        let mut record = FutureRecord::to(target)
+            .payload(&letter.payload)
+            .headers(headers);
+        if let Some(ref key) = letter.key {
+            record = record.key(key);
+        }
+
+        match self.producer.send(record, Timeout::After(self.timeout)).await {
+            Ok(_) => {
+                warn!(
+                    "Dead-lettered message {}/{}/{} ({})",
+                    topic, partition, offset, letter.error
+                );
+                metrics::record_dead_letter(letter.error.class());
+                Ok(())
+            }
+            Err((e, _)) => Err(anyhow::anyhow!("Kafka send error: {:?}", e)),
+        }
+    }
+
+    /// Re-publish a dead-lettered message to its source topic, with its
+    /// original headers
+    pub async fn replay(
+        &self,
+        topic: &str,
+        key: Option<&[u8]>,
+        headers: Option<OwnedHeaders>,
+        payload: &[u8],
+    ) -> anyhow::Result<()> {
+        let mut record = FutureRecord::to(topic).payload(payload);
+        if let Some(key) = key {
+            record = record.key(key);
+        }
+        if let Some(headers) = headers {
+            record = record.headers(headers);
+        }
+
+        self.producer
+            .send(record, Timeout::After(self.timeout))
+            .await
+            .map_err(|(e, _)| anyhow::anyhow!("Kafka send error: {:?}", e))?;
+        metrics::record_dead_letter_replayed();
+        Ok(())
+    }
+
+    /// Wait for every queued message to be delivered, before shutdown
+    pub fn flush(&self, timeout: Duration) -> anyhow::Result<()> {
+        self.producer
+            .flush(Timeout::After(timeout))
+            .map_err(|e| anyhow::anyhow!("Kafka flush error: {:?}", e))
+    }
+}
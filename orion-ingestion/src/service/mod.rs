@@ -1,7 +1,25 @@
+mod asn1_decoder;
+mod ber;
+mod compression;
+mod dead_letter;
+mod delimited;
+mod file_checkpoint;
+mod file_source;
+mod http_ingest;
 mod kafka_consumer;
+mod kafka_producer;
 mod processor;
+mod routing;
 mod model;
+mod worker_pool;
 
-pub use kafka_consumer::KafkaConsumerService;
+pub use dead_letter::{DeadLetter, DeadLetterReplayer};
+pub use delimited::DelimitedFormats;
+pub use file_source::FileSource;
+pub use http_ingest::{HttpIngestor, IngestError};
+pub use kafka_consumer::{KafkaConsumerService, KafkaWorker, SourceOffset};
+pub use kafka_producer::KafkaProducerService;
 pub use processor::MessageProcessor;
+pub use routing::RoutingTable;
 pub use model::ProcessedCDR;
+pub use worker_pool::WorkerPool;
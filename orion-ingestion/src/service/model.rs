@@ -1,66 +1,85 @@
+use crate::service::asn1_decoder::Asn1RecordType;
 use serde::{Deserialize, Serialize};
 
+pub use orion_model::{KafkaOrigin, ProcessedCDR};
+
 /// Raw CDR from Kafka - can be in various formats
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum RawCDR {
     Json(serde_json::Value),
+    /// 3GPP TS 32.298 record decoded from BER into the JSON field names
+    /// expected downstream
+    Asn1 {
+        record_type: Asn1RecordType,
+        fields: serde_json::Value,
+    },
     Text(String),
 }
 
-/// Processed CDR ready for validation
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProcessedCDR {
-    pub raw_payload: String,
-    pub source_topic: String,
-    pub country: String,
-    pub ingestion_timestamp: String,
-}
+impl RawCDR {
+    /// String field of a structured payload
+    pub fn field(&self, name: &str) -> Option<&str> {
+        match self {
+            RawCDR::Json(value) | RawCDR::Asn1 { fields: value, .. } => {
+                value.get(name).and_then(|v| v.as_str())
+            }
+            RawCDR::Text(_) => None,
+        }
+    }
 
-impl ProcessedCDR {
-    pub fn new(raw: RawCDR, topic: &str) -> Self {
-        let country = extract_country_from_topic(topic);
-        let raw_payload = match raw {
+    /// Payload forwarded downstream: the fields of a structured record as
+    /// JSON, text as is
+    pub fn into_payload(self) -> String {
+        match self {
             RawCDR::Json(val) => serde_json::to_string(&val).unwrap_or_default(),
+            RawCDR::Asn1 { fields, .. } => serde_json::to_string(&fields).unwrap_or_default(),
             RawCDR::Text(text) => text,
-        };
-
-        Self {
-            raw_payload,
-            source_topic: topic.to_string(),
-            country,
-            ingestion_timestamp: chrono::Utc::now().to_rfc3339(),
         }
     }
 }
 
-fn extract_country_from_topic(topic: &str) -> String {
-    // Topic format: cdr.raw.FR -> FR
-    topic
-        .split('.')
-        .last()
-        .unwrap_or("UNKNOWN")
-        .to_uppercase()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_extract_country() {
-        assert_eq!(extract_country_from_topic("cdr.raw.FR"), "FR");
-        assert_eq!(extract_country_from_topic("cdr.raw.FN"), "FN");
-        assert_eq!(extract_country_from_topic("cdr.raw.MA"), "MA");
-    }
-
     #[test]
     fn test_processed_cdr_creation() {
         let raw = RawCDR::Text("test".to_string());
-        let processed = ProcessedCDR::new(raw, "cdr.raw.FR");
+        let processed = ProcessedCDR::new(raw.into_payload(), "cdr.raw.FR", "FR", None);
         
         assert_eq!(processed.country, "FR");
         assert_eq!(processed.source_topic, "cdr.raw.FR");
         assert_eq!(processed.raw_payload, "test");
     }
+
+    #[test]
+    fn test_processed_cdr_from_asn1() {
+        let raw = RawCDR::Asn1 {
+            record_type: Asn1RecordType::Pgw,
+            fields: serde_json::json!({"record_type": "PGWRecord", "imsi": "244911234567890"}),
+        };
+        let processed = ProcessedCDR::new(raw.into_payload(), "cdr.raw.FN", "FN", None);
+
+        assert!(processed.raw_payload.contains("PGWRecord"));
+        assert_eq!(processed.subscriber_key(), Some("244911234567890".to_string()));
+    }
+
+    #[test]
+    fn test_subscriber_key() {
+        let raw = RawCDR::Json(serde_json::json!({"imsi": "208150123456789", "msisdn": "+33612345678"}));
+        assert_eq!(
+            ProcessedCDR::new(raw.into_payload(), "cdr.raw.FR", "FR", None).subscriber_key(),
+            Some("208150123456789".to_string())
+        );
+
+        let raw = RawCDR::Json(serde_json::json!({"msisdn": "+33612345678"}));
+        assert_eq!(
+            ProcessedCDR::new(raw.into_payload(), "cdr.raw.FR", "FR", None).subscriber_key(),
+            Some("+33612345678".to_string())
+        );
+
+        let raw = RawCDR::Text("208150123456789;+33612345678;data".to_string());
+        assert_eq!(ProcessedCDR::new(raw.into_payload(), "cdr.raw.TN", "TN", None).subscriber_key(), None);
+    }
 }
@@ -1,58 +1,225 @@
+use crate::service::asn1_decoder;
+use crate::service::compression;
+use crate::service::delimited::{DelimitedFormat, DelimitedFormats};
+use crate::service::routing::{PayloadFormat, Route, RoutingTable};
 use crate::service::model::{RawCDR, ProcessedCDR};
 use crate::metrics;
-use tracing::{info, error};
+use tracing::{debug, error};
 use std::time::Instant;
 
-pub struct MessageProcessor;
+/// Reason a raw message could not be processed.
+/// Such messages are sent to the dead-letter topic.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProcessError {
+    #[error("empty payload")]
+    EmptyPayload,
+    #[error("payload of {size} bytes exceeds the {max} bytes limit")]
+    Oversized { size: usize, max: usize },
+    #[error("payload is not valid UTF-8: {0}")]
+    NonUtf8(String),
+    #[error("ASN.1 record could not be decoded: {0}")]
+    Asn1(String),
+    #[error("delimited record could not be parsed: {0}")]
+    Delimited(String),
+    #[error("payload could not be decompressed: {0}")]
+    Decompression(String),
+    #[error("no route for topic {0}")]
+    UnknownTopic(String),
+    #[error("expected a {expected} payload, found {found}")]
+    UnexpectedFormat { expected: PayloadFormat, found: PayloadFormat },
+}
+
+impl ProcessError {
+    /// Stable error class, used in dead-letter headers and metric labels
+    pub fn class(&self) -> &'static str {
+        match self {
+            ProcessError::EmptyPayload => "empty_payload",
+            ProcessError::Oversized { .. } => "oversized",
+            ProcessError::NonUtf8(_) => "non_utf8",
+            ProcessError::Asn1(_) => "asn1_decode",
+            ProcessError::Delimited(_) => "delimited_parse",
+            ProcessError::Decompression(_) => "decompression",
+            ProcessError::UnknownTopic(_) => "unknown_topic",
+            ProcessError::UnexpectedFormat { .. } => "unexpected_format",
+        }
+    }
+}
+
+pub struct MessageProcessor {
+    max_message_bytes: usize,
+    formats: DelimitedFormats,
+    routing: RoutingTable,
+}
 
 impl MessageProcessor {
-    pub fn new() -> Self {
-        Self
+    pub fn new(max_message_bytes: usize, formats: DelimitedFormats, routing: RoutingTable) -> Self {
+        Self { max_message_bytes, formats, routing }
+    }
+
+    /// Route of a topic, `None` when the topic is unknown
+    pub fn route(&self, topic: &str) -> Option<Route> {
+        self.routing.resolve(topic)
+    }
+
+    /// Kafka header that may override the country of the route
+    pub fn country_header(&self) -> &str {
+        &self.routing.country_header
+    }
+
+    /// Delimited format configured for a topic, if any
+    pub fn delimited_format(&self, topic: &str) -> Option<&DelimitedFormat> {
+        self.formats.for_topic(topic)
     }
 
     /// Process a raw message from Kafka
-    /// Returns ProcessedCDR or None if parsing fails
-    pub async fn process(&self, payload: &[u8], topic: &str) -> Option<ProcessedCDR> {
+    /// Returns ProcessedCDR or the reason the message must be dead-lettered.
+    /// `country_override` comes from the country header, if any.
+    pub async fn process(
+        &self,
+        payload: &[u8],
+        topic: &str,
+        country_override: Option<&str>,
+    ) -> Result<ProcessedCDR, ProcessError> {
+        let route = self.routing.resolve(topic);
+        self.process_inner(payload, topic, route, country_override).await
+    }
+
+    /// Process a record whose route is known by the caller
+    pub async fn process_with_route(
+        &self,
+        payload: &[u8],
+        topic: &str,
+        route: Route,
+    ) -> Result<ProcessedCDR, ProcessError> {
+        self.process_inner(payload, topic, Some(route), None).await
+    }
+
+    async fn process_inner(
+        &self,
+        payload: &[u8],
+        topic: &str,
+        route: Option<Route>,
+        country_override: Option<&str>,
+    ) -> Result<ProcessedCDR, ProcessError> {
         let start = Instant::now();
         
         // Record bytes ingested
         metrics::record_bytes(payload.len() as u64);
+
+        if payload.is_empty() {
+            metrics::record_error();
+            return Err(ProcessError::EmptyPayload);
+        }
+
+        if payload.len() > self.max_message_bytes {
+            error!(
+                "Message from {} exceeds size limit ({} > {} bytes)",
+                topic, payload.len(), self.max_message_bytes
+            );
+            metrics::record_error();
+            return Err(ProcessError::Oversized {
+                size: payload.len(),
+                max: self.max_message_bytes,
+            });
+        }
+
+        // Values compressed by the producer, inflated within the same limit
+        let decompressed;
+        let payload = match compression::detect(payload) {
+            Some(codec) => {
+                decompressed = compression::decompress(codec, payload, Some(self.max_message_bytes))
+                    .map_err(|e| {
+                        error!("Failed to decompress message from {}: {}", topic, e);
+                        metrics::record_error();
+                        ProcessError::Decompression(e.to_string())
+                    })?;
+                metrics::record_decompressed(codec.as_str());
+                decompressed.as_slice()
+            }
+            None => payload,
+        };
+
+        let Some(route) = route else {
+            error!("No route for topic {}, quarantining message", topic);
+            metrics::record_error();
+            return Err(ProcessError::UnknownTopic(topic.to_string()));
+        };
         
         // Try to parse as JSON first
-        let raw_cdr = match serde_json::from_slice::<serde_json::Value>(payload) {
+        let (raw_cdr, format) = match serde_json::from_slice::<serde_json::Value>(payload) {
             Ok(json) => {
-                info!("Parsed JSON CDR from topic: {}", topic);
-                RawCDR::Json(json)
+                debug!("Parsed JSON CDR from topic: {}", topic);
+                (RawCDR::Json(json), PayloadFormat::Json)
+            }
+            // Binary 3GPP record (TS 32.298)
+            Err(_) if asn1_decoder::looks_like_ber(payload) => {
+                match asn1_decoder::decode(payload) {
+                    Ok((record_type, fields)) => {
+                        debug!("Decoded {} from topic: {}", record_type.as_str(), topic);
+                        (RawCDR::Asn1 { record_type, fields }, PayloadFormat::Asn1)
+                    }
+                    Err(e) => {
+                        error!("Failed to decode ASN.1 CDR from {}: {}", topic, e);
+                        metrics::record_error();
+                        return Err(ProcessError::Asn1(e.to_string()));
+                    }
+                }
             }
             Err(_) => {
                 // Fallback to text
                 match String::from_utf8(payload.to_vec()) {
-                    Ok(text) => {
-                        info!("Parsed text CDR from topic: {}", topic);
-                        RawCDR::Text(text)
-                    }
+                    Ok(text) => match self.formats.for_topic(topic) {
+                        Some(format) => match format.parse(&text) {
+                            Ok(record) => {
+                                debug!("Parsed {} CDR from topic: {}", format.name, topic);
+                                (RawCDR::Json(record), PayloadFormat::Delimited)
+                            }
+                            Err(e) => {
+                                error!("Failed to parse {} CDR from {}: {}", format.name, topic, e);
+                                metrics::record_error();
+                                return Err(ProcessError::Delimited(e.to_string()));
+                            }
+                        },
+                        None => {
+                            debug!("Parsed text CDR from topic: {}", topic);
+                            (RawCDR::Text(text), PayloadFormat::Text)
+                        }
+                    },
                     Err(e) => {
                         error!("Failed to parse message as UTF-8: {:?}", e);
                         metrics::record_error();
-                        return None;
+                        return Err(ProcessError::NonUtf8(e.utf8_error().to_string()));
                     }
                 }
             }
         };
 
-        let processed = ProcessedCDR::new(raw_cdr, topic);
+        if let Some(expected) = route.format.filter(|expected| *expected != format) {
+            error!("Unexpected {} payload on {} (expected {})", format, topic, expected);
+            metrics::record_error();
+            return Err(ProcessError::UnexpectedFormat { expected, found: format });
+        }
+
+        // Country: header, then payload field, then route
+        let country = country_override
+            .or_else(|| raw_cdr.field(&self.routing.country_field))
+            .map(|country| country.trim().to_uppercase())
+            .filter(|country| !country.is_empty())
+            .unwrap_or(route.country);
+
+        let processed = ProcessedCDR::new(raw_cdr.into_payload(), topic, &country, route.source_system);
         
         // Record latency
         let duration = start.elapsed();
         metrics::record_latency(duration.as_secs_f64());
         metrics::record_message_consumed();
         
-        info!(
+        debug!(
             "Processed CDR from {} (country: {}) in {:?}",
             topic, processed.country, duration
         );
         
-        Some(processed)
+        Ok(processed)
     }
 }
 
@@ -62,12 +229,12 @@ mod tests {
 
     #[tokio::test]
     async fn test_process_json_message() {
-        let processor = MessageProcessor::new();
+        let processor = MessageProcessor::new(1024, DelimitedFormats::default(), RoutingTable::bundled());
         let json_payload = r#"{"imsi": "208150123456789", "msisdn": "+33612345678"}"#;
         
-        let result = processor.process(json_payload.as_bytes(), "cdr.raw.FR").await;
+        let result = processor.process(json_payload.as_bytes(), "cdr.raw.FR", None).await;
         
-        assert!(result.is_some());
+        assert!(result.is_ok());
         let processed = result.unwrap();
         assert_eq!(processed.country, "FR");
         assert!(processed.raw_payload.contains("imsi"));
@@ -75,23 +242,138 @@ mod tests {
 
     #[tokio::test]
     async fn test_process_text_message() {
-        let processor = MessageProcessor::new();
+        let processor = MessageProcessor::new(1024, DelimitedFormats::default(), RoutingTable::bundled());
         let text_payload = "208150123456789;+33612345678;data";
         
-        let result = processor.process(text_payload.as_bytes(), "cdr.raw.TN").await;
+        let result = processor.process(text_payload.as_bytes(), "cdr.raw.TN", None).await;
         
-        assert!(result.is_some());
+        assert!(result.is_ok());
         let processed = result.unwrap();
         assert_eq!(processed.country, "TN");
     }
 
+    #[tokio::test]
+    async fn test_process_delimited_message() {
+        let formats = DelimitedFormats::parse(include_str!("../../formats/delimited.json")).unwrap();
+        let processor = MessageProcessor::new(1024, formats, RoutingTable::bundled());
+
+        let processed = processor
+            .process(b"208150123456789;+33612345678;data", "cdr.raw.TN", None)
+            .await
+            .unwrap();
+        let fields: serde_json::Value = serde_json::from_str(&processed.raw_payload).unwrap();
+        assert_eq!(fields["imsi"], "208150123456789");
+        assert_eq!(fields["event_type"], "data");
+
+        let result = processor.process(b"208150123456789|+33612345678|voice|long", "cdr.raw.CH", None).await;
+        assert_eq!(result.unwrap_err().class(), "delimited_parse");
+    }
+
+    #[tokio::test]
+    async fn test_process_routing() {
+        let processor = MessageProcessor::new(1024, DelimitedFormats::default(), RoutingTable::bundled());
+        let payload = br#"{"imsi": "208150123456789"}"#;
+
+        let processed = processor.process(payload, "cdr.raw.fr.v2", None).await.unwrap();
+        assert_eq!(processed.country, "FR");
+
+        let processed = processor.process(payload, "cdr.raw.FR", Some("tn")).await.unwrap();
+        assert_eq!(processed.country, "TN");
+        assert_eq!(processed.source_system.as_deref(), Some("fr-mediation"));
+
+        let payload = br#"{"imsi": "208150123456789", "country": "CH"}"#;
+        let processed = processor.process(payload, "cdr.raw.FR", None).await.unwrap();
+        assert_eq!(processed.country, "CH");
+
+        let result = processor.process(payload, "cdr.raw.fr.beta", None).await;
+        assert_eq!(result.unwrap_err(), ProcessError::UnknownTopic("cdr.raw.fr.beta".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_process_unexpected_format() {
+        let routing = RoutingTable::parse(
+            r#"{"routes": [{"topic": "cdr.raw.FR", "country": "FR", "format": "asn1"}]}"#,
+        )
+        .unwrap();
+        let processor = MessageProcessor::new(1024, DelimitedFormats::default(), routing);
+
+        let result = processor.process(br#"{"imsi": "1"}"#, "cdr.raw.FR", None).await;
+
+        assert_eq!(
+            result.unwrap_err(),
+            ProcessError::UnexpectedFormat { expected: PayloadFormat::Asn1, found: PayloadFormat::Json }
+        );
+    }
+
     #[tokio::test]
     async fn test_process_invalid_message() {
-        let processor = MessageProcessor::new();
+        let processor = MessageProcessor::new(1024, DelimitedFormats::default(), RoutingTable::bundled());
         let invalid_payload = vec![0xFF, 0xFE, 0xFD]; // Invalid UTF-8
         
-        let result = processor.process(&invalid_payload, "cdr.raw.FN").await;
+        let result = processor.process(&invalid_payload, "cdr.raw.FN", None).await;
         
-        assert!(result.is_none());
+        assert_eq!(result.unwrap_err().class(), "non_utf8");
+    }
+
+    #[tokio::test]
+    async fn test_process_asn1_message() {
+        let processor = MessageProcessor::new(1024, DelimitedFormats::default(), RoutingTable::bundled());
+        let payload = include_bytes!("../../tests/fixtures/asn1/mo_call_record.ber");
+
+        let processed = processor.process(payload, "cdr.raw.FR", None).await.unwrap();
+
+        let fields: serde_json::Value = serde_json::from_str(&processed.raw_payload).unwrap();
+        assert_eq!(fields["record_type"], "MOCallRecord");
+        assert_eq!(fields["imsi"], "208150123456789");
+        assert_eq!(fields["event_type"], "voice");
+    }
+
+    #[tokio::test]
+    async fn test_process_corrupted_asn1_message() {
+        let processor = MessageProcessor::new(1024, DelimitedFormats::default(), RoutingTable::bundled());
+        let payload = include_bytes!("../../tests/fixtures/asn1/pgw_record.ber");
+
+        let result = processor.process(&payload[..40], "cdr.raw.FN", None).await;
+
+        assert_eq!(result.unwrap_err().class(), "asn1_decode");
+    }
+
+    #[tokio::test]
+    async fn test_process_oversized_message() {
+        let processor = MessageProcessor::new(16, DelimitedFormats::default(), RoutingTable::bundled());
+        let payload = r#"{"imsi": "208150123456789", "msisdn": "+33612345678"}"#;
+
+        let result = processor.process(payload.as_bytes(), "cdr.raw.FR", None).await;
+
+        assert_eq!(
+            result.unwrap_err(),
+            ProcessError::Oversized { size: payload.len(), max: 16 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_compressed_message() {
+        use std::io::Write;
+
+        let processor = MessageProcessor::new(64, DelimitedFormats::default(), RoutingTable::bundled());
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(br#"{"imsi": "208150123456789"}"#).unwrap();
+
+        let processed = processor.process(&gzip.finish().unwrap(), "cdr.raw.FR", None).await.unwrap();
+        assert!(processed.raw_payload.contains("208150123456789"));
+
+        // Inflated size counts against the message limit
+        let bomb = zstd::stream::encode_all(&[b' '; 1024][..], 3).unwrap();
+        let result = processor.process(&bomb, "cdr.raw.FR", None).await;
+        assert_eq!(result.unwrap_err().class(), "decompression");
+    }
+
+    #[tokio::test]
+    async fn test_process_empty_message() {
+        let processor = MessageProcessor::new(1024, DelimitedFormats::default(), RoutingTable::bundled());
+
+        let result = processor.process(&[], "cdr.raw.CH", None).await;
+
+        assert_eq!(result.unwrap_err(), ProcessError::EmptyPayload);
     }
 }
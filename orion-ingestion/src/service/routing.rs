@@ -0,0 +1,230 @@
+//! Topic routing table.
+//!
+//! Maps source topics to the country, source system and payload format
+//! they carry. Exact topic names take precedence over regex patterns,
+//! which are tried in file order. A pattern may capture the country in a
+//! named group `country` instead of declaring it.
+
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+const BUNDLED: &str = include_str!("../../config/routing.json");
+
+/// Payload format detected by the processor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadFormat {
+    Json,
+    Asn1,
+    Delimited,
+    Text,
+}
+
+impl fmt::Display for PayloadFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PayloadFormat::Json => "json",
+            PayloadFormat::Asn1 => "asn1",
+            PayloadFormat::Delimited => "delimited",
+            PayloadFormat::Text => "text",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Where a record comes from, once its topic has been resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub country: String,
+    pub source_system: Option<String>,
+    /// Expected payload format, any format when `None`
+    pub format: Option<PayloadFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RouteEntry {
+    #[serde(default)]
+    topic: Option<String>,
+    #[serde(default)]
+    pattern: Option<String>,
+    #[serde(default)]
+    country: Option<String>,
+    #[serde(default)]
+    source_system: Option<String>,
+    #[serde(default)]
+    format: Option<PayloadFormat>,
+}
+
+fn default_country_header() -> String {
+    "x-orion-country".to_string()
+}
+
+fn default_country_field() -> String {
+    "country".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+struct RoutingFile {
+    #[serde(default = "default_country_header")]
+    country_header: String,
+    #[serde(default = "default_country_field")]
+    country_field: String,
+    routes: Vec<RouteEntry>,
+}
+
+struct PatternRoute {
+    regex: Regex,
+    country: Option<String>,
+    source_system: Option<String>,
+    format: Option<PayloadFormat>,
+}
+
+pub struct RoutingTable {
+    exact: HashMap<String, Route>,
+    patterns: Vec<PatternRoute>,
+    /// Kafka header overriding the country of the route
+    pub country_header: String,
+    /// Payload field overriding the country of the route
+    pub country_field: String,
+}
+
+impl RoutingTable {
+    /// Load the routing table from a JSON file
+    pub fn from_json(path: &str) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::parse(&contents)
+    }
+
+    /// Table shipped with the service (`config/routing.json`)
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED).expect("bundled routing table is valid")
+    }
+
+    pub fn parse(contents: &str) -> anyhow::Result<Self> {
+        let file: RoutingFile = serde_json::from_str(contents)?;
+        let mut exact = HashMap::new();
+        let mut patterns = Vec::new();
+
+        for entry in file.routes {
+            let country = entry.country.map(|c| c.to_uppercase());
+            match (entry.topic, entry.pattern) {
+                (Some(topic), None) => {
+                    let country = country
+                        .ok_or_else(|| anyhow::anyhow!("Route for {} has no country", topic))?;
+                    let route = Route {
+                        country,
+                        source_system: entry.source_system,
+                        format: entry.format,
+                    };
+                    if exact.insert(topic.clone(), route).is_some() {
+                        anyhow::bail!("Topic {} is routed twice", topic);
+                    }
+                }
+                (None, Some(pattern)) => {
+                    let regex = Regex::new(&pattern)?;
+                    if country.is_none() && regex.capture_names().all(|n| n != Some("country")) {
+                        anyhow::bail!("Pattern {} has no country nor `country` group", pattern);
+                    }
+                    patterns.push(PatternRoute {
+                        regex,
+                        country,
+                        source_system: entry.source_system,
+                        format: entry.format,
+                    });
+                }
+                _ => anyhow::bail!("Each route needs exactly one of `topic` or `pattern`"),
+            }
+        }
+
+        Ok(Self {
+            exact,
+            patterns,
+            country_header: file.country_header,
+            country_field: file.country_field,
+        })
+    }
+
+    /// Route of a topic, `None` when the topic is unknown
+    pub fn resolve(&self, topic: &str) -> Option<Route> {
+        if let Some(route) = self.exact.get(topic) {
+            return Some(route.clone());
+        }
+
+        self.patterns.iter().find_map(|route| {
+            let captures = route.regex.captures(topic)?;
+            let country = match &route.country {
+                Some(country) => country.clone(),
+                None => captures.name("country")?.as_str().to_uppercase(),
+            };
+            Some(Route {
+                country,
+                source_system: route.source_system.clone(),
+                format: route.format,
+            })
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.exact.len() + self.patterns.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> RoutingTable {
+        RoutingTable::parse(
+            r#"{
+                "routes": [
+                    {"topic": "cdr.raw.FR", "country": "FR", "source_system": "fr-msc"},
+                    {"pattern": "^cdr\\.raw\\.fr\\.v\\d+$", "country": "fr", "format": "asn1"},
+                    {"pattern": "^cdr\\.raw\\.(?P<country>[a-zA-Z]{2})$", "source_system": "legacy"}
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_exact_route_wins() {
+        let route = table().resolve("cdr.raw.FR").unwrap();
+        assert_eq!(route.country, "FR");
+        assert_eq!(route.source_system.as_deref(), Some("fr-msc"));
+    }
+
+    #[test]
+    fn test_pattern_routes() {
+        let table = table();
+
+        let route = table.resolve("cdr.raw.fr.v2").unwrap();
+        assert_eq!(route.country, "FR");
+        assert_eq!(route.format, Some(PayloadFormat::Asn1));
+
+        // Country captured from the topic
+        let route = table.resolve("cdr.raw.tn").unwrap();
+        assert_eq!(route.country, "TN");
+        assert_eq!(route.source_system.as_deref(), Some("legacy"));
+
+        assert_eq!(table.resolve("cdr.raw.unknown.topic"), None);
+        assert_eq!(table.country_header, "x-orion-country");
+    }
+
+    #[test]
+    fn test_invalid_tables() {
+        assert!(RoutingTable::parse(r#"{"routes": [{"topic": "a"}]}"#).is_err());
+        assert!(RoutingTable::parse(r#"{"routes": [{"pattern": "^a$"}]}"#).is_err());
+        assert!(RoutingTable::parse(r#"{"routes": [{"topic": "a", "pattern": "b", "country": "FR"}]}"#).is_err());
+    }
+
+    #[test]
+    fn test_bundled_table() {
+        let table = RoutingTable::bundled();
+        for country in ["FR", "TN", "FN", "CH"] {
+            assert_eq!(table.resolve(&format!("cdr.raw.{}", country)).unwrap().country, country);
+        }
+        assert_eq!(table.resolve("cdr.raw.fr.v2").unwrap().country, "FR");
+    }
+}
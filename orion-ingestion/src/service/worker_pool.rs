@@ -0,0 +1,141 @@
+//! Partition-aware worker pool.
+//!
+//! Each worker owns a bounded queue. Items are routed by source partition,
+//! so all messages of a partition are handled by the same worker in the
+//! order they were dispatched, while different partitions progress
+//! concurrently. A full queue blocks the dispatcher, which in turn stops
+//! polling the broker.
+
+use crate::metrics;
+use std::collections::hash_map::DefaultHasher;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::task::JoinSet;
+
+pub struct WorkerPool<T> {
+    queues: Vec<mpsc::Sender<T>>,
+    workers: JoinSet<()>,
+}
+
+impl<T: Send + 'static> WorkerPool<T> {
+    /// Spawn `workers` workers, each fed by a queue of `queue_size` items.
+    /// `worker` builds the task of a worker from its index and queue.
+    pub fn new<F, Fut>(workers: usize, queue_size: usize, mut worker: F) -> Self
+    where
+        F: FnMut(usize, mpsc::Receiver<T>) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut queues = Vec::new();
+        let mut tasks = JoinSet::new();
+        for index in 0..workers.max(1) {
+            let (tx, rx) = mpsc::channel(queue_size.max(1));
+            queues.push(tx);
+            tasks.spawn(worker(index, rx));
+        }
+        Self { queues, workers: tasks }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queues.len()
+    }
+
+    /// Worker owning a source partition
+    pub fn worker_for(&self, topic: &str, partition: i32) -> usize {
+        let mut hasher = DefaultHasher::new();
+        (topic, partition).hash(&mut hasher);
+        (hasher.finish() % self.queues.len() as u64) as usize
+    }
+
+    /// Queue an item on the worker owning its partition, waiting while that
+    /// worker's queue is full. Returns the item if the worker has stopped.
+    pub async fn dispatch(&self, topic: &str, partition: i32, item: T) -> Result<(), T> {
+        let queue = &self.queues[self.worker_for(topic, partition)];
+        match queue.try_send(item) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(item)) => {
+                metrics::record_worker_queue_full();
+                queue.send(item).await.map_err(|e| e.0)
+            }
+            Err(TrySendError::Closed(item)) => Err(item),
+        }
+    }
+
+    /// Close the queues and wait for the workers to drain them
+    pub async fn close(self) {
+        let Self { queues, mut workers } = self;
+        drop(queues);
+        while workers.join_next().await.is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+
+    #[tokio::test]
+    async fn test_partitions_are_pinned_to_workers() {
+        let pool: WorkerPool<()> = WorkerPool::new(4, 1, |_, _rx| async {});
+
+        let workers: HashSet<usize> = (0..64).map(|p| pool.worker_for("cdr.raw.FR", p)).collect();
+        assert_eq!(workers.len(), 4);
+        assert_eq!(pool.worker_for("cdr.raw.FR", 7), pool.worker_for("cdr.raw.FR", 7));
+    }
+
+    #[tokio::test]
+    async fn test_close_drains_queues() {
+        let (done_tx, mut done_rx) = mpsc::unbounded_channel();
+        let pool = WorkerPool::new(2, 100, |_, mut rx: mpsc::Receiver<u32>| {
+            let done_tx = done_tx.clone();
+            async move {
+                while let Some(item) = rx.recv().await {
+                    tokio::task::yield_now().await;
+                    done_tx.send(item).unwrap();
+                }
+            }
+        });
+        drop(done_tx);
+
+        for item in 0..20 {
+            pool.dispatch("cdr.raw.CH", item as i32 % 4, item).await.unwrap();
+        }
+        pool.close().await;
+
+        let mut done = Vec::new();
+        while let Some(item) = done_rx.recv().await {
+            done.push(item);
+        }
+        assert_eq!(done.len(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_order_is_kept_within_partition() {
+        let (done_tx, mut done_rx) = mpsc::unbounded_channel();
+        // Tiny queues: dispatch has to wait for the workers
+        let pool = WorkerPool::new(3, 2, |worker, mut rx: mpsc::Receiver<(i32, u32)>| {
+            let done_tx = done_tx.clone();
+            async move {
+                while let Some((partition, seq)) = rx.recv().await {
+                    tokio::task::yield_now().await;
+                    done_tx.send((worker, partition, seq)).unwrap();
+                }
+            }
+        });
+
+        for seq in 0..50 {
+            for partition in 0..8 {
+                pool.dispatch("cdr.raw.TN", partition, (partition, seq)).await.unwrap();
+            }
+        }
+
+        let mut last: HashMap<i32, (usize, u32)> = HashMap::new();
+        for _ in 0..400 {
+            let (worker, partition, seq) = done_rx.recv().await.unwrap();
+            if let Some((previous_worker, previous_seq)) = last.insert(partition, (worker, seq)) {
+                assert_eq!(worker, previous_worker);
+                assert_eq!(seq, previous_seq + 1);
+            }
+        }
+    }
+}
@@ -0,0 +1,261 @@
+"""Encode the BER fixtures with pyasn1.
+
+The types follow 3GPP TS 32.298 (implicit tags): the CallEventRecord CHOICE
+alternatives moCallRecord [0], mtCallRecord [1], moSMSRecord [6] and
+pGWRecord [79], with the fields used by the decoder. The MT record is
+CER-encoded (indefinite lengths) to cover both length forms. The values are
+synthetic: test IMSIs, numbers and addresses, no subscriber data.
+
+    pip install pyasn1
+    python3 generate_records.py
+"""
+
+from pyasn1.codec.ber import encoder as ber
+from pyasn1.codec.cer import encoder as cer
+from pyasn1.type import namedtype, tag, univ
+
+
+def ctx(number, constructed=False):
+    fmt = tag.tagFormatConstructed if constructed else tag.tagFormatSimple
+    return tag.Tag(tag.tagClassContext, fmt, number)
+
+
+def octets(number):
+    return univ.OctetString().subtype(implicitTag=ctx(number))
+
+
+def integer(number):
+    return univ.Integer().subtype(implicitTag=ctx(number))
+
+
+class LocationAreaAndCell(univ.Sequence):
+    componentType = namedtype.NamedTypes(
+        namedtype.NamedType("locationAreaCode", octets(0)),
+        namedtype.NamedType("cellId", octets(1)),
+    )
+
+
+def location(number):
+    return LocationAreaAndCell().subtype(implicitTag=ctx(number, constructed=True))
+
+
+class MOCallRecord(univ.Set):
+    tagSet = univ.Set.tagSet.tagImplicitly(ctx(0, constructed=True))
+    componentType = namedtype.NamedTypes(
+        namedtype.NamedType("recordType", integer(0)),
+        namedtype.NamedType("servedIMSI", octets(1)),
+        namedtype.OptionalNamedType("servedIMEI", octets(2)),
+        namedtype.OptionalNamedType("servedMSISDN", octets(3)),
+        namedtype.OptionalNamedType("callingNumber", octets(4)),
+        namedtype.OptionalNamedType("calledNumber", octets(5)),
+        namedtype.NamedType("recordingEntity", octets(9)),
+        namedtype.OptionalNamedType("location", location(12)),
+        namedtype.OptionalNamedType("seizureTime", octets(22)),
+        namedtype.OptionalNamedType("answerTime", octets(23)),
+        namedtype.OptionalNamedType("releaseTime", octets(24)),
+        namedtype.NamedType("callDuration", integer(25)),
+        namedtype.NamedType("causeForTerm", integer(30)),
+    )
+
+
+class MTCallRecord(univ.Set):
+    tagSet = univ.Set.tagSet.tagImplicitly(ctx(1, constructed=True))
+    componentType = namedtype.NamedTypes(
+        namedtype.NamedType("recordType", integer(0)),
+        namedtype.NamedType("servedIMSI", octets(1)),
+        namedtype.OptionalNamedType("servedIMEI", octets(2)),
+        namedtype.OptionalNamedType("servedMSISDN", octets(3)),
+        namedtype.OptionalNamedType("callingNumber", octets(4)),
+        namedtype.NamedType("recordingEntity", octets(6)),
+        namedtype.OptionalNamedType("location", location(9)),
+        namedtype.OptionalNamedType("seizureTime", octets(19)),
+        namedtype.OptionalNamedType("answerTime", octets(20)),
+        namedtype.OptionalNamedType("releaseTime", octets(21)),
+        namedtype.NamedType("callDuration", integer(22)),
+        namedtype.NamedType("causeForTerm", integer(27)),
+    )
+
+
+class SMSMORecord(univ.Set):
+    tagSet = univ.Set.tagSet.tagImplicitly(ctx(6, constructed=True))
+    componentType = namedtype.NamedTypes(
+        namedtype.NamedType("recordType", integer(0)),
+        namedtype.NamedType("servedIMSI", octets(1)),
+        namedtype.OptionalNamedType("servedIMEI", octets(2)),
+        namedtype.OptionalNamedType("servedMSISDN", octets(3)),
+        namedtype.NamedType("serviceCentre", octets(5)),
+        namedtype.NamedType("recordingEntity", octets(6)),
+        namedtype.OptionalNamedType("location", location(7)),
+        namedtype.NamedType("messageReference", octets(8)),
+        namedtype.NamedType("originationTime", octets(9)),
+        namedtype.OptionalNamedType("destinationNumber", octets(12)),
+    )
+
+
+class IPAddress(univ.Choice):
+    componentType = namedtype.NamedTypes(
+        namedtype.NamedType("iPBinV4Address", octets(0)),
+    )
+
+
+class ChangeOfCharCondition(univ.Sequence):
+    componentType = namedtype.NamedTypes(
+        namedtype.OptionalNamedType("dataVolumeGPRSUplink", integer(3)),
+        namedtype.OptionalNamedType("dataVolumeGPRSDownlink", integer(4)),
+        namedtype.NamedType("changeCondition", univ.Enumerated().subtype(implicitTag=ctx(5))),
+        namedtype.NamedType("changeTime", octets(6)),
+    )
+
+
+class PGWRecord(univ.Set):
+    tagSet = univ.Set.tagSet.tagImplicitly(ctx(79, constructed=True))
+    componentType = namedtype.NamedTypes(
+        namedtype.NamedType("recordType", integer(0)),
+        namedtype.OptionalNamedType("servedIMSI", octets(3)),
+        namedtype.OptionalNamedType(
+            "p-GWAddress", IPAddress().subtype(explicitTag=ctx(4, constructed=True))
+        ),
+        namedtype.OptionalNamedType("chargingID", integer(5)),
+        namedtype.OptionalNamedType("accessPointNameNI", octets(7)),
+        namedtype.OptionalNamedType(
+            "listOfTrafficVolumes",
+            univ.SequenceOf(componentType=ChangeOfCharCondition()).subtype(
+                implicitTag=ctx(12, constructed=True)
+            ),
+        ),
+        namedtype.OptionalNamedType("recordOpeningTime", octets(13)),
+        namedtype.OptionalNamedType("duration", integer(14)),
+        namedtype.OptionalNamedType("causeForRecClosing", integer(15)),
+        namedtype.OptionalNamedType("recordSequenceNumber", integer(17)),
+        namedtype.OptionalNamedType("servedMSISDN", octets(22)),
+        namedtype.OptionalNamedType("rATType", integer(30)),
+    )
+
+
+# CallEventRecordType
+MO_CALL_RECORD = 0
+MT_CALL_RECORD = 1
+MO_SMS_RECORD = 6
+PGW_RECORD = 85
+
+# CauseForRecClosing
+VOLUME_LIMIT = 16
+TIME_LIMIT = 17
+
+# ChangeCondition
+RECORD_CLOSURE = 2
+TARIFF_TIME = 1
+
+# RATType
+EUTRAN = 6
+
+
+def fill(record, values):
+    for name, value in values.items():
+        if name == "location":
+            component = record.setComponentByName(name).getComponentByName(name)
+            component["locationAreaCode"] = value[0]
+            component["cellId"] = value[1]
+        elif name == "p-GWAddress":
+            record.setComponentByName(name).getComponentByName(name)["iPBinV4Address"] = value
+        elif name == "listOfTrafficVolumes":
+            volumes = record.setComponentByName(name).getComponentByName(name)
+            for index, (uplink, downlink, condition, time) in enumerate(value):
+                volumes[index]["dataVolumeGPRSUplink"] = uplink
+                volumes[index]["dataVolumeGPRSDownlink"] = downlink
+                volumes[index]["changeCondition"] = condition
+                volumes[index]["changeTime"] = time
+        else:
+            record[name] = value
+    return record
+
+
+def main():
+    mo = fill(MOCallRecord(), {
+        "recordType": MO_CALL_RECORD,
+        "servedIMSI": bytes.fromhex("02180521436587f9"),    # 208150123456789
+        "servedIMEI": bytes.fromhex("53029900711684f1"),    # 352099001761481
+        "servedMSISDN": bytes.fromhex("913316325476f8"),    # +33612345678
+        "calledNumber": bytes.fromhex("913396785634f2"),    # +33698765432
+        "recordingEntity": bytes.fromhex("913306090000f1"),
+        "location": (bytes.fromhex("1001"), bytes.fromhex("3039")),  # 4097, 12345
+        "seizureTime": bytes.fromhex("2601291015002b0100"),  # 2026-01-29 10:15:00 +01:00
+        "answerTime": bytes.fromhex("2601291015302b0100"),
+        "releaseTime": bytes.fromhex("2601291017452b0100"),
+        "callDuration": 135,
+        "causeForTerm": 0,
+    })
+    mt = fill(MTCallRecord(), {
+        "recordType": MT_CALL_RECORD,
+        "servedIMSI": bytes.fromhex("06051132547698f0"),    # 605011234567890
+        "servedMSISDN": bytes.fromhex("911296183254f6"),    # +21698123456
+        "callingNumber": bytes.fromhex("913316325476f8"),   # +33612345678
+        "recordingEntity": bytes.fromhex("911296080000f1"),
+        "location": (bytes.fromhex("0201"), bytes.fromhex("0321")),
+        "seizureTime": bytes.fromhex("2601291100002b0100"),
+        "answerTime": bytes.fromhex("2601291100042b0100"),
+        "releaseTime": bytes.fromhex("2601291101062b0100"),
+        "callDuration": 62,
+        "causeForTerm": 0,
+    })
+    sms_mo = fill(SMSMORecord(), {
+        "recordType": MO_SMS_RECORD,
+        "servedIMSI": bytes.fromhex("02180521436587f9"),    # 208150123456789
+        "servedIMEI": bytes.fromhex("53029900711684f1"),    # 352099001761481
+        "servedMSISDN": bytes.fromhex("913316325476f8"),    # +33612345678
+        "serviceCentre": bytes.fromhex("913306091093f0"),   # +33609001390
+        "recordingEntity": bytes.fromhex("913306090000f1"),
+        "location": (bytes.fromhex("1001"), bytes.fromhex("3039")),
+        "messageReference": bytes.fromhex("2a"),
+        "originationTime": bytes.fromhex("2601290900052b0100"),  # 2026-01-29 09:00:05 +01:00
+        # TP-DA: 11 digits, international: +41791234567
+        "destinationNumber": bytes.fromhex("0b911497214365f7"),
+    })
+    pgw = fill(PGWRecord(), {
+        "recordType": PGW_RECORD,
+        "servedIMSI": bytes.fromhex("42941132547698f0"),    # 244911234567890
+        "p-GWAddress": bytes.fromhex("0a000001"),           # 10.0.0.1
+        "chargingID": 0x12345678,
+        "accessPointNameNI": b"internet",
+        "listOfTrafficVolumes": [
+            (1_500_000, 20_000_000, TARIFF_TIME, bytes.fromhex("2601291130002b0200")),
+            (250_000, 4_000_000, RECORD_CLOSURE, bytes.fromhex("2601291200002b0200")),
+        ],
+        "recordOpeningTime": bytes.fromhex("2601291100002b0200"),  # 2026-01-29 11:00:00 +02:00
+        "duration": 3600,
+        "causeForRecClosing": VOLUME_LIMIT,
+        "servedMSISDN": bytes.fromhex("91534810325476"),    # +358401234567
+        "rATType": EUTRAN,
+    })
+    # Second partial record of the same session, closed by the time limit
+    pgw_partial = fill(PGWRecord(), {
+        "recordType": PGW_RECORD,
+        "servedIMSI": bytes.fromhex("42941132547698f0"),
+        "p-GWAddress": bytes.fromhex("0a000001"),
+        "chargingID": 0x12345678,
+        "accessPointNameNI": b"internet",
+        "listOfTrafficVolumes": [
+            (800_000, 9_000_000, RECORD_CLOSURE, bytes.fromhex("2601291300002b0200")),
+        ],
+        "recordOpeningTime": bytes.fromhex("2601291200002b0200"),
+        "duration": 3600,
+        "causeForRecClosing": TIME_LIMIT,
+        "recordSequenceNumber": 2,
+        "servedMSISDN": bytes.fromhex("91534810325476"),
+        "rATType": EUTRAN,
+    })
+
+    with open("mo_call_record.ber", "wb") as f:
+        f.write(ber.encode(mo))
+    with open("mt_call_record.ber", "wb") as f:
+        f.write(cer.encode(mt))
+    with open("sms_mo_record.ber", "wb") as f:
+        f.write(ber.encode(sms_mo))
+    with open("pgw_record.ber", "wb") as f:
+        f.write(ber.encode(pgw))
+    with open("pgw_partial_record.ber", "wb") as f:
+        f.write(ber.encode(pgw_partial))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,18 @@
+//! Kafka delivery building blocks shared by the ORION services.
+//!
+//! [`OffsetCommitter`] commits the offsets of the consumed messages in
+//! batches, once their output is acknowledged (at-least-once delivery).
+//! [`TransactionBatch`] tracks the inputs of a producer transaction, whose
+//! outputs and input offsets are committed atomically (exactly-once).
+//!
+//! With the `testing` feature, [`testing`] runs the exactly-once scenarios
+//! against a service binary and a Kafka broker in a container.
+
+mod offset_committer;
+mod transaction_batch;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub use offset_committer::OffsetCommitter;
+pub use transaction_batch::TransactionBatch;
@@ -0,0 +1,111 @@
+use rdkafka::consumer::{CommitMode, Consumer};
+use rdkafka::error::KafkaResult;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Stores offsets of fully handled messages and commits them in batches.
+///
+/// An offset is only stored once the downstream write for that message has
+/// been acknowledged, so a crash replays at most the uncommitted batch
+/// (at-least-once delivery). A service keeping state for the batch stores
+/// the offsets with [`OffsetCommitter::store`] and commits a due batch once
+/// that state is saved.
+pub struct OffsetCommitter {
+    batch_size: usize,
+    interval: Duration,
+    pending: usize,
+    last_commit: Instant,
+}
+
+impl OffsetCommitter {
+    pub fn new(batch_size: usize, interval: Duration) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            interval,
+            pending: 0,
+            last_commit: Instant::now(),
+        }
+    }
+
+    /// Maximum time an acknowledged offset waits before being committed
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Mark a message as handled, committing if the batch is full or the
+    /// commit interval has elapsed
+    pub fn ack<C: Consumer>(
+        &mut self,
+        consumer: &C,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+    ) -> KafkaResult<()> {
+        if self.store(consumer, topic, partition, offset)? {
+            self.commit(consumer)?;
+        }
+        Ok(())
+    }
+
+    /// Mark a message as handled without committing. Returns whether the
+    /// batch is due, being full or the commit interval having elapsed.
+    pub fn store<C: Consumer>(
+        &mut self,
+        consumer: &C,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+    ) -> KafkaResult<bool> {
+        consumer.store_offset(topic, partition, offset)?;
+        self.pending += 1;
+        Ok(self.should_commit())
+    }
+
+    /// Commit all stored offsets
+    pub fn commit<C: Consumer>(&mut self, consumer: &C) -> KafkaResult<()> {
+        self.commit_with(consumer, CommitMode::Async)
+    }
+
+    /// Commit all stored offsets and wait for the broker, before shutdown
+    pub fn commit_sync<C: Consumer>(&mut self, consumer: &C) -> KafkaResult<()> {
+        self.commit_with(consumer, CommitMode::Sync)
+    }
+
+    fn commit_with<C: Consumer>(&mut self, consumer: &C, mode: CommitMode) -> KafkaResult<()> {
+        if self.pending == 0 {
+            return Ok(());
+        }
+
+        consumer.commit_consumer_state(mode)?;
+        debug!("Committed offsets for {} messages", self.pending);
+
+        self.pending = 0;
+        self.last_commit = Instant::now();
+        Ok(())
+    }
+
+    fn should_commit(&self) -> bool {
+        self.pending >= self.batch_size || self.last_commit.elapsed() >= self.interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_when_batch_full() {
+        let mut committer = OffsetCommitter::new(3, Duration::from_secs(60));
+        committer.pending = 2;
+        assert!(!committer.should_commit());
+        committer.pending = 3;
+        assert!(committer.should_commit());
+    }
+
+    #[test]
+    fn test_commit_when_interval_elapsed() {
+        let mut committer = OffsetCommitter::new(1000, Duration::from_millis(0));
+        committer.pending = 1;
+        assert!(committer.should_commit());
+    }
+}
@@ -0,0 +1,171 @@
+//! Exactly-once scenarios, run by the services' integration tests against a
+//! single-node Kafka broker.
+//!
+//! The broker is started in a container and the service binary against it,
+//! so the scenarios need a Docker daemon. A service describes its binary,
+//! its inputs and the records expected on each output topic; the scenarios
+//! check that a restart or a crash never duplicates nor loses them.
+
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use testcontainers_modules::kafka::apache::{self, KAFKA_PORT};
+use testcontainers_modules::testcontainers::runners::AsyncRunner;
+use testcontainers_modules::testcontainers::{ContainerAsync, ImageExt};
+
+/// Service under test
+pub struct Service {
+    /// Path of the service binary, `env!("CARGO_BIN_EXE_<name>")`
+    pub binary: &'static str,
+    /// Consumer group and transactional id of the service
+    pub name: &'static str,
+    /// Environment of the service besides the brokers, consumer group and
+    /// delivery settings (topics)
+    pub env: Vec<(&'static str, &'static str)>,
+    pub input_topic: &'static str,
+    /// Payloads published on the input topic
+    pub inputs: Vec<String>,
+    /// Records expected on each output topic, the main output first
+    pub outputs: Vec<(&'static str, usize)>,
+}
+
+impl Service {
+    fn spawn(&self, brokers: &str, batch_size: usize, interval_ms: u64) -> Child {
+        Command::new(self.binary)
+            .envs(self.env.iter().copied())
+            .env("KAFKA_BROKERS", brokers)
+            .env("KAFKA_INPUT_TOPIC", self.input_topic)
+            .env("KAFKA_CONSUMER_GROUP", self.name)
+            .env("KAFKA_DELIVERY_MODE", "exactly_once")
+            .env("KAFKA_TRANSACTIONAL_ID", self.name)
+            .env("KAFKA_COMMIT_BATCH_SIZE", batch_size.to_string())
+            .env("KAFKA_COMMIT_INTERVAL_MS", interval_ms.to_string())
+            .env("SERVER_PORT", "0")
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap_or_else(|e| panic!("Failed to start {}: {}", self.binary, e))
+    }
+
+    fn main_output(&self) -> (&'static str, usize) {
+        *self.outputs.first().expect("the service has an output topic")
+    }
+
+    async fn produce_inputs(&self, brokers: &str) {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .create()
+            .unwrap();
+
+        for payload in &self.inputs {
+            producer
+                .send(
+                    FutureRecord::<(), _>::to(self.input_topic).payload(payload),
+                    Duration::from_secs(5),
+                )
+                .await
+                .expect("Failed to produce input");
+        }
+    }
+
+    async fn assert_outputs(&self, brokers: &str) {
+        for (topic, expected) in &self.outputs {
+            let count = count_committed(brokers, topic, *expected, Duration::from_secs(30)).await;
+            assert_eq!(count, *expected, "records committed on {}", topic);
+        }
+    }
+}
+
+/// A restart after every output is committed processes nothing again
+pub async fn restart_does_not_duplicate_outputs(service: &Service) {
+    let (_node, brokers) = start_broker().await;
+    service.produce_inputs(&brokers).await;
+    let (topic, expected) = service.main_output();
+
+    let mut child = service.spawn(&brokers, 5, 500);
+    assert_eq!(count_committed(&brokers, topic, expected, Duration::from_secs(90)).await, expected);
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    // Offsets were committed with the outputs: nothing is processed again
+    let mut child = service.spawn(&brokers, 5, 500);
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    service.assert_outputs(&brokers).await;
+}
+
+/// A crash with the transaction open commits nothing, and the restarted
+/// instance replays the batch without duplicates
+pub async fn crash_mid_transaction_replays_without_duplicates(service: &Service) {
+    let (_node, brokers) = start_broker().await;
+    service.produce_inputs(&brokers).await;
+    let (topic, expected) = service.main_output();
+
+    // Transaction stays open: all inputs are processed but nothing is committed
+    let mut child = service.spawn(&brokers, 1000, 600_000);
+    tokio::time::sleep(Duration::from_secs(20)).await;
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert_eq!(count_committed(&brokers, topic, 1, Duration::from_secs(10)).await, 0);
+
+    // The restarted instance fences the crashed one and replays the batch
+    let mut child = service.spawn(&brokers, 5, 500);
+    let outputs = count_committed(&brokers, topic, expected, Duration::from_secs(120)).await;
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert_eq!(outputs, expected);
+    service.assert_outputs(&brokers).await;
+}
+
+async fn start_broker() -> (ContainerAsync<apache::Kafka>, String) {
+    // Single node: the transaction log cannot be replicated
+    let node = apache::Kafka::default()
+        .with_env_var("KAFKA_TRANSACTION_STATE_LOG_REPLICATION_FACTOR", "1")
+        .with_env_var("KAFKA_TRANSACTION_STATE_LOG_MIN_ISR", "1")
+        .start()
+        .await
+        .expect("Failed to start Kafka container");
+    let port = node.get_host_port_ipv4(KAFKA_PORT).await.unwrap();
+    (node, format!("127.0.0.1:{}", port))
+}
+
+/// Count committed records on `topic`. Waits until `expected` records have
+/// been read (or `deadline` elapsed), then keeps reading a few seconds to
+/// catch duplicates.
+async fn count_committed(brokers: &str, topic: &str, expected: usize, deadline: Duration) -> usize {
+    let consumer: StreamConsumer = ClientConfig::new()
+        .set("bootstrap.servers", brokers)
+        .set("group.id", format!("it-reader-{}", topic))
+        .set("enable.auto.commit", "false")
+        .set("isolation.level", "read_committed")
+        .set("auto.offset.reset", "earliest")
+        .set("allow.auto.create.topics", "true")
+        .create()
+        .unwrap();
+    consumer.subscribe(&[topic]).unwrap();
+
+    let started = Instant::now();
+    let mut count = 0;
+    let mut grace: Option<Instant> = None;
+    loop {
+        if count >= expected && grace.is_none() {
+            grace = Some(Instant::now());
+        }
+        let done = match grace {
+            Some(grace) => grace.elapsed() >= Duration::from_secs(5),
+            None => started.elapsed() >= deadline,
+        };
+        if done {
+            return count;
+        }
+
+        if let Ok(Ok(_)) = tokio::time::timeout(Duration::from_secs(1), consumer.recv()).await {
+            count += 1;
+        }
+    }
+}
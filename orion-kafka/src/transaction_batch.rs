@@ -0,0 +1,132 @@
+use rdkafka::error::KafkaResult;
+use rdkafka::{Offset, TopicPartitionList};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Input offsets consumed inside the current producer transaction.
+///
+/// In exactly-once mode the outputs of a batch and the offsets of its inputs
+/// are committed atomically. When the transaction is aborted, the consumer
+/// is rewound to the first offset of each partition so the whole batch is
+/// processed again.
+pub struct TransactionBatch {
+    batch_size: usize,
+    interval: Duration,
+    /// (topic, partition) -> (first, last) offset consumed in the transaction
+    offsets: HashMap<(String, i32), (i64, i64)>,
+    messages: usize,
+    opened_at: Option<Instant>,
+}
+
+impl TransactionBatch {
+    pub fn new(batch_size: usize, interval: Duration) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            interval,
+            offsets: HashMap::new(),
+            messages: 0,
+            opened_at: None,
+        }
+    }
+
+    /// Maximum time a transaction stays open before being committed
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.opened_at.is_some()
+    }
+
+    pub fn open(&mut self) {
+        self.opened_at = Some(Instant::now());
+    }
+
+    /// Add a consumed message to the transaction
+    pub fn record(&mut self, topic: &str, partition: i32, offset: i64) {
+        self.offsets
+            .entry((topic.to_string(), partition))
+            .and_modify(|(_, last)| *last = offset)
+            .or_insert((offset, offset));
+        self.messages += 1;
+    }
+
+    pub fn should_commit(&self) -> bool {
+        match self.opened_at {
+            Some(opened_at) => {
+                self.messages >= self.batch_size || opened_at.elapsed() >= self.interval
+            }
+            None => false,
+        }
+    }
+
+    /// Next offsets to consume, to be sent to the transaction
+    pub fn commit_offsets(&self) -> KafkaResult<TopicPartitionList> {
+        let mut list = TopicPartitionList::new();
+        for ((topic, partition), (_, last)) in &self.offsets {
+            list.add_partition_offset(topic, *partition, Offset::Offset(last + 1))?;
+        }
+        Ok(list)
+    }
+
+    /// Positions to seek to in order to replay the transaction
+    pub fn rewind_offsets(&self) -> impl Iterator<Item = (&str, i32, i64)> {
+        self.offsets
+            .iter()
+            .map(|((topic, partition), (first, _))| (topic.as_str(), *partition, *first))
+    }
+
+    pub fn messages(&self) -> usize {
+        self.messages
+    }
+
+    pub fn reset(&mut self) {
+        self.offsets.clear();
+        self.messages = 0;
+        self.opened_at = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offsets_track_first_and_last() {
+        let mut batch = TransactionBatch::new(100, Duration::from_secs(60));
+        batch.open();
+        batch.record("cdr.validated", 0, 10);
+        batch.record("cdr.validated", 0, 11);
+        batch.record("cdr.validated", 1, 5);
+
+        let commit = batch.commit_offsets().unwrap();
+        assert_eq!(
+            commit.find_partition("cdr.validated", 0).unwrap().offset(),
+            Offset::Offset(12)
+        );
+        assert_eq!(
+            commit.find_partition("cdr.validated", 1).unwrap().offset(),
+            Offset::Offset(6)
+        );
+
+        let mut rewind: Vec<_> = batch.rewind_offsets().collect();
+        rewind.sort();
+        assert_eq!(rewind, vec![("cdr.validated", 0, 10), ("cdr.validated", 1, 5)]);
+    }
+
+    #[test]
+    fn test_should_commit() {
+        let mut batch = TransactionBatch::new(2, Duration::from_secs(60));
+        assert!(!batch.should_commit());
+
+        batch.open();
+        batch.record("cdr.validated", 0, 1);
+        assert!(!batch.should_commit());
+        batch.record("cdr.validated", 0, 2);
+        assert!(batch.should_commit());
+
+        batch.reset();
+        assert!(!batch.is_open());
+        assert_eq!(batch.messages(), 0);
+    }
+}
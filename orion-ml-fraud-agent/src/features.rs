@@ -1,3 +1,4 @@
+use chrono::{DateTime, Datelike, FixedOffset, Timelike};
 use serde::{Deserialize, Serialize};
 
 /// Input features for fraud detection model
@@ -31,9 +32,29 @@ pub struct FraudFeatures {
     // Statistical features
     pub duration_zscore: f32,   // Z-score of duration
     pub cost_zscore: f32,       // Z-score of cost
+
+    /// Start of the call in the subscriber's local time (the unified CDR's
+    /// `local_start_timestamp`); when given, the time features are derived
+    /// from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_start_timestamp: Option<DateTime<FixedOffset>>,
 }
 
 impl FraudFeatures {
+    /// Features with the time features derived from the local start of the
+    /// call, when given
+    pub fn with_local_time(mut self) -> Self {
+        if let Some(local) = self.local_start_timestamp {
+            let hour = local.hour();
+            let day = local.weekday().num_days_from_monday();
+            self.hour_of_day = hour as f32;
+            self.day_of_week = day as f32;
+            self.is_weekend = if day >= 5 { 1.0 } else { 0.0 };
+            self.is_night_call = if !(6..22).contains(&hour) { 1.0 } else { 0.0 };
+        }
+        self
+    }
+
     /// Convert features to array for ONNX input
     pub fn to_array(&self) -> Vec<f32> {
         vec![
@@ -94,6 +115,7 @@ mod tests {
             signal_strength: 0.7,
             duration_zscore: 1.5,
             cost_zscore: 2.0,
+            local_start_timestamp: None,
         };
 
         let array = features.to_array();
@@ -102,6 +124,37 @@ mod tests {
         assert_eq!(array[1], 1.0);
     }
 
+    #[test]
+    fn test_time_features_from_local_start() {
+        let features: FraudFeatures = serde_json::from_value(serde_json::json!({
+            "cdr_id": "test-456",
+            "duration_seconds": 120.0,
+            "is_international": 0.0,
+            "is_premium": 0.0,
+            "is_roaming": 0.0,
+            "hour_of_day": 21.0,
+            "day_of_week": 4.0,
+            "is_weekend": 0.0,
+            "is_night_call": 0.0,
+            "daily_call_count": 5.0,
+            "daily_call_duration": 600.0,
+            "unique_destinations_count": 3.0,
+            "call_frequency_per_hour": 0.5,
+            "cell_tower_changes": 0.0,
+            "signal_strength": 0.7,
+            "duration_zscore": 0.0,
+            "cost_zscore": 0.0,
+            // Friday 21:30 UTC, Saturday 00:30 in Moscow
+            "local_start_timestamp": "2024-07-13T00:30:00+03:00"
+        })).unwrap();
+
+        let features = features.with_local_time();
+        assert_eq!(features.hour_of_day, 0.0);
+        assert_eq!(features.day_of_week, 5.0);
+        assert_eq!(features.is_weekend, 1.0);
+        assert_eq!(features.is_night_call, 1.0);
+    }
+
     #[test]
     fn test_feature_count_constant() {
         assert_eq!(FraudFeatures::FEATURE_COUNT, 16);
@@ -8,10 +8,13 @@ mod simple_ml;
 use axum::{routing::{get, post}, Router};
 use config::Config;
 use metrics_exporter_prometheus::PrometheusBuilder;
+use orion_shutdown::Shutdown;
 use model::FraudDetector;
 use routes::AppState;
+use std::future::IntoFuture;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
@@ -69,7 +72,20 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Starting HTTP server on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // On SIGTERM/SIGINT, complete the requests in flight, within the deadline
+    let mut shutdown = Shutdown::on_signal();
+    let mut server_shutdown = shutdown.clone();
+    let timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown(async move { server_shutdown.requested().await });
+    tokio::select! {
+        result = server.into_future() => result?,
+        _ = async {
+            shutdown.requested().await;
+            tokio::time::sleep(timeout).await;
+        } => tracing::warn!("Shutdown deadline of {:?} exceeded, exiting with requests in flight", timeout),
+    }
+    tracing::info!("orion-ml-fraud-agent service stopped");
 
     Ok(())
 }
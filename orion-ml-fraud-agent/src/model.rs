@@ -96,7 +96,7 @@ impl FraudDetector {
         // Run inference using Burn
         let fraud_score = model.predict(&feature_array);
         
-        Ok(fraud_score.max(0.0).min(1.0))
+        Ok(fraud_score.clamp(0.0, 1.0))
     }
 
     /// Predict fraud for a batch of CDRs
@@ -131,7 +131,7 @@ impl FraudDetector {
             
             // Process each prediction
             for (features, fraud_score) in features_batch.iter().zip(scores.iter()) {
-                let fraud_score = fraud_score.max(0.0).min(1.0);
+                let fraud_score = fraud_score.clamp(0.0, 1.0);
                 let is_fraud = fraud_score > self.threshold;
                 let confidence = if is_fraud { fraud_score } else { 1.0 - fraud_score };
                 
@@ -199,7 +199,7 @@ impl FraudDetector {
         }
         
         // Clamp to [0, 1]
-        score.min(1.0).max(0.0)
+        score.clamp(0.0, 1.0)
     }
 
     /// Get model information
@@ -255,6 +255,7 @@ mod tests {
             signal_strength: 0.1,
             duration_zscore: 3.5,
             cost_zscore: 4.0,
+            local_start_timestamp: None,
         };
         
         let score = detector.calculate_fallback_score(&features);
@@ -290,6 +291,7 @@ mod tests {
             signal_strength: 0.8,
             duration_zscore: 0.2,
             cost_zscore: 0.1,
+            local_start_timestamp: None,
         };
         
         let score = detector.calculate_fallback_score(&features);
@@ -55,7 +55,8 @@ pub async fn predict(
     State(state): State<AppState>,
     Json(request): Json<PredictRequest>,
 ) -> Result<Json<FraudPrediction>, AppError> {
-    let prediction = state.detector.predict(&request.features).await?;
+    let features = request.features.with_local_time();
+    let prediction = state.detector.predict(&features).await?;
     Ok(Json(prediction))
 }
 
@@ -64,7 +65,12 @@ pub async fn predict_batch(
     State(state): State<AppState>,
     Json(request): Json<BatchPredictRequest>,
 ) -> Result<Json<Vec<FraudPrediction>>, AppError> {
-    let predictions = state.detector.predict_batch(&request.features_batch).await?;
+    let features_batch: Vec<FraudFeatures> = request
+        .features_batch
+        .into_iter()
+        .map(FraudFeatures::with_local_time)
+        .collect();
+    let predictions = state.detector.predict_batch(&features_batch).await?;
     Ok(Json(predictions))
 }
 
@@ -0,0 +1,68 @@
+//! Canonical JSON text of a CDR, for hashes and ids.
+//!
+//! Object keys are sorted recursively (byte order) and the text is compact,
+//! so the digest of a record does not depend on the order its fields were
+//! received or inserted in, nor on serde_json's map implementation (the
+//! `preserve_order` feature, enabled by any crate of the build, would
+//! otherwise keep the insertion order).
+
+use serde_json::Value;
+
+/// Compact JSON text of `value` with object keys sorted recursively
+pub fn canonical_json(value: &Value) -> String {
+    let mut text = String::new();
+    write_canonical(value, &mut text);
+    text
+}
+
+fn write_canonical(value: &Value, text: &mut String) {
+    match value {
+        Value::Object(fields) => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_unstable_by_key(|(key, _)| *key);
+            text.push('{');
+            for (index, (key, value)) in fields.into_iter().enumerate() {
+                if index > 0 {
+                    text.push(',');
+                }
+                text.push_str(&Value::from(key.as_str()).to_string());
+                text.push(':');
+                write_canonical(value, text);
+            }
+            text.push('}');
+        }
+        Value::Array(items) => {
+            text.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    text.push(',');
+                }
+                write_canonical(item, text);
+            }
+            text.push(']');
+        }
+        scalar => text.push_str(&scalar.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Map};
+
+    #[test]
+    fn test_canonical_json_sorts_keys_recursively() {
+        let mut inner = Map::new();
+        inner.insert("zone".to_string(), json!("é\"1"));
+        inner.insert("cell".to_string(), json!([{"b": 2, "a": 1}, null]));
+        let mut outer = Map::new();
+        outer.insert("location".to_string(), Value::Object(inner));
+        outer.insert("duration".to_string(), json!(120));
+
+        assert_eq!(
+            canonical_json(&Value::Object(outer)),
+            r#"{"duration":120,"location":{"cell":[{"a":1,"b":2},null],"zone":"é\"1"}}"#
+        );
+        assert_eq!(canonical_json(&json!([1.5, true, "x"])), r#"[1.5,true,"x"]"#);
+    }
+}
@@ -0,0 +1,75 @@
+//! CDR enriched by orion-enrichment, stored by orion-storage-hot.
+
+use crate::{Contract, UnifiedCDR};
+use serde::{Deserialize, Serialize};
+
+/// Enriched CDR: the unified fields, flattened, with the enrichments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnrichedCDR {
+    // All fields from UnifiedCDR
+    #[serde(flatten)]
+    pub unified: UnifiedCDR,
+
+    // Fraud detection
+    pub fraud_info: Option<FraudInfo>,
+
+    // Network enrichment
+    pub network_info: Option<NetworkInfo>,
+
+    // Client/Subscriber enrichment
+    pub client_info: Option<ClientInfo>,
+
+    // Rating, absent when rated upstream or by no tariff plan
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating_info: Option<RatingInfo>,
+
+    // Metadata
+    pub enrichment_timestamp: String,
+    pub enrichment_version: String,
+}
+
+impl Contract for EnrichedCDR {
+    const NAME: &'static str = "EnrichedCDR";
+    const VERSION: u32 = 1;
+}
+
+/// Fraud detection information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FraudInfo {
+    pub fraud_score: f64,           // 0.0 - 1.0
+    pub risk_level: String,          // low, medium, high
+    pub reasons: Vec<String>,        // List of fraud indicators
+    pub model_version: String,       // fraud_model_v1
+    pub detection_timestamp: String,
+}
+
+/// Network enrichment data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkInfo {
+    pub network_name: String,        // Orange, SFR, Bouygues...
+    pub network_type: String,        // 3G, 4G, 5G
+    pub cell_tower_location: Option<String>, // Lat/Long
+    pub signal_strength: Option<i32>, // dBm
+    pub handover_count: Option<i32>, // Number of cell handovers
+}
+
+/// Rating by the tariff plans; the amount and currency are the unified
+/// `rated_amount` and `currency`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RatingInfo {
+    pub tariff_plan: String,         // fr-individual
+    pub tariff_class: String,        // voice_national_peak, data_roaming_world...
+    pub rating_version: String,      // Version of the tariff file, for audit
+    pub billed_units: i64,           // Seconds, messages or data bundles
+}
+
+/// Client/Subscriber enrichment data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientInfo {
+    pub subscriber_segment: String,  // individual, business, premium
+    pub contract_type: String,       // prepaid, postpaid
+    pub customer_since: Option<String>, // 2020-01-15
+    pub lifetime_value: Option<f64>, // EUR
+    pub is_vip: bool,
+    pub data_plan_limit_mb: Option<i64>,
+}
@@ -0,0 +1,103 @@
+//! Event time of a raw CDR, as read by orion-validation to date the
+//! `ValidatedCDR`.
+//!
+//! The event time is the first present of `timestamp`, `start_timestamp`
+//! and `event_time`, in any format switches and the traffic generator send:
+//! RFC 3339, epoch seconds or milliseconds (number or digits) and 3GPP TS
+//! 32.298 `YYMMDDhhmmss+hhmm`.
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use serde_json::Value;
+
+/// Fields holding the event time, by precedence
+pub const EVENT_TIME_FIELDS: &[&str] = &["timestamp", "start_timestamp", "event_time"];
+
+/// Epoch values from this one on are milliseconds (year 5138 in seconds,
+/// 1973 in milliseconds)
+const EPOCH_MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// First non-null event time field of a CDR, with its value
+pub fn field(cdr: &Value) -> Option<(&'static str, &Value)> {
+    EVENT_TIME_FIELDS
+        .iter()
+        .find_map(|&field| cdr.get(field).filter(|value| !value.is_null()).map(|value| (field, value)))
+}
+
+/// Parse an event time in any supported format
+pub fn parse(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::Number(number) => match number.as_i64() {
+            Some(epoch) => from_epoch(epoch),
+            None => from_epoch(number.as_f64()?.round() as i64),
+        },
+        Value::String(text) => {
+            let text = text.trim();
+            if let Ok(timestamp) = DateTime::parse_from_rfc3339(text) {
+                return Some(timestamp.with_timezone(&Utc));
+            }
+            if !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) {
+                return from_epoch(text.parse().ok()?);
+            }
+            from_3gpp(text)
+        }
+        _ => None,
+    }
+}
+
+fn from_epoch(epoch: i64) -> Option<DateTime<Utc>> {
+    if epoch.abs() >= EPOCH_MILLIS_THRESHOLD {
+        DateTime::from_timestamp_millis(epoch)
+    } else {
+        DateTime::from_timestamp(epoch, 0)
+    }
+}
+
+/// `YYMMDDhhmmss` followed by the UTC offset `+hhmm` / `-hhmm`, as in the
+/// TimeStamp of 3GPP TS 32.298
+fn from_3gpp(text: &str) -> Option<DateTime<Utc>> {
+    if text.len() != 17 || !text.is_ascii() {
+        return None;
+    }
+    let (local, offset) = text.split_at(12);
+    let local = NaiveDateTime::parse_from_str(local, "%y%m%d%H%M%S").ok()?;
+
+    let (sign, offset) = offset.split_at(1);
+    if !offset.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = offset[..2].parse().ok()?;
+    let minutes: i32 = offset[2..].parse().ok()?;
+    let seconds = (hours * 60 + minutes) * 60;
+    let offset = match sign {
+        "+" => FixedOffset::east_opt(seconds)?,
+        "-" => FixedOffset::west_opt(seconds)?,
+        _ => return None,
+    };
+    Some(offset.from_local_datetime(&local).single()?.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_supported_formats() {
+        let expected = "2026-01-29T09:15:30Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(parse(&json!("2026-01-29T10:15:30+01:00")), Some(expected));
+        assert_eq!(parse(&json!(1769678130)), Some(expected));
+        assert_eq!(parse(&json!(1769678130000_i64)), Some(expected));
+        assert_eq!(parse(&json!("1769678130000")), Some(expected));
+        assert_eq!(parse(&json!("260129101530+0100")), Some(expected));
+        assert_eq!(parse(&json!("260129071530-0200")), Some(expected));
+        assert_eq!(parse(&json!("261329101530+0100")), None);
+        assert_eq!(parse(&json!("yesterday")), None);
+    }
+
+    #[test]
+    fn test_field_by_precedence() {
+        let cdr = json!({"timestamp": null, "start_timestamp": 1, "event_time": 2});
+        assert_eq!(field(&cdr), Some(("start_timestamp", &json!(1))));
+        assert_eq!(field(&json!({})), None);
+    }
+}
@@ -0,0 +1,103 @@
+//! Contracts of the CDRs exchanged over Kafka between the ORION services.
+//!
+//! Every contract is published with a top-level `schema_version` field. A
+//! consumer accepts any version up to the one it was built with, a payload
+//! without `schema_version` being version 1. Adding an optional field
+//! (`#[serde(default)]`) keeps the version; renaming, removing or retyping a
+//! field bumps it.
+//!
+//! | Contract | Topic | Producer |
+//! |----------|-------|----------|
+//! | [`ProcessedCDR`] | `cdr.processed` | orion-ingestion |
+//! | [`ValidatedCDR`] | `cdr.validated` | orion-validation |
+//! | [`UnifiedCDR`] | `cdr.normalized` | orion-normalization |
+//! | [`EnrichedCDR`] | `cdr.enriched` | orion-enrichment |
+//!
+//! With the `testing` feature, [`testing`] builds CDR fixtures for the
+//! services' tests.
+
+pub mod canonical;
+pub mod enriched;
+pub mod event_time;
+pub mod processed;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod unified;
+pub mod validated;
+
+pub use canonical::canonical_json;
+pub use enriched::{ClientInfo, EnrichedCDR, FraudInfo, NetworkInfo, RatingInfo};
+pub use processed::{KafkaOrigin, ProcessedCDR};
+pub use unified::{CallType, RoamingType, ServiceType, SmsType, UnifiedCDR};
+pub use validated::{EventType, Severity, ValidatedCDR, Violation};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+/// Top-level field holding the version of a contract
+pub const SCHEMA_VERSION_FIELD: &str = "schema_version";
+
+/// CDR exchanged between services
+pub trait Contract: Serialize + DeserializeOwned {
+    const NAME: &'static str;
+    /// Version written by this build, and the newest one it reads
+    const VERSION: u32;
+}
+
+#[derive(Debug, Error)]
+pub enum ContractError {
+    #[error("{contract} schema version {version} is not supported (up to {supported})")]
+    UnsupportedVersion {
+        contract: &'static str,
+        version: u64,
+        supported: u32,
+    },
+    #[error("schema_version must be a positive integer, got: {0}")]
+    InvalidVersion(Value),
+    #[error("payload is not a JSON object")]
+    NotAnObject,
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// Encode a contract, with its schema version
+pub fn to_json<C: Contract>(cdr: &C) -> Result<String, ContractError> {
+    let mut value = serde_json::to_value(cdr)?;
+    let Value::Object(fields) = &mut value else {
+        return Err(ContractError::NotAnObject);
+    };
+    fields.insert(SCHEMA_VERSION_FIELD.to_string(), Value::from(C::VERSION));
+    Ok(serde_json::to_string(&value)?)
+}
+
+/// Decode a contract, rejecting versions newer than this build
+pub fn from_slice<C: Contract>(payload: &[u8]) -> Result<C, ContractError> {
+    from_value(serde_json::from_slice(payload)?)
+}
+
+pub fn from_str<C: Contract>(payload: &str) -> Result<C, ContractError> {
+    from_value(serde_json::from_str(payload)?)
+}
+
+pub fn from_value<C: Contract>(mut value: Value) -> Result<C, ContractError> {
+    let Value::Object(fields) = &mut value else {
+        return Err(ContractError::NotAnObject);
+    };
+    let version = match fields.remove(SCHEMA_VERSION_FIELD) {
+        None => 1,
+        Some(version) => match version.as_u64() {
+            Some(version) if version > 0 => version,
+            _ => return Err(ContractError::InvalidVersion(version)),
+        },
+    };
+    if version > u64::from(C::VERSION) {
+        return Err(ContractError::UnsupportedVersion {
+            contract: C::NAME,
+            version,
+            supported: C::VERSION,
+        });
+    }
+    Ok(serde_json::from_value(value)?)
+}
@@ -0,0 +1,97 @@
+//! CDR read by orion-ingestion, before validation.
+
+use crate::Contract;
+use serde::{Deserialize, Serialize};
+
+/// Processed CDR ready for validation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessedCDR {
+    pub raw_payload: String,
+    pub source_topic: String,
+    pub country: String,
+    /// Source system of the topic route
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_system: Option<String>,
+    /// Kafka message the CDR was read from, absent for files and HTTP
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<KafkaOrigin>,
+    pub ingestion_timestamp: String,
+}
+
+/// Exact Kafka origin of a CDR, so that any record can be traced back to
+/// its source message
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KafkaOrigin {
+    pub partition: i32,
+    pub offset: i64,
+    /// Message key, lossily decoded as UTF-8
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Broker or producer timestamp of the message (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub producer_id: Option<String>,
+}
+
+impl Contract for ProcessedCDR {
+    const NAME: &'static str = "ProcessedCDR";
+    const VERSION: u32 = 1;
+}
+
+impl ProcessedCDR {
+    pub fn new(raw_payload: String, topic: &str, country: &str, source_system: Option<String>) -> Self {
+        Self {
+            raw_payload,
+            source_topic: topic.to_string(),
+            country: country.to_string(),
+            source_system,
+            origin: None,
+            ingestion_timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn with_origin(mut self, origin: KafkaOrigin) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    /// Partition key for the output topic: IMSI, falling back to MSISDN.
+    /// Non-JSON payloads have no key and are spread round-robin.
+    pub fn subscriber_key(&self) -> Option<String> {
+        let json: serde_json::Value = serde_json::from_str(&self.raw_payload).ok()?;
+        json.get("imsi")
+            .or_else(|| json.get("msisdn"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin_serialization() {
+        let processed = ProcessedCDR::new("test".to_string(), "cdr.raw.FR", "FR", None);
+        let json = serde_json::to_value(&processed).unwrap();
+        assert!(json.get("origin").is_none());
+
+        let origin = KafkaOrigin {
+            partition: 3,
+            offset: 42,
+            trace_id: Some("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string()),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(processed.with_origin(origin.clone())).unwrap();
+        assert_eq!(json["origin"], serde_json::json!({
+            "partition": 3,
+            "offset": 42,
+            "trace_id": "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        }));
+        let decoded: ProcessedCDR = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.origin, Some(origin));
+    }
+}
@@ -0,0 +1,68 @@
+//! Fixtures for the services' tests.
+
+use crate::{EventType, ServiceType, UnifiedCDR};
+use chrono::{TimeZone, Utc};
+
+/// Unified CDR of a French subscriber of the Free network (208-15), on
+/// Tuesday 2026-03-10 at 10:00 UTC, with no event details: tests set the
+/// fields they exercise.
+pub fn unified_cdr(cdr_id: &str, event_type: EventType) -> UnifiedCDR {
+    let start = Utc.with_ymd_and_hms(2026, 3, 10, 10, 0, 0).unwrap();
+    UnifiedCDR {
+        cdr_id: cdr_id.to_string(),
+        session_id: None,
+        imsi: "208150123456789".to_string(),
+        msisdn: "+33612345678".to_string(),
+        imei: None,
+        device_model: None,
+        event_type,
+        service_type: ServiceType::Standard,
+        start_timestamp: start,
+        end_timestamp: None,
+        duration_seconds: None,
+        timezone: None,
+        local_start_timestamp: None,
+        utc_offset_seconds: None,
+        is_dst: None,
+        country_code: "FR".to_string(),
+        mcc: Some("208".to_string()),
+        mnc: Some("15".to_string()),
+        lac: None,
+        cell_id: None,
+        calling_number: None,
+        called_number: None,
+        call_type: None,
+        bytes_uploaded: None,
+        bytes_downloaded: None,
+        apn: None,
+        sms_type: None,
+        message_length: None,
+        is_roaming: false,
+        visited_country: None,
+        visited_network: None,
+        roaming_type: None,
+        roaming_zone: None,
+        charging_id: None,
+        rated_amount: None,
+        currency: None,
+        partial_records: None,
+        incomplete: false,
+        normalization_timestamp: start.to_rfc3339(),
+        source_system: "test".to_string(),
+        raw_data_hash: format!("hash-{}", cdr_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_cdr_round_trip() {
+        let cdr = unified_cdr("test-1", EventType::Voice);
+        let decoded: UnifiedCDR = crate::from_str(&crate::to_json(&cdr).unwrap()).unwrap();
+        assert_eq!(decoded.cdr_id, "test-1");
+        assert_eq!(decoded.event_type, EventType::Voice);
+        assert_eq!(decoded.start_timestamp, cdr.start_timestamp);
+    }
+}
@@ -0,0 +1,236 @@
+//! CDR in the unified schema, produced by orion-normalization.
+
+use crate::{Contract, EventType};
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Unified CDR schema (normalized)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnifiedCDR {
+    // Identifiers
+    pub cdr_id: String,
+    pub session_id: Option<String>,
+
+    // Subscriber info
+    pub imsi: String,
+    pub msisdn: String,
+    pub imei: Option<String>,
+    /// Device model looked up from the IMEI's TAC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_model: Option<String>,
+
+    // Event classification
+    pub event_type: EventType,
+    pub service_type: ServiceType,
+
+    // Temporal
+    pub start_timestamp: DateTime<Utc>,
+    pub end_timestamp: Option<DateTime<Utc>>,
+    pub duration_seconds: Option<i64>,
+    /// IANA timezone where the subscriber is: of the visited country when
+    /// roaming, of the CDR's country otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Start in the local time of the subscriber, with its UTC offset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_start_timestamp: Option<DateTime<FixedOffset>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utc_offset_seconds: Option<i32>,
+    /// Daylight saving time in effect at the start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_dst: Option<bool>,
+
+    // Location
+    pub country_code: String,
+    pub mcc: Option<String>, // Mobile Country Code
+    pub mnc: Option<String>, // Mobile Network Code
+    pub lac: Option<String>, // Location Area Code
+    pub cell_id: Option<String>,
+
+    // Voice specific
+    pub calling_number: Option<String>,
+    pub called_number: Option<String>,
+    pub call_type: Option<CallType>,
+
+    // Data specific
+    pub bytes_uploaded: Option<i64>,
+    pub bytes_downloaded: Option<i64>,
+    pub apn: Option<String>,
+
+    // SMS specific
+    pub sms_type: Option<SmsType>,
+    pub message_length: Option<i32>,
+
+    // Roaming
+    pub is_roaming: bool,
+    pub visited_country: Option<String>,
+    pub visited_network: Option<String>,
+    /// Home network of the subscriber compared with the serving network
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roaming_type: Option<RoamingType>,
+    /// Zone of the home and visited countries for international roaming
+    /// (`eu` for Roam Like At Home, `world` otherwise)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roaming_zone: Option<String>,
+
+    // Charging
+    pub charging_id: Option<String>,
+    pub rated_amount: Option<f64>,
+    pub currency: Option<String>,
+
+    // Partial records
+    /// Number of partial records merged into this one, absent for a record
+    /// emitted in one piece
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partial_records: Option<u32>,
+    /// The merged record was closed by the aggregation timeout, its last
+    /// partial record never having been received
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub incomplete: bool,
+
+    // Metadata
+    pub normalization_timestamp: String,
+    pub source_system: String,
+    pub raw_data_hash: String,
+}
+
+impl Contract for UnifiedCDR {
+    const NAME: &'static str = "UnifiedCDR";
+    const VERSION: u32 = 1;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceType {
+    Standard,
+    Premium,
+    Roaming,
+    Emergency,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CallType {
+    Mobile,
+    Landline,
+    International,
+    Emergency,
+    Premium,
+    #[serde(rename = "toll_free")]
+    TollFree,
+    #[serde(rename = "short_code")]
+    ShortCode,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmsType {
+    MtSms, // Mobile Terminated
+    MoSms, // Mobile Originated
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RoamingType {
+    /// Served by a home network of the subscriber
+    Domestic,
+    /// Served by another network of the subscriber's country
+    NationalRoaming,
+    /// Foreign subscriber served in the CDR's country
+    InboundInternational,
+    /// Subscriber of the CDR's country served abroad
+    OutboundInternational,
+}
+
+impl ServiceType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServiceType::Standard => "standard",
+            ServiceType::Premium => "premium",
+            ServiceType::Roaming => "roaming",
+            ServiceType::Emergency => "emergency",
+            ServiceType::Unknown => "unknown",
+        }
+    }
+}
+
+impl CallType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CallType::Mobile => "mobile",
+            CallType::Landline => "landline",
+            CallType::International => "international",
+            CallType::Emergency => "emergency",
+            CallType::Premium => "premium",
+            CallType::TollFree => "toll_free",
+            CallType::ShortCode => "short_code",
+            CallType::Unknown => "unknown",
+        }
+    }
+}
+
+impl RoamingType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoamingType::Domestic => "domestic",
+            RoamingType::NationalRoaming => "national_roaming",
+            RoamingType::InboundInternational => "inbound_international",
+            RoamingType::OutboundInternational => "outbound_international",
+        }
+    }
+}
+
+impl SmsType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SmsType::MtSms => "mtsms",
+            SmsType::MoSms => "mosms",
+            SmsType::Unknown => "unknown",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enum_names_match_serialization() {
+        let name = |value: serde_json::Value| value.as_str().unwrap().to_string();
+        for call_type in [
+            CallType::Mobile,
+            CallType::Landline,
+            CallType::International,
+            CallType::Emergency,
+            CallType::Premium,
+            CallType::TollFree,
+            CallType::ShortCode,
+            CallType::Unknown,
+        ] {
+            assert_eq!(name(serde_json::to_value(&call_type).unwrap()), call_type.as_str());
+        }
+        for service_type in [
+            ServiceType::Standard,
+            ServiceType::Premium,
+            ServiceType::Roaming,
+            ServiceType::Emergency,
+            ServiceType::Unknown,
+        ] {
+            assert_eq!(name(serde_json::to_value(&service_type).unwrap()), service_type.as_str());
+        }
+        for roaming_type in [
+            RoamingType::Domestic,
+            RoamingType::NationalRoaming,
+            RoamingType::InboundInternational,
+            RoamingType::OutboundInternational,
+        ] {
+            assert_eq!(name(serde_json::to_value(roaming_type).unwrap()), roaming_type.as_str());
+        }
+        for sms_type in [SmsType::MtSms, SmsType::MoSms, SmsType::Unknown] {
+            assert_eq!(name(serde_json::to_value(&sms_type).unwrap()), sms_type.as_str());
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! CDR accepted by orion-validation.
+
+use crate::Contract;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Validated CDR ready for normalization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidatedCDR {
+    pub cdr_id: String,
+    pub event_type: EventType,
+    pub imsi: String,
+    pub msisdn: String,
+    pub timestamp: DateTime<Utc>,
+    pub country: String,
+    /// Source system of the ingestion route, selecting the normalization
+    /// mapping
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_system: Option<String>,
+    pub raw_data: serde_json::Value,
+    pub validation_timestamp: String,
+    /// Warnings raised by the checks, which do not reject the CDR
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Violation>,
+}
+
+impl Contract for ValidatedCDR {
+    const NAME: &'static str = "ValidatedCDR";
+    const VERSION: u32 = 1;
+}
+
+/// Event type classification
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventType {
+    Voice,
+    Data,
+    Sms,
+    Unknown,
+}
+
+impl EventType {
+    /// Event type of a raw CDR's `event_type`, in any case
+    pub fn parse(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "voice" => EventType::Voice,
+            "data" => EventType::Data,
+            "sms" => EventType::Sms,
+            _ => EventType::Unknown,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::Voice => "voice",
+            EventType::Data => "data",
+            EventType::Sms => "sms",
+            EventType::Unknown => "unknown",
+        }
+    }
+}
+
+/// Validation rule broken by a CDR
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Violation {
+    pub field: String,
+    pub error_type: String,
+    pub message: String,
+    /// Rule set of the broken rule, absent for built-in checks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_set: Option<String>,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+impl Violation {
+    /// Violation of a built-in check, outside of any rule set
+    pub fn new(field: &str, error_type: &str, message: String, severity: Severity) -> Self {
+        Self {
+            field: field.to_string(),
+            error_type: error_type.to_string(),
+            message,
+            rule_set: None,
+            severity,
+        }
+    }
+}
+
+/// Whether a violation rejects the CDR or is only reported with it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_type_serialization() {
+        for event in [EventType::Voice, EventType::Data, EventType::Sms, EventType::Unknown] {
+            let json = serde_json::to_string(&event).unwrap();
+            assert_eq!(json, format!("\"{}\"", event.as_str()));
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Compatibility of the contracts with the payloads already on the topics.
+//!
+//! The fixtures are version 1 payloads as published before `schema_version`
+//! existed. They must keep decoding, and re-encode to the same fields.
+
+use orion_model::{
+    from_str, to_json, CallType, Contract, ContractError, EnrichedCDR, EventType, ProcessedCDR, ServiceType,
+    UnifiedCDR, ValidatedCDR, SCHEMA_VERSION_FIELD,
+};
+use serde_json::Value;
+
+const PROCESSED_V1: &str = include_str!("fixtures/processed_v1.json");
+const VALIDATED_V1: &str = include_str!("fixtures/validated_v1.json");
+const UNIFIED_V1: &str = include_str!("fixtures/unified_v1.json");
+const ENRICHED_V1: &str = include_str!("fixtures/enriched_v1.json");
+
+/// Decode a fixture, re-encode it and compare the fields
+fn assert_round_trip<C: Contract>(fixture: &str) -> C {
+    let cdr: C = from_str(fixture).unwrap();
+    let mut encoded: Value = serde_json::from_str(&to_json(&cdr).unwrap()).unwrap();
+    assert_eq!(encoded[SCHEMA_VERSION_FIELD], C::VERSION);
+
+    encoded.as_object_mut().unwrap().remove(SCHEMA_VERSION_FIELD);
+    assert_eq!(encoded, serde_json::from_str::<Value>(fixture).unwrap(), "{} fields changed", C::NAME);
+    cdr
+}
+
+/// Fixture with `schema_version` and an unknown field added
+fn with_field(fixture: &str, name: &str, value: Value) -> String {
+    let mut json: Value = serde_json::from_str(fixture).unwrap();
+    json.as_object_mut().unwrap().insert(name.to_string(), value);
+    json.to_string()
+}
+
+#[test]
+fn test_processed_v1() {
+    let cdr: ProcessedCDR = assert_round_trip(PROCESSED_V1);
+    assert_eq!(cdr.subscriber_key().as_deref(), Some("208150123456789"));
+    assert_eq!(cdr.origin.unwrap().offset, 42);
+}
+
+#[test]
+fn test_validated_v1() {
+    let cdr: ValidatedCDR = assert_round_trip(VALIDATED_V1);
+    assert_eq!(cdr.event_type, EventType::Voice);
+    assert_eq!(cdr.warnings.len(), 1);
+}
+
+#[test]
+fn test_unified_v1() {
+    let cdr: UnifiedCDR = assert_round_trip(UNIFIED_V1);
+    assert_eq!(cdr.country_code, "FR");
+    assert_eq!(cdr.call_type, Some(CallType::International));
+}
+
+#[test]
+fn test_enriched_v1() {
+    let cdr: EnrichedCDR = assert_round_trip(ENRICHED_V1);
+    assert_eq!(cdr.unified.service_type, ServiceType::Roaming);
+    assert_eq!(cdr.unified.raw_data_hash, "5d41402abc4b2a76b9719d911017c592");
+    assert_eq!(cdr.network_info.unwrap().handover_count, Some(2));
+}
+
+#[test]
+fn test_enriched_is_unified_with_enrichments() {
+    // Consumers of cdr.normalized can read cdr.enriched
+    let enriched: EnrichedCDR = from_str(ENRICHED_V1).unwrap();
+    let unified: UnifiedCDR = from_str(ENRICHED_V1).unwrap();
+    assert_eq!(serde_json::to_value(&enriched.unified).unwrap(), serde_json::to_value(&unified).unwrap());
+}
+
+#[test]
+fn test_optional_fields_may_be_absent() {
+    // Fields added with a default since version 1
+    let mut json: Value = serde_json::from_str(UNIFIED_V1).unwrap();
+    json.as_object_mut().unwrap().remove("device_model");
+    let cdr: UnifiedCDR = from_str(&json.to_string()).unwrap();
+    assert_eq!(cdr.device_model, None);
+
+    let mut json: Value = serde_json::from_str(VALIDATED_V1).unwrap();
+    json.as_object_mut().unwrap().remove("warnings");
+    let cdr: ValidatedCDR = from_str(&json.to_string()).unwrap();
+    assert!(cdr.warnings.is_empty());
+    assert_eq!(cdr.source_system, None);
+}
+
+#[test]
+fn test_validated_source_system() {
+    // Optional field added in version 1: absent from the fixture, written
+    // only when set
+    let payload = with_field(VALIDATED_V1, "source_system", Value::from("fr-mediation"));
+    let cdr: ValidatedCDR = from_str(&payload).unwrap();
+    assert_eq!(cdr.source_system.as_deref(), Some("fr-mediation"));
+
+    let encoded: Value = serde_json::from_str(&to_json(&cdr).unwrap()).unwrap();
+    assert_eq!(encoded["source_system"], "fr-mediation");
+}
+
+#[test]
+fn test_unknown_fields_are_ignored() {
+    let payload = with_field(UNIFIED_V1, "tariff_zone", Value::from("EU"));
+    assert!(from_str::<UnifiedCDR>(&payload).is_ok());
+    let payload = with_field(ENRICHED_V1, "tariff_zone", Value::from("EU"));
+    assert!(from_str::<EnrichedCDR>(&payload).is_ok());
+}
+
+#[test]
+fn test_newer_version_is_rejected() {
+    let payload = with_field(UNIFIED_V1, SCHEMA_VERSION_FIELD, Value::from(UnifiedCDR::VERSION + 1));
+    match from_str::<UnifiedCDR>(&payload) {
+        Err(ContractError::UnsupportedVersion { contract, version, supported }) => {
+            assert_eq!((contract, supported), ("UnifiedCDR", UnifiedCDR::VERSION));
+            assert_eq!(version, u64::from(UnifiedCDR::VERSION) + 1);
+        }
+        other => panic!("expected an unsupported version, got {:?}", other.map(|cdr| cdr.cdr_id)),
+    }
+
+    let payload = with_field(PROCESSED_V1, SCHEMA_VERSION_FIELD, Value::from("1"));
+    assert!(matches!(from_str::<ProcessedCDR>(&payload), Err(ContractError::InvalidVersion(_))));
+    let payload = with_field(PROCESSED_V1, SCHEMA_VERSION_FIELD, Value::from(0));
+    assert!(matches!(from_str::<ProcessedCDR>(&payload), Err(ContractError::InvalidVersion(_))));
+}
+
+#[test]
+fn test_renamed_field_is_an_error() {
+    // The storage layer once expected `country` and `hash`
+    let mut json: Value = serde_json::from_str(UNIFIED_V1).unwrap();
+    let fields = json.as_object_mut().unwrap();
+    let country = fields.remove("country_code").unwrap();
+    fields.insert("country".to_string(), country);
+    assert!(matches!(from_str::<UnifiedCDR>(&json.to_string()), Err(ContractError::Json(_))));
+}
@@ -4,6 +4,26 @@ use std::env;
 pub struct Config {
     pub kafka: KafkaConfig,
     pub server: ServerConfig,
+    /// Time allowed to drain in-flight work after SIGTERM/SIGINT
+    pub shutdown_timeout_secs: u64,
+    /// `tac,brand,model` file of device models; the bundled sample if unset
+    pub tac_path: Option<String>,
+    /// Mapping profiles of the raw CDRs; the bundled profiles if unset
+    pub mappings_path: Option<String>,
+    /// `country,timezone` file of the local times; the bundled table if unset
+    pub timezones_path: Option<String>,
+    /// Operator groups and roaming zones; the bundled plan if unset
+    pub roaming_path: Option<String>,
+    pub partials: PartialsConfig,
+}
+
+/// Aggregation of partial CDRs
+#[derive(Debug, Clone)]
+pub struct PartialsConfig {
+    /// File of the open sessions, saved before offsets are committed
+    pub state_path: String,
+    /// Idle time after which a session is closed without its last partial
+    pub timeout_secs: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -12,6 +32,30 @@ pub struct KafkaConfig {
     pub input_topic: String,
     pub output_topic: String,
     pub consumer_group: String,
+    pub delivery_mode: DeliveryMode,
+    pub commit_batch_size: usize,
+    pub commit_interval_ms: u64,
+    pub transactional_id: String,
+    pub transaction_timeout_ms: u64,
+}
+
+/// Delivery guarantee for consumed CDRs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryMode {
+    /// Offsets are committed in batches once the downstream produce is acknowledged
+    AtLeastOnce,
+    /// Offsets are committed atomically with the produced records
+    ExactlyOnce,
+}
+
+impl DeliveryMode {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "at_least_once" | "at-least-once" => Ok(Self::AtLeastOnce),
+            "exactly_once" | "exactly-once" => Ok(Self::ExactlyOnce),
+            other => anyhow::bail!("Invalid KAFKA_DELIVERY_MODE: {}", other),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -34,12 +78,57 @@ impl Config {
         let consumer_group = env::var("KAFKA_CONSUMER_GROUP")
             .unwrap_or_else(|_| "orion-normalization".to_string());
         
+        let delivery_mode = DeliveryMode::parse(
+            &env::var("KAFKA_DELIVERY_MODE").unwrap_or_else(|_| "at_least_once".to_string()),
+        )?;
+
+        let commit_batch_size = env::var("KAFKA_COMMIT_BATCH_SIZE")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<usize>()?;
+
+        let commit_interval_ms = env::var("KAFKA_COMMIT_INTERVAL_MS")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse::<u64>()?;
+
+        // Must be unique per running instance: a new producer with the same
+        // id fences the previous one
+        let transactional_id = env::var("KAFKA_TRANSACTIONAL_ID").unwrap_or_else(|_| {
+            format!(
+                "{}-{}",
+                consumer_group,
+                env::var("HOSTNAME").unwrap_or_else(|_| "0".to_string())
+            )
+        });
+
+        let transaction_timeout_ms = env::var("KAFKA_TRANSACTION_TIMEOUT_MS")
+            .unwrap_or_else(|_| "60000".to_string())
+            .parse::<u64>()?;
+        
         let server_host = env::var("SERVER_HOST")
             .unwrap_or_else(|_| "0.0.0.0".to_string());
         
         let server_port = env::var("SERVER_PORT")
             .unwrap_or_else(|_| "8083".to_string())
             .parse::<u16>()?;
+
+        let shutdown_timeout_secs = env::var("SHUTDOWN_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "25".to_string())
+            .parse::<u64>()?;
+
+        let tac_path = env::var("NORMALIZATION_TAC_PATH").ok().filter(|path| !path.is_empty());
+
+        let mappings_path = env::var("NORMALIZATION_MAPPINGS_PATH").ok().filter(|path| !path.is_empty());
+
+        let timezones_path = env::var("NORMALIZATION_TIMEZONES_PATH").ok().filter(|path| !path.is_empty());
+
+        let roaming_path = env::var("NORMALIZATION_ROAMING_PATH").ok().filter(|path| !path.is_empty());
+
+        let partials_state_path = env::var("NORMALIZATION_PARTIALS_STATE_PATH")
+            .unwrap_or_else(|_| "data/partials.json".to_string());
+
+        let partials_timeout_secs = env::var("NORMALIZATION_PARTIALS_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "7200".to_string())
+            .parse::<u64>()?;
         
         Ok(Config {
             kafka: KafkaConfig {
@@ -47,11 +136,25 @@ impl Config {
                 input_topic,
                 output_topic,
                 consumer_group,
+                delivery_mode,
+                commit_batch_size,
+                commit_interval_ms,
+                transactional_id,
+                transaction_timeout_ms,
             },
             server: ServerConfig {
                 host: server_host,
                 port: server_port,
             },
+            shutdown_timeout_secs,
+            tac_path,
+            mappings_path,
+            timezones_path,
+            roaming_path,
+            partials: PartialsConfig {
+                state_path: partials_state_path,
+                timeout_secs: partials_timeout_secs,
+            },
         })
     }
 }
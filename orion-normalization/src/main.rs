@@ -3,15 +3,23 @@ mod metrics;
 mod routes;
 mod service;
 
-use axum::{Router, routing::get};
+use axum::{Router, routing::{get, post}};
 use config::Config;
 use metrics_exporter_prometheus::PrometheusBuilder;
-use service::KafkaConsumerService;
+use orion_shutdown::Shutdown;
+use service::{Aggregator, KafkaConsumerService, Normalizer};
+use service::dry_run::{self, DryRun};
 use std::net::SocketAddr;
+use std::time::Duration;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // Offline mapping dry-run, without Kafka nor HTTP server
+    if std::env::args().nth(1).as_deref() == Some("dry-run") {
+        return dry_run_offline();
+    }
+
     // Initialize tracing
     tracing_subscriber::registry()
         .with(
@@ -26,11 +34,12 @@ async fn main() -> anyhow::Result<()> {
     // Load configuration
     let config = Config::from_env()?;
     tracing::info!(
-        "Configuration loaded - Server: {}:{}, Input: {}, Output: {}",
+        "Configuration loaded - Server: {}:{}, Input: {}, Output: {}, Delivery: {:?}",
         config.server.host,
         config.server.port,
         config.kafka.input_topic,
         config.kafka.output_topic,
+        config.kafka.delivery_mode,
     );
 
     // Initialize metrics
@@ -40,12 +49,30 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Metrics initialized");
 
     // Create Kafka consumer service
-    let kafka_service = KafkaConsumerService::new(&config.kafka)?;
+    let normalizer = Normalizer::from_config(
+        config.tac_path.as_deref(),
+        config.mappings_path.as_deref(),
+        config.timezones_path.as_deref(),
+        config.roaming_path.as_deref(),
+    )?;
+    let aggregator = Aggregator::open(
+        &config.partials.state_path,
+        Duration::from_secs(config.partials.timeout_secs),
+    )?;
+    tracing::info!(
+        "Partial aggregation: {} open sessions resumed from {}, timeout {}s",
+        aggregator.open_sessions(),
+        config.partials.state_path,
+        config.partials.timeout_secs,
+    );
+    let kafka_service = KafkaConsumerService::new(&config.kafka, normalizer.clone(), aggregator)?;
     tracing::info!("Kafka consumer service initialized");
 
-    // Spawn Kafka consumer
-    tokio::spawn(async move {
-        if let Err(e) = kafka_service.run().await {
+    // Spawn Kafka consumer, stopped by SIGTERM/SIGINT
+    let shutdown = Shutdown::on_signal();
+    let consumer_shutdown = shutdown.clone();
+    let consumer_handle = tokio::spawn(async move {
+        if let Err(e) = kafka_service.run(consumer_shutdown).await {
             tracing::error!("Kafka consumer error: {}", e);
         }
     });
@@ -56,13 +83,44 @@ async fn main() -> anyhow::Result<()> {
         .route("/metrics", get({
             let handle = prometheus_handle.clone();
             move || routes::metrics(handle)
-        }));
+        }))
+        .route("/mappings", get(routes::mappings))
+        .route("/mappings/dry-run", post(routes::dry_run))
+        .with_state(normalizer);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], config.server.port));
     tracing::info!("Starting HTTP server on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    let mut server_shutdown = shutdown.clone();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move { server_shutdown.requested().await })
+        .await?;
+
+    // Let the consumer finish its message and commit, within the deadline
+    let timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    if tokio::time::timeout(timeout, consumer_handle).await.is_err() {
+        tracing::warn!("Shutdown deadline of {:?} exceeded, exiting with work in flight", timeout);
+    }
+    tracing::info!("orion-normalization service stopped");
 
     Ok(())
 }
+
+/// `orion-normalization dry-run`: a `POST /mappings/dry-run` request read
+/// from stdin and normalized with the configured reference data, its report
+/// written to stdout
+fn dry_run_offline() -> anyhow::Result<()> {
+    let config = Config::from_env()?;
+    let normalizer = Normalizer::from_config(
+        config.tac_path.as_deref(),
+        config.mappings_path.as_deref(),
+        config.timezones_path.as_deref(),
+        config.roaming_path.as_deref(),
+    )?;
+    let request: DryRun = serde_json::from_reader(std::io::stdin().lock())?;
+    let report = dry_run::run(&normalizer, request)?;
+    serde_json::to_writer_pretty(std::io::stdout().lock(), &report)?;
+    println!();
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use metrics::{describe_counter, describe_histogram, counter, histogram};
+use metrics::{describe_counter, describe_gauge, describe_histogram, counter, gauge, histogram};
 
 pub fn init_metrics() {
     describe_counter!(
@@ -26,6 +26,26 @@ pub fn init_metrics() {
         "Total SMS CDRs normalized"
     );
     
+    describe_counter!(
+        "orion_normalization_mapping_profile_total",
+        "Total CDRs normalized, by mapping profile"
+    );
+    
+    describe_counter!(
+        "orion_normalization_partials_total",
+        "Total partial CDRs received for aggregation"
+    );
+    
+    describe_counter!(
+        "orion_normalization_partials_merged_total",
+        "Total partial sessions merged, by closing (complete or timeout)"
+    );
+    
+    describe_gauge!(
+        "orion_normalization_partial_sessions",
+        "Partial sessions waiting for their remaining partials"
+    );
+    
     describe_histogram!(
         "orion_normalization_latency_seconds",
         "Normalization latency in seconds"
@@ -48,6 +68,22 @@ pub fn increment_sms_total() {
     counter!("orion_normalization_sms_total").increment(1);
 }
 
+pub fn increment_mapping_profile(profile: &str) {
+    counter!("orion_normalization_mapping_profile_total", "profile" => profile.to_string()).increment(1);
+}
+
+pub fn increment_partials_total() {
+    counter!("orion_normalization_partials_total").increment(1);
+}
+
+pub fn increment_partials_merged(closed: &'static str) {
+    counter!("orion_normalization_partials_merged_total", "closed" => closed).increment(1);
+}
+
+pub fn set_partial_sessions(sessions: usize) {
+    gauge!("orion_normalization_partial_sessions").set(sessions as f64);
+}
+
 pub fn record_latency(duration: f64) {
     histogram!("orion_normalization_latency_seconds").record(duration);
 }
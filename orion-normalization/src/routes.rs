@@ -1,5 +1,9 @@
-use axum::{response::IntoResponse, http::StatusCode};
+use axum::{extract::State, response::IntoResponse, http::StatusCode, Json};
 use metrics_exporter_prometheus::PrometheusHandle;
+use serde_json::json;
+
+use crate::service::dry_run::{self, DryRun, DryRunError};
+use crate::service::Normalizer;
 
 pub async fn health() -> impl IntoResponse {
     (StatusCode::OK, "OK")
@@ -8,3 +12,23 @@ pub async fn health() -> impl IntoResponse {
 pub async fn metrics(handle: PrometheusHandle) -> impl IntoResponse {
     handle.render()
 }
+
+/// Mapping profiles currently applied
+pub async fn mappings(State(normalizer): State<Normalizer>) -> impl IntoResponse {
+    Json(normalizer.mappings().describe())
+}
+
+/// Normalize sample CDRs without publishing them, to check a mapping
+/// profile before deploying it
+pub async fn dry_run(
+    State(normalizer): State<Normalizer>,
+    Json(request): Json<DryRun>,
+) -> impl IntoResponse {
+    match dry_run::run(&normalizer, request) {
+        Ok(report) => (StatusCode::OK, Json(report)),
+        Err(e @ DryRunError::InvalidMappings(_)) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({"error": e.to_string()})))
+        }
+        Err(e @ DryRunError::NoRecords) => (StatusCode::BAD_REQUEST, Json(json!({"error": e.to_string()}))),
+    }
+}
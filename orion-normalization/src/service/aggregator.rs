@@ -0,0 +1,471 @@
+//! Aggregation of partial CDRs.
+//!
+//! Switches split long calls, and gateways split data sessions on volume or
+//! time limits, into partial records sharing a charging id (or session id).
+//! The partials of a subscriber's session are held until the last one and
+//! every sequence number before it have been received, then merged into one
+//! CDR: bytes, durations and amounts summed, first start, last end, the
+//! other fields (and the cdr_id) taken from the first partial. A session
+//! receiving no partial for the timeout is closed as it stands and flagged
+//! incomplete, as is a session whose total overflows (the total is then
+//! left out).
+//!
+//! Changes are undone by `rollback`, so a batch replayed after a failure is
+//! aggregated again from the same state. The open sessions are saved by
+//! `persist` before the input offsets are committed: a restart resumes them
+//! instead of losing their partials. Changes not committed yet are saved
+//! with their undo log and the input offsets that commit them; once the
+//! state is reopened, `reconcile` keeps them if those offsets were
+//! committed and undoes them otherwise, so a crash between the save and the
+//! commit replays the batch from the state before it.
+
+use crate::metrics;
+use crate::service::model::UnifiedCDR;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Minimum time between two searches for expired sessions
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Position of a record in a series of partial records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Partial {
+    pub sequence: i64,
+    /// The record closes the series
+    pub last: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Session {
+    /// Partials by sequence number
+    partials: BTreeMap<i64, UnifiedCDR>,
+    /// Sequence number of the last partial, once received
+    last: Option<i64>,
+    /// Arrival of the latest partial
+    updated_at: DateTime<Utc>,
+}
+
+impl Session {
+    /// Whether the last partial and every partial before it were received,
+    /// the series starting at 0 or 1
+    fn is_complete(&self) -> bool {
+        let (Some(last), Some(&first), Some(&max)) =
+            (self.last, self.partials.keys().next(), self.partials.keys().next_back())
+        else {
+            return false;
+        };
+        let span = last.checked_sub(first).and_then(|span| span.checked_add(1));
+        first <= 1 && max == last && span == Some(self.partials.len() as i64)
+    }
+
+    fn merge(self, incomplete: bool) -> UnifiedCDR {
+        let count = self.partials.len() as u32;
+        let partials: Vec<UnifiedCDR> = self.partials.into_values().collect();
+        let mut merged = partials.first().cloned().expect("a session holds at least one partial");
+        let mut hasher = Sha256::new();
+        hasher.update(merged.raw_data_hash.as_bytes());
+
+        for partial in &partials[1..] {
+            merged.start_timestamp = merged.start_timestamp.min(partial.start_timestamp);
+            merged.end_timestamp = merged.end_timestamp.max(partial.end_timestamp);
+            hasher.update(partial.raw_data_hash.as_bytes());
+        }
+
+        let mut overflow = false;
+        let mut sum = |field: fn(&UnifiedCDR) -> Option<i64>| {
+            total(&partials, field).unwrap_or_else(|| {
+                overflow = true;
+                None
+            })
+        };
+        merged.duration_seconds = sum(|cdr| cdr.duration_seconds);
+        merged.bytes_uploaded = sum(|cdr| cdr.bytes_uploaded);
+        merged.bytes_downloaded = sum(|cdr| cdr.bytes_downloaded);
+        merged.rated_amount = partials
+            .iter()
+            .filter_map(|cdr| cdr.rated_amount)
+            .reduce(|a, b| a + b);
+        if overflow {
+            warn!("Partial session of CDR {} overflows its totals, flagged incomplete", merged.cdr_id);
+        }
+
+        merged.partial_records = Some(count);
+        merged.incomplete = incomplete || overflow;
+        merged.raw_data_hash = format!("{:x}", hasher.finalize());
+        merged.normalization_timestamp = Utc::now().to_rfc3339();
+        merged
+    }
+}
+
+/// Sum of a field over the partials giving it: `Some(None)` when none
+/// does, `None` when the sum overflows
+fn total(partials: &[UnifiedCDR], field: fn(&UnifiedCDR) -> Option<i64>) -> Option<Option<i64>> {
+    partials
+        .iter()
+        .filter_map(field)
+        .try_fold(None, |total: Option<i64>, value| match total {
+            Some(total) => total.checked_add(value).map(Some),
+            None => Some(Some(value)),
+        })
+}
+
+/// Next input offset of a partition: (topic, partition, offset)
+pub type PendingOffset = (String, i32, i64);
+
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+    sessions: HashMap<String, Session>,
+    /// Value before the first uncommitted change, by session key
+    #[serde(default)]
+    undo: HashMap<String, Option<Session>>,
+    /// Input offsets committing the changes, as of the last `persist`
+    #[serde(default)]
+    pending: Vec<PendingOffset>,
+    /// Changed since the last `persist`
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl State {
+    /// Remember the committed value of a session before changing it
+    fn touch(&mut self, key: &str) {
+        if !self.undo.contains_key(key) {
+            self.undo.insert(key.to_string(), self.sessions.get(key).cloned());
+        }
+        self.dirty = true;
+    }
+}
+
+pub struct Aggregator {
+    path: PathBuf,
+    timeout: chrono::Duration,
+    state: Mutex<State>,
+    last_sweep: Mutex<Instant>,
+}
+
+impl Aggregator {
+    /// Aggregator resuming the sessions saved in the state file, starting
+    /// empty if the file does not exist yet. Changes saved before their
+    /// commit are kept pending until `reconcile`.
+    pub fn open(path: impl Into<PathBuf>, timeout: Duration) -> anyhow::Result<Self> {
+        let path = path.into();
+        let state: State = match std::fs::read(&path) {
+            Ok(contents) => serde_json::from_slice(&contents)
+                .with_context(|| format!("Invalid partial sessions file {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => State::default(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read partial sessions file {}", path.display()))
+            }
+        };
+        metrics::set_partial_sessions(state.sessions.len());
+
+        Ok(Self {
+            path,
+            timeout: chrono::Duration::from_std(timeout)?,
+            state: Mutex::new(state),
+            last_sweep: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Input offsets committing the changes saved by the last run
+    pub fn pending_offsets(&self) -> Vec<PendingOffset> {
+        self.lock().pending.clone()
+    }
+
+    /// Keep the changes saved by the last run if their input offsets were
+    /// committed (`committed` gives the committed offset of a partition),
+    /// undo them otherwise so that their batch is aggregated again
+    pub fn reconcile(&self, committed: impl Fn(&str, i32) -> Option<i64>) -> anyhow::Result<()> {
+        let kept = self
+            .lock()
+            .pending
+            .iter()
+            .all(|(topic, partition, offset)| committed(topic, *partition).is_some_and(|c| c >= *offset));
+        if kept {
+            self.commit();
+        } else {
+            warn!("Partial sessions saved before an uncommitted batch, undoing its changes");
+            self.rollback();
+        }
+        self.persist(&[])
+    }
+
+    /// Add a partial record: the merged CDR when it completes its session,
+    /// `None` while the session stays open. A partial with neither charging
+    /// id nor session id cannot be aggregated and is returned as is.
+    pub fn add(&self, cdr: UnifiedCDR, partial: Partial) -> Option<UnifiedCDR> {
+        metrics::increment_partials_total();
+        let Some(key) = session_key(&cdr) else {
+            warn!("Partial CDR {} has no charging or session id, not aggregated", cdr.cdr_id);
+            return Some(cdr);
+        };
+
+        let mut state = self.lock();
+        state.touch(&key);
+        let session = state.sessions.entry(key.clone()).or_insert_with(|| Session {
+            partials: BTreeMap::new(),
+            last: None,
+            updated_at: Utc::now(),
+        });
+        // A replayed partial is already held
+        session.partials.entry(partial.sequence).or_insert(cdr);
+        if partial.last {
+            session.last = Some(partial.sequence);
+        }
+        session.updated_at = Utc::now();
+
+        let merged = if session.is_complete() {
+            let session = state.sessions.remove(&key)?;
+            metrics::increment_partials_merged("complete");
+            Some(session.merge(false))
+        } else {
+            None
+        };
+        metrics::set_partial_sessions(state.sessions.len());
+        merged
+    }
+
+    /// Sessions without a partial for the timeout, closed and merged.
+    /// Searched at most once per second.
+    pub fn expired(&self) -> Vec<UnifiedCDR> {
+        {
+            let mut last_sweep = self.last_sweep.lock().unwrap_or_else(|e| e.into_inner());
+            if last_sweep.elapsed() < SWEEP_INTERVAL {
+                return Vec::new();
+            }
+            *last_sweep = Instant::now();
+        }
+        self.expired_at(Utc::now())
+    }
+
+    fn expired_at(&self, now: DateTime<Utc>) -> Vec<UnifiedCDR> {
+        let mut state = self.lock();
+        let keys: Vec<String> = state
+            .sessions
+            .iter()
+            .filter(|(_, session)| now - session.updated_at >= self.timeout)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let mut merged = Vec::new();
+        for key in keys {
+            state.touch(&key);
+            if let Some(session) = state.sessions.remove(&key) {
+                warn!("Partial session {} timed out with {} partials", key, session.partials.len());
+                metrics::increment_partials_merged("timeout");
+                merged.push(session.merge(true));
+            }
+        }
+        metrics::set_partial_sessions(state.sessions.len());
+        merged
+    }
+
+    /// The changes are committed downstream: keep them
+    pub fn commit(&self) {
+        let mut state = self.lock();
+        state.undo.clear();
+        state.pending.clear();
+    }
+
+    /// The changes will be replayed: undo them
+    pub fn rollback(&self) {
+        let mut state = self.lock();
+        state.dirty |= !state.undo.is_empty();
+        state.pending.clear();
+        for (key, session) in std::mem::take(&mut state.undo) {
+            match session {
+                Some(session) => state.sessions.insert(key, session),
+                None => state.sessions.remove(&key),
+            };
+        }
+        metrics::set_partial_sessions(state.sessions.len());
+    }
+
+    /// Save the open sessions if they changed, with the undo log of the
+    /// uncommitted changes and the input offsets (`pending`) whose commit
+    /// commits them. Written to a temporary file then renamed, so a crash
+    /// never leaves a truncated state.
+    pub fn persist(&self, pending: &[PendingOffset]) -> anyhow::Result<()> {
+        let mut state = self.lock();
+        if !state.dirty {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        state.pending = pending.to_vec();
+        let tmp = tmp_path(&self.path);
+        std::fs::write(&tmp, serde_json::to_vec(&*state)?)
+            .with_context(|| format!("Failed to write partial sessions file {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)?;
+        state.dirty = false;
+        Ok(())
+    }
+
+    pub fn open_sessions(&self) -> usize {
+        self.lock().sessions.len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Session of a partial: its subscriber and charging id, or session id
+fn session_key(cdr: &UnifiedCDR) -> Option<String> {
+    let id = cdr.charging_id.as_ref().or(cdr.session_id.as_ref())?;
+    Some(format!("{}/{}", cdr.imsi, id))
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::model::EventType;
+    use chrono::TimeZone;
+    use orion_model::testing;
+
+    fn partial_cdr(sequence: i64, bytes: i64, start_minute: u32) -> UnifiedCDR {
+        let start = Utc.with_ymd_and_hms(2026, 1, 29, 10, start_minute, 0).unwrap();
+        let mut cdr = testing::unified_cdr(&format!("cdr-{}", sequence), EventType::Data);
+        cdr.start_timestamp = start;
+        cdr.end_timestamp = Some(start + chrono::Duration::minutes(30));
+        cdr.duration_seconds = Some(1800);
+        cdr.bytes_uploaded = Some(bytes);
+        cdr.apn = Some("internet".to_string());
+        cdr.charging_id = Some("4711".to_string());
+        cdr.source_system = "fr-mediation".to_string();
+        cdr
+    }
+
+    fn state_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("orion-partials-{}-{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_merge_out_of_order_partials() {
+        let aggregator = Aggregator::open(state_path("merge"), Duration::from_secs(3600)).unwrap();
+        let partial = |sequence, last| Partial { sequence, last };
+
+        assert!(aggregator.add(partial_cdr(1, 100, 0), partial(1, false)).is_none());
+        // Last partial received before the second one: still open
+        assert!(aggregator.add(partial_cdr(3, 300, 30), partial(3, true)).is_none());
+        // Replayed partial is ignored
+        assert!(aggregator.add(partial_cdr(1, 100, 0), partial(1, false)).is_none());
+
+        let merged = aggregator.add(partial_cdr(2, 200, 15), partial(2, false)).unwrap();
+        assert_eq!(merged.cdr_id, "cdr-1");
+        assert_eq!(merged.bytes_uploaded, Some(600));
+        assert_eq!(merged.bytes_downloaded, None);
+        assert_eq!(merged.duration_seconds, Some(5400));
+        assert_eq!(merged.start_timestamp, Utc.with_ymd_and_hms(2026, 1, 29, 10, 0, 0).unwrap());
+        assert_eq!(merged.end_timestamp, Some(Utc.with_ymd_and_hms(2026, 1, 29, 11, 0, 0).unwrap()));
+        assert_eq!((merged.partial_records, merged.incomplete), (Some(3), false));
+        assert_eq!(aggregator.open_sessions(), 0);
+
+        // No charging nor session id: passed through
+        let mut orphan = partial_cdr(1, 100, 0);
+        orphan.charging_id = None;
+        assert_eq!(aggregator.add(orphan, partial(1, false)).unwrap().partial_records, None);
+    }
+
+    #[test]
+    fn test_overflowing_totals_are_flagged() {
+        let aggregator = Aggregator::open(state_path("overflow"), Duration::from_secs(3600)).unwrap();
+        aggregator.add(partial_cdr(1, i64::MAX, 0), Partial { sequence: 1, last: false });
+        let merged = aggregator.add(partial_cdr(2, 1, 30), Partial { sequence: 2, last: true }).unwrap();
+        assert_eq!(merged.bytes_uploaded, None);
+        assert_eq!(merged.duration_seconds, Some(3600));
+        assert!(merged.incomplete);
+
+        // Sequence numbers far apart never complete the session
+        let mut first = partial_cdr(1, 100, 0);
+        first.charging_id = Some("4712".to_string());
+        let mut last = partial_cdr(2, 100, 30);
+        last.charging_id = Some("4712".to_string());
+        assert!(aggregator.add(first, Partial { sequence: i64::MIN, last: false }).is_none());
+        assert!(aggregator.add(last, Partial { sequence: i64::MAX, last: true }).is_none());
+    }
+
+    #[test]
+    fn test_timeout_and_rollback() {
+        let aggregator = Aggregator::open(state_path("timeout"), Duration::from_secs(600)).unwrap();
+        aggregator.add(partial_cdr(1, 100, 0), Partial { sequence: 1, last: false });
+        aggregator.commit();
+
+        aggregator.add(partial_cdr(2, 200, 15), Partial { sequence: 2, last: false });
+        aggregator.rollback();
+        assert!(aggregator.expired_at(Utc::now()).is_empty());
+
+        let expired = aggregator.expired_at(Utc::now() + chrono::Duration::minutes(10));
+        assert_eq!(expired.len(), 1);
+        assert_eq!((expired[0].bytes_uploaded, expired[0].partial_records, expired[0].incomplete), (Some(100), Some(1), true));
+
+        // Expiry not committed downstream: the session is open again
+        aggregator.rollback();
+        assert_eq!(aggregator.open_sessions(), 1);
+    }
+
+    #[test]
+    fn test_sessions_survive_restart() {
+        let path = state_path("restart");
+        let aggregator = Aggregator::open(&path, Duration::from_secs(3600)).unwrap();
+        aggregator.add(partial_cdr(1, 100, 0), Partial { sequence: 1, last: false });
+        aggregator.commit();
+        aggregator.persist(&[]).unwrap();
+        drop(aggregator);
+
+        let aggregator = Aggregator::open(&path, Duration::from_secs(3600)).unwrap();
+        assert_eq!(aggregator.open_sessions(), 1);
+        let merged = aggregator.add(partial_cdr(2, 200, 30), Partial { sequence: 2, last: true }).unwrap();
+        assert_eq!(merged.bytes_uploaded, Some(300));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_crash_before_commit_replays_the_batch() {
+        let path = state_path("crash");
+        let pending = vec![("cdr.validated".to_string(), 0, 12)];
+        let aggregator = Aggregator::open(&path, Duration::from_secs(3600)).unwrap();
+        aggregator.add(partial_cdr(1, 100, 0), Partial { sequence: 1, last: false });
+        aggregator.commit();
+        aggregator.persist(&[]).unwrap();
+
+        // The batch completes the session, is saved, then the process
+        // crashes before the transaction commits
+        assert!(aggregator.add(partial_cdr(2, 200, 30), Partial { sequence: 2, last: true }).is_some());
+        aggregator.persist(&pending).unwrap();
+        drop(aggregator);
+
+        // Offsets not committed: the batch is undone, then replayed
+        let aggregator = Aggregator::open(&path, Duration::from_secs(3600)).unwrap();
+        assert_eq!(aggregator.pending_offsets(), pending);
+        aggregator.reconcile(|_, _| Some(11)).unwrap();
+        assert_eq!(aggregator.open_sessions(), 1);
+        let merged = aggregator.add(partial_cdr(2, 200, 30), Partial { sequence: 2, last: true }).unwrap();
+        assert_eq!((merged.bytes_uploaded, merged.partial_records), (Some(300), Some(2)));
+
+        // Replay saved, crash after the commit: the batch is kept
+        aggregator.persist(&pending).unwrap();
+        drop(aggregator);
+        let aggregator = Aggregator::open(&path, Duration::from_secs(3600)).unwrap();
+        aggregator.reconcile(|topic, partition| ((topic, partition) == ("cdr.validated", 0)).then_some(12)).unwrap();
+        assert_eq!(aggregator.open_sessions(), 0);
+        assert!(aggregator.pending_offsets().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,188 @@
+//! Normalization of sample CDRs without publishing them, to check a mapping
+//! profile before deploying it.
+//!
+//! Samples are raw CDRs, as sent to orion-ingestion. The fields read by
+//! orion-validation into the `ValidatedCDR` are read the same way: event
+//! type, IMSI, MSISDN in E.164 and event time (`orion_model::event_time`).
+//! The other checks of orion-validation are not run. A sample that
+//! orion-validation would reject, or whose event time is missing, is still
+//! normalized and reported with the violation.
+
+use crate::service::model::{EventType, Severity, ValidatedCDR, Violation};
+use crate::service::{MappingProfiles, Normalizer};
+use chrono::Utc;
+use orion_model::event_time::{self, EVENT_TIME_FIELDS};
+use orion_numbering::NumberType;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Debug, Deserialize)]
+pub struct DryRun {
+    pub country: String,
+    pub source_system: Option<String>,
+    /// Mappings file to try instead of the deployed one
+    pub mappings: Option<String>,
+    /// Raw CDRs, as sent to orion-ingestion
+    pub records: Vec<Value>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DryRunError {
+    #[error("{0:#}")]
+    InvalidMappings(anyhow::Error),
+    #[error("No records to normalize")]
+    NoRecords,
+}
+
+/// Profile selected for the samples, and the `UnifiedCDR` and violations of
+/// each one
+pub fn run(normalizer: &Normalizer, dry_run: DryRun) -> Result<Value, DryRunError> {
+    let normalizer = match dry_run.mappings.as_deref().map(MappingProfiles::from_yaml) {
+        None => normalizer.clone(),
+        Some(Ok(mappings)) => normalizer.with_mappings(mappings),
+        Some(Err(e)) => return Err(DryRunError::InvalidMappings(e)),
+    };
+
+    let validated: Vec<ValidatedCDR> = dry_run
+        .records
+        .into_iter()
+        .enumerate()
+        .map(|(index, raw)| sample(index, raw, &dry_run.country, dry_run.source_system.clone()))
+        .collect();
+    let first = validated.first().ok_or(DryRunError::NoRecords)?;
+    let profile = normalizer.mapping(first).name().to_string();
+
+    let results: Vec<Value> = validated
+        .iter()
+        .map(|cdr| {
+            json!({
+                "cdr": normalizer.unify(cdr, &normalizer.mapping(cdr)),
+                "violations": cdr.warnings,
+            })
+        })
+        .collect();
+    Ok(json!({"profile": profile, "results": results}))
+}
+
+/// Validated CDR of a sample record. Fields orion-validation would reject
+/// are kept as found and reported in `warnings` with their severity.
+fn sample(index: usize, raw: Value, country: &str, source_system: Option<String>) -> ValidatedCDR {
+    let mut violations = Vec::new();
+    let mut text = |field: &str| {
+        let value = raw.get(field).and_then(Value::as_str).filter(|value| !value.is_empty());
+        if value.is_none() {
+            let message = format!("{} field is required", field.to_uppercase());
+            violations.push(Violation::new(field, "missing_field", message, Severity::Error));
+        }
+        value.unwrap_or_default().to_string()
+    };
+    let imsi = text("imsi");
+    let mut msisdn = text("msisdn");
+
+    if !msisdn.is_empty() {
+        match orion_numbering::parse(&msisdn, country) {
+            Ok(number) if number.number_type != NumberType::ShortCode => msisdn = number.canonical,
+            Ok(_) => violations.push(Violation::new(
+                "msisdn",
+                "invalid_msisdn",
+                format!("MSISDN cannot be a short code: {}", msisdn),
+                Severity::Error,
+            )),
+            Err(e) => violations.push(Violation::new(
+                "msisdn",
+                "invalid_msisdn",
+                format!("Impossible MSISDN: {}", e),
+                Severity::Error,
+            )),
+        }
+    }
+
+    let timestamp = match event_time::field(&raw) {
+        None => {
+            violations.push(Violation::new(
+                EVENT_TIME_FIELDS[0],
+                "missing_field",
+                "No event time, processing time used".to_string(),
+                Severity::Warning,
+            ));
+            None
+        }
+        Some((field, value)) => {
+            let timestamp = event_time::parse(value);
+            if timestamp.is_none() {
+                violations.push(Violation::new(
+                    field,
+                    "invalid_timestamp",
+                    format!("{} must be RFC 3339, epoch seconds/millis or YYMMDDhhmmss+hhmm", field),
+                    Severity::Error,
+                ));
+            }
+            timestamp
+        }
+    };
+
+    ValidatedCDR {
+        cdr_id: format!("dry-run-{}", index + 1),
+        event_type: raw.get("event_type").and_then(Value::as_str).map_or(EventType::Unknown, EventType::parse),
+        imsi,
+        msisdn,
+        timestamp: timestamp.unwrap_or_else(Utc::now),
+        country: country.to_string(),
+        source_system,
+        validation_timestamp: Utc::now().to_rfc3339(),
+        warnings: violations,
+        raw_data: raw,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_samples_read_as_validation_does() {
+        let normalizer = Normalizer::default();
+        let dry_run = DryRun {
+            country: "FR".to_string(),
+            source_system: None,
+            mappings: None,
+            records: vec![
+                json!({"event_type": "VOICE", "imsi": "208150123456789", "msisdn": "06 12 34 56 78", "start_timestamp": "260129101530+0100", "duration": 60}),
+                json!({"event_type": "sms", "imsi": "208150123456789", "timestamp": "soon"}),
+            ],
+        };
+
+        let report = run(&normalizer, dry_run).unwrap();
+        let results = report["results"].as_array().unwrap();
+
+        let cdr = &results[0]["cdr"];
+        assert_eq!(cdr["event_type"], "voice");
+        assert_eq!(cdr["msisdn"], "+33612345678");
+        assert_eq!(cdr["start_timestamp"], "2026-01-29T09:15:30Z");
+        assert_eq!(results[0]["violations"], json!([]));
+
+        let flagged: Vec<&str> = results[1]["violations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["error_type"].as_str().unwrap())
+            .collect();
+        assert_eq!(flagged, vec!["missing_field", "invalid_timestamp"]);
+    }
+
+    #[test]
+    fn test_invalid_mappings_and_empty_samples() {
+        let normalizer = Normalizer::default();
+        let dry_run = |mappings: Option<&str>, records: Vec<Value>| DryRun {
+            country: "FR".to_string(),
+            source_system: None,
+            mappings: mappings.map(str::to_string),
+            records,
+        };
+        assert!(matches!(
+            run(&normalizer, dry_run(Some("profiles: 3"), vec![json!({})])),
+            Err(DryRunError::InvalidMappings(_))
+        ));
+        assert!(matches!(run(&normalizer, dry_run(None, Vec::new())), Err(DryRunError::NoRecords)));
+    }
+}
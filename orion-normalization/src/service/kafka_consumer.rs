@@ -1,79 +1,366 @@
 use rdkafka::config::ClientConfig;
 use rdkafka::consumer::{Consumer, StreamConsumer};
-use rdkafka::message::Message;
-use crate::config::KafkaConfig;
+use rdkafka::message::{BorrowedMessage, Message};
+use rdkafka::{Offset, TopicPartitionList};
+use crate::config::{DeliveryMode, KafkaConfig};
+use crate::service::aggregator::Aggregator;
 use crate::service::model::ValidatedCDR;
 use crate::service::normalizer::Normalizer;
 use crate::service::kafka_producer::KafkaProducerService;
-use tracing::{info, error};
+use orion_kafka::{OffsetCommitter, TransactionBatch};
+use orion_shutdown::Shutdown;
+use std::time::Duration;
+use tracing::{debug, info, error};
+
+/// Time given to the producer to deliver queued records on shutdown
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct KafkaConsumerService {
     consumer: StreamConsumer,
     normalizer: Normalizer,
+    aggregator: Aggregator,
     producer: KafkaProducerService,
+    delivery_mode: DeliveryMode,
+    commit_batch_size: usize,
+    commit_interval: Duration,
 }
 
 impl KafkaConsumerService {
-    pub fn new(config: &KafkaConfig) -> anyhow::Result<Self> {
+    pub fn new(config: &KafkaConfig, normalizer: Normalizer, aggregator: Aggregator) -> anyhow::Result<Self> {
+        // Offsets are stored and committed manually once the output is acknowledged
         let consumer: StreamConsumer = ClientConfig::new()
             .set("bootstrap.servers", &config.brokers)
             .set("group.id", &config.consumer_group)
-            .set("enable.auto.commit", "true")
+            .set("enable.auto.commit", "false")
+            .set("enable.auto.offset.store", "false")
+            .set("isolation.level", "read_committed")
             .set("auto.offset.reset", "earliest")
             .create()?;
 
         consumer.subscribe(&[&config.input_topic])?;
 
-        let producer = KafkaProducerService::new(
-            &config.brokers,
-            config.output_topic.clone(),
-        )?;
+        let producer = KafkaProducerService::new(config)?;
+
+        // The producer has settled the previous instance's transaction:
+        // keep the partial sessions it saved only if it committed
+        let pending = aggregator.pending_offsets();
+        if !pending.is_empty() {
+            let mut partitions = TopicPartitionList::new();
+            for (topic, partition, _) in &pending {
+                partitions.add_partition(topic, *partition);
+            }
+            let committed = consumer.committed_offsets(partitions, Duration::from_secs(10))?;
+            aggregator.reconcile(|topic, partition| {
+                match committed.find_partition(topic, partition)?.offset() {
+                    Offset::Offset(offset) => Some(offset),
+                    _ => None,
+                }
+            })?;
+        }
 
         Ok(Self {
             consumer,
-            normalizer: Normalizer::new(),
+            normalizer,
+            aggregator,
             producer,
+            delivery_mode: config.delivery_mode,
+            commit_batch_size: config.commit_batch_size,
+            commit_interval: Duration::from_millis(config.commit_interval_ms),
         })
     }
 
-    pub async fn run(self) -> anyhow::Result<()> {
-        info!("Kafka consumer service started");
+    /// Consume until shutdown is requested. The message in hand is
+    /// finished, then its offsets (or open transaction) are committed.
+    pub async fn run(self, shutdown: Shutdown) -> anyhow::Result<()> {
+        info!("Kafka consumer service started ({:?})", self.delivery_mode);
+
+        match self.delivery_mode {
+            DeliveryMode::AtLeastOnce => {
+                let committer = OffsetCommitter::new(self.commit_batch_size, self.commit_interval);
+                self.run_at_least_once(committer, shutdown).await
+            }
+            DeliveryMode::ExactlyOnce => {
+                let batch = TransactionBatch::new(self.commit_batch_size, self.commit_interval);
+                self.run_exactly_once(batch, shutdown).await
+            }
+        }
+    }
 
+    async fn run_at_least_once(
+        &self,
+        mut committer: OffsetCommitter,
+        mut shutdown: Shutdown,
+    ) -> anyhow::Result<()> {
         loop {
-            match self.consumer.recv().await {
-                Ok(message) => {
-                    if let Some(payload) = message.payload() {
-                        match serde_json::from_slice::<ValidatedCDR>(payload) {
-                            Ok(validated_cdr) => {
-                                info!(
-                                    "Received validated CDR {} (event: {:?}, country: {})",
-                                    validated_cdr.cdr_id,
-                                    validated_cdr.event_type,
-                                    validated_cdr.country
-                                );
-                                
-                                match self.normalizer.normalize(&validated_cdr).await {
-                                    Ok(unified_cdr) => {
-                                        if let Err(e) = self.producer.send(&unified_cdr).await {
-                                            error!("Failed to send normalized CDR: {}", e);
-                                        }
-                                    }
-                                    Err(e) => {
-                                        error!("Failed to normalize CDR {}: {}", validated_cdr.cdr_id, e);
+            // Sessions not published are kept open and retried
+            match self.publish_expired().await {
+                Ok(()) => self.aggregator.commit(),
+                Err(e) => {
+                    error!("Failed to send expired partial sessions, retrying: {}", e);
+                    self.aggregator.rollback();
+                }
+            }
+
+            let received = tokio::select! {
+                biased;
+                _ = shutdown.requested() => break,
+                received = tokio::time::timeout(committer.interval(), self.consumer.recv()) => received,
+            };
+            match received {
+                // Idle: flush offsets acknowledged since the last commit
+                Err(_) => {
+                    if let Err(e) = self.commit_offsets(&mut committer) {
+                        error!("Failed to commit offsets: {:?}", e);
+                    }
+                }
+                Ok(Ok(message)) => {
+                    match self.handle_message(&message).await {
+                        Ok(()) => {
+                            self.aggregator.commit();
+                            match committer.store(
+                                &self.consumer,
+                                message.topic(),
+                                message.partition(),
+                                message.offset(),
+                            ) {
+                                Ok(true) => {
+                                    if let Err(e) = self.commit_offsets(&mut committer) {
+                                        error!("Failed to commit offsets: {:?}", e);
                                     }
                                 }
+                                Ok(false) => {}
+                                Err(e) => error!("Failed to store offset: {:?}", e),
                             }
-                            Err(e) => {
-                                error!("Failed to deserialize ValidatedCDR: {}", e);
+                        }
+                        Err(e) => {
+                            // Output not acknowledged: rewind so the message is redelivered
+                            error!("Failed to send normalized CDR, retrying: {}", e);
+                            self.aggregator.rollback();
+                            tokio::time::sleep(Duration::from_secs(1)).await;
+                            self.rewind(message.topic(), message.partition(), message.offset());
+                        }
+                    }
+                }
+                Ok(Err(e)) => {
+                    error!("Kafka consumer error: {:?}", e);
+                    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                }
+            }
+        }
+
+        info!("Kafka consumer stopped, committing final offsets");
+        match self.aggregator.persist(&[]) {
+            Ok(()) => {
+                if let Err(e) = committer.commit_sync(&self.consumer) {
+                    error!("Failed to commit final offsets: {:?}", e);
+                }
+            }
+            Err(e) => error!("Failed to save partial sessions, offsets not committed: {:#}", e),
+        }
+        if let Err(e) = self.producer.flush(FLUSH_TIMEOUT) {
+            error!("Failed to flush producer: {:?}", e);
+        }
+        Ok(())
+    }
+
+    async fn run_exactly_once(
+        &self,
+        mut batch: TransactionBatch,
+        mut shutdown: Shutdown,
+    ) -> anyhow::Result<()> {
+        loop {
+            self.publish_expired_in_transaction(&mut batch).await?;
+
+            let received = tokio::select! {
+                biased;
+                _ = shutdown.requested() => break,
+                received = tokio::time::timeout(batch.interval(), self.consumer.recv()) => received,
+            };
+            match received {
+                // Idle: commit the transaction opened before the pause
+                Err(_) => {
+                    if batch.is_open() {
+                        self.commit_transaction(&mut batch).await?;
+                    }
+                }
+                Ok(Ok(message)) => {
+                    if !batch.is_open() {
+                        if let Err(e) = self.producer.begin_transaction() {
+                            error!("Failed to begin transaction: {:?}", e);
+                            tokio::time::sleep(Duration::from_secs(1)).await;
+                            self.rewind(message.topic(), message.partition(), message.offset());
+                            continue;
+                        }
+                        batch.open();
+                    }
+                    batch.record(message.topic(), message.partition(), message.offset());
+
+                    match self.handle_message(&message).await {
+                        Ok(()) => {
+                            if batch.should_commit() {
+                                self.commit_transaction(&mut batch).await?;
                             }
                         }
+                        Err(e) => {
+                            error!("Failed to send normalized CDR, aborting transaction: {}", e);
+                            self.abort_transaction(&mut batch).await?;
+                        }
                     }
                 }
-                Err(e) => {
+                Ok(Err(e)) => {
                     error!("Kafka consumer error: {:?}", e);
                     tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
                 }
             }
         }
+
+        info!("Kafka consumer stopped, committing the open transaction");
+        if batch.is_open() {
+            self.commit_transaction(&mut batch).await?;
+        }
+        Ok(())
+    }
+
+    /// Save the open partial sessions, then commit the stored offsets: a
+    /// committed offset never refers to a partial only held in memory
+    fn commit_offsets(&self, committer: &mut OffsetCommitter) -> anyhow::Result<()> {
+        self.aggregator.persist(&[])?;
+        committer.commit(&self.consumer)?;
+        Ok(())
+    }
+
+    /// Publish the partial sessions closed by the timeout
+    async fn publish_expired(&self) -> anyhow::Result<()> {
+        for cdr in self.aggregator.expired() {
+            self.producer.send(&cdr).await?;
+        }
+        Ok(())
+    }
+
+    /// Publish the partial sessions closed by the timeout in the open
+    /// transaction, opening one if needed
+    async fn publish_expired_in_transaction(&self, batch: &mut TransactionBatch) -> anyhow::Result<()> {
+        let expired = self.aggregator.expired();
+        if expired.is_empty() {
+            return Ok(());
+        }
+        if !batch.is_open() {
+            if let Err(e) = self.producer.begin_transaction() {
+                error!("Failed to begin transaction: {:?}", e);
+                self.aggregator.rollback();
+                return Ok(());
+            }
+            batch.open();
+        }
+        for cdr in &expired {
+            if let Err(e) = self.producer.send(cdr).await {
+                error!("Failed to send expired partial session, aborting transaction: {}", e);
+                return self.abort_transaction(batch).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Commit the outputs of the batch together with its input offsets,
+    /// once the open partial sessions are saved with the changes of the
+    /// batch and those offsets (undone on restart if the commit did not
+    /// happen). A failed commit aborts the transaction so that the batch is
+    /// replayed.
+    async fn commit_transaction(&self, batch: &mut TransactionBatch) -> anyhow::Result<()> {
+        let result = batch.commit_offsets().map_err(anyhow::Error::from).and_then(|offsets| {
+            let pending: Vec<_> = offsets
+                .elements()
+                .iter()
+                .filter_map(|element| match element.offset() {
+                    Offset::Offset(offset) => Some((element.topic().to_string(), element.partition(), offset)),
+                    _ => None,
+                })
+                .collect();
+            self.aggregator.persist(&pending)?;
+            self.producer.commit_transaction(&self.consumer, &offsets)
+        });
+
+        match result {
+            Ok(()) => {
+                debug!("Committed transaction of {} messages", batch.messages());
+                self.aggregator.commit();
+                batch.reset();
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to commit transaction: {}", e);
+                self.abort_transaction(batch).await
+            }
+        }
+    }
+
+    /// Abort the open transaction and rewind the consumer to replay it.
+    /// Fails only when the producer can no longer be used (e.g. fenced by
+    /// another instance with the same transactional id).
+    async fn abort_transaction(&self, batch: &mut TransactionBatch) -> anyhow::Result<()> {
+        self.producer
+            .abort_transaction()
+            .map_err(|e| anyhow::anyhow!("Failed to abort transaction: {:?}", e))?;
+        self.aggregator.rollback();
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        for (topic, partition, offset) in batch.rewind_offsets() {
+            self.rewind(topic, partition, offset);
+        }
+        batch.reset();
+        Ok(())
+    }
+
+    fn rewind(&self, topic: &str, partition: i32, offset: i64) {
+        if let Err(e) = self.consumer.seek(
+            topic,
+            partition,
+            Offset::Offset(offset),
+            Duration::from_secs(5),
+        ) {
+            error!("Failed to rewind partition {}: {:?}", partition, e);
+        }
+    }
+
+    /// Normalize one message and publish the result. Returns an error only
+    /// when the output could not be published; undecodable or
+    /// unnormalizable messages are logged and considered handled.
+    async fn handle_message(&self, message: &BorrowedMessage<'_>) -> anyhow::Result<()> {
+        let Some(payload) = message.payload() else {
+            return Ok(());
+        };
+
+        let validated_cdr = match orion_model::from_slice::<ValidatedCDR>(payload) {
+            Ok(cdr) => cdr,
+            Err(e) => {
+                error!("Failed to deserialize ValidatedCDR: {}", e);
+                return Ok(());
+            }
+        };
+
+        info!(
+            "Received validated CDR {} (event: {:?}, country: {})",
+            validated_cdr.cdr_id,
+            validated_cdr.event_type,
+            validated_cdr.country
+        );
+
+        match self.normalizer.normalize(&validated_cdr).await {
+            Ok(unified_cdr) => {
+                // Partials are held until their session is complete
+                let unified_cdr = match self.normalizer.partial(&validated_cdr) {
+                    None => unified_cdr,
+                    Some(partial) => match self.aggregator.add(unified_cdr, partial) {
+                        Some(merged) => merged,
+                        None => return Ok(()),
+                    },
+                };
+                self.producer.send(&unified_cdr).await
+            }
+            Err(e) => {
+                error!("Failed to normalize CDR {}: {}", validated_cdr.cdr_id, e);
+                Ok(())
+            }
+        }
     }
 }
@@ -0,0 +1,495 @@
+//! Normalization mappings: where each unified field is read in a raw CDR.
+//!
+//! Mappings are grouped in profiles, selected by the country and source
+//! system of the CDR. The profile without selector is the default one; the
+//! other profiles only declare the fields their switches export differently.
+
+use crate::service::model::{CallType, SmsType};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Mappings shipped with the service, used when no mappings file is deployed
+const BUNDLED_MAPPINGS: &str = include_str!("../../config/mappings.yaml");
+
+/// Unified field read from the raw CDR
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    SessionId,
+    Imei,
+    EndTimestamp,
+    Lac,
+    CellId,
+    CallingNumber,
+    CalledNumber,
+    CallType,
+    DurationSeconds,
+    BytesUploaded,
+    BytesDownloaded,
+    Apn,
+    SmsType,
+    MessageLength,
+    VisitedCountry,
+    VisitedNetwork,
+    ServingMcc,
+    ServingMnc,
+    ServingPlmn,
+    CellGlobalId,
+    ChargingId,
+    RatedAmount,
+    Currency,
+    IsPremium,
+    IsRoaming,
+    IsEmergency,
+    RecordSequenceNumber,
+    LastPartial,
+}
+
+impl Field {
+    /// Quantity of a field whose raw value may need a unit conversion
+    fn quantity(self) -> Option<Quantity> {
+        match self {
+            Field::DurationSeconds => Some(Quantity::Time),
+            Field::BytesUploaded | Field::BytesDownloaded => Some(Quantity::Volume),
+            _ => None,
+        }
+    }
+
+    /// Unified value of a value map target, `None` for the fields taking no
+    /// value map and `Some(None)` for an invalid target
+    fn target(self, target: &str) -> Option<Option<Value>> {
+        let lowercase = Value::from(target.to_lowercase());
+        let valid = match self {
+            Field::CallType => serde_json::from_value::<CallType>(lowercase.clone()).is_ok(),
+            Field::SmsType => serde_json::from_value::<SmsType>(lowercase.clone()).is_ok(),
+            Field::LastPartial => return Some(target.parse().ok().map(Value::Bool)),
+            _ => return None,
+        };
+        Some(valid.then_some(lowercase))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quantity {
+    Time,
+    Volume,
+}
+
+/// Unit of a raw value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Unit {
+    Ms,
+    S,
+    Min,
+    Bytes,
+    Kb,
+    Kib,
+    Mb,
+    Mib,
+}
+
+impl Unit {
+    fn quantity(self) -> Quantity {
+        match self {
+            Unit::Ms | Unit::S | Unit::Min => Quantity::Time,
+            _ => Quantity::Volume,
+        }
+    }
+
+    /// Factor to the unified unit: seconds or bytes
+    fn factor(self) -> f64 {
+        match self {
+            Unit::Ms => 0.001,
+            Unit::S | Unit::Bytes => 1.0,
+            Unit::Min => 60.0,
+            Unit::Kb => 1_000.0,
+            Unit::Kib => 1_024.0,
+            Unit::Mb => 1_000_000.0,
+            Unit::Mib => 1_048_576.0,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MappingFile {
+    #[serde(default)]
+    profiles: Vec<ProfileSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileSpec {
+    name: String,
+    country: Option<String>,
+    source_system: Option<String>,
+    #[serde(default)]
+    fields: HashMap<Field, FieldSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FieldSpec {
+    #[serde(default)]
+    paths: Vec<String>,
+    unit: Option<Unit>,
+    #[serde(default)]
+    values: HashMap<String, Value>,
+    default: Option<Value>,
+}
+
+struct FieldMapping {
+    /// JSON pointers, in order of preference
+    pointers: Vec<String>,
+    unit: Option<Unit>,
+    /// Unified value by lowercased raw value
+    values: HashMap<String, Value>,
+    default: Option<Value>,
+}
+
+impl FieldMapping {
+    fn compile(field: Field, spec: FieldSpec) -> anyhow::Result<Self> {
+        if spec.paths.is_empty() && spec.default.is_none() {
+            anyhow::bail!("needs paths or a default");
+        }
+        if let Some(unit) = spec.unit {
+            if field.quantity() != Some(unit.quantity()) {
+                anyhow::bail!("unit {:?} does not apply to this field", unit);
+            }
+        }
+        let mut values = HashMap::new();
+        for (raw, target) in spec.values {
+            let text = raw_key(&target).unwrap_or_default();
+            let value = match field.target(&text) {
+                None => anyhow::bail!("values only apply to call_type, sms_type and last_partial"),
+                Some(None) => anyhow::bail!("invalid value {}", target),
+                Some(Some(value)) => value,
+            };
+            values.insert(raw.to_lowercase(), value);
+        }
+        if let Some(default) = spec.default.as_ref().and_then(raw_key) {
+            if field.target(&default) == Some(None) {
+                anyhow::bail!("invalid default {}", default);
+            }
+        }
+
+        Ok(Self {
+            pointers: spec.paths.iter().map(|path| pointer(path)).collect(),
+            unit: spec.unit,
+            values,
+            default: spec.default,
+        })
+    }
+
+    /// Unified value: the first path present, mapped and converted, else the
+    /// default
+    fn value(&self, raw: &Value) -> Option<Value> {
+        let Some(value) = self.pointers.iter().filter_map(|p| raw.pointer(p)).find(|v| !v.is_null()) else {
+            return self.default.clone();
+        };
+
+        if let Some(unified) = raw_key(value).and_then(|key| self.values.get(&key.to_lowercase())) {
+            return Some(unified.clone());
+        }
+        match (self.unit, number(value)) {
+            (Some(unit), Some(number)) => Some(Value::from((number * unit.factor()).round() as i64)),
+            _ => Some(value.clone()),
+        }
+    }
+}
+
+/// JSON pointer of a path: a pointer as is, a field name otherwise
+fn pointer(path: &str) -> String {
+    if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path.replace('~', "~0").replace('/', "~1"))
+    }
+}
+
+fn raw_key(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    value.as_f64().or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+struct Profile {
+    name: String,
+    country: Option<String>,
+    source_system: Option<String>,
+    fields: HashMap<Field, FieldMapping>,
+}
+
+impl Profile {
+    /// Specificity of the profile for a CDR, `None` when it does not apply
+    fn score(&self, country: &str, source_system: Option<&str>) -> Option<u8> {
+        let country_score = match &self.country {
+            None => 0,
+            Some(c) if c.eq_ignore_ascii_case(country) => 1,
+            Some(_) => return None,
+        };
+        let source_score = match (&self.source_system, source_system) {
+            (None, _) => 0,
+            (Some(s), Some(source)) if s.eq_ignore_ascii_case(source) => 2,
+            (Some(_), _) => return None,
+        };
+        Some(country_score + source_score)
+    }
+}
+
+/// Profile selectors, as exposed on `GET /mappings`
+#[derive(Debug, Serialize)]
+pub struct ProfileSummary {
+    pub name: String,
+    pub country: Option<String>,
+    pub source_system: Option<String>,
+    pub fields: usize,
+}
+
+/// Compiled mapping profiles
+pub struct MappingProfiles {
+    default: Profile,
+    profiles: Vec<Profile>,
+}
+
+impl MappingProfiles {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read mappings file {}", path.display()))?;
+        Self::from_yaml(&content).with_context(|| format!("Invalid mappings file {}", path.display()))
+    }
+
+    pub fn from_yaml(content: &str) -> anyhow::Result<Self> {
+        Self::compile(serde_yaml::from_str(content)?)
+    }
+
+    pub fn bundled() -> Self {
+        Self::from_yaml(BUNDLED_MAPPINGS).expect("bundled mappings are valid")
+    }
+
+    fn compile(file: MappingFile) -> anyhow::Result<Self> {
+        let mut default = None;
+        let mut profiles: Vec<Profile> = Vec::new();
+        for spec in file.profiles {
+            let name = spec.name;
+            let mut fields = HashMap::new();
+            for (field, field_spec) in spec.fields {
+                let mapping = FieldMapping::compile(field, field_spec)
+                    .with_context(|| format!("Profile {}: field {:?}", name, field))?;
+                fields.insert(field, mapping);
+            }
+            let profile = Profile {
+                name,
+                country: spec.country,
+                source_system: spec.source_system,
+                fields,
+            };
+
+            let same = |other: &Profile| {
+                other.name == profile.name
+                    || (eq(&other.country, &profile.country) && eq(&other.source_system, &profile.source_system))
+            };
+            if default.as_ref().is_some_and(same) || profiles.iter().any(same) {
+                anyhow::bail!("Profile {} duplicates the name or selectors of another profile", profile.name);
+            }
+            if profile.country.is_none() && profile.source_system.is_none() {
+                default = Some(profile);
+            } else {
+                profiles.push(profile);
+            }
+        }
+
+        Ok(Self {
+            default: default.unwrap_or_else(|| Profile {
+                name: "default".to_string(),
+                country: None,
+                source_system: None,
+                fields: HashMap::new(),
+            }),
+            profiles,
+        })
+    }
+
+    /// Mapping of a CDR: the profiles matching its country and source
+    /// system, the most specific first, completed by the default profile
+    pub fn select(&self, country: &str, source_system: Option<&str>) -> Mapping<'_> {
+        let mut matching: Vec<(u8, &Profile)> = self
+            .profiles
+            .iter()
+            .filter_map(|profile| profile.score(country, source_system).map(|score| (score, profile)))
+            .collect();
+        matching.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        let mut profiles: Vec<&Profile> = matching.into_iter().map(|(_, profile)| profile).collect();
+        profiles.push(&self.default);
+        Mapping { profiles }
+    }
+
+    pub fn describe(&self) -> Vec<ProfileSummary> {
+        std::iter::once(&self.default)
+            .chain(&self.profiles)
+            .map(|profile| ProfileSummary {
+                name: profile.name.clone(),
+                country: profile.country.clone(),
+                source_system: profile.source_system.clone(),
+                fields: profile.fields.len(),
+            })
+            .collect()
+    }
+}
+
+fn eq(a: &Option<String>, b: &Option<String>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Mapping selected for one CDR
+pub struct Mapping<'a> {
+    /// Matching profiles, the most specific first and the default last
+    profiles: Vec<&'a Profile>,
+}
+
+impl Mapping<'_> {
+    /// Name of the most specific profile
+    pub fn name(&self) -> &str {
+        &self.profiles[0].name
+    }
+
+    /// Value of a field, as declared by the most specific profile declaring
+    /// it
+    pub fn value(&self, field: Field, raw: &Value) -> Option<Value> {
+        self.profiles
+            .iter()
+            .find_map(|profile| profile.fields.get(&field))
+            .and_then(|mapping| mapping.value(raw))
+    }
+
+    /// Text value; numbers are kept as their text
+    pub fn string(&self, field: Field, raw: &Value) -> Option<String> {
+        match self.value(field, raw)? {
+            Value::String(s) => Some(s),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Integer value, rounded
+    pub fn integer(&self, field: Field, raw: &Value) -> Option<i64> {
+        let value = self.value(field, raw)?;
+        value.as_i64().or_else(|| number(&value).map(|n| n.round() as i64))
+    }
+
+    pub fn float(&self, field: Field, raw: &Value) -> Option<f64> {
+        number(&self.value(field, raw)?)
+    }
+
+    /// Boolean value, `false` when absent
+    pub fn flag(&self, field: Field, raw: &Value) -> bool {
+        self.value(field, raw).and_then(|v| v.as_bool()).unwrap_or(false)
+    }
+
+    /// Value of an enum field, `None` when absent and the unknown variant
+    /// when not recognized
+    pub fn variant<T: serde::de::DeserializeOwned>(&self, field: Field, raw: &Value, unknown: T) -> Option<T> {
+        let value = self.string(field, raw)?;
+        Some(serde_json::from_value(Value::from(value.to_lowercase())).unwrap_or(unknown))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const PROFILES: &str = r#"
+profiles:
+  - name: default
+    fields:
+      duration_seconds: { paths: [duration, duration_seconds] }
+      called_number: { paths: [called_number] }
+      call_type: { paths: [call_type], default: mobile }
+  - name: ch
+    country: CH
+    fields:
+      duration_seconds: { paths: [/call/duration_ms], unit: ms }
+  - name: ch-switch
+    country: CH
+    source_system: ch-switch-v2
+    fields:
+      called_number: { paths: [/call/b_number] }
+      call_type:
+        paths: [/call/type]
+        values: { "1": mobile, "3": International }
+      bytes_uploaded: { paths: [/data/volume_up], unit: kb }
+"#;
+
+    #[test]
+    fn test_profile_selection() {
+        let profiles = MappingProfiles::from_yaml(PROFILES).unwrap();
+        assert_eq!(profiles.select("FR", Some("fr-mediation")).name(), "default");
+        assert_eq!(profiles.select("ch", None).name(), "ch");
+        assert_eq!(profiles.select("CH", Some("ch-legacy")).name(), "ch");
+        assert_eq!(profiles.select("CH", Some("CH-SWITCH-V2")).name(), "ch-switch");
+        assert_eq!(profiles.describe().len(), 3);
+    }
+
+    #[test]
+    fn test_paths_units_and_values() {
+        let profiles = MappingProfiles::from_yaml(PROFILES).unwrap();
+        let raw = json!({
+            "duration": 12,
+            "call": {"duration_ms": 61_600, "b_number": "+41791234567", "type": 3},
+            "data": {"volume_up": "1.5"}
+        });
+
+        let mapping = profiles.select("CH", Some("ch-switch-v2"));
+        // Inherited from the country profile, then converted
+        assert_eq!(mapping.integer(Field::DurationSeconds, &raw), Some(62));
+        assert_eq!(mapping.string(Field::CalledNumber, &raw).as_deref(), Some("+41791234567"));
+        assert_eq!(mapping.variant(Field::CallType, &raw, CallType::Unknown), Some(CallType::International));
+        assert_eq!(mapping.integer(Field::BytesUploaded, &raw), Some(1_500));
+        assert_eq!(mapping.integer(Field::BytesDownloaded, &raw), None);
+
+        let mapping = profiles.select("FR", None);
+        assert_eq!(mapping.integer(Field::DurationSeconds, &raw), Some(12));
+        // Default when absent, unknown variant when not recognized
+        assert_eq!(mapping.variant(Field::CallType, &raw, CallType::Unknown), Some(CallType::Mobile));
+        let raw = json!({"call_type": "satellite"});
+        assert_eq!(mapping.variant(Field::CallType, &raw, CallType::Unknown), Some(CallType::Unknown));
+    }
+
+    #[test]
+    fn test_invalid_profiles() {
+        let invalid = |fields: &str| {
+            let yaml = format!("profiles:\n  - name: default\n    fields:\n      {}\n", fields);
+            MappingProfiles::from_yaml(&yaml).is_err()
+        };
+        assert!(invalid("duration_seconds: { paths: [duration], unit: kb }"));
+        assert!(invalid("apn: { paths: [apn], values: { a: b } }"));
+        assert!(invalid("call_type: { paths: [call_type], values: { \"1\": cellular } }"));
+        assert!(invalid("call_type: { default: cellular }"));
+        assert!(invalid("last_partial: { paths: [cause], values: { timeLimit: maybe } }"));
+        assert!(!invalid("last_partial: { paths: [cause], values: { normalRelease: true, timeLimit: false } }"));
+        assert!(invalid("unknown_field: { paths: [x] }"));
+        assert!(invalid("apn: {}"));
+        assert!(!invalid("apn: { paths: [apn, /gprs/apn] }"));
+
+        let duplicate = "profiles:\n  - name: a\n    country: FR\n  - name: b\n    country: fr\n";
+        assert!(MappingProfiles::from_yaml(duplicate).is_err());
+        MappingProfiles::bundled();
+    }
+}
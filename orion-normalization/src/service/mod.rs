@@ -1,6 +1,14 @@
+mod aggregator;
+pub mod dry_run;
 mod kafka_consumer;
 mod kafka_producer;
+mod mapping;
 mod model;
+mod roaming;
+mod timezone;
 mod normalizer;
 
+pub use aggregator::Aggregator;
 pub use kafka_consumer::KafkaConsumerService;
+pub use mapping::MappingProfiles;
+pub use normalizer::Normalizer;
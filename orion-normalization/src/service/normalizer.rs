@@ -1,15 +1,116 @@
 use crate::metrics;
+use crate::service::aggregator::Partial;
+use crate::service::mapping::{Field, Mapping, MappingProfiles};
 use crate::service::model::*;
-use chrono::Utc;
+use crate::service::roaming::RoamingPlan;
+use crate::service::timezone::{LocalTime, Timezones};
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Instant;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use sha2::{Digest, Sha256};
+use anyhow::Context;
+use orion_numbering::{NumberType, PhoneNumber, Plmn, Registry, TacDatabase};
+use orion_model::canonical_json;
 
-pub struct Normalizer;
+/// Source system of the CDRs published before the ingestion route was
+/// carried along
+const DEFAULT_SOURCE_SYSTEM: &str = "orion-ingestion";
+
+#[derive(Clone)]
+pub struct Normalizer {
+    devices: Arc<TacDatabase>,
+    mappings: Arc<MappingProfiles>,
+    timezones: Arc<Timezones>,
+    roaming: Arc<RoamingPlan>,
+}
 
 impl Normalizer {
-    pub fn new() -> Self {
-        Self
+    pub fn new(devices: TacDatabase, mappings: MappingProfiles, timezones: Timezones, roaming: RoamingPlan) -> Self {
+        Self {
+            devices: Arc::new(devices),
+            mappings: Arc::new(mappings),
+            timezones: Arc::new(timezones),
+            roaming: Arc::new(roaming),
+        }
+    }
+
+    /// Normalizer resolving device models from a `tac,brand,model` file,
+    /// reading raw CDRs with the profiles of a mappings file, local times
+    /// with a `country,timezone` file and roaming with a roaming plan; the
+    /// datasets bundled with the service are used for those not configured
+    pub fn from_config(
+        tac_path: Option<&str>,
+        mappings_path: Option<&str>,
+        timezones_path: Option<&str>,
+        roaming_path: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        let devices = match tac_path {
+            Some(path) => {
+                let csv = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read TAC database {}", path))?;
+                let devices = TacDatabase::parse(&csv)
+                    .with_context(|| format!("Invalid TAC database {}", path))?;
+                tracing::info!("Loaded {} device models from {}", devices.len(), path);
+                devices
+            }
+            None => TacDatabase::bundled(),
+        };
+        let mappings = match mappings_path {
+            Some(path) => {
+                let mappings = MappingProfiles::from_file(Path::new(path))?;
+                tracing::info!("Loaded {} mapping profiles from {}", mappings.describe().len(), path);
+                mappings
+            }
+            None => MappingProfiles::bundled(),
+        };
+        let timezones = match timezones_path {
+            Some(path) => {
+                let timezones = Timezones::from_file(Path::new(path))?;
+                tracing::info!("Loaded country timezones from {}", path);
+                timezones
+            }
+            None => Timezones::bundled(),
+        };
+        let roaming = match roaming_path {
+            Some(path) => {
+                let roaming = RoamingPlan::from_file(Path::new(path))?;
+                tracing::info!("Loaded roaming plan from {}", path);
+                roaming
+            }
+            None => RoamingPlan::bundled(),
+        };
+        Ok(Self::new(devices, mappings, timezones, roaming))
+    }
+
+    /// Same normalizer reading raw CDRs with other mapping profiles
+    pub fn with_mappings(&self, mappings: MappingProfiles) -> Self {
+        Self {
+            devices: Arc::clone(&self.devices),
+            mappings: Arc::new(mappings),
+            timezones: Arc::clone(&self.timezones),
+            roaming: Arc::clone(&self.roaming),
+        }
+    }
+
+    pub fn mappings(&self) -> &MappingProfiles {
+        &self.mappings
+    }
+
+    /// Mapping of a CDR, selected by its country and source system
+    pub fn mapping(&self, validated: &ValidatedCDR) -> Mapping<'_> {
+        self.mappings.select(&validated.country, validated.source_system.as_deref())
+    }
+
+    /// Position of the CDR in a series of partial records, `None` for a
+    /// record emitted in one piece
+    pub fn partial(&self, validated: &ValidatedCDR) -> Option<Partial> {
+        let mapping = self.mapping(validated);
+        let sequence = mapping.integer(Field::RecordSequenceNumber, &validated.raw_data)?;
+        Some(Partial {
+            sequence,
+            last: mapping.flag(Field::LastPartial, &validated.raw_data),
+        })
     }
 
     pub async fn normalize(&self, validated: &ValidatedCDR) -> anyhow::Result<UnifiedCDR> {
@@ -24,61 +125,95 @@ impl Normalizer {
             _ => {}
         }
 
+        let mapping = self.mapping(validated);
+        metrics::increment_mapping_profile(mapping.name());
+        let unified = self.unify(validated, &mapping);
+
+        metrics::record_latency(start.elapsed().as_secs_f64());
+        Ok(unified)
+    }
+
+    /// Unified CDR of a validated one, its raw data being read with the
+    /// given mapping
+    pub fn unify(&self, validated: &ValidatedCDR, mapping: &Mapping) -> UnifiedCDR {
+        let raw_data = &validated.raw_data;
+
         // Extract MCC/MNC from IMSI (first 5-6 digits)
         let (mcc, mnc) = Self::extract_mcc_mnc(&validated.imsi);
 
-        // Determine service type
-        let service_type = Self::determine_service_type(&validated);
+        let imei = mapping.string(Field::Imei, raw_data);
+        let device_model = imei.as_deref().and_then(|imei| self.device_model(imei));
 
         // Extract voice-specific fields
         let (calling_number, called_number, call_type, duration) = 
-            Self::extract_voice_fields(&validated.raw_data, &validated.event_type);
+            Self::extract_voice_fields(raw_data, mapping, &validated.event_type, &validated.country);
 
         // Extract data-specific fields
         let (bytes_uploaded, bytes_downloaded, apn) = 
-            Self::extract_data_fields(&validated.raw_data, &validated.event_type);
+            Self::extract_data_fields(raw_data, mapping, &validated.event_type);
 
         // Extract SMS-specific fields
         let (sms_type, message_length) = 
-            Self::extract_sms_fields(&validated.raw_data, &validated.event_type);
+            Self::extract_sms_fields(raw_data, mapping, &validated.event_type);
 
-        // Detect roaming
-        let is_roaming = Self::detect_roaming(&validated.country, mcc.as_deref());
+        // Classify roaming; the visited country and network are derived
+        // from the serving network when the CDR does not give them
+        let visited_country = mapping.string(Field::VisitedCountry, raw_data);
+        let roaming = Registry::bundled().resolve(&validated.imsi).ok().map(|home| {
+            let serving = Self::serving_network(raw_data, mapping);
+            self.roaming.classify(&home, serving.as_ref(), &validated.country, visited_country.as_deref())
+        });
+        let is_roaming = match &roaming {
+            Some(roaming) => roaming.roaming_type != RoamingType::Domestic,
+            None => mapping.flag(Field::IsRoaming, raw_data),
+        };
+
+        // Determine service type, roaming as classified above
+        let service_type = Self::determine_service_type(raw_data, mapping, is_roaming);
+        let visited_country = visited_country
+            .or_else(|| roaming.as_ref().and_then(|roaming| roaming.visited_country.clone()));
+        let visited_network = mapping.string(Field::VisitedNetwork, raw_data)
+            .or_else(|| roaming.as_ref().and_then(|roaming| roaming.visited_network.clone()));
+
+        // Local time where the subscriber is
+        let local_time = self.timezones
+            .resolve(&validated.country, visited_country.as_deref(), visited_network.as_deref())
+            .map(|timezone| LocalTime::at(timezone, validated.timestamp));
+
+        let end_timestamp = mapping.string(Field::EndTimestamp, raw_data)
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc));
+        let (end_timestamp, duration_seconds) =
+            Self::complete_period(validated.timestamp, end_timestamp, duration);
 
         // Calculate hash of raw data for deduplication
-        let raw_data_hash = Self::calculate_hash(&validated.raw_data);
+        let raw_data_hash = Self::calculate_hash(raw_data);
 
-        let unified = UnifiedCDR {
+        UnifiedCDR {
             cdr_id: validated.cdr_id.clone(),
-            session_id: validated.raw_data.get("session_id")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
+            session_id: mapping.string(Field::SessionId, raw_data),
             
             imsi: validated.imsi.clone(),
             msisdn: validated.msisdn.clone(),
-            imei: validated.raw_data.get("imei")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
+            imei,
+            device_model,
             
             event_type: validated.event_type.clone(),
             service_type,
             
             start_timestamp: validated.timestamp,
-            end_timestamp: validated.raw_data.get("end_timestamp")
-                .and_then(|v| v.as_str())
-                .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
-                .map(|dt| dt.with_timezone(&Utc)),
-            duration_seconds: duration,
+            end_timestamp,
+            duration_seconds,
+            timezone: local_time.as_ref().map(|local| local.timezone.name().to_string()),
+            local_start_timestamp: local_time.as_ref().map(|local| local.timestamp),
+            utc_offset_seconds: local_time.as_ref().map(|local| local.utc_offset_seconds),
+            is_dst: local_time.as_ref().map(|local| local.is_dst),
             
             country_code: validated.country.clone(),
             mcc,
             mnc,
-            lac: validated.raw_data.get("lac")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            cell_id: validated.raw_data.get("cell_id")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
+            lac: mapping.string(Field::Lac, raw_data),
+            cell_id: mapping.string(Field::CellId, raw_data),
             
             calling_number,
             called_number,
@@ -92,32 +227,30 @@ impl Normalizer {
             message_length,
             
             is_roaming,
-            visited_country: validated.raw_data.get("visited_country")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            visited_network: validated.raw_data.get("visited_network")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
+            visited_country,
+            visited_network,
+            roaming_type: roaming.as_ref().map(|roaming| roaming.roaming_type),
+            roaming_zone: roaming.and_then(|roaming| roaming.zone),
             
-            charging_id: validated.raw_data.get("charging_id")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            rated_amount: validated.raw_data.get("amount")
-                .and_then(|v| v.as_f64()),
-            currency: validated.raw_data.get("currency")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
+            charging_id: mapping.string(Field::ChargingId, raw_data),
+            rated_amount: mapping.float(Field::RatedAmount, raw_data),
+            currency: mapping.string(Field::Currency, raw_data),
             
             normalization_timestamp: Utc::now().to_rfc3339(),
-            source_system: "orion-ingestion".to_string(),
+            source_system: validated.source_system.clone()
+                .unwrap_or_else(|| DEFAULT_SOURCE_SYSTEM.to_string()),
+            partial_records: None,
+            incomplete: false,
             raw_data_hash,
-        };
-
-        metrics::record_latency(start.elapsed().as_secs_f64());
-        Ok(unified)
+        }
     }
 
+    /// MCC and MNC of the IMSI, the MNC being 2 or 3 digits as listed in
+    /// the MCC/MNC registry; 2 digits when the MCC is not registered
     fn extract_mcc_mnc(imsi: &str) -> (Option<String>, Option<String>) {
+        if let Ok(plmn) = Registry::bundled().resolve(imsi) {
+            return (Some(plmn.mcc), Some(plmn.mnc));
+        }
         if imsi.len() >= 5 {
             let mcc = Some(imsi[0..3].to_string());
             let mnc = Some(imsi[3..5].to_string());
@@ -127,124 +260,164 @@ impl Normalizer {
         }
     }
 
-    fn determine_service_type(validated: &ValidatedCDR) -> ServiceType {
-        if validated.raw_data.get("is_premium").and_then(|v| v.as_bool()).unwrap_or(false) {
+    /// "Brand Model" of the device, by the TAC of a valid IMEI
+    fn device_model(&self, imei: &str) -> Option<String> {
+        let imei = orion_numbering::imei::parse(imei).ok()?;
+        self.devices
+            .lookup(&imei.tac)
+            .map(|device| format!("{} {}", device.brand, device.model))
+    }
+
+    /// Service of the CDR; `is_roaming` is the roaming classification, the
+    /// `is_roaming` field of the CDR being only its fallback
+    fn determine_service_type(raw_data: &serde_json::Value, mapping: &Mapping, is_roaming: bool) -> ServiceType {
+        if mapping.flag(Field::IsPremium, raw_data) {
             return ServiceType::Premium;
         }
         
-        if validated.raw_data.get("is_roaming").and_then(|v| v.as_bool()).unwrap_or(false) {
+        if is_roaming {
             return ServiceType::Roaming;
         }
         
-        if validated.raw_data.get("is_emergency").and_then(|v| v.as_bool()).unwrap_or(false) {
+        if mapping.flag(Field::IsEmergency, raw_data) {
             return ServiceType::Emergency;
         }
         
         ServiceType::Standard
     }
 
-    fn extract_voice_fields(raw_data: &serde_json::Value, event_type: &EventType) -> 
+    /// Calling and called numbers in E.164 (as given when they cannot be
+    /// parsed); the call type is derived from the called number, the
+    /// `call_type` field is only used when it cannot be parsed
+    fn extract_voice_fields(raw_data: &serde_json::Value, mapping: &Mapping, event_type: &EventType, country: &str) -> 
         (Option<String>, Option<String>, Option<CallType>, Option<i64>) {
         
         if *event_type != EventType::Voice {
             return (None, None, None, None);
         }
 
-        let calling = raw_data.get("calling_number")
-            .or_else(|| raw_data.get("msisdn"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+        let calling = mapping.string(Field::CallingNumber, raw_data)
+            .map(|s| orion_numbering::parse(&s, country).map_or(s, |n| n.canonical));
         
-        let called = raw_data.get("called_number")
-            .or_else(|| raw_data.get("destination"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+        let called = mapping.string(Field::CalledNumber, raw_data)
+            .map(|s| {
+                let number = orion_numbering::parse(&s, country).ok();
+                (s, number)
+            });
         
-        let call_type = raw_data.get("call_type")
-            .and_then(|v| v.as_str())
-            .map(|s| match s.to_lowercase().as_str() {
-                "mobile" => CallType::Mobile,
-                "landline" | "fixed" => CallType::Landline,
-                "international" => CallType::International,
-                "emergency" => CallType::Emergency,
-                _ => CallType::Unknown,
-            })
-            .or(Some(CallType::Mobile)); // Default to mobile
+        let call_type = called.as_ref()
+            .and_then(|(_, number)| number.as_ref())
+            .map(|number| Self::derive_call_type(number, country))
+            .or_else(|| mapping.variant(Field::CallType, raw_data, CallType::Unknown));
+        let called = called.map(|(s, number)| number.map_or(s, |n| n.canonical));
         
-        let duration = raw_data.get("duration")
-            .or_else(|| raw_data.get("duration_seconds"))
-            .and_then(|v| v.as_i64());
+        let duration = mapping.integer(Field::DurationSeconds, raw_data);
         
         (calling, called, call_type, duration)
     }
 
-    fn extract_data_fields(raw_data: &serde_json::Value, event_type: &EventType) -> 
+    fn derive_call_type(called: &PhoneNumber, country: &str) -> CallType {
+        if called.emergency {
+            return CallType::Emergency;
+        }
+        if called.is_international_from(country) {
+            return CallType::International;
+        }
+        match called.number_type {
+            NumberType::Mobile => CallType::Mobile,
+            NumberType::Fixed => CallType::Landline,
+            NumberType::Premium => CallType::Premium,
+            NumberType::TollFree => CallType::TollFree,
+            NumberType::ShortCode => CallType::ShortCode,
+            NumberType::Unknown => CallType::Unknown,
+        }
+    }
+
+    fn extract_data_fields(raw_data: &serde_json::Value, mapping: &Mapping, event_type: &EventType) -> 
         (Option<i64>, Option<i64>, Option<String>) {
         
         if *event_type != EventType::Data {
             return (None, None, None);
         }
 
-        let bytes_up = raw_data.get("bytes_uploaded")
-            .or_else(|| raw_data.get("bytes_up"))
-            .and_then(|v| v.as_i64());
-        
-        let bytes_down = raw_data.get("bytes_downloaded")
-            .or_else(|| raw_data.get("bytes_down"))
-            .and_then(|v| v.as_i64());
-        
-        let apn = raw_data.get("apn")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+        let bytes_up = mapping.integer(Field::BytesUploaded, raw_data);
+        let bytes_down = mapping.integer(Field::BytesDownloaded, raw_data);
+        let apn = mapping.string(Field::Apn, raw_data);
         
         (bytes_up, bytes_down, apn)
     }
 
-    fn extract_sms_fields(raw_data: &serde_json::Value, event_type: &EventType) -> 
+    fn extract_sms_fields(raw_data: &serde_json::Value, mapping: &Mapping, event_type: &EventType) -> 
         (Option<SmsType>, Option<i32>) {
         
         if *event_type != EventType::Sms {
             return (None, None);
         }
 
-        let sms_type = raw_data.get("sms_type")
-            .and_then(|v| v.as_str())
-            .map(|s| match s.to_lowercase().as_str() {
-                "mt" | "mt_sms" => SmsType::MtSms,
-                "mo" | "mo_sms" => SmsType::MoSms,
-                _ => SmsType::Unknown,
-            })
-            .or(Some(SmsType::MoSms)); // Default to MO
-        
-        let length = raw_data.get("message_length")
-            .or_else(|| raw_data.get("length"))
-            .and_then(|v| v.as_i64())
+        let sms_type = mapping.variant(Field::SmsType, raw_data, SmsType::Unknown);
+        let length = mapping.integer(Field::MessageLength, raw_data)
             .map(|l| l as i32);
         
         (sms_type, length)
     }
 
-    fn detect_roaming(country: &str, mcc: Option<&str>) -> bool {
-        // Simple roaming detection: MCC doesn't match country
-        match (country, mcc) {
-            ("FR", Some(mcc)) => !mcc.starts_with("208"),
-            ("TN", Some(mcc)) => !mcc.starts_with("605"),
-            ("FN", Some(mcc)) => !mcc.starts_with("244"),
-            ("CH", Some(mcc)) => !mcc.starts_with("228"),
-            _ => false,
+    /// Network serving the CDR: its MCC and MNC, else its PLMN id, else
+    /// the MCC and MNC of the cell global id (`MCC-MNC-LAC-CI` or
+    /// `MCC-MNC-ECI`), else the visited network given as a PLMN id
+    fn serving_network(raw_data: &serde_json::Value, mapping: &Mapping) -> Option<Plmn> {
+        let mcc_mnc = mapping.string(Field::ServingMcc, raw_data)
+            .zip(mapping.string(Field::ServingMnc, raw_data))
+            .map(|(mcc, mnc)| format!("{}-{}", mcc, mnc));
+        let cell = mapping.string(Field::CellGlobalId, raw_data).and_then(|cgi| {
+            let mut parts = cgi.split('-');
+            Some(format!("{}-{}", parts.next()?, parts.next()?))
+        });
+
+        [
+            mcc_mnc,
+            mapping.string(Field::ServingPlmn, raw_data),
+            cell,
+            mapping.string(Field::VisitedNetwork, raw_data),
+        ]
+        .into_iter()
+        .flatten()
+        .find_map(|plmn| Registry::bundled().network(&plmn))
+    }
+
+    /// End and duration of the CDR, the missing one being derived from the
+    /// other and the start. A duration overflowing the timestamp range
+    /// derives no end.
+    fn complete_period(
+        start: DateTime<Utc>,
+        end: Option<DateTime<Utc>>,
+        duration: Option<i64>,
+    ) -> (Option<DateTime<Utc>>, Option<i64>) {
+        match (end, duration) {
+            (None, Some(duration)) if duration >= 0 => {
+                let end = chrono::Duration::try_seconds(duration)
+                    .and_then(|duration| start.checked_add_signed(duration));
+                (end, Some(duration))
+            }
+            (Some(end), None) if end >= start => (Some(end), Some((end - start).num_seconds())),
+            period => period,
         }
     }
 
+    /// SHA-256 of the canonical raw data (keys sorted recursively), stable
+    /// across processes, Rust versions and field order
     fn calculate_hash(data: &serde_json::Value) -> String {
-        let mut hasher = DefaultHasher::new();
-        data.to_string().hash(&mut hasher);
-        format!("{:x}", hasher.finish())
+        format!("{:x}", Sha256::digest(canonical_json(data).as_bytes()))
     }
 }
 
 impl Default for Normalizer {
     fn default() -> Self {
-        Self::new()
+        Self::new(
+            TacDatabase::bundled(),
+            MappingProfiles::bundled(),
+            Timezones::bundled(),
+            RoamingPlan::bundled(),
+        )
     }
 }
 
@@ -254,7 +427,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_normalize_voice_cdr() {
-        let normalizer = Normalizer::new();
+        let normalizer = Normalizer::default();
         
         let raw_data = serde_json::json!({
             "calling_number": "+33612345678",
@@ -272,6 +445,8 @@ mod tests {
             country: "FR".to_string(),
             raw_data,
             validation_timestamp: Utc::now().to_rfc3339(),
+            warnings: Vec::new(),
+            source_system: None,
         };
 
         let result = normalizer.normalize(&validated).await;
@@ -281,6 +456,45 @@ mod tests {
         assert_eq!(unified.event_type, EventType::Voice);
         assert_eq!(unified.mcc, Some("208".to_string()));
         assert_eq!(unified.calling_number, Some("+33612345678".to_string()));
+        assert_eq!(unified.source_system, "orion-ingestion");
+    }
+
+    #[test]
+    fn test_unify_with_country_profile() {
+        let mappings = MappingProfiles::from_yaml(r#"
+profiles:
+  - name: default
+    fields:
+      called_number: { paths: [called_number] }
+  - name: ch-switch
+    country: CH
+    source_system: ch-switch-v2
+    fields:
+      called_number: { paths: [/call/b_number] }
+      duration_seconds: { paths: [/call/duration_ms], unit: ms }
+"#).unwrap();
+        let normalizer = Normalizer::default().with_mappings(mappings);
+
+        let validated = ValidatedCDR {
+            cdr_id: "test-456".to_string(),
+            event_type: EventType::Voice,
+            imsi: "228010123456789".to_string(),
+            msisdn: "+41791234567".to_string(),
+            timestamp: Utc::now(),
+            country: "CH".to_string(),
+            raw_data: serde_json::json!({"call": {"b_number": "0791234568", "duration_ms": 45_400}}),
+            validation_timestamp: Utc::now().to_rfc3339(),
+            warnings: Vec::new(),
+            source_system: Some("ch-switch-v2".to_string()),
+        };
+
+        let mapping = normalizer.mapping(&validated);
+        assert_eq!(mapping.name(), "ch-switch");
+        let unified = normalizer.unify(&validated, &mapping);
+        assert_eq!(unified.called_number.as_deref(), Some("+41791234568"));
+        assert_eq!(unified.call_type, Some(CallType::Mobile));
+        assert_eq!(unified.duration_seconds, Some(45));
+        assert_eq!(unified.source_system, "ch-switch-v2");
     }
 
     #[test]
@@ -291,8 +505,188 @@ mod tests {
     }
 
     #[test]
-    fn test_detect_roaming() {
-        assert!(!Normalizer::detect_roaming("FR", Some("208")));
-        assert!(Normalizer::detect_roaming("FR", Some("605")));
+    fn test_extract_three_digit_mnc() {
+        let (mcc, mnc) = Normalizer::extract_mcc_mnc("310260123456789");
+        assert_eq!(mcc.as_deref(), Some("310"));
+        assert_eq!(mnc.as_deref(), Some("260"));
+    }
+
+    #[test]
+    fn test_device_model_from_tac() {
+        let normalizer = Normalizer::default();
+        assert_eq!(normalizer.device_model("350000011234560").as_deref(), Some("ORION Test Smartphone 5G"));
+        // Wrong check digit: no lookup
+        assert_eq!(normalizer.device_model("350000011234561"), None);
+        assert_eq!(normalizer.device_model("490154203237518"), None);
+    }
+
+    #[test]
+    fn test_roaming_from_serving_network() {
+        let normalizer = Normalizer::default();
+        let unify = |imsi: &str, country: &str, raw_data: serde_json::Value| {
+            let validated = ValidatedCDR {
+                cdr_id: "test-roaming".to_string(),
+                event_type: EventType::Data,
+                imsi: imsi.to_string(),
+                msisdn: "+33612345678".to_string(),
+                timestamp: Utc::now(),
+                country: country.to_string(),
+                raw_data,
+                validation_timestamp: Utc::now().to_rfc3339(),
+                warnings: Vec::new(),
+                source_system: None,
+            };
+            normalizer.unify(&validated, &normalizer.mapping(&validated))
+        };
+
+        let unified = unify("208010123456789", "FR", serde_json::json!({"cell_global_id": "208-02-1234-5678"}));
+        assert_eq!(unified.roaming_type, Some(RoamingType::Domestic));
+        assert!(!unified.is_roaming);
+        assert_eq!(unified.service_type, ServiceType::Standard);
+        assert_eq!(unified.visited_country, None);
+
+        // Classified domestic: the roaming flag of the CDR is ignored
+        let unified = unify("208010123456789", "FR", serde_json::json!({"is_roaming": true}));
+        assert_eq!(unified.roaming_type, Some(RoamingType::Domestic));
+        assert_eq!(unified.service_type, ServiceType::Standard);
+
+        let unified = unify("208010123456789", "FR", serde_json::json!({"serving_mcc": "228", "serving_mnc": "01"}));
+        assert_eq!(unified.roaming_type, Some(RoamingType::OutboundInternational));
+        assert!(unified.is_roaming);
+        assert_eq!(unified.service_type, ServiceType::Roaming);
+        assert_eq!(unified.visited_country.as_deref(), Some("CH"));
+        assert_eq!(unified.visited_network.as_deref(), Some("22801"));
+        assert_eq!(unified.roaming_zone.as_deref(), Some("world"));
+        assert_eq!(unified.timezone.as_deref(), Some("Europe/Zurich"));
+
+        // Visited country given by the CDR is kept
+        let unified = unify("244050123456789", "FN", serde_json::json!({"visited_country": "RU"}));
+        assert_eq!(unified.roaming_type, Some(RoamingType::OutboundInternational));
+        assert_eq!(unified.visited_country.as_deref(), Some("RU"));
+
+        // Unknown home network: the roaming flag of the CDR is used
+        let unified = unify("999990123456789", "FR", serde_json::json!({"is_roaming": true}));
+        assert_eq!(unified.roaming_type, None);
+        assert!(unified.is_roaming);
+        assert_eq!(unified.service_type, ServiceType::Roaming);
+    }
+
+    #[test]
+    fn test_voice_numbers_and_derived_call_type() {
+        let mappings = MappingProfiles::bundled();
+        let voice = |raw: serde_json::Value, country: &str| {
+            let mapping = mappings.select(country, None);
+            let (calling, called, call_type, _) = Normalizer::extract_voice_fields(&raw, &mapping, &EventType::Voice, country);
+            (calling, called, call_type.unwrap())
+        };
+
+        let (calling, called, call_type) = voice(
+            serde_json::json!({"msisdn": "06 12 34 56 78", "called_number": "0145678901", "call_type": "mobile"}),
+            "FR",
+        );
+        assert_eq!(calling.as_deref(), Some("+33612345678"));
+        assert_eq!(called.as_deref(), Some("+33145678901"));
+        assert_eq!(call_type, CallType::Landline);
+
+        let (_, called, call_type) = voice(serde_json::json!({"called_number": "0033612345678"}), "TN");
+        assert_eq!(called.as_deref(), Some("+33612345678"));
+        assert_eq!(call_type, CallType::International);
+
+        assert_eq!(voice(serde_json::json!({"called_number": "117"}), "CH").2, CallType::Emergency);
+        assert_eq!(voice(serde_json::json!({"called_number": "0899123456"}), "FR").2, CallType::Premium);
+
+        // Unparseable number: kept as given, call type from the input
+        let (_, called, call_type) = voice(serde_json::json!({"called_number": "anonymous", "call_type": "fixed"}), "FR");
+        assert_eq!(called.as_deref(), Some("anonymous"));
+        assert_eq!(call_type, CallType::Landline);
+    }
+
+    #[test]
+    fn test_local_time_of_roaming_cdr() {
+        let normalizer = Normalizer::default();
+        let validated = ValidatedCDR {
+            cdr_id: "test-789".to_string(),
+            event_type: EventType::Voice,
+            imsi: "208150123456789".to_string(),
+            msisdn: "+33612345678".to_string(),
+            timestamp: "2024-03-31T00:30:00Z".parse().unwrap(),
+            country: "FR".to_string(),
+            raw_data: serde_json::json!({"duration": 90, "visited_country": "FN"}),
+            validation_timestamp: Utc::now().to_rfc3339(),
+            warnings: Vec::new(),
+            source_system: None,
+        };
+
+        let unified = normalizer.unify(&validated, &normalizer.mapping(&validated));
+        assert_eq!(unified.timezone.as_deref(), Some("Europe/Helsinki"));
+        assert_eq!(unified.local_start_timestamp.unwrap().to_rfc3339(), "2024-03-31T02:30:00+02:00");
+        assert_eq!(unified.utc_offset_seconds, Some(7200));
+        assert_eq!(unified.is_dst, Some(false));
+        assert_eq!(unified.end_timestamp, Some("2024-03-31T00:31:30Z".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_partial_from_pgw_closing_cause() {
+        let normalizer = Normalizer::default();
+        let partial = |raw_data: serde_json::Value| {
+            let validated = ValidatedCDR {
+                cdr_id: "test-partial".to_string(),
+                event_type: EventType::Data,
+                imsi: "244911234567890".to_string(),
+                msisdn: "+358401234567".to_string(),
+                timestamp: Utc::now(),
+                country: "FN".to_string(),
+                raw_data,
+                validation_timestamp: Utc::now().to_rfc3339(),
+                warnings: Vec::new(),
+                source_system: None,
+            };
+            normalizer.partial(&validated)
+        };
+
+        // As decoded from a PGWRecord: timeLimit, then normalRelease
+        let first = partial(serde_json::json!({"record_sequence_number": 1, "cause_for_record_closing": 17}));
+        assert_eq!(first, Some(Partial { sequence: 1, last: false }));
+        let last = partial(serde_json::json!({"record_sequence_number": 2, "cause_for_record_closing": 0}));
+        assert_eq!(last, Some(Partial { sequence: 2, last: true }));
+        // An explicit flag wins over the closing cause
+        let flagged = partial(serde_json::json!({"record_sequence_number": 3, "last_partial": true, "cause_for_record_closing": 16}));
+        assert_eq!(flagged, Some(Partial { sequence: 3, last: true }));
+        assert_eq!(partial(serde_json::json!({"cause_for_record_closing": 0})), None);
+    }
+
+    #[test]
+    fn test_complete_period() {
+        let start: DateTime<Utc> = "2024-01-15T10:30:00Z".parse().unwrap();
+        let end: DateTime<Utc> = "2024-01-15T10:32:00Z".parse().unwrap();
+        assert_eq!(Normalizer::complete_period(start, None, Some(120)), (Some(end), Some(120)));
+        assert_eq!(Normalizer::complete_period(start, Some(end), None), (Some(end), Some(120)));
+        // Both given: kept as they are, even when inconsistent
+        assert_eq!(Normalizer::complete_period(start, Some(end), Some(60)), (Some(end), Some(60)));
+        // End before the start: no duration derived
+        assert_eq!(Normalizer::complete_period(end, Some(start), None), (Some(start), None));
+        assert_eq!(Normalizer::complete_period(start, None, None), (None, None));
+        // Duration past the timestamp range: kept, no end derived
+        assert_eq!(Normalizer::complete_period(start, None, Some(i64::MAX)), (None, Some(i64::MAX)));
+        assert_eq!(
+            Normalizer::complete_period(DateTime::<Utc>::MAX_UTC, None, Some(1)),
+            (None, Some(1))
+        );
+    }
+
+    #[test]
+    fn test_calculate_hash_is_stable() {
+        let hash = Normalizer::calculate_hash(&serde_json::json!({
+            "msisdn": "+33612345678",
+            "location": {"lac": "1001", "cell_id": "12345"},
+            "imsi": "208150123456789"
+        }));
+        assert_eq!(hash, Normalizer::calculate_hash(&serde_json::json!({
+            "imsi": "208150123456789",
+            "location": {"cell_id": "12345", "lac": "1001"},
+            "msisdn": "+33612345678"
+        })));
+        // SHA-256 of the canonical text, keys sorted at every level
+        assert_eq!(hash, "72f3dfb7d66637837b5bf913b53943dbfd778beeeef0f01ce610430220bc14a0");
     }
 }
@@ -0,0 +1,269 @@
+//! Roaming classification of the CDRs.
+//!
+//! The home network of the subscriber (the MCC/MNC of the IMSI) is compared
+//! with the network serving the CDR. Home networks are grouped by operator
+//! and countries by roaming zone in a roaming plan, whose format is
+//! documented in `config/roaming.yaml`.
+
+use crate::service::model::RoamingType;
+use anyhow::Context;
+use orion_numbering::{Plmn, Registry};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Roaming plan shipped with the service, used when no plan is deployed
+const BUNDLED_ROAMING: &str = include_str!("../../config/roaming.yaml");
+
+/// Zone of international roaming between countries of no common zone
+pub const WORLD_ZONE: &str = "world";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RoamingFile {
+    #[serde(default)]
+    operator_groups: Vec<GroupSpec>,
+    #[serde(default)]
+    zones: Vec<ZoneSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GroupSpec {
+    name: String,
+    networks: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ZoneSpec {
+    name: String,
+    countries: Vec<String>,
+}
+
+/// Home networks by operator group and countries by roaming zone
+pub struct RoamingPlan {
+    /// Operator group by network, keyed `mcc-mnc`
+    groups: HashMap<String, String>,
+    /// Roaming zone by country
+    zones: HashMap<String, String>,
+}
+
+/// Roaming of a CDR
+#[derive(Debug, Clone, PartialEq)]
+pub struct Roaming {
+    pub roaming_type: RoamingType,
+    /// Zone of international roaming
+    pub zone: Option<String>,
+    /// Country where the subscriber is, absent when domestic
+    pub visited_country: Option<String>,
+    /// PLMN id (`20801`) of the serving network, absent when domestic or
+    /// not known
+    pub visited_network: Option<String>,
+}
+
+impl RoamingPlan {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read roaming plan {}", path.display()))?;
+        Self::from_yaml(&content).with_context(|| format!("Invalid roaming plan {}", path.display()))
+    }
+
+    pub fn from_yaml(content: &str) -> anyhow::Result<Self> {
+        Self::compile(serde_yaml::from_str(content)?)
+    }
+
+    pub fn bundled() -> Self {
+        Self::from_yaml(BUNDLED_ROAMING).expect("bundled roaming plan is valid")
+    }
+
+    fn compile(file: RoamingFile) -> anyhow::Result<Self> {
+        let registry = Registry::bundled();
+        let mut groups = HashMap::new();
+        for group in file.operator_groups {
+            // A group is domestic only within one country
+            let mut country = None;
+            for network in &group.networks {
+                let plmn = registry
+                    .network(network)
+                    .with_context(|| format!("Operator group {}: unknown network {}", group.name, network))?;
+                match country {
+                    Some(country) if country != plmn.country => anyhow::bail!(
+                        "Operator group {} has networks of {} and {}",
+                        group.name,
+                        country,
+                        plmn.country
+                    ),
+                    _ => country = Some(plmn.country),
+                }
+                if let Some(other) = groups.insert(key(&plmn), group.name.clone()) {
+                    anyhow::bail!("Network {} is in operator groups {} and {}", network, other, group.name);
+                }
+            }
+        }
+
+        let mut zones = HashMap::new();
+        for zone in file.zones {
+            for country in &zone.countries {
+                if let Some(other) = zones.insert(country.to_uppercase(), zone.name.clone()) {
+                    anyhow::bail!("Country {} is in zones {} and {}", country, other, zone.name);
+                }
+            }
+        }
+
+        Ok(Self { groups, zones })
+    }
+
+    /// Roaming of a subscriber of the `home` network in a CDR of `country`,
+    /// served by the `serving` network when known, else in the visited
+    /// country given by the CDR
+    pub fn classify(
+        &self,
+        home: &Plmn,
+        serving: Option<&Plmn>,
+        country: &str,
+        visited_country: Option<&str>,
+    ) -> Roaming {
+        let country = iso_country(country);
+
+        if let Some(serving) = serving {
+            if self.is_home(home, serving) {
+                return Roaming::domestic();
+            }
+            let visited_network = Some(format!("{}{}", serving.mcc, serving.mnc));
+            if serving.country == home.country {
+                return Roaming {
+                    roaming_type: RoamingType::NationalRoaming,
+                    zone: None,
+                    visited_country: Some(serving.country.to_string()),
+                    visited_network,
+                };
+            }
+            return self.international(home, country, serving.country, visited_network);
+        }
+
+        match visited_country.map(iso_country) {
+            Some(visited) if visited != home.country => self.international(home, country, visited, None),
+            _ if home.country == country => Roaming::domestic(),
+            // Foreign subscriber, served in the CDR's country
+            _ => self.international(home, country, country, None),
+        }
+    }
+
+    fn international(&self, home: &Plmn, country: &str, visited: &str, visited_network: Option<String>) -> Roaming {
+        let roaming_type = if home.country == country {
+            RoamingType::OutboundInternational
+        } else {
+            RoamingType::InboundInternational
+        };
+        Roaming {
+            roaming_type,
+            zone: Some(self.zone(home.country, visited)),
+            visited_country: Some(visited.to_string()),
+            visited_network,
+        }
+    }
+
+    /// The serving network is the home network or in its operator group,
+    /// whose networks are all of the same country
+    fn is_home(&self, home: &Plmn, serving: &Plmn) -> bool {
+        let (home, serving) = (key(home), key(serving));
+        if home == serving {
+            return true;
+        }
+        match (self.groups.get(&home), self.groups.get(&serving)) {
+            (Some(home), Some(serving)) => home == serving,
+            _ => false,
+        }
+    }
+
+    fn zone(&self, home_country: &str, visited_country: &str) -> String {
+        match (self.zones.get(home_country), self.zones.get(visited_country)) {
+            (Some(home), Some(visited)) if home == visited => home.clone(),
+            _ => WORLD_ZONE.to_string(),
+        }
+    }
+}
+
+impl Roaming {
+    fn domestic() -> Self {
+        Self {
+            roaming_type: RoamingType::Domestic,
+            zone: None,
+            visited_country: None,
+            visited_network: None,
+        }
+    }
+}
+
+fn key(plmn: &Plmn) -> String {
+    format!("{}-{}", plmn.mcc, plmn.mnc)
+}
+
+/// ISO 3166 alpha-2 code of an ORION country code (`FN` is Finland)
+fn iso_country(country: &str) -> &str {
+    orion_numbering::plans::by_region(country).map_or(country, |plan| plan.iso)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(plmn: &str) -> Plmn {
+        Registry::bundled().network(plmn).unwrap()
+    }
+
+    #[test]
+    fn test_classify_by_serving_network() {
+        let plan = RoamingPlan::bundled();
+        let orange = network("20801");
+
+        let roaming = plan.classify(&orange, Some(&network("20802")), "FR", None);
+        assert_eq!(roaming, Roaming::domestic());
+
+        // Free subscriber on the Orange network
+        let roaming = plan.classify(&network("20815"), Some(&orange), "FR", None);
+        assert_eq!(roaming.roaming_type, RoamingType::NationalRoaming);
+        assert_eq!(roaming.visited_network.as_deref(), Some("20801"));
+        assert_eq!(roaming.zone, None);
+
+        let roaming = plan.classify(&network("24405"), Some(&network("21401")), "FN", None);
+        assert_eq!(roaming.roaming_type, RoamingType::OutboundInternational);
+        assert_eq!(roaming.zone.as_deref(), Some("eu"));
+        assert_eq!(roaming.visited_country.as_deref(), Some("ES"));
+
+        let roaming = plan.classify(&network("22801"), Some(&orange), "FR", None);
+        assert_eq!(roaming.roaming_type, RoamingType::InboundInternational);
+        assert_eq!(roaming.zone.as_deref(), Some(WORLD_ZONE));
+        assert_eq!(roaming.visited_country.as_deref(), Some("FR"));
+    }
+
+    #[test]
+    fn test_classify_without_serving_network() {
+        let plan = RoamingPlan::bundled();
+        let elisa = network("24405");
+
+        assert_eq!(plan.classify(&elisa, None, "FN", None), Roaming::domestic());
+        assert_eq!(plan.classify(&elisa, None, "FN", Some("FN")), Roaming::domestic());
+
+        let roaming = plan.classify(&elisa, None, "FN", Some("RU"));
+        assert_eq!(roaming.roaming_type, RoamingType::OutboundInternational);
+        assert_eq!(roaming.zone.as_deref(), Some(WORLD_ZONE));
+
+        let roaming = plan.classify(&network("60501"), None, "FR", None);
+        assert_eq!(roaming.roaming_type, RoamingType::InboundInternational);
+        assert_eq!(roaming.visited_country.as_deref(), Some("FR"));
+    }
+
+    #[test]
+    fn test_plan_rejects_invalid_networks() {
+        assert!(RoamingPlan::from_yaml("operator_groups: [{name: a, networks: [208-01]}]").is_ok());
+        assert!(RoamingPlan::from_yaml("operator_groups: [{name: a, networks: [999-01]}]").is_err());
+        assert!(RoamingPlan::from_yaml(
+            "operator_groups: [{name: a, networks: [208-01]}, {name: b, networks: [208-01]}]"
+        ).is_err());
+        // Grouping networks of two countries would make roaming domestic
+        assert!(RoamingPlan::from_yaml("operator_groups: [{name: a, networks: [208-01, 605-01]}]").is_err());
+        assert!(RoamingPlan::from_yaml("zones: [{name: eu, countries: [FR]}, {name: world, countries: [FR]}]").is_err());
+    }
+}
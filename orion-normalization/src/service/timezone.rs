@@ -0,0 +1,160 @@
+//! Local time of the CDRs.
+//!
+//! The timezone of a CDR is the one of the country where the subscriber
+//! is: the visited country when roaming, else the country of the visited
+//! network's MCC, else the country of the CDR. Countries are mapped to
+//! timezones by a `country,timezone` table.
+
+use anyhow::Context;
+use chrono::{DateTime, FixedOffset, Utc};
+use chrono_tz::{OffsetComponents, Tz};
+use orion_numbering::Registry;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Timezones shipped with the service, used when no table is deployed
+const BUNDLED_TIMEZONES: &str = include_str!("../../config/timezones.csv");
+
+/// Timezone of the subscribers, by country
+pub struct Timezones {
+    by_country: HashMap<String, Tz>,
+}
+
+impl Timezones {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let csv = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read timezones file {}", path.display()))?;
+        Self::parse(&csv).with_context(|| format!("Invalid timezones file {}", path.display()))
+    }
+
+    /// Table with a `country,timezone` header; blank lines and lines
+    /// starting with `#` are skipped
+    pub fn parse(csv: &str) -> anyhow::Result<Self> {
+        let mut lines = csv
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        match lines.next() {
+            Some((_, "country,timezone")) => {}
+            _ => anyhow::bail!("Expected a country,timezone header"),
+        }
+
+        let mut by_country = HashMap::new();
+        for (number, line) in lines {
+            let (country, timezone) = line
+                .split_once(',')
+                .with_context(|| format!("Line {}: expected country,timezone", number))?;
+            let timezone: Tz = timezone
+                .trim()
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Line {}: unknown timezone {}", number, timezone))?;
+            if by_country.insert(country.trim().to_uppercase(), timezone).is_some() {
+                anyhow::bail!("Line {}: duplicate country {}", number, country);
+            }
+        }
+        Ok(Self { by_country })
+    }
+
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_TIMEZONES).expect("bundled timezones are valid")
+    }
+
+    pub fn country(&self, country: &str) -> Option<Tz> {
+        self.by_country.get(&country.to_uppercase()).copied()
+    }
+
+    /// Timezone where the subscriber is, `None` when no candidate country
+    /// is in the table
+    pub fn resolve(
+        &self,
+        country: &str,
+        visited_country: Option<&str>,
+        visited_network: Option<&str>,
+    ) -> Option<Tz> {
+        visited_country
+            .and_then(|visited| self.country(visited))
+            .or_else(|| {
+                visited_network
+                    .and_then(network_country)
+                    .and_then(|visited| self.country(visited))
+            })
+            .or_else(|| self.country(country))
+    }
+}
+
+/// Country of a visited network given as a PLMN (MCC followed by MNC)
+fn network_country(network: &str) -> Option<&'static str> {
+    let mcc = network
+        .get(..3)
+        .filter(|mcc| mcc.bytes().all(|b| b.is_ascii_digit()))?;
+    Registry::bundled().country(mcc)
+}
+
+/// Instant in the local time of a timezone
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalTime {
+    pub timezone: Tz,
+    pub timestamp: DateTime<FixedOffset>,
+    pub utc_offset_seconds: i32,
+    pub is_dst: bool,
+}
+
+impl LocalTime {
+    pub fn at(timezone: Tz, utc: DateTime<Utc>) -> Self {
+        let local = utc.with_timezone(&timezone);
+        let is_dst = !local.offset().dst_offset().is_zero();
+        let timestamp = local.fixed_offset();
+        Self {
+            timezone,
+            utc_offset_seconds: timestamp.offset().local_minus_utc(),
+            timestamp,
+            is_dst,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_resolve_visited_then_home_country() {
+        let timezones = Timezones::bundled();
+        assert_eq!(timezones.resolve("FR", None, None), Some(Tz::Europe__Paris));
+        assert_eq!(timezones.resolve("FN", None, None), Some(Tz::Europe__Helsinki));
+        assert_eq!(timezones.resolve("FR", Some("RU"), Some("20801")), Some(Tz::Europe__Moscow));
+        // Visited network given as a PLMN: country of its MCC
+        assert_eq!(timezones.resolve("FR", None, Some("60502")), Some(Tz::Africa__Tunis));
+        assert_eq!(timezones.resolve("FR", Some("ZZ"), Some("MTS")), Some(Tz::Europe__Paris));
+        assert_eq!(timezones.resolve("ZZ", None, None), None);
+    }
+
+    #[test]
+    fn test_local_time_offset_and_dst() {
+        let summer = LocalTime::at(Tz::Europe__Paris, Utc.with_ymd_and_hms(2024, 7, 14, 22, 30, 0).unwrap());
+        assert_eq!(summer.timestamp.to_rfc3339(), "2024-07-15T00:30:00+02:00");
+        assert_eq!(summer.utc_offset_seconds, 7200);
+        assert!(summer.is_dst);
+
+        let winter = LocalTime::at(Tz::Europe__Paris, Utc.with_ymd_and_hms(2024, 1, 15, 22, 30, 0).unwrap());
+        assert_eq!(winter.timestamp.to_rfc3339(), "2024-01-15T23:30:00+01:00");
+        assert_eq!(winter.utc_offset_seconds, 3600);
+        assert!(!winter.is_dst);
+
+        // Tunisia has no daylight saving time
+        let tunis = LocalTime::at(Tz::Africa__Tunis, Utc.with_ymd_and_hms(2024, 7, 14, 22, 30, 0).unwrap());
+        assert_eq!(tunis.utc_offset_seconds, 3600);
+        assert!(!tunis.is_dst);
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_timezone() {
+        assert!(Timezones::parse("country,timezone\nFR,Europe/Paris\n").is_ok());
+        assert!(Timezones::parse("country,timezone\nFR,Europe/Lyon\n").is_err());
+        assert!(Timezones::parse("country,timezone\nFR,Europe/Paris\nFR,Europe/Paris\n").is_err());
+        assert!(Timezones::parse("FR,Europe/Paris\n").is_err());
+    }
+}
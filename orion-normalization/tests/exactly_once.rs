@@ -0,0 +1,49 @@
+//! Exactly-once integration tests, run by the shared `orion_kafka::testing`
+//! scenarios against a single-node Kafka broker in a container. They need a
+//! Docker daemon:
+//!
+//! ```bash
+//! cargo test --test exactly_once -- --ignored
+//! ```
+
+use orion_kafka::testing::{self, Service};
+
+/// 20 validated voice CDRs
+fn service() -> Service {
+    let inputs = (0..20)
+        .map(|i| {
+            serde_json::json!({
+                "cdr_id": format!("cdr-{}", i),
+                "event_type": "voice",
+                "imsi": format!("2081501234{:05}", i),
+                "msisdn": "+33612345678",
+                "timestamp": "2026-01-29T10:00:00Z",
+                "country": "FR",
+                "raw_data": { "duration": 60, "called_number": "+33698765432" },
+                "validation_timestamp": "2026-01-29T10:00:01Z",
+            })
+            .to_string()
+        })
+        .collect();
+
+    Service {
+        binary: env!("CARGO_BIN_EXE_orion-normalization"),
+        name: "orion-normalization-it",
+        env: vec![("KAFKA_OUTPUT_TOPIC", "cdr.enriched")],
+        input_topic: "cdr.normalized",
+        inputs,
+        outputs: vec![("cdr.enriched", 20)],
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires a Docker daemon"]
+async fn test_restart_does_not_duplicate_outputs() {
+    testing::restart_does_not_duplicate_outputs(&service()).await;
+}
+
+#[tokio::test]
+#[ignore = "requires a Docker daemon"]
+async fn test_crash_mid_transaction_replays_without_duplicates() {
+    testing::crash_mid_transaction_replays_without_duplicates(&service()).await;
+}
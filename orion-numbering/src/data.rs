@@ -0,0 +1,36 @@
+//! Bundled reference data, as small CSV files.
+//!
+//! Lines starting with `#` are comments and the first other line is the
+//! header. Values cannot contain commas, except in the last column.
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("line {line}: {message}")]
+pub struct DataError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Records of a CSV file with `columns` columns, with their line number
+pub(crate) fn records(text: &str, columns: usize) -> impl Iterator<Item = Result<(usize, Vec<&str>), DataError>> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .skip(1)
+        .map(move |(line, text)| {
+            let values: Vec<&str> = text.splitn(columns, ',').map(str::trim).collect();
+            if values.len() != columns || values.iter().any(|value| value.is_empty()) {
+                return Err(DataError {
+                    line,
+                    message: format!("expected {} non-empty values, got: {}", columns, text),
+                });
+            }
+            Ok((line, values))
+        })
+}
+
+pub(crate) fn invalid(line: usize, message: String) -> DataError {
+    DataError { line, message }
+}
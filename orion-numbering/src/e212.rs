@@ -0,0 +1,209 @@
+//! IMSI structure (ITU-T E.212): MCC, MNC and MSIN.
+//!
+//! The MCC/MNC registry is loaded from the bundled `data/mcc.csv` and
+//! `data/mnc.csv`. The MNC length is not encoded in the IMSI: a listed 3-digit
+//! MNC is tried first, then a listed 2-digit one, then the usual length of
+//! the MCC.
+
+use crate::data::{invalid, records, DataError};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+const BUNDLED_MCC: &str = include_str!("../data/mcc.csv");
+const BUNDLED_MNC: &str = include_str!("../data/mnc.csv");
+
+/// Longest IMSI, in digits
+const MAX_IMSI_DIGITS: usize = 15;
+/// Shortest IMSI accepted: MCC, 2-digit MNC and at least one MSIN digit
+const MIN_IMSI_DIGITS: usize = 6;
+
+/// Mobile network: home network of a subscriber, or network serving a CDR
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Plmn {
+    pub mcc: String,
+    pub mnc: String,
+    /// ISO 3166 alpha-2 code of the MCC
+    pub country: &'static str,
+    /// Operator of the MNC, `None` when the MNC is not in the registry
+    pub operator: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ImsiError {
+    #[error("IMSI must only contain digits")]
+    InvalidCharacters,
+    #[error("IMSI must be {MIN_IMSI_DIGITS}-{MAX_IMSI_DIGITS} digits, got {0}")]
+    InvalidLength(usize),
+    #[error("unknown MCC {0}")]
+    UnknownMcc(String),
+}
+
+struct Mcc {
+    country: &'static str,
+    mnc_digits: usize,
+}
+
+pub struct Registry {
+    countries: HashMap<&'static str, Mcc>,
+    /// Operator by MCC and MNC
+    networks: HashMap<(&'static str, &'static str), &'static str>,
+}
+
+impl Registry {
+    /// Registry of the bundled data files
+    pub fn bundled() -> &'static Registry {
+        static REGISTRY: OnceLock<Registry> = OnceLock::new();
+        REGISTRY.get_or_init(|| Self::parse(BUNDLED_MCC, BUNDLED_MNC).expect("bundled MCC/MNC registry is valid"))
+    }
+
+    /// Registry of `mcc,country,mnc_digits` and `mcc,mnc,operator` files
+    pub fn parse(mcc_csv: &'static str, mnc_csv: &'static str) -> Result<Self, DataError> {
+        let mut countries = HashMap::new();
+        for record in records(mcc_csv, 3) {
+            let (line, values) = record?;
+            if !is_code(values[0], 3) {
+                return Err(invalid(line, format!("invalid MCC {}", values[0])));
+            }
+            let mnc_digits = match values[2] {
+                "2" => 2,
+                "3" => 3,
+                other => return Err(invalid(line, format!("MNC length must be 2 or 3, got: {}", other))),
+            };
+            countries.insert(values[0], Mcc { country: values[1], mnc_digits });
+        }
+
+        let mut networks = HashMap::new();
+        for record in records(mnc_csv, 3) {
+            let (line, values) = record?;
+            if !countries.contains_key(values[0]) {
+                return Err(invalid(line, format!("MCC {} is not in the MCC list", values[0])));
+            }
+            if !is_code(values[1], 2) && !is_code(values[1], 3) {
+                return Err(invalid(line, format!("invalid MNC {}", values[1])));
+            }
+            networks.insert((values[0], values[1]), values[2]);
+        }
+
+        Ok(Self { countries, networks })
+    }
+
+    /// Home network of an IMSI
+    pub fn resolve(&self, imsi: &str) -> Result<Plmn, ImsiError> {
+        if !imsi.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ImsiError::InvalidCharacters);
+        }
+        if !(MIN_IMSI_DIGITS..=MAX_IMSI_DIGITS).contains(&imsi.len()) {
+            return Err(ImsiError::InvalidLength(imsi.len()));
+        }
+
+        let (mcc, rest) = imsi.split_at(3);
+        let Some((&mcc, entry)) = self.countries.get_key_value(mcc) else {
+            return Err(ImsiError::UnknownMcc(mcc.to_string()));
+        };
+
+        let listed = [3, 2]
+            .into_iter()
+            .filter_map(|digits| rest.get(..digits))
+            .find_map(|mnc| self.networks.get(&(mcc, mnc)).map(|operator| (mnc, *operator)));
+        let (mnc, operator) = match listed {
+            Some((mnc, operator)) => (mnc, Some(operator)),
+            None => (&rest[..entry.mnc_digits.min(rest.len())], None),
+        };
+
+        Ok(Plmn {
+            mcc: mcc.to_string(),
+            mnc: mnc.to_string(),
+            country: entry.country,
+            operator,
+        })
+    }
+
+    /// Network of a PLMN id: the MCC followed by the 2 or 3-digit MNC,
+    /// optionally separated by `-` (`20801`, `208-01`, `310260`)
+    pub fn network(&self, plmn: &str) -> Option<Plmn> {
+        let (mcc, mnc) = match plmn.split_once('-') {
+            Some(parts) => parts,
+            None => (plmn.get(..3)?, plmn.get(3..)?),
+        };
+        if !is_code(mnc, 2) && !is_code(mnc, 3) {
+            return None;
+        }
+        let (&mcc, entry) = self.countries.get_key_value(mcc)?;
+        Some(Plmn {
+            mcc: mcc.to_string(),
+            mnc: mnc.to_string(),
+            country: entry.country,
+            operator: self.operator(mcc, mnc),
+        })
+    }
+
+    /// Country (ISO 3166 alpha-2) of an MCC
+    pub fn country(&self, mcc: &str) -> Option<&'static str> {
+        self.countries.get(mcc).map(|entry| entry.country)
+    }
+
+    pub fn operator(&self, mcc: &str, mnc: &str) -> Option<&'static str> {
+        self.countries
+            .get_key_value(mcc)
+            .and_then(|(&mcc, _)| self.networks.get(&(mcc, mnc)).copied())
+    }
+}
+
+fn is_code(value: &str, digits: usize) -> bool {
+    value.len() == digits && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_mnc_length() {
+        let registry = Registry::bundled();
+
+        let plmn = registry.resolve("208150123456789").unwrap();
+        assert_eq!((plmn.mcc.as_str(), plmn.mnc.as_str()), ("208", "15"));
+        assert_eq!((plmn.country, plmn.operator), ("FR", Some("Free Mobile")));
+
+        let plmn = registry.resolve("310260123456789").unwrap();
+        assert_eq!((plmn.mnc.as_str(), plmn.operator), ("260", Some("T-Mobile US")));
+
+        // Unlisted MNC: usual length of the MCC
+        let plmn = registry.resolve("208990123456789").unwrap();
+        assert_eq!((plmn.mnc.as_str(), plmn.operator), ("99", None));
+        assert_eq!(registry.resolve("310999123456789").unwrap().mnc, "999");
+    }
+
+    #[test]
+    fn test_network_of_plmn_id() {
+        let registry = Registry::bundled();
+
+        let plmn = registry.network("20801").unwrap();
+        assert_eq!((plmn.mcc.as_str(), plmn.mnc.as_str()), ("208", "01"));
+        assert_eq!((plmn.country, plmn.operator), ("FR", Some("Orange France")));
+        assert_eq!(registry.network("208-01"), Some(plmn));
+        assert_eq!(registry.network("310260").unwrap().operator, Some("T-Mobile US"));
+        assert_eq!(registry.network("20899").unwrap().operator, None);
+
+        assert_eq!(registry.network("2080"), None);
+        assert_eq!(registry.network("208-0A"), None);
+        assert_eq!(registry.network("12301"), None);
+    }
+
+    #[test]
+    fn test_invalid_imsi() {
+        let registry = Registry::bundled();
+        assert_eq!(registry.resolve("20815A123456789"), Err(ImsiError::InvalidCharacters));
+        assert_eq!(registry.resolve("2081501234567890"), Err(ImsiError::InvalidLength(16)));
+        assert_eq!(registry.resolve("123456789012345"), Err(ImsiError::UnknownMcc("123".to_string())));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_data() {
+        let error = Registry::parse("mcc,country,mnc_digits\n208,FR,4\n", "mcc,mnc,operator\n").err().unwrap();
+        assert_eq!(error.line, 2);
+        assert!(Registry::parse("mcc,country,mnc_digits\n208,FR,2\n", "mcc,mnc,operator\n999,01,Nobody\n").is_err());
+    }
+}
@@ -0,0 +1,174 @@
+//! IMEI structure (3GPP TS 23.003): TAC, serial number and Luhn check digit.
+//!
+//! A 15-digit IMEI ends with its Luhn check digit; a 14-digit one is given
+//! without it and a 16-digit IMEISV ends with a software version instead.
+
+use crate::data::{invalid, records, DataError};
+use serde::Serialize;
+use std::collections::HashMap;
+use thiserror::Error;
+
+const BUNDLED_TAC: &str = include_str!("../data/tac.csv");
+
+/// Type Allocation Code length, in digits
+pub const TAC_DIGITS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Imei {
+    /// Type Allocation Code, identifying the device model
+    pub tac: String,
+    pub serial: String,
+    pub check_digit: Option<u8>,
+    /// Software version of an IMEISV
+    pub software_version: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ImeiError {
+    #[error("IMEI must only contain digits")]
+    InvalidCharacters,
+    #[error("IMEI must be 14, 15 or 16 (IMEISV) digits, got {0}")]
+    InvalidLength(usize),
+    #[error("IMEI check digit {0} does not match the Luhn checksum")]
+    InvalidCheckDigit(u8),
+}
+
+/// Parse an IMEI or IMEISV, checking the Luhn digit of a 15-digit IMEI.
+/// Spaces, dashes and slashes are ignored.
+pub fn parse(imei: &str) -> Result<Imei, ImeiError> {
+    let digits: String = imei.chars().filter(|c| !matches!(c, ' ' | '-' | '/')).collect();
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ImeiError::InvalidCharacters);
+    }
+
+    let (tac, serial) = (digits.get(..TAC_DIGITS), digits.get(TAC_DIGITS..14));
+    let (Some(tac), Some(serial)) = (tac, serial) else {
+        return Err(ImeiError::InvalidLength(digits.len()));
+    };
+    let (check_digit, software_version) = match digits.len() {
+        14 => (None, None),
+        15 => {
+            let check_digit = digits.as_bytes()[14] - b'0';
+            if !luhn_valid(&digits) {
+                return Err(ImeiError::InvalidCheckDigit(check_digit));
+            }
+            (Some(check_digit), None)
+        }
+        16 => (None, Some(digits[14..].to_string())),
+        other => return Err(ImeiError::InvalidLength(other)),
+    };
+
+    Ok(Imei {
+        tac: tac.to_string(),
+        serial: serial.to_string(),
+        check_digit,
+        software_version,
+    })
+}
+
+/// Whether a digit string passes the Luhn checksum (its last digit being
+/// the check digit)
+pub fn luhn_valid(digits: &str) -> bool {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(index, b)| {
+            let digit = u32::from(b - b'0');
+            if index % 2 == 1 {
+                let doubled = digit * 2;
+                doubled / 10 + doubled % 10
+            } else {
+                digit
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Luhn check digit of the first 14 digits of an IMEI
+pub fn check_digit(digits: &str) -> u8 {
+    (0..=9).find(|digit| luhn_valid(&format!("{}{}", digits, digit))).unwrap_or(0)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Device {
+    pub brand: String,
+    pub model: String,
+}
+
+/// Device models by TAC
+pub struct TacDatabase {
+    devices: HashMap<String, Device>,
+}
+
+impl TacDatabase {
+    /// Sample database bundled with the crate
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_TAC).expect("bundled TAC database is valid")
+    }
+
+    /// Database of a `tac,brand,model` file
+    pub fn parse(csv: &str) -> Result<Self, DataError> {
+        let mut devices = HashMap::new();
+        for record in records(csv, 3) {
+            let (line, values) = record?;
+            if values[0].len() != TAC_DIGITS || !values[0].bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid(line, format!("invalid TAC {}", values[0])));
+            }
+            devices.insert(
+                values[0].to_string(),
+                Device {
+                    brand: values[1].to_string(),
+                    model: values[2].to_string(),
+                },
+            );
+        }
+        Ok(Self { devices })
+    }
+
+    pub fn lookup(&self, tac: &str) -> Option<&Device> {
+        self.devices.get(tac)
+    }
+
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_luhn_check_digit() {
+        // Example IMEI of 3GPP TS 23.003 annex B
+        assert!(luhn_valid("490154203237518"));
+        assert_eq!(check_digit("49015420323751"), 8);
+
+        let imei = parse("49-015420-323751-8").unwrap();
+        assert_eq!((imei.tac.as_str(), imei.serial.as_str(), imei.check_digit), ("49015420", "323751", Some(8)));
+        assert_eq!(parse("490154203237517"), Err(ImeiError::InvalidCheckDigit(7)));
+    }
+
+    #[test]
+    fn test_imei_forms() {
+        assert_eq!(parse("49015420323751").unwrap().check_digit, None);
+        assert_eq!(parse("4901542032375101").unwrap().software_version.as_deref(), Some("01"));
+        assert_eq!(parse("4901542032375"), Err(ImeiError::InvalidLength(13)));
+        assert_eq!(parse("49015420323751X"), Err(ImeiError::InvalidCharacters));
+    }
+
+    #[test]
+    fn test_tac_lookup() {
+        let database = TacDatabase::bundled();
+        assert!(!database.is_empty());
+        assert_eq!(database.lookup("35000001").unwrap().model, "Smartphone 5G");
+        assert!(database.lookup("49015420").is_none());
+        assert!(TacDatabase::parse("tac,brand,model\n1234,Brand,Model\n").is_err());
+    }
+}
@@ -0,0 +1,249 @@
+//! Numbering plans shared by the ORION services.
+//!
+//! [`parse`] turns a dialled number, in national (`0612345678`),
+//! international (`0033612345678`) or E.164 (`+33612345678`) form, into its
+//! canonical E.164 form and classifies it. Numbers of the countries operated
+//! by ORION are checked against their numbering plan; foreign numbers only
+//! need a known country code and an E.164 length.
+//!
+//! [`e212`] resolves the home network of an IMSI, or the network of a PLMN
+//! id, from the bundled MCC/MNC registry, and [`imei`] checks IMEIs and looks up their device model.
+
+mod country_codes;
+mod data;
+pub mod e212;
+pub mod imei;
+pub mod plans;
+
+pub use data::DataError;
+pub use e212::{ImsiError, Plmn, Registry};
+pub use imei::{Device, Imei, ImeiError, TacDatabase};
+
+use serde::Serialize;
+use thiserror::Error;
+
+/// Longest E.164 number, country code included
+const MAX_E164_DIGITS: usize = 15;
+/// Shortest foreign number accepted, country code included
+const MIN_FOREIGN_DIGITS: usize = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberType {
+    Mobile,
+    Fixed,
+    Premium,
+    TollFree,
+    ShortCode,
+    /// Foreign number, whose plan is not known
+    Unknown,
+}
+
+impl NumberType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Mobile => "mobile",
+            Self::Fixed => "fixed",
+            Self::Premium => "premium",
+            Self::TollFree => "toll_free",
+            Self::ShortCode => "short_code",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PhoneNumber {
+    /// E.164 form (`+33612345678`), or the dialled digits of a short code
+    pub canonical: String,
+    pub country_code: String,
+    pub national_number: String,
+    /// Region of the number, when its plan is known
+    pub region: Option<&'static str>,
+    pub number_type: NumberType,
+    pub emergency: bool,
+}
+
+impl PhoneNumber {
+    /// Whether the number is outside the country of `home_region`
+    pub fn is_international_from(&self, home_region: &str) -> bool {
+        match plans::by_region(home_region) {
+            Some(plan) => plan.country_code != self.country_code,
+            None => !self.region.is_some_and(|region| region.eq_ignore_ascii_case(home_region)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum NumberError {
+    #[error("empty number")]
+    Empty,
+    #[error("invalid character '{0}'")]
+    InvalidCharacter(char),
+    #[error("unknown country code in +{0}")]
+    UnknownCountryCode(String),
+    #[error("+{0} does not have a valid length")]
+    InvalidLength(String),
+    #[error("{0} is not a possible number of {1}")]
+    NotInPlan(String, &'static str),
+    #[error("no numbering plan for region {0}, national number {1} cannot be resolved")]
+    UnknownRegion(String, String),
+}
+
+/// Parse a dialled number; national forms are read with the plan of
+/// `home_region` (ORION or ISO country code).
+pub fn parse(number: &str, home_region: &str) -> Result<PhoneNumber, NumberError> {
+    let (international, digits) = digits(number)?;
+    if international {
+        return parse_international(&digits);
+    }
+    if let Some(rest) = digits.strip_prefix("00") {
+        return parse_international(rest);
+    }
+
+    let Some(plan) = plans::by_region(home_region) else {
+        return Err(NumberError::UnknownRegion(home_region.to_string(), digits));
+    };
+
+    if let Some(nsn) = plan.trunk_prefix.and_then(|trunk| digits.strip_prefix(trunk)) {
+        return national(plan, nsn, &digits);
+    }
+    if let Some(number_type) = plan.classify_short(&digits) {
+        return Ok(PhoneNumber {
+            canonical: digits.clone(),
+            country_code: plan.country_code.to_string(),
+            emergency: plan.is_emergency(&digits),
+            national_number: digits,
+            region: Some(plan.region),
+            number_type,
+        });
+    }
+    if plan.trunk_prefix.is_none() {
+        if let Ok(number) = national(plan, &digits, &digits) {
+            return Ok(number);
+        }
+    }
+    // International number written without prefix (e.g. 33612345678)
+    if digits.len() > plan.max_national_len() {
+        if let Ok(number) = parse_international(&digits) {
+            return Ok(number);
+        }
+    }
+    Err(NumberError::NotInPlan(digits, plan.region))
+}
+
+fn parse_international(digits: &str) -> Result<PhoneNumber, NumberError> {
+    let Some((country_code, nsn)) = country_codes::split(digits) else {
+        return Err(NumberError::UnknownCountryCode(digits.to_string()));
+    };
+    if let Some(plan) = plans::by_country_code(country_code) {
+        return national(plan, nsn, &format!("+{}", digits));
+    }
+    if !(MIN_FOREIGN_DIGITS..=MAX_E164_DIGITS).contains(&digits.len()) {
+        return Err(NumberError::InvalidLength(digits.to_string()));
+    }
+    Ok(PhoneNumber {
+        canonical: format!("+{}", digits),
+        country_code: country_code.to_string(),
+        national_number: nsn.to_string(),
+        region: None,
+        number_type: NumberType::Unknown,
+        emergency: false,
+    })
+}
+
+fn national(plan: &plans::Plan, nsn: &str, dialled: &str) -> Result<PhoneNumber, NumberError> {
+    let number_type = plan
+        .classify(nsn)
+        .ok_or_else(|| NumberError::NotInPlan(dialled.to_string(), plan.region))?;
+    Ok(PhoneNumber {
+        canonical: format!("+{}{}", plan.country_code, nsn),
+        country_code: plan.country_code.to_string(),
+        national_number: nsn.to_string(),
+        region: Some(plan.region),
+        number_type,
+        emergency: false,
+    })
+}
+
+/// Digits of a number, and whether it starts with `+`. Spaces, dots,
+/// dashes, parentheses and a `(0)` trunk hint are ignored.
+fn digits(number: &str) -> Result<(bool, String), NumberError> {
+    let number = number.trim().replace("(0)", "");
+    let (international, rest) = match number.strip_prefix('+') {
+        Some(rest) => (true, rest),
+        None => (false, number.as_str()),
+    };
+
+    let mut digits = String::with_capacity(rest.len());
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => digits.push(c),
+            ' ' | '.' | '-' | '(' | ')' => {}
+            other => return Err(NumberError::InvalidCharacter(other)),
+        }
+    }
+    if digits.is_empty() {
+        return Err(NumberError::Empty);
+    }
+    Ok((international, digits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(number: &str, region: &str) -> String {
+        parse(number, region).unwrap().canonical
+    }
+
+    #[test]
+    fn test_forms_give_the_same_e164() {
+        for number in ["+33612345678", "0033612345678", "06 12 34 56 78", "+33 (0)6.12.34.56.78", "33612345678"] {
+            assert_eq!(canonical(number, "FR"), "+33612345678", "{}", number);
+        }
+        assert_eq!(canonical("20123456", "TN"), "+21620123456");
+        assert_eq!(canonical("00216 71 123 456", "FR"), "+21671123456");
+        assert_eq!(canonical("040 1234567", "FN"), "+358401234567");
+        assert_eq!(canonical("079 123 45 67", "CH"), "+41791234567");
+    }
+
+    #[test]
+    fn test_classification() {
+        let number_type = |number: &str, region: &str| parse(number, region).unwrap().number_type;
+        assert_eq!(number_type("0612345678", "FR"), NumberType::Mobile);
+        assert_eq!(number_type("0145678901", "FR"), NumberType::Fixed);
+        assert_eq!(number_type("0800123456", "FR"), NumberType::TollFree);
+        assert_eq!(number_type("0899123456", "FR"), NumberType::Premium);
+        assert_eq!(number_type("71123456", "TN"), NumberType::Fixed);
+        assert_eq!(number_type("0201234567", "FI"), NumberType::Fixed);
+        assert_eq!(number_type("+1 212 555 0100", "FR"), NumberType::Unknown);
+
+        let emergency = parse("112", "CH").unwrap();
+        assert_eq!(emergency.number_type, NumberType::ShortCode);
+        assert!(emergency.emergency);
+        assert!(!emergency.is_international_from("CH"));
+        assert!(!parse("3639", "FR").unwrap().emergency);
+    }
+
+    #[test]
+    fn test_international_from_home() {
+        let number = parse("+21620123456", "FR").unwrap();
+        assert!(number.is_international_from("FR"));
+        assert!(!number.is_international_from("TN"));
+        assert!(parse("+14155550100", "TN").unwrap().is_international_from("TN"));
+    }
+
+    #[test]
+    fn test_impossible_numbers() {
+        assert_eq!(parse("", "FR"), Err(NumberError::Empty));
+        assert_eq!(parse("06ABC", "FR"), Err(NumberError::InvalidCharacter('A')));
+        assert!(matches!(parse("+33 0612345678", "FR"), Err(NumberError::NotInPlan(..))));
+        assert!(matches!(parse("061234567", "FR"), Err(NumberError::NotInPlan(..))));
+        assert!(matches!(parse("+999123456789", "FR"), Err(NumberError::UnknownCountryCode(_))));
+        assert!(matches!(parse("+4412", "FR"), Err(NumberError::InvalidLength(_))));
+        assert!(matches!(parse("+4412345678901234", "FR"), Err(NumberError::InvalidLength(_))));
+        assert!(matches!(parse("60123456", "TN"), Err(NumberError::NotInPlan(..))));
+        assert!(matches!(parse("0612345678", "XX"), Err(NumberError::UnknownRegion(..))));
+    }
+}
@@ -0,0 +1,178 @@
+//! Numbering plans of the countries operated by ORION.
+//!
+//! Ranges are matched on the national significant number (NSN, without
+//! trunk prefix nor country code); the longest matching prefix wins and its
+//! lengths must fit, otherwise the number is impossible.
+
+use crate::NumberType;
+
+/// Range of national significant numbers
+#[derive(Debug)]
+pub struct Range {
+    pub prefix: &'static str,
+    pub min_len: usize,
+    pub max_len: usize,
+    pub number_type: NumberType,
+}
+
+/// Numbering plan of one country
+#[derive(Debug)]
+pub struct Plan {
+    /// ORION country code (as in the `cdr.raw.*` topics)
+    pub region: &'static str,
+    /// ISO 3166 alpha-2 code, when different from the ORION code
+    pub iso: &'static str,
+    pub country_code: &'static str,
+    /// Prefix dialled before a national number, if any
+    pub trunk_prefix: Option<&'static str>,
+    pub ranges: &'static [Range],
+    /// Short codes, dialled without trunk prefix
+    pub short_codes: &'static [Range],
+    pub emergency: &'static [&'static str],
+}
+
+impl Plan {
+    /// Type of a national significant number, if it is possible
+    pub fn classify(&self, nsn: &str) -> Option<NumberType> {
+        longest_match(self.ranges, nsn)
+    }
+
+    /// Type of a short code, if it is possible
+    pub fn classify_short(&self, digits: &str) -> Option<NumberType> {
+        longest_match(self.short_codes, digits)
+    }
+
+    pub fn is_emergency(&self, digits: &str) -> bool {
+        self.emergency.contains(&digits)
+    }
+
+    /// Longest national number as dialled, trunk prefix included
+    pub fn max_national_len(&self) -> usize {
+        let nsn = self.ranges.iter().map(|range| range.max_len).max().unwrap_or(0);
+        nsn + self.trunk_prefix.map_or(0, str::len)
+    }
+}
+
+fn longest_match(ranges: &[Range], digits: &str) -> Option<NumberType> {
+    ranges
+        .iter()
+        .filter(|range| digits.starts_with(range.prefix))
+        .max_by_key(|range| range.prefix.len())
+        .filter(|range| (range.min_len..=range.max_len).contains(&digits.len()))
+        .map(|range| range.number_type)
+}
+
+const fn range(prefix: &'static str, min_len: usize, max_len: usize, number_type: NumberType) -> Range {
+    Range { prefix, min_len, max_len, number_type }
+}
+
+use NumberType::{Fixed, Mobile, Premium, ShortCode, TollFree};
+
+/// France (ARCEP plan): 9-digit NSN after the trunk prefix 0
+const FRANCE: Plan = Plan {
+    region: "FR",
+    iso: "FR",
+    country_code: "33",
+    trunk_prefix: Some("0"),
+    ranges: &[
+        range("1", 9, 9, Fixed),
+        range("2", 9, 9, Fixed),
+        range("3", 9, 9, Fixed),
+        range("4", 9, 9, Fixed),
+        range("5", 9, 9, Fixed),
+        range("6", 9, 9, Mobile),
+        range("7", 9, 9, Mobile),
+        range("80", 9, 9, TollFree),
+        range("81", 9, 9, Premium),
+        range("82", 9, 9, Premium),
+        range("89", 9, 9, Premium),
+        range("9", 9, 9, Fixed),
+    ],
+    short_codes: &[range("1", 2, 6, ShortCode), range("3", 4, 4, ShortCode)],
+    emergency: &["15", "17", "18", "112", "114", "115", "119", "191", "196", "197"],
+};
+
+/// Tunisia: 8-digit NSN, no trunk prefix
+const TUNISIA: Plan = Plan {
+    region: "TN",
+    iso: "TN",
+    country_code: "216",
+    trunk_prefix: None,
+    ranges: &[
+        range("2", 8, 8, Mobile),
+        range("3", 8, 8, Fixed),
+        range("4", 8, 8, Mobile),
+        range("5", 8, 8, Mobile),
+        range("7", 8, 8, Fixed),
+        range("8", 8, 8, Premium),
+        range("80", 8, 8, TollFree),
+        range("9", 8, 8, Mobile),
+    ],
+    short_codes: &[range("1", 3, 4, ShortCode)],
+    emergency: &["190", "193", "197", "198"],
+};
+
+/// Finland (the `FN` region): variable-length NSN after the trunk prefix 0
+const FINLAND: Plan = Plan {
+    region: "FN",
+    iso: "FI",
+    country_code: "358",
+    trunk_prefix: Some("0"),
+    ranges: &[
+        range("1", 5, 9, Fixed),
+        range("2", 5, 9, Fixed),
+        range("3", 5, 9, Fixed),
+        range("4", 7, 10, Mobile),
+        range("5", 5, 9, Fixed),
+        range("50", 7, 10, Mobile),
+        range("6", 5, 9, Fixed),
+        range("600", 6, 9, Premium),
+        range("700", 6, 9, Premium),
+        range("8", 5, 9, Fixed),
+        range("800", 6, 9, TollFree),
+        range("9", 5, 9, Fixed),
+    ],
+    short_codes: &[range("1", 3, 5, ShortCode)],
+    emergency: &["112"],
+};
+
+/// Switzerland: 9-digit NSN after the trunk prefix 0
+const SWITZERLAND: Plan = Plan {
+    region: "CH",
+    iso: "CH",
+    country_code: "41",
+    trunk_prefix: Some("0"),
+    ranges: &[
+        range("2", 9, 9, Fixed),
+        range("3", 9, 9, Fixed),
+        range("4", 9, 9, Fixed),
+        range("5", 9, 9, Fixed),
+        range("6", 9, 9, Fixed),
+        range("71", 9, 9, Fixed),
+        range("75", 9, 9, Mobile),
+        range("76", 9, 9, Mobile),
+        range("77", 9, 9, Mobile),
+        range("78", 9, 9, Mobile),
+        range("79", 9, 9, Mobile),
+        range("800", 9, 9, TollFree),
+        range("81", 9, 9, Fixed),
+        range("84", 9, 9, Premium),
+        range("90", 9, 9, Premium),
+        range("91", 9, 9, Fixed),
+    ],
+    short_codes: &[range("1", 3, 4, ShortCode)],
+    emergency: &["112", "117", "118", "144"],
+};
+
+pub static PLANS: [Plan; 4] = [FRANCE, TUNISIA, FINLAND, SWITZERLAND];
+
+/// Plan of a region, by ORION or ISO code (case-insensitive)
+pub fn by_region(region: &str) -> Option<&'static Plan> {
+    PLANS
+        .iter()
+        .find(|plan| plan.region.eq_ignore_ascii_case(region) || plan.iso.eq_ignore_ascii_case(region))
+}
+
+pub fn by_country_code(country_code: &str) -> Option<&'static Plan> {
+    PLANS.iter().find(|plan| plan.country_code == country_code)
+}
@@ -7,6 +7,8 @@ use std::env;
 pub struct Config {
     pub server: ServerConfig,
     pub services: ServicesConfig,
+    /// Time allowed to complete in-flight requests after SIGTERM/SIGINT
+    pub shutdown_timeout_secs: u64,
 }
 
 /// HTTP server configuration
@@ -59,7 +61,11 @@ impl Config {
                 .parse()?,
         };
 
-        Ok(Config { server, services })
+        let shutdown_timeout_secs = env::var("SHUTDOWN_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "25".to_string())
+            .parse()?;
+
+        Ok(Config { server, services, shutdown_timeout_secs })
     }
 }
 
@@ -0,0 +1,86 @@
+//! Coordinated shutdown shared by the ORION services.
+//!
+//! SIGTERM or SIGINT flips a watch channel observed by every task. Each task
+//! (consumer loop, HTTP server) stops taking new work, drains what it holds
+//! and returns; `main` waits for the tasks up to the configured deadline.
+
+use tokio::sync::watch;
+use tracing::info;
+
+#[derive(Clone)]
+pub struct Shutdown {
+    rx: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    /// Trigger and listener of a shutdown
+    pub fn channel() -> (watch::Sender<bool>, Self) {
+        let (tx, rx) = watch::channel(false);
+        (tx, Self { rx })
+    }
+
+    /// Listener triggered by SIGTERM or SIGINT
+    pub fn on_signal() -> Self {
+        let (tx, shutdown) = Self::channel();
+        tokio::spawn(async move {
+            signal().await;
+            info!("🛑 Shutdown signal received, draining...");
+            let _ = tx.send(true);
+            // Keep the channel open for late listeners
+            std::future::pending::<()>().await;
+        });
+        shutdown
+    }
+
+    /// Resolves once shutdown has been requested
+    pub async fn requested(&mut self) {
+        let _ = self.rx.wait_for(|requested| *requested).await;
+    }
+
+    /// Whether shutdown has already been requested
+    pub fn is_requested(&self) -> bool {
+        *self.rx.borrow()
+    }
+}
+
+async fn signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_requested_resolves_for_every_listener() {
+        let (tx, mut shutdown) = Shutdown::channel();
+        let mut late = shutdown.clone();
+
+        assert!(tokio::time::timeout(Duration::from_millis(10), shutdown.requested()).await.is_err());
+        assert!(!late.is_requested());
+        tx.send(true).unwrap();
+        assert!(late.is_requested());
+        shutdown.requested().await;
+        late.requested().await;
+    }
+}
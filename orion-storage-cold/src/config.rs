@@ -6,6 +6,8 @@ pub struct Config {
     pub server: ServerConfig,
     pub kafka: KafkaConfig,
     pub s3: S3Config,
+    /// Time allowed to flush the pending batch after SIGTERM/SIGINT
+    pub shutdown_timeout_secs: u64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -67,6 +69,10 @@ impl Config {
                     .parse()
                     .unwrap_or(true),
             },
+            shutdown_timeout_secs: env::var("SHUTDOWN_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "25".to_string())
+                .parse()
+                .unwrap_or(25),
         }
     }
 }
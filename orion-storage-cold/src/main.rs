@@ -7,9 +7,11 @@ mod uploader;
 mod writer;
 
 use anyhow::Result;
+use orion_shutdown::Shutdown;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 use tracing_subscriber;
 
 use config::Config;
@@ -43,23 +45,47 @@ async fn main() -> Result<()> {
 
     info!("HTTP server listening on {}", addr);
 
-    let server_handle = tokio::spawn(async move {
+    let shutdown = Shutdown::on_signal();
+    let timeout = Duration::from_secs(config.shutdown_timeout_secs);
+
+    let mut server_shutdown = shutdown.clone();
+    let mut server_handle = tokio::spawn(async move {
         axum::serve(listener, app)
+            .with_graceful_shutdown(async move { server_shutdown.requested().await })
             .await
             .expect("Server error");
     });
 
-    let consumer_handle = tokio::spawn(async move {
-        if let Err(e) = service.start_consumer(&config).await {
+    let consumer_shutdown = shutdown.clone();
+    let mut consumer_handle = tokio::spawn(async move {
+        if let Err(e) = service.start_consumer(&config, consumer_shutdown).await {
             error!("Consumer error: {}", e);
         }
     });
 
+    let mut signal = shutdown.clone();
     tokio::select! {
-        _ = server_handle => info!("Server stopped"),
-        _ = consumer_handle => info!("Consumer stopped"),
+        _ = signal.requested() => {}
+        _ = &mut server_handle => {
+            info!("Server stopped");
+            return Ok(());
+        }
+        _ = &mut consumer_handle => {
+            info!("Consumer stopped");
+            return Ok(());
+        }
     }
 
+    // Let the consumer flush its pending batch to S3, within the deadline
+    let drained = tokio::time::timeout(timeout, async {
+        let _ = consumer_handle.await;
+        let _ = server_handle.await;
+    })
+    .await;
+    if drained.is_err() {
+        warn!("Shutdown deadline of {:?} exceeded, exiting with records in flight", timeout);
+    }
+    info!("Cold storage service stopped");
+
     Ok(())
 }
-
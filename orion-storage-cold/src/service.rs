@@ -1,4 +1,5 @@
 use anyhow::Result;
+use orion_shutdown::Shutdown;
 use serde_json::json;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -50,13 +51,18 @@ impl ArchiveService {
     /// Start periodic archiving (stub for Kafka replacement)
     /// In production: use proper Kafka consumer with rdkafka
     /// For Windows dev: simulate with HTTP polling or mock data
-    pub async fn start_consumer(&self, _config: &Config) -> Result<()> {
+    /// On shutdown, the pending batch is flushed to S3 before returning.
+    pub async fn start_consumer(&self, _config: &Config, mut shutdown: Shutdown) -> Result<()> {
         info!("Archive service started (polling mode - replace with Kafka on Linux)");
 
         let mut timer = interval(Duration::from_secs(POLL_INTERVAL_SECS));
 
         loop {
-            timer.tick().await;
+            tokio::select! {
+                biased;
+                _ = shutdown.requested() => break,
+                _ = timer.tick() => {}
+            }
 
             // TODO: Replace with actual Kafka consumer or HTTP endpoint
             // For now, log periodic checks
@@ -68,6 +74,10 @@ impl ArchiveService {
                 info!("No records to archive (waiting for Kafka integration)");
             }
         }
+
+        info!("Archive service stopping, flushing pending batch...");
+        self.flush_batch_internal().await;
+        Ok(())
     }
 
     /// Add CDR to batch (for testing/HTTP ingestion)
@@ -7,6 +7,8 @@ pub struct Config {
     pub kafka: KafkaConfig,
     pub scylla: ScyllaConfig,
     pub server: ServerConfig,
+    /// Time allowed to drain in-flight work after SIGTERM/SIGINT
+    pub shutdown_timeout_secs: u64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -14,6 +16,30 @@ pub struct KafkaConfig {
     pub brokers: Vec<String>,
     pub input_topic: String,
     pub consumer_group: String,
+    pub delivery_mode: DeliveryMode,
+    pub commit_batch_size: usize,
+    pub commit_interval_ms: u64,
+}
+
+/// Delivery guarantee for consumed CDRs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryMode {
+    /// Offsets are committed in batches once the ScyllaDB write succeeded
+    AtLeastOnce,
+    /// Not offered here: inserts are upserts keyed by cdr_id, so replays
+    /// after a crash are already idempotent
+    ExactlyOnce,
+}
+
+impl DeliveryMode {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "at_least_once" | "at-least-once" => Ok(Self::AtLeastOnce),
+            "exactly_once" | "exactly-once" => Ok(Self::ExactlyOnce),
+            other => anyhow::bail!("Invalid KAFKA_DELIVERY_MODE: {}", other),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -21,6 +47,8 @@ pub struct ScyllaConfig {
     pub nodes: Vec<String>,
     pub keyspace: String,
     pub replication_factor: usize,
+    /// How long a stored CDR id is remembered in `cdr_seen`, 0 to disable
+    pub dedup_window_secs: u64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -43,8 +71,26 @@ impl Config {
                 .unwrap_or_else(|_| "cdr.stored".to_string()),
             consumer_group: env::var("KAFKA_CONSUMER_GROUP")
                 .unwrap_or_else(|_| "orion-storage-hot".to_string()),
+            delivery_mode: DeliveryMode::parse(
+                &env::var("KAFKA_DELIVERY_MODE").unwrap_or_else(|_| "at_least_once".to_string()),
+            )?,
+            commit_batch_size: env::var("KAFKA_COMMIT_BATCH_SIZE")
+                .unwrap_or_else(|_| "100".to_string())
+                .parse()
+                .context("Invalid KAFKA_COMMIT_BATCH_SIZE")?,
+            commit_interval_ms: env::var("KAFKA_COMMIT_INTERVAL_MS")
+                .unwrap_or_else(|_| "1000".to_string())
+                .parse()
+                .context("Invalid KAFKA_COMMIT_INTERVAL_MS")?,
         };
 
+        if kafka.delivery_mode == DeliveryMode::ExactlyOnce {
+            anyhow::bail!(
+                "KAFKA_DELIVERY_MODE=exactly_once is not supported by orion-storage-hot: \
+                 ScyllaDB inserts are idempotent, use at_least_once"
+            );
+        }
+
         let scylla = ScyllaConfig {
             nodes: env::var("SCYLLA_NODES")
                 .unwrap_or_else(|_| "localhost:9042".to_string())
@@ -57,6 +103,10 @@ impl Config {
                 .unwrap_or_else(|_| "1".to_string())
                 .parse()
                 .context("Invalid SCYLLA_REPLICATION_FACTOR")?,
+            dedup_window_secs: env::var("STORAGE_DEDUP_WINDOW_SECS")
+                .unwrap_or_else(|_| "86400".to_string())
+                .parse()
+                .context("Invalid STORAGE_DEDUP_WINDOW_SECS")?,
         };
 
         let server = ServerConfig {
@@ -67,10 +117,16 @@ impl Config {
                 .context("Invalid SERVER_PORT")?,
         };
 
+        let shutdown_timeout_secs = env::var("SHUTDOWN_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "25".to_string())
+            .parse()
+            .context("Invalid SHUTDOWN_TIMEOUT_SECS")?;
+
         Ok(Config {
             kafka,
             scylla,
             server,
+            shutdown_timeout_secs,
         })
     }
 }
@@ -6,9 +6,11 @@ mod service;
 use axum::{Router, routing::get};
 use config::Config;
 use metrics_exporter_prometheus::PrometheusBuilder;
+use orion_shutdown::Shutdown;
 use service::{KafkaConsumerService, ScyllaRepository};
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
@@ -27,10 +29,11 @@ async fn main() -> anyhow::Result<()> {
     // Load configuration
     let config = Config::from_env()?;
     tracing::info!(
-        "Configuration loaded - Server: {}:{}, ScyllaDB keyspace: {}",
+        "Configuration loaded - Server: {}:{}, ScyllaDB keyspace: {}, Delivery: {:?}",
         config.server.host,
         config.server.port,
         config.scylla.keyspace,
+        config.kafka.delivery_mode,
     );
 
     // Initialize metrics
@@ -46,9 +49,11 @@ async fn main() -> anyhow::Result<()> {
     let kafka_service = KafkaConsumerService::new(&config.kafka, repository)?;
     tracing::info!("Kafka consumer service initialized");
 
-    // Spawn Kafka consumer
-    tokio::spawn(async move {
-        if let Err(e) = kafka_service.run().await {
+    // Spawn Kafka consumer, stopped by SIGTERM/SIGINT
+    let shutdown = Shutdown::on_signal();
+    let consumer_shutdown = shutdown.clone();
+    let consumer_handle = tokio::spawn(async move {
+        if let Err(e) = kafka_service.run(consumer_shutdown).await {
             tracing::error!("Kafka consumer error: {}", e);
         }
     });
@@ -65,7 +70,17 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Starting HTTP server on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    let mut server_shutdown = shutdown.clone();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move { server_shutdown.requested().await })
+        .await?;
+
+    // Let the consumer persist its CDR and commit, within the deadline
+    let timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    if tokio::time::timeout(timeout, consumer_handle).await.is_err() {
+        tracing::warn!("Shutdown deadline of {:?} exceeded, exiting with work in flight", timeout);
+    }
+    tracing::info!("orion-storage-hot service stopped");
 
     Ok(())
 }
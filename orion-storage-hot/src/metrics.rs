@@ -5,6 +5,7 @@ pub fn init_metrics() {
     let _ = counter!("orion_storage_messages_total");
     let _ = counter!("orion_storage_errors_total");
     let _ = counter!("orion_storage_inserted_total");
+    let _ = counter!("orion_storage_duplicates_total");
 
     // Histograms
     let _ = histogram!("orion_storage_latency_seconds");
@@ -22,6 +23,10 @@ pub fn increment_inserted_total() {
     counter!("orion_storage_inserted_total").increment(1);
 }
 
+pub fn increment_duplicates_total() {
+    counter!("orion_storage_duplicates_total").increment(1);
+}
+
 pub fn record_latency(duration: f64) {
     histogram!("orion_storage_latency_seconds").record(duration);
 }
@@ -2,25 +2,31 @@ use crate::config::KafkaConfig;
 use crate::metrics;
 use crate::service::{model::EnrichedCDR, ScyllaRepository};
 use anyhow::Result;
+use orion_kafka::OffsetCommitter;
+use orion_shutdown::Shutdown;
 use rdkafka::{
     consumer::{Consumer, StreamConsumer},
-    ClientConfig, Message,
+    message::BorrowedMessage,
+    ClientConfig, Message, Offset,
 };
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing;
 
 pub struct KafkaConsumerService {
     consumer: StreamConsumer,
     repository: Arc<ScyllaRepository>,
+    committer: OffsetCommitter,
 }
 
 impl KafkaConsumerService {
     pub fn new(kafka_config: &KafkaConfig, repository: Arc<ScyllaRepository>) -> Result<Self> {
+        // Offsets are stored and committed manually once the CDR is persisted
         let consumer: StreamConsumer = ClientConfig::new()
             .set("bootstrap.servers", kafka_config.brokers.join(","))
             .set("group.id", &kafka_config.consumer_group)
-            .set("enable.auto.commit", "true")
+            .set("enable.auto.commit", "false")
+            .set("enable.auto.offset.store", "false")
             .set("auto.offset.reset", "earliest")
             .create()?;
 
@@ -34,48 +40,126 @@ impl KafkaConsumerService {
         Ok(Self {
             consumer,
             repository,
+            committer: OffsetCommitter::new(
+                kafka_config.commit_batch_size,
+                Duration::from_millis(kafka_config.commit_interval_ms),
+            ),
         })
     }
 
-    pub async fn run(&self) -> Result<()> {
+    /// Consume until shutdown is requested. The CDR in hand is persisted,
+    /// then acknowledged offsets are committed.
+    pub async fn run(mut self, mut shutdown: Shutdown) -> Result<()> {
         tracing::info!("Starting Kafka consumer loop");
 
         loop {
-            match self.consumer.recv().await {
-                Ok(message) => {
+            let received = tokio::select! {
+                biased;
+                _ = shutdown.requested() => break,
+                received = tokio::time::timeout(self.committer.interval(), self.consumer.recv()) => received,
+            };
+            match received {
+                // Idle: flush offsets acknowledged since the last commit
+                Err(_) => {
+                    if let Err(e) = self.committer.commit(&self.consumer) {
+                        tracing::error!("Failed to commit offsets: {}", e);
+                    }
+                }
+                Ok(Ok(message)) => {
                     let start = Instant::now();
                     metrics::increment_messages_total();
 
-                    if let Err(e) = self.process_message(&message).await {
-                        tracing::error!("Failed to process message: {}", e);
-                        metrics::increment_errors_total();
+                    let handled = match Self::decode(&message) {
+                        // Undecodable payloads will never succeed: skip them
+                        None => {
+                            metrics::increment_errors_total();
+                            true
+                        }
+                        Some(enriched) => match self.process_cdr(&enriched).await {
+                            Ok(()) => {
+                                metrics::record_latency(start.elapsed().as_secs_f64());
+                                true
+                            }
+                            Err(e) => {
+                                tracing::error!("Failed to store CDR, retrying: {}", e);
+                                metrics::increment_errors_total();
+                                false
+                            }
+                        },
+                    };
+
+                    if handled {
+                        if let Err(e) = self.committer.ack(
+                            &self.consumer,
+                            message.topic(),
+                            message.partition(),
+                            message.offset(),
+                        ) {
+                            tracing::error!("Failed to store offset: {}", e);
+                        }
                     } else {
-                        metrics::record_latency(start.elapsed().as_secs_f64());
+                        // Not persisted: rewind so the message is redelivered
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        if let Err(e) = self.consumer.seek(
+                            message.topic(),
+                            message.partition(),
+                            Offset::Offset(message.offset()),
+                            Duration::from_secs(5),
+                        ) {
+                            tracing::error!(
+                                "Failed to rewind partition {}: {}",
+                                message.partition(),
+                                e
+                            );
+                        }
                     }
                 }
-                Err(e) => {
+                Ok(Err(e)) => {
                     tracing::error!("Kafka consumer error: {}", e);
                     metrics::increment_errors_total();
                 }
             }
         }
-    }
 
-    async fn process_message(&self, message: &rdkafka::message::BorrowedMessage<'_>) -> Result<()> {
-        let payload = message
-            .payload()
-            .ok_or_else(|| anyhow::anyhow!("Empty message payload"))?;
+        tracing::info!("Kafka consumer stopped, committing final offsets");
+        if let Err(e) = self.committer.commit_sync(&self.consumer) {
+            tracing::error!("Failed to commit final offsets: {}", e);
+        }
+        Ok(())
+    }
 
-        let enriched: EnrichedCDR = serde_json::from_slice(payload)?;
+    fn decode(message: &BorrowedMessage<'_>) -> Option<EnrichedCDR> {
+        let Some(payload) = message.payload() else {
+            tracing::error!("Empty message payload");
+            return None;
+        };
+
+        match orion_model::from_slice(payload) {
+            Ok(enriched) => Some(enriched),
+            Err(e) => {
+                tracing::error!("Failed to deserialize EnrichedCDR: {}", e);
+                None
+            }
+        }
+    }
 
+    async fn process_cdr(&self, enriched: &EnrichedCDR) -> Result<()> {
         tracing::debug!(
             "Processing CDR: {} (event_type: {})",
             enriched.unified.cdr_id,
-            enriched.unified.event_type
+            enriched.unified.event_type.as_str()
         );
 
+        // Redelivered or replayed upstream: already stored
+        if self.repository.is_seen(&enriched.unified.cdr_id).await? {
+            tracing::debug!("Dropping duplicate CDR {}", enriched.unified.cdr_id);
+            metrics::increment_duplicates_total();
+            return Ok(());
+        }
+
         // Insert into ScyllaDB
-        self.repository.insert_cdr(&enriched).await?;
+        self.repository.insert_cdr(enriched).await?;
+        self.repository.mark_seen(&enriched.unified.cdr_id).await?;
         metrics::increment_inserted_total();
 
         tracing::info!(
@@ -93,19 +177,20 @@ mod tests {
 
     #[test]
     fn test_enriched_cdr_deserialization() {
+        // As published by orion-enrichment: unified fields flattened
         let json = r#"{
-            "unified": {
-                "cdr_id": "123",
-                "event_type": "voice",
-                "imsi": "208150123456789",
-                "msisdn": "+33612345678",
-                "country": "FR",
-                "start_timestamp": "2024-01-15T10:30:00Z",
-                "is_roaming": false,
-                "hash": "abc123",
-                "ingestion_timestamp": "2024-01-15T10:30:00Z",
-                "normalization_timestamp": "2024-01-15T10:30:05Z"
-            },
+            "schema_version": 1,
+            "cdr_id": "123",
+            "event_type": "voice",
+            "service_type": "standard",
+            "imsi": "208150123456789",
+            "msisdn": "+33612345678",
+            "country_code": "FR",
+            "start_timestamp": "2024-01-15T10:30:00Z",
+            "is_roaming": false,
+            "normalization_timestamp": "2024-01-15T10:30:05Z",
+            "source_system": "orion-ingestion",
+            "raw_data_hash": "abc123",
             "fraud_info": null,
             "network_info": null,
             "client_info": null,
@@ -113,11 +198,13 @@ mod tests {
             "enrichment_version": "v1.0.0"
         }"#;
 
-        let enriched: Result<EnrichedCDR, _> = serde_json::from_str(json);
-        assert!(enriched.is_ok());
-
-        let cdr = enriched.unwrap();
+        let cdr: EnrichedCDR = orion_model::from_str(json).unwrap();
         assert_eq!(cdr.unified.cdr_id, "123");
-        assert_eq!(cdr.unified.event_type, "voice");
+        assert_eq!(cdr.unified.event_type.as_str(), "voice");
+        assert_eq!(cdr.unified.country_code, "FR");
+
+        // The former nested layout is not what the pipeline produces
+        let nested = format!(r#"{{"unified": {}, "enrichment_timestamp": "", "enrichment_version": ""}}"#, json);
+        assert!(orion_model::from_str::<EnrichedCDR>(&nested).is_err());
     }
 }
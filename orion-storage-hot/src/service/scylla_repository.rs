@@ -8,6 +8,8 @@ use tracing;
 pub struct ScyllaRepository {
     session: Arc<Session>,
     keyspace: String,
+    /// TTL of the seen-set entries, deduplication disabled when `None`
+    dedup_ttl_secs: Option<i32>,
 }
 
 impl ScyllaRepository {
@@ -24,6 +26,8 @@ impl ScyllaRepository {
         let repo = Self {
             session,
             keyspace: config.keyspace.clone(),
+            dedup_ttl_secs: (config.dedup_window_secs > 0)
+                .then(|| config.dedup_window_secs.min(i32::MAX as u64) as i32),
         };
 
         // Initialize keyspace and tables
@@ -131,6 +135,14 @@ impl ScyllaRepository {
 
         tracing::info!("Indexes created successfully");
 
+        // Seen-set of stored CDR ids, entries expire after the dedup window
+        let create_seen_table = format!(
+            "CREATE TABLE IF NOT EXISTS {}.cdr_seen (cdr_id text PRIMARY KEY, seen_at timestamp)",
+            self.keyspace
+        );
+        self.session.query(create_seen_table, &[]).await?;
+        tracing::info!("Table '{}.cdr_seen' initialized", self.keyspace);
+
         Ok(())
     }
 
@@ -140,18 +152,9 @@ impl ScyllaRepository {
         let network = enriched.network_info.as_ref();
         let client = enriched.client_info.as_ref();
 
-        // Parse timestamps to i64 (milliseconds since epoch)
-        let start_ts = chrono::DateTime::parse_from_rfc3339(&cdr.start_timestamp)
-            .ok()
-            .map(|dt| dt.timestamp_millis());
-        let end_ts = cdr
-            .end_timestamp
-            .as_ref()
-            .and_then(|ts| chrono::DateTime::parse_from_rfc3339(ts).ok())
-            .map(|dt| dt.timestamp_millis());
-        let ingestion_ts = chrono::DateTime::parse_from_rfc3339(&cdr.ingestion_timestamp)
-            .ok()
-            .map(|dt| dt.timestamp_millis());
+        // Timestamps as i64 (milliseconds since epoch)
+        let start_ts = cdr.start_timestamp.timestamp_millis();
+        let end_ts = cdr.end_timestamp.map(|dt| dt.timestamp_millis());
         let normalization_ts = chrono::DateTime::parse_from_rfc3339(&cdr.normalization_timestamp)
             .ok()
             .map(|dt| dt.timestamp_millis());
@@ -170,7 +173,7 @@ impl ScyllaRepository {
         let network_type = network.map(|n| n.network_type.clone());
         let cell_tower_location = network.and_then(|n| n.cell_tower_location.clone());
         let signal_strength = network.and_then(|n| n.signal_strength);
-        let handover_count = network.and_then(|n| n.handover_count.map(|v| v as i32));
+        let handover_count = network.and_then(|n| n.handover_count);
 
         // Prepare client info
         let subscriber_segment = client.map(|c| c.subscriber_segment.clone());
@@ -178,7 +181,15 @@ impl ScyllaRepository {
         let customer_since = client.and_then(|c| c.customer_since.clone());
         let lifetime_value = client.and_then(|c| c.lifetime_value);
         let is_vip = client.map(|c| c.is_vip);
-        let data_plan_limit_mb = client.and_then(|c| c.data_plan_limit_mb.map(|v| v as i64));
+        let data_plan_limit_mb = client.and_then(|c| c.data_plan_limit_mb);
+
+        let tariff_class = enriched.rating_info.as_ref().map(|r| r.tariff_class.clone());
+
+        let call_type = cdr.call_type.as_ref().map(|t| t.as_str());
+        let sms_type = cdr.sms_type.as_ref().map(|t| t.as_str());
+
+        // Columns without a counterpart in the unified schema stay null
+        let none = None::<String>;
 
         let insert_query = format!(
             r#"
@@ -217,49 +228,49 @@ impl ScyllaRepository {
                 (
                     ( // Group 1: 16 fields
                         &cdr.cdr_id,
-                        &cdr.event_type,
+                        cdr.event_type.as_str(),
                         &cdr.imsi,
                         &cdr.msisdn,
                         &cdr.imei,
-                        &cdr.country,
-                        &cdr.operator,
+                        &cdr.country_code,
+                        &network_name, // operator
                         &cdr.mcc,
                         &cdr.mnc,
                         &cdr.lac,
                         &cdr.cell_id,
                         start_ts,
                         end_ts,
-                        cdr.duration_seconds.map(|v| v as i64),
-                        &cdr.service_type,
-                        &cdr.call_type,
+                        cdr.duration_seconds,
+                        cdr.service_type.as_str(),
+                        call_type,
                     ),
                     ( // Group 2: 15 fields
                         &cdr.called_number,
                         &cdr.calling_number,
-                        &cdr.call_direction,
-                        &cdr.sms_type,
-                        &cdr.sms_direction,
-                        &cdr.destination_number,
-                        &cdr.originating_number,
+                        &none, // call_direction
+                        sms_type,
+                        &none, // sms_direction
+                        &none, // destination_number
+                        &none, // originating_number
                         &cdr.apn,
-                        cdr.bytes_uploaded.map(|v| v as i64),
-                        cdr.bytes_downloaded.map(|v| v as i64),
-                        cdr.session_duration.map(|v| v as i64),
+                        cdr.bytes_uploaded,
+                        cdr.bytes_downloaded,
+                        None::<i64>, // session_duration
                         cdr.is_roaming,
                         &cdr.visited_country,
                         &cdr.visited_network,
-                        cdr.charge_amount,
+                        cdr.rated_amount,
                     ),
                     ( // Group 3: 13 fields
                         &cdr.currency,
-                        &cdr.tariff_class,
-                        &cdr.cause_for_termination,
-                        &cdr.hash,
+                        &tariff_class,
+                        &none, // cause_for_termination
+                        &cdr.raw_data_hash,
                         fraud_score,
                         risk_level,
                         fraud_reasons,
                         fraud_model,
-                        network_name,
+                        &network_name,
                         network_type,
                         cell_tower_location,
                         signal_strength,
@@ -272,7 +283,7 @@ impl ScyllaRepository {
                         lifetime_value,
                         is_vip,
                         data_plan_limit_mb,
-                        ingestion_ts,
+                        None::<i64>, // ingestion_timestamp
                         normalization_ts,
                         enrichment_ts,
                     ),
@@ -282,6 +293,30 @@ impl ScyllaRepository {
 
         Ok(())
     }
+
+    /// Whether the CDR was already stored within the dedup window
+    pub async fn is_seen(&self, cdr_id: &str) -> Result<bool> {
+        if self.dedup_ttl_secs.is_none() {
+            return Ok(false);
+        }
+        let query = format!("SELECT cdr_id FROM {}.cdr_seen WHERE cdr_id = ?", self.keyspace);
+        let result = self.session.query(query, (cdr_id,)).await?;
+        Ok(result.rows.is_some_and(|rows| !rows.is_empty()))
+    }
+
+    /// Remember a stored CDR for the dedup window. Called after the insert,
+    /// so a failed insert is retried rather than taken for a duplicate.
+    pub async fn mark_seen(&self, cdr_id: &str) -> Result<()> {
+        let Some(ttl) = self.dedup_ttl_secs else {
+            return Ok(());
+        };
+        let query = format!(
+            "INSERT INTO {}.cdr_seen (cdr_id, seen_at) VALUES (?, toTimestamp(now())) USING TTL ?",
+            self.keyspace
+        );
+        self.session.query(query, (cdr_id, ttl)).await?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -7,6 +7,8 @@ pub struct Config {
     pub generation: GenerationConfig,
     pub simulation: SimulationConfig,
     pub server: ServerConfig,
+    /// Time allowed to deliver queued CDRs after SIGTERM/SIGINT
+    pub shutdown_timeout_secs: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -123,11 +125,17 @@ impl Config {
                 .context("Invalid SERVER_PORT")?,
         };
 
+        let shutdown_timeout_secs = env::var("SHUTDOWN_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "25".to_string())
+            .parse()
+            .context("Invalid SHUTDOWN_TIMEOUT_SECS")?;
+
         Ok(Config {
             kafka,
             generation,
             simulation,
             server,
+            shutdown_timeout_secs,
         })
     }
 }
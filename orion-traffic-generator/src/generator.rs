@@ -2,13 +2,13 @@ use crate::config::Config;
 use crate::metrics;
 use anyhow::Result;
 use chrono::Utc;
+use orion_shutdown::Shutdown;
 use rand::Rng;
-use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
 use rdkafka::ClientConfig;
 use serde_json::json;
 use std::time::{Duration, Instant};
 use tokio::time::sleep;
-use tracing;
 use uuid::Uuid;
 
 pub struct TrafficGenerator {
@@ -26,7 +26,8 @@ impl TrafficGenerator {
         Ok(Self { producer, config })
     }
 
-    pub async fn run(&self) {
+    /// Generate traffic until shutdown, then deliver what is still queued
+    pub async fn run(&self, mut shutdown: Shutdown) {
         tracing::info!("Starting traffic generator");
         let mut burst_mode = false;
         let mut last_burst = Instant::now();
@@ -56,6 +57,9 @@ impl TrafficGenerator {
 
             // Generate CDR batch
             for _ in 0..rate {
+                if shutdown.is_requested() {
+                    break;
+                }
                 if let Err(e) = self.generate_and_send_cdr().await {
                     tracing::error!("Failed to generate/send CDR: {}", e);
                     metrics::increment_errors();
@@ -65,7 +69,15 @@ impl TrafficGenerator {
             metrics::record_latency(start.elapsed().as_secs_f64());
 
             // Sleep to maintain rate
-            sleep(Duration::from_secs(1)).await;
+            tokio::select! {
+                _ = shutdown.requested() => break,
+                _ = sleep(Duration::from_secs(1)) => {}
+            }
+        }
+
+        tracing::info!("Traffic generator stopped, flushing producer");
+        if let Err(e) = self.producer.flush(Duration::from_secs(self.config.shutdown_timeout_secs)) {
+            tracing::error!("Failed to flush producer: {}", e);
         }
     }
 
@@ -164,12 +176,13 @@ impl TrafficGenerator {
         let event_type = event_types[rng.gen_range(0..event_types.len())];
 
         let imsi = format!("{}{}{:010}", mcc, mnc, rng.gen_range(1000000000..9999999999u64));
-        let msisdn = format!("+{}{:09}", mcc, rng.gen_range(100000000..999999999));
+        let msisdn = mobile_number(country, &mut rng);
 
         let mut cdr = json!({
             "event_type": event_type,
             "imsi": imsi,
             "msisdn": msisdn,
+            "imei": imei(&mut rng),
             "country": country,
             "timestamp": Utc::now().to_rfc3339(),
         });
@@ -183,7 +196,12 @@ impl TrafficGenerator {
                     rng.gen_range(30..900) // Normal: 30s-15min
                 };
                 cdr["duration"] = json!(duration);
-                cdr["called_number"] = json!(format!("+{}{:09}", mcc, rng.gen_range(100000000..999999999)));
+                // Fraud: calls to a high-risk destination
+                cdr["called_number"] = if is_fraud {
+                    json!(format!("+79{:09}", rng.gen_range(0..999999999)))
+                } else {
+                    json!(mobile_number(country, &mut rng))
+                };
                 cdr["call_type"] = json!(if is_fraud { "international" } else { "domestic" });
             }
             "data" => {
@@ -197,7 +215,7 @@ impl TrafficGenerator {
                 cdr["apn"] = json!("internet.example.com");
             }
             "sms" => {
-                cdr["destination_number"] = json!(format!("+{}{:09}", mcc, rng.gen_range(100000000..999999999)));
+                cdr["destination_number"] = json!(mobile_number(country, &mut rng));
                 cdr["sms_type"] = json!("text");
                 cdr["message_length"] = json!(rng.gen_range(10..160));
             }
@@ -247,3 +265,30 @@ impl TrafficGenerator {
     }
 }
 
+/// E.164 mobile number in the numbering plan of the country
+fn mobile_number(country: &str, rng: &mut impl Rng) -> String {
+    match country {
+        "TN" => format!("+2162{:07}", rng.gen_range(0..9999999)),
+        "FN" => format!("+35840{:07}", rng.gen_range(0..9999999)),
+        "CH" => format!("+4179{:07}", rng.gen_range(0..9999999)),
+        _ => format!("+336{:08}", rng.gen_range(0..99999999)),
+    }
+}
+
+/// IMEI with a TAC of the sample database of orion-numbering and a valid
+/// Luhn check digit
+fn imei(rng: &mut impl Rng) -> String {
+    let tac = ["35000001", "35000002", "35000003", "86000001"][rng.gen_range(0..4)];
+    let body = format!("{}{:06}", tac, rng.gen_range(0..999999));
+    let sum: u32 = body
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(index, b)| {
+            let digit = u32::from(b - b'0');
+            // Every other digit from the check digit is doubled
+            if index % 2 == 0 { (digit * 2) / 10 + (digit * 2) % 10 } else { digit }
+        })
+        .sum();
+    format!("{}{}", body, (10 - sum % 10) % 10)
+}
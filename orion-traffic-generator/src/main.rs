@@ -6,7 +6,9 @@ use axum::{routing::get, Router};
 use config::Config;
 use generator::TrafficGenerator;
 use metrics_exporter_prometheus::PrometheusBuilder;
+use orion_shutdown::Shutdown;
 use std::net::SocketAddr;
+use std::time::Duration;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
@@ -42,9 +44,11 @@ async fn main() -> anyhow::Result<()> {
     let generator = TrafficGenerator::new(config.clone())?;
     tracing::info!("Traffic generator initialized");
 
-    // Spawn generator task
-    tokio::spawn(async move {
-        generator.run().await;
+    // Spawn generator task, stopped by SIGTERM/SIGINT
+    let shutdown = Shutdown::on_signal();
+    let generator_shutdown = shutdown.clone();
+    let generator_handle = tokio::spawn(async move {
+        generator.run(generator_shutdown).await;
     });
 
     // Build HTTP server
@@ -59,7 +63,17 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("HTTP server starting on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    let mut server_shutdown = shutdown.clone();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move { server_shutdown.requested().await })
+        .await?;
+
+    // Let the generator deliver its queued CDRs, within the deadline
+    let timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    if tokio::time::timeout(timeout, generator_handle).await.is_err() {
+        tracing::warn!("Shutdown deadline of {:?} exceeded, exiting with CDRs in flight", timeout);
+    }
+    tracing::info!("Traffic generator stopped");
 
     Ok(())
 }
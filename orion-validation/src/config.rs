@@ -4,6 +4,13 @@ use std::env;
 pub struct Config {
     pub kafka: KafkaConfig,
     pub server: ServerConfig,
+    pub rules: RulesConfig,
+    pub semantic: SemanticConfig,
+    pub event_time: EventTimeConfig,
+    pub dedup: DedupConfig,
+    pub rejections: RejectionsConfig,
+    /// Time allowed to drain in-flight work after SIGTERM/SIGINT
+    pub shutdown_timeout_secs: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -13,6 +20,30 @@ pub struct KafkaConfig {
     pub output_topic: String,
     pub rejected_topic: String,
     pub consumer_group: String,
+    pub delivery_mode: DeliveryMode,
+    pub commit_batch_size: usize,
+    pub commit_interval_ms: u64,
+    pub transactional_id: String,
+    pub transaction_timeout_ms: u64,
+}
+
+/// Delivery guarantee for consumed CDRs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryMode {
+    /// Offsets are committed in batches once the downstream produce is acknowledged
+    AtLeastOnce,
+    /// Offsets are committed atomically with the produced records
+    ExactlyOnce,
+}
+
+impl DeliveryMode {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "at_least_once" | "at-least-once" => Ok(Self::AtLeastOnce),
+            "exactly_once" | "exactly-once" => Ok(Self::ExactlyOnce),
+            other => anyhow::bail!("Invalid KAFKA_DELIVERY_MODE: {}", other),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -21,6 +52,51 @@ pub struct ServerConfig {
     pub port: u16,
 }
 
+/// Declarative validation rules
+#[derive(Debug, Clone)]
+pub struct RulesConfig {
+    /// YAML (or `.toml`) rules file, bundled rules if absent
+    pub path: String,
+    /// How often the rules file is checked for changes
+    pub reload_interval_secs: u64,
+}
+
+/// Semantic checks per event type
+#[derive(Debug, Clone)]
+pub struct SemanticConfig {
+    /// Longest voice call accepted
+    pub max_call_duration_secs: u64,
+}
+
+/// Event time extraction
+#[derive(Debug, Clone)]
+pub struct EventTimeConfig {
+    /// How far in the future an event time may be, for clock skew
+    pub clock_skew_secs: u64,
+    /// Oldest event accepted, 0 for no limit
+    pub max_age_secs: u64,
+}
+
+/// Deduplication of validated CDRs
+#[derive(Debug, Clone)]
+pub struct DedupConfig {
+    /// How long a published CDR id is remembered, 0 to disable
+    pub window_secs: u64,
+    /// Most ids remembered at once
+    pub max_entries: usize,
+}
+
+/// Rejection store and replay
+#[derive(Debug, Clone)]
+pub struct RejectionsConfig {
+    /// Directory of the rejection files
+    pub dir: String,
+    /// Days of rejections kept, 0 to keep them all
+    pub retention_days: u64,
+    /// Consumer group persisting the rejected topic
+    pub consumer_group: String,
+}
+
 impl Config {
     pub fn from_env() -> anyhow::Result<Self> {
         let kafka_brokers = env::var("KAFKA_BROKERS")
@@ -38,12 +114,79 @@ impl Config {
         let consumer_group = env::var("KAFKA_CONSUMER_GROUP")
             .unwrap_or_else(|_| "orion-validation".to_string());
         
+        let delivery_mode = DeliveryMode::parse(
+            &env::var("KAFKA_DELIVERY_MODE").unwrap_or_else(|_| "at_least_once".to_string()),
+        )?;
+
+        let commit_batch_size = env::var("KAFKA_COMMIT_BATCH_SIZE")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<usize>()?;
+
+        let commit_interval_ms = env::var("KAFKA_COMMIT_INTERVAL_MS")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse::<u64>()?;
+
+        // Must be unique per running instance: a new producer with the same
+        // id fences the previous one
+        let transactional_id = env::var("KAFKA_TRANSACTIONAL_ID").unwrap_or_else(|_| {
+            format!(
+                "{}-{}",
+                consumer_group,
+                env::var("HOSTNAME").unwrap_or_else(|_| "0".to_string())
+            )
+        });
+
+        let transaction_timeout_ms = env::var("KAFKA_TRANSACTION_TIMEOUT_MS")
+            .unwrap_or_else(|_| "60000".to_string())
+            .parse::<u64>()?;
+        
         let server_host = env::var("SERVER_HOST")
             .unwrap_or_else(|_| "0.0.0.0".to_string());
         
         let server_port = env::var("SERVER_PORT")
             .unwrap_or_else(|_| "8082".to_string())
             .parse::<u16>()?;
+
+        let rules_path = env::var("VALIDATION_RULES_PATH")
+            .unwrap_or_else(|_| "config/rules.yaml".to_string());
+
+        let rules_reload_interval_secs = env::var("VALIDATION_RULES_RELOAD_INTERVAL_SECS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<u64>()?;
+
+        let max_call_duration_secs = env::var("VALIDATION_MAX_CALL_DURATION_SECS")
+            .unwrap_or_else(|_| "86400".to_string())
+            .parse::<u64>()?;
+
+        let clock_skew_secs = env::var("VALIDATION_CLOCK_SKEW_SECS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse::<u64>()?;
+
+        let max_event_age_secs = env::var("VALIDATION_MAX_EVENT_AGE_SECS")
+            .unwrap_or_else(|_| "604800".to_string())
+            .parse::<u64>()?;
+
+        let dedup_window_secs = env::var("VALIDATION_DEDUP_WINDOW_SECS")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse::<u64>()?;
+
+        let dedup_max_entries = env::var("VALIDATION_DEDUP_MAX_ENTRIES")
+            .unwrap_or_else(|_| "1000000".to_string())
+            .parse::<usize>()?;
+
+        let rejections_dir = env::var("VALIDATION_REJECTIONS_DIR")
+            .unwrap_or_else(|_| "data/rejections".to_string());
+
+        let rejections_retention_days = env::var("VALIDATION_REJECTIONS_RETENTION_DAYS")
+            .unwrap_or_else(|_| "7".to_string())
+            .parse::<u64>()?;
+
+        let rejections_consumer_group = env::var("KAFKA_REJECTIONS_CONSUMER_GROUP")
+            .unwrap_or_else(|_| format!("{}-rejections", consumer_group));
+
+        let shutdown_timeout_secs = env::var("SHUTDOWN_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "25".to_string())
+            .parse::<u64>()?;
         
         Ok(Config {
             kafka: KafkaConfig {
@@ -52,11 +195,37 @@ impl Config {
                 output_topic,
                 rejected_topic,
                 consumer_group,
+                delivery_mode,
+                commit_batch_size,
+                commit_interval_ms,
+                transactional_id,
+                transaction_timeout_ms,
             },
             server: ServerConfig {
                 host: server_host,
                 port: server_port,
             },
+            rules: RulesConfig {
+                path: rules_path,
+                reload_interval_secs: rules_reload_interval_secs,
+            },
+            semantic: SemanticConfig {
+                max_call_duration_secs,
+            },
+            event_time: EventTimeConfig {
+                clock_skew_secs,
+                max_age_secs: max_event_age_secs,
+            },
+            dedup: DedupConfig {
+                window_secs: dedup_window_secs,
+                max_entries: dedup_max_entries,
+            },
+            rejections: RejectionsConfig {
+                dir: rejections_dir,
+                retention_days: rejections_retention_days,
+                consumer_group: rejections_consumer_group,
+            },
+            shutdown_timeout_secs,
         })
     }
 }
@@ -3,11 +3,17 @@ mod metrics;
 mod routes;
 mod service;
 
-use axum::{Router, routing::get};
+use axum::{Router, routing::{get, post}};
 use config::Config;
 use metrics_exporter_prometheus::PrometheusBuilder;
-use service::KafkaConsumerService;
+use orion_shutdown::Shutdown;
+use service::{
+    Deduplicator, EventTimePolicy, KafkaConsumerService, RejectionConsumer, RejectionStore, Resubmitter,
+    RuleStore, SemanticChecker, Validator,
+};
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
@@ -26,12 +32,13 @@ async fn main() -> anyhow::Result<()> {
     // Load configuration
     let config = Config::from_env()?;
     tracing::info!(
-        "Configuration loaded - Server: {}:{}, Input topic: {}, Output topic: {}, Rejected topic: {}",
+        "Configuration loaded - Server: {}:{}, Input topic: {}, Output topic: {}, Rejected topic: {}, Delivery: {:?}",
         config.server.host,
         config.server.port,
         config.kafka.input_topic,
         config.kafka.output_topic,
         config.kafka.rejected_topic,
+        config.kafka.delivery_mode,
     );
 
     // Initialize metrics
@@ -40,30 +47,99 @@ async fn main() -> anyhow::Result<()> {
     metrics::init_metrics();
     tracing::info!("Metrics initialized");
 
+    // Load validation rules
+    let rules = Arc::new(RuleStore::open(&config.rules.path)?);
+    let engine = rules.current();
+    tracing::info!(
+        "Validation rules loaded ({} rule sets, {} rules)",
+        engine.rule_sets(),
+        engine.rules()
+    );
+
     // Create Kafka consumer service
-    let kafka_service = KafkaConsumerService::new(&config.kafka)?;
+    let validator = Validator::new(
+        Arc::clone(&rules),
+        SemanticChecker::new(config.semantic.max_call_duration_secs),
+        EventTimePolicy::new(config.event_time.clock_skew_secs, config.event_time.max_age_secs),
+    );
+    let dedup = Deduplicator::new(
+        Duration::from_secs(config.dedup.window_secs),
+        config.dedup.max_entries,
+    );
+    let kafka_service = KafkaConsumerService::new(&config.kafka, validator, dedup)?;
     tracing::info!("Kafka consumer service initialized");
 
-    // Spawn Kafka consumer
-    tokio::spawn(async move {
-        if let Err(e) = kafka_service.run().await {
+    // Spawn Kafka consumer, stopped by SIGTERM/SIGINT
+    let shutdown = Shutdown::on_signal();
+
+    // Hot reload: rules are swapped when the file changes
+    tokio::spawn(Arc::clone(&rules).watch(
+        Duration::from_secs(config.rules.reload_interval_secs),
+        shutdown.clone(),
+    ));
+
+    let consumer_shutdown = shutdown.clone();
+    let consumer_handle = tokio::spawn(async move {
+        if let Err(e) = kafka_service.run(consumer_shutdown).await {
             tracing::error!("Kafka consumer error: {}", e);
         }
     });
 
+    // Persist rejected CDRs so they can be listed, fixed and resubmitted
+    let rejection_store = Arc::new(RejectionStore::open(
+        &config.rejections.dir,
+        config.rejections.retention_days,
+    )?);
+    let rejection_consumer = RejectionConsumer::new(
+        &config.kafka,
+        &config.rejections.consumer_group,
+        Arc::clone(&rejection_store),
+    )?;
+    let rejection_shutdown = shutdown.clone();
+    let rejection_handle = tokio::spawn(async move {
+        if let Err(e) = rejection_consumer.run(rejection_shutdown).await {
+            tracing::error!("Rejection consumer error: {}", e);
+        }
+    });
+    let resubmitter = Arc::new(Resubmitter::new(
+        &config.kafka.brokers,
+        &config.kafka.input_topic,
+        Arc::clone(&rejection_store),
+    )?);
+    tracing::info!("Rejection store initialized in {}", config.rejections.dir);
+
     // Build HTTP server
     let app = Router::new()
         .route("/health", get(routes::health))
         .route("/metrics", get({
             let handle = prometheus_handle.clone();
             move || routes::metrics(handle)
-        }));
+        }))
+        .route("/rules", get(routes::rules).with_state(Arc::clone(&rules)))
+        .route("/rules/reload", post(routes::reload_rules).with_state(rules))
+        .route("/rejections", get(routes::rejections).with_state(Arc::clone(&rejection_store)))
+        .route("/rejections/resubmit", post(routes::resubmit_batch).with_state(Arc::clone(&resubmitter)))
+        .route("/rejections/:id", get(routes::rejection).with_state(rejection_store))
+        .route("/rejections/:id/resubmit", post(routes::resubmit).with_state(resubmitter));
 
     let addr = SocketAddr::from(([0, 0, 0, 0], config.server.port));
     tracing::info!("Starting HTTP server on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    let mut server_shutdown = shutdown.clone();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move { server_shutdown.requested().await })
+        .await?;
+
+    // Let the consumers finish their message and commit, within the deadline
+    let timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    let consumers = async {
+        let _ = tokio::join!(consumer_handle, rejection_handle);
+    };
+    if tokio::time::timeout(timeout, consumers).await.is_err() {
+        tracing::warn!("Shutdown deadline of {:?} exceeded, exiting with work in flight", timeout);
+    }
+    tracing::info!("orion-validation service stopped");
 
     Ok(())
 }
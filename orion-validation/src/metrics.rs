@@ -21,6 +21,31 @@ pub fn init_metrics() {
         "Total number of validation errors"
     );
     
+    describe_counter!(
+        "orion_validation_violations_total",
+        "Total number of rule violations by error type and severity"
+    );
+
+    describe_counter!(
+        "orion_validation_duplicates_total",
+        "Total number of duplicate CDRs dropped"
+    );
+
+    describe_counter!(
+        "orion_validation_rejections_stored_total",
+        "Total number of rejected CDRs persisted to the rejection store"
+    );
+
+    describe_counter!(
+        "orion_validation_resubmissions_total",
+        "Total number of rejected CDRs resubmitted by status"
+    );
+
+    describe_counter!(
+        "orion_validation_rules_reloads_total",
+        "Total number of validation rules reloads by status"
+    );
+
     describe_histogram!(
         "orion_validation_latency_seconds",
         "Validation latency in seconds"
@@ -42,3 +67,28 @@ pub fn increment_invalid_total() {
 pub fn record_latency(duration: f64) {
     histogram!("orion_validation_latency_seconds").record(duration);
 }
+
+pub fn record_violation(error_type: &str, severity: &'static str) {
+    counter!(
+        "orion_validation_violations_total",
+        "error_type" => error_type.to_string(),
+        "severity" => severity
+    )
+    .increment(1);
+}
+
+pub fn record_rules_reload(status: &'static str) {
+    counter!("orion_validation_rules_reloads_total", "status" => status).increment(1);
+}
+
+pub fn increment_duplicates_total() {
+    counter!("orion_validation_duplicates_total").increment(1);
+}
+
+pub fn increment_rejections_stored_total() {
+    counter!("orion_validation_rejections_stored_total").increment(1);
+}
+
+pub fn record_resubmission(status: &'static str) {
+    counter!("orion_validation_resubmissions_total", "status" => status).increment(1);
+}
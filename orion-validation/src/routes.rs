@@ -1,5 +1,10 @@
-use axum::{response::IntoResponse, http::StatusCode};
+use axum::{extract::{Path, Query, State}, response::IntoResponse, http::StatusCode, Json};
 use metrics_exporter_prometheus::PrometheusHandle;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::service::{Fix, RejectionQuery, RejectionStore, ResubmitError, Resubmitter, RuleStore};
 
 pub async fn health() -> impl IntoResponse {
     (StatusCode::OK, "OK")
@@ -8,3 +13,103 @@ pub async fn health() -> impl IntoResponse {
 pub async fn metrics(handle: PrometheusHandle) -> impl IntoResponse {
     handle.render()
 }
+
+/// Rule sets currently applied
+pub async fn rules(State(rules): State<Arc<RuleStore>>) -> impl IntoResponse {
+    Json(rules.current().describe())
+}
+
+/// Reload the rules file without waiting for the next change check
+pub async fn reload_rules(State(rules): State<Arc<RuleStore>>) -> impl IntoResponse {
+    match rules.reload() {
+        Ok(engine) => (
+            StatusCode::OK,
+            Json(json!({"rule_sets": engine.rule_sets(), "rules": engine.rules()})),
+        ),
+        Err(e) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"error": format!("{:#}", e)})),
+        ),
+    }
+}
+
+/// Rejections filtered by reason, country and time
+pub async fn rejections(
+    State(store): State<Arc<RejectionStore>>,
+    Query(query): Query<RejectionQuery>,
+) -> impl IntoResponse {
+    let listed = tokio::task::spawn_blocking(move || store.list(&query)).await;
+    match listed.unwrap_or_else(|e| Err(e.into())) {
+        Ok(rejections) => (StatusCode::OK, Json(json!(rejections))),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("{:#}", e)})),
+        ),
+    }
+}
+
+pub async fn rejection(
+    State(store): State<Arc<RejectionStore>>,
+    Path(rejection_id): Path<String>,
+) -> impl IntoResponse {
+    let id = rejection_id.clone();
+    let found = tokio::task::spawn_blocking(move || store.get(&id)).await;
+    match found.unwrap_or_else(|e| Err(e.into())) {
+        Ok(Some(rejection)) => (StatusCode::OK, Json(json!(rejection))),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": format!("Rejection {} not found", rejection_id)})),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("{:#}", e)})),
+        ),
+    }
+}
+
+/// Fix a rejected CDR and send it through the pipeline again
+pub async fn resubmit(
+    State(resubmitter): State<Arc<Resubmitter>>,
+    Path(rejection_id): Path<String>,
+    fix: Option<Json<Fix>>,
+) -> impl IntoResponse {
+    let fix = fix.map(|Json(fix)| fix).unwrap_or_default();
+    match resubmitter.resubmit(&rejection_id, &fix).await {
+        Ok(resubmitted_at) => (
+            StatusCode::OK,
+            Json(json!({"rejection_id": rejection_id, "resubmitted_at": resubmitted_at})),
+        ),
+        Err(e) => {
+            let status = match e {
+                ResubmitError::NotFound(_) => StatusCode::NOT_FOUND,
+                ResubmitError::InvalidFix(_) => StatusCode::UNPROCESSABLE_ENTITY,
+                ResubmitError::Failed(_) => StatusCode::BAD_GATEWAY,
+            };
+            (status, Json(json!({"error": format!("{:#}", e)})))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResubmitBatch {
+    pub rejection_ids: Vec<String>,
+    /// Same correction for every record, e.g. a field a bad feed left out
+    #[serde(flatten)]
+    pub fix: Fix,
+}
+
+/// Apply one correction to several rejected CDRs and resubmit them
+pub async fn resubmit_batch(
+    State(resubmitter): State<Arc<Resubmitter>>,
+    Json(batch): Json<ResubmitBatch>,
+) -> impl IntoResponse {
+    let mut resubmitted = Vec::new();
+    let mut failed = Vec::new();
+    for rejection_id in batch.rejection_ids {
+        match resubmitter.resubmit(&rejection_id, &batch.fix).await {
+            Ok(_) => resubmitted.push(rejection_id),
+            Err(e) => failed.push(json!({"rejection_id": rejection_id, "error": format!("{:#}", e)})),
+        }
+    }
+    Json(json!({"resubmitted": resubmitted, "failed": failed}))
+}
@@ -0,0 +1,81 @@
+//! Deterministic CDR identifiers.
+//!
+//! A CDR's id is a UUID v5 (SHA-1 based, stable across processes and Rust
+//! versions) of its source identifiers: IMSI, event type and event time,
+//! plus the charging id and record sequence number when the switch sends
+//! them. Without either of those, a digest of the whole record is added so
+//! distinct records of the same subscriber and millisecond keep distinct
+//! ids. A redelivered message therefore always gets the same id, including
+//! one without event time (the processing time is not part of the key).
+//! The digest is taken over the canonical text of the record (keys sorted
+//! recursively), so it does not depend on the order of its fields.
+
+use chrono::{DateTime, Utc};
+use orion_model::canonical_json;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Namespace of ORION CDR ids
+const CDR_ID_NAMESPACE: Uuid = Uuid::from_u128(0x6f72_696f_6e2d_4364_7200_0000_0000_0001);
+
+const CHARGING_ID: &[&str] = &["charging_id", "charging_identifier"];
+const SEQUENCE: &[&str] = &["record_sequence_number", "sequence_number", "local_record_sequence_number"];
+
+pub fn cdr_id(imsi: &str, event_type: &str, event_time: Option<DateTime<Utc>>, raw_data: &Value) -> String {
+    let event_time = event_time.map(|time| time.timestamp_millis().to_string());
+    let mut key = format!("{}|{}|{}", imsi, event_type, event_time.unwrap_or_default());
+
+    let charging_id = identifier(raw_data, CHARGING_ID);
+    let sequence = identifier(raw_data, SEQUENCE);
+    if charging_id.is_none() && sequence.is_none() {
+        let digest = Sha256::digest(canonical_json(raw_data).as_bytes());
+        key.push_str(&format!("|{:x}", digest));
+    } else {
+        key.push_str(&format!(
+            "|{}|{}",
+            charging_id.unwrap_or_default(),
+            sequence.unwrap_or_default()
+        ));
+    }
+
+    Uuid::new_v5(&CDR_ID_NAMESPACE, key.as_bytes()).to_string()
+}
+
+fn identifier(raw_data: &Value, aliases: &[&str]) -> Option<String> {
+    aliases.iter().find_map(|alias| match raw_data.get(alias)? {
+        Value::String(text) if !text.is_empty() => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cdr_id_is_deterministic() {
+        let time = "2026-01-29T10:15:30Z".parse::<DateTime<Utc>>().ok();
+        let cdr = json!({"imsi": "208150123456789", "duration": 120, "called_number": "+33698765432"});
+
+        let id = cdr_id("208150123456789", "voice", time, &cdr);
+        assert_eq!(id, cdr_id("208150123456789", "voice", time, &cdr));
+        assert_eq!(Uuid::parse_str(&id).unwrap().get_version_num(), 5);
+        assert_ne!(id, cdr_id("208150123456789", "voice", time, &json!({"duration": 121})));
+
+        // With source identifiers, only they tell records apart
+        let partial = |sequence: u32, duration: u32| {
+            json!({"charging_id": 305419896, "record_sequence_number": sequence, "duration": duration})
+        };
+        assert_eq!(
+            cdr_id("208150123456789", "data", time, &partial(1, 10)),
+            cdr_id("208150123456789", "data", time, &partial(1, 20))
+        );
+        assert_ne!(
+            cdr_id("208150123456789", "data", time, &partial(1, 10)),
+            cdr_id("208150123456789", "data", time, &partial(2, 10))
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Deduplication of validated CDRs.
+//!
+//! Ids of the CDRs published within the window are kept in memory, up to a
+//! maximum number of entries (oldest first out). A CDR whose id was already
+//! published is dropped. Ids are only remembered once their output is
+//! committed, so a batch that is replayed after a failure is not mistaken
+//! for duplicates.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub struct Deduplicator {
+    window: Duration,
+    max_entries: usize,
+    state: Mutex<SeenSet>,
+}
+
+#[derive(Default)]
+struct SeenSet {
+    seen: HashMap<String, Instant>,
+    order: VecDeque<(String, Instant)>,
+    /// Published, not committed yet
+    pending: Vec<String>,
+}
+
+impl Deduplicator {
+    /// A zero window disables deduplication
+    pub fn new(window: Duration, max_entries: usize) -> Self {
+        Self {
+            window,
+            max_entries,
+            state: Mutex::new(SeenSet::default()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.window.is_zero() && self.max_entries > 0
+    }
+
+    /// Whether the CDR was already published within the window
+    pub fn is_duplicate(&self, cdr_id: &str) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+        let mut state = self.lock();
+        state.expire(Instant::now(), self.window);
+        state.seen.contains_key(cdr_id) || state.pending.iter().any(|id| id == cdr_id)
+    }
+
+    /// Record a published CDR, remembered once `commit` is called
+    pub fn record(&self, cdr_id: &str) {
+        if self.is_enabled() {
+            self.lock().pending.push(cdr_id.to_string());
+        }
+    }
+
+    /// The recorded CDRs are committed: remember them for the window
+    pub fn commit(&self) {
+        let mut state = self.lock();
+        let now = Instant::now();
+        for id in std::mem::take(&mut state.pending) {
+            state.seen.insert(id.clone(), now);
+            state.order.push_back((id, now));
+        }
+        while state.seen.len() > self.max_entries {
+            let Some((id, at)) = state.order.pop_front() else { break };
+            state.forget(&id, at);
+        }
+    }
+
+    /// The recorded CDRs will be replayed: forget them
+    pub fn rollback(&self) {
+        self.lock().pending.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SeenSet> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl SeenSet {
+    fn expire(&mut self, now: Instant, window: Duration) {
+        while let Some((_, at)) = self.order.front() {
+            if now.duration_since(*at) < window {
+                break;
+            }
+            let Some((id, at)) = self.order.pop_front() else { break };
+            self.forget(&id, at);
+        }
+    }
+
+    /// Forget an id unless it was seen again since
+    fn forget(&mut self, id: &str, at: Instant) {
+        if self.seen.get(id) == Some(&at) {
+            self.seen.remove(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicates_within_window() {
+        let dedup = Deduplicator::new(Duration::from_millis(50), 100);
+        assert!(!dedup.is_duplicate("a"));
+        dedup.record("a");
+        // Pending ids already count, so a batch cannot publish one twice
+        assert!(dedup.is_duplicate("a"));
+        dedup.commit();
+        assert!(dedup.is_duplicate("a"));
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(!dedup.is_duplicate("a"));
+    }
+
+    #[test]
+    fn test_rollback_and_capacity() {
+        let dedup = Deduplicator::new(Duration::from_secs(60), 2);
+        dedup.record("a");
+        dedup.rollback();
+        assert!(!dedup.is_duplicate("a"));
+
+        for id in ["a", "b", "c"] {
+            dedup.record(id);
+        }
+        dedup.commit();
+        assert!(!dedup.is_duplicate("a"));
+        assert!(dedup.is_duplicate("c"));
+
+        assert!(!Deduplicator::new(Duration::ZERO, 2).is_duplicate("c"));
+    }
+}
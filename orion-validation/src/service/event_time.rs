@@ -0,0 +1,123 @@
+//! Event time of a CDR.
+//!
+//! The event time is read with `orion_model::event_time`, shared with the
+//! mapping dry-run of orion-normalization. A CDR dated further in the future
+//! than the clock skew tolerance, or older than the maximum age, is
+//! rejected.
+
+use crate::service::model::{Severity, Violation};
+use crate::service::rules::display;
+use chrono::{DateTime, Duration, Utc};
+use orion_model::event_time::{self, EVENT_TIME_FIELDS};
+use serde_json::Value;
+
+pub struct EventTimePolicy {
+    clock_skew: Duration,
+    max_age: Option<Duration>,
+}
+
+impl EventTimePolicy {
+    /// `max_age_secs` of 0 accepts records of any age
+    pub fn new(clock_skew_secs: u64, max_age_secs: u64) -> Self {
+        Self {
+            clock_skew: Duration::seconds(clock_skew_secs as i64),
+            max_age: (max_age_secs > 0).then(|| Duration::seconds(max_age_secs as i64)),
+        }
+    }
+
+    /// Event time of a CDR. A missing one is reported as a warning (the
+    /// caller falls back to processing time), an unreadable or out of bounds
+    /// one as an error.
+    pub fn resolve(&self, cdr: &Value, now: DateTime<Utc>, violations: &mut Vec<Violation>) -> Option<DateTime<Utc>> {
+        let Some((field, value)) = event_time::field(cdr) else {
+            violations.push(Violation::new(
+                EVENT_TIME_FIELDS[0],
+                "missing_field",
+                "No event time, processing time used".to_string(),
+                Severity::Warning,
+            ));
+            return None;
+        };
+
+        let Some(event_time) = event_time::parse(value) else {
+            violations.push(Violation::new(
+                field,
+                "invalid_timestamp",
+                format!(
+                    "{} must be RFC 3339, epoch seconds/millis or YYMMDDhhmmss+hhmm, got: {}",
+                    field,
+                    display(value)
+                ),
+                Severity::Error,
+            ));
+            return None;
+        };
+
+        if event_time > now + self.clock_skew {
+            violations.push(Violation::new(
+                field,
+                "future_timestamp",
+                format!(
+                    "{} is more than {}s in the future, got: {}",
+                    field,
+                    self.clock_skew.num_seconds(),
+                    event_time.to_rfc3339()
+                ),
+                Severity::Error,
+            ));
+        } else if let Some(max_age) = self.max_age.filter(|max_age| event_time < now - *max_age) {
+            violations.push(Violation::new(
+                field,
+                "stale_timestamp",
+                format!(
+                    "{} is more than {}s old, got: {}",
+                    field,
+                    max_age.num_seconds(),
+                    event_time.to_rfc3339()
+                ),
+                Severity::Error,
+            ));
+        }
+        Some(event_time)
+    }
+}
+
+impl Default for EventTimePolicy {
+    fn default() -> Self {
+        Self::new(300, 7 * 86_400)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_resolve_rejects_future_and_stale_records() {
+        let policy = EventTimePolicy::new(300, 86_400);
+        let now = "2026-01-29T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let resolve = |cdr: Value| {
+            let mut violations = Vec::new();
+            let time = policy.resolve(&cdr, now, &mut violations);
+            (time, violations.into_iter().map(|v| (v.error_type, v.severity)).collect::<Vec<_>>())
+        };
+
+        let (time, violations) = resolve(json!({"start_timestamp": "2026-01-29T10:04:00Z"}));
+        assert_eq!(time, Some(now + Duration::minutes(4)));
+        assert!(violations.is_empty());
+
+        let (_, violations) = resolve(json!({"timestamp": "2026-01-29T10:06:00Z"}));
+        assert_eq!(violations, vec![("future_timestamp".to_string(), Severity::Error)]);
+
+        let (_, violations) = resolve(json!({"timestamp": "2026-01-27T10:00:00Z"}));
+        assert_eq!(violations, vec![("stale_timestamp".to_string(), Severity::Error)]);
+
+        let (_, violations) = resolve(json!({"timestamp": "soon"}));
+        assert_eq!(violations, vec![("invalid_timestamp".to_string(), Severity::Error)]);
+
+        let (time, violations) = resolve(json!({}));
+        assert_eq!(time, None);
+        assert_eq!(violations, vec![("missing_field".to_string(), Severity::Warning)]);
+    }
+}
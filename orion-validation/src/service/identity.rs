@@ -0,0 +1,87 @@
+//! Structural checks of the subscriber and device identities.
+//!
+//! The IMSI prefix must be a registered MCC (an unregistered MNC is only a
+//! warning, the registry listing the networks seen by ORION). An IMEI is
+//! optional; when present it must pass its Luhn check, otherwise a warning
+//! is attached: a bad IMEI does not make the usage unbillable.
+
+use crate::service::model::{Severity, Violation};
+use crate::service::rules::{display, is_present};
+use orion_numbering::{imei, ImsiError, Registry};
+use serde_json::Value;
+
+/// Check the IMSI against the MCC/MNC registry, unless it is already
+/// reported
+pub fn check_imsi(imsi: &str, violations: &mut Vec<Violation>) {
+    if violations.iter().any(|v| v.field == "imsi") {
+        return;
+    }
+    match Registry::bundled().resolve(imsi) {
+        Ok(plmn) if plmn.operator.is_none() => violations.push(Violation::new(
+            "imsi",
+            "unknown_mnc",
+            format!("MNC {} of MCC {} ({}) is not in the registry", plmn.mnc, plmn.mcc, plmn.country),
+            Severity::Warning,
+        )),
+        Ok(_) => {}
+        Err(e @ ImsiError::UnknownMcc(_)) => {
+            violations.push(Violation::new("imsi", "invalid_imsi", format!("Invalid IMSI prefix: {}", e), Severity::Error))
+        }
+        Err(e) => violations.push(Violation::new("imsi", "invalid_imsi", e.to_string(), Severity::Error)),
+    }
+}
+
+/// Check the IMEI of the CDR, if any
+pub fn check_imei(cdr: &Value, violations: &mut Vec<Violation>) {
+    let Some(value) = cdr.get("imei").filter(|value| is_present(value)) else {
+        return;
+    };
+    if violations.iter().any(|v| v.field == "imei") {
+        return;
+    }
+    let message = match value.as_str().map(imei::parse) {
+        Some(Ok(_)) => return,
+        Some(Err(e)) => e.to_string(),
+        None => format!("IMEI must be a string, got: {}", display(value)),
+    };
+    violations.push(Violation::new("imei", "invalid_imei", message, Severity::Warning));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn summary(violations: &[Violation]) -> Vec<(&str, Severity)> {
+        violations.iter().map(|v| (v.error_type.as_str(), v.severity)).collect()
+    }
+
+    #[test]
+    fn test_imsi_prefix() {
+        let mut violations = Vec::new();
+        check_imsi("208150123456789", &mut violations);
+        check_imsi("310260123456789", &mut violations);
+        assert!(violations.is_empty());
+
+        check_imsi("123456789012345", &mut violations);
+        assert_eq!(summary(&violations), vec![("invalid_imsi", Severity::Error)]);
+
+        let mut violations = Vec::new();
+        check_imsi("208990123456789", &mut violations);
+        assert_eq!(summary(&violations), vec![("unknown_mnc", Severity::Warning)]);
+    }
+
+    #[test]
+    fn test_imei_is_a_warning() {
+        let check = |cdr: Value| {
+            let mut violations = Vec::new();
+            check_imei(&cdr, &mut violations);
+            violations
+        };
+        assert!(check(json!({})).is_empty());
+        assert!(check(json!({"imei": "490154203237518"})).is_empty());
+        assert!(check(json!({"imei": "4901542032375101"})).is_empty());
+        assert_eq!(summary(&check(json!({"imei": "490154203237517"}))), vec![("invalid_imei", Severity::Warning)]);
+        assert_eq!(summary(&check(json!({"imei": 490154203237518u64}))), vec![("invalid_imei", Severity::Warning)]);
+    }
+}
@@ -1,78 +1,271 @@
 use rdkafka::config::ClientConfig;
 use rdkafka::consumer::{Consumer, StreamConsumer};
-use rdkafka::message::Message;
-use crate::config::KafkaConfig;
+use rdkafka::message::{BorrowedMessage, Message};
+use rdkafka::Offset;
+use crate::config::{DeliveryMode, KafkaConfig};
+use crate::metrics;
 use crate::service::model::{ProcessedCDR, ValidationResult};
+use crate::service::dedup::Deduplicator;
 use crate::service::validator::Validator;
 use crate::service::kafka_producer::KafkaProducerService;
-use tracing::{info, error, warn};
+use orion_kafka::{OffsetCommitter, TransactionBatch};
+use orion_shutdown::Shutdown;
+use std::time::Duration;
+use tracing::{debug, info, error, warn};
+
+/// Time given to the producer to deliver queued records on shutdown
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct KafkaConsumerService {
     consumer: StreamConsumer,
     validator: Validator,
+    dedup: Deduplicator,
     producer: KafkaProducerService,
+    delivery_mode: DeliveryMode,
+    commit_batch_size: usize,
+    commit_interval: Duration,
 }
 
 impl KafkaConsumerService {
-    pub fn new(config: &KafkaConfig) -> anyhow::Result<Self> {
+    pub fn new(config: &KafkaConfig, validator: Validator, dedup: Deduplicator) -> anyhow::Result<Self> {
+        // Offsets are stored and committed manually once the output is acknowledged
         let consumer: StreamConsumer = ClientConfig::new()
             .set("bootstrap.servers", &config.brokers)
             .set("group.id", &config.consumer_group)
-            .set("enable.auto.commit", "true")
+            .set("enable.auto.commit", "false")
+            .set("enable.auto.offset.store", "false")
+            .set("isolation.level", "read_committed")
             .set("auto.offset.reset", "earliest")
             .create()?;
 
         consumer.subscribe(&[&config.input_topic])?;
 
-        let producer = KafkaProducerService::new(
-            &config.brokers,
-            config.output_topic.clone(),
-            config.rejected_topic.clone(),
-        )?;
+        let producer = KafkaProducerService::new(config)?;
 
         Ok(Self {
             consumer,
-            validator: Validator::new(),
+            validator,
+            dedup,
             producer,
+            delivery_mode: config.delivery_mode,
+            commit_batch_size: config.commit_batch_size,
+            commit_interval: Duration::from_millis(config.commit_interval_ms),
         })
     }
 
-    pub async fn run(self) -> anyhow::Result<()> {
-        info!("Kafka consumer service started");
+    /// Consume until shutdown is requested. The message in hand is
+    /// finished, then its offsets (or open transaction) are committed.
+    pub async fn run(self, shutdown: Shutdown) -> anyhow::Result<()> {
+        info!("Kafka consumer service started ({:?})", self.delivery_mode);
 
+        match self.delivery_mode {
+            DeliveryMode::AtLeastOnce => {
+                let committer = OffsetCommitter::new(self.commit_batch_size, self.commit_interval);
+                self.run_at_least_once(committer, shutdown).await
+            }
+            DeliveryMode::ExactlyOnce => {
+                let batch = TransactionBatch::new(self.commit_batch_size, self.commit_interval);
+                self.run_exactly_once(batch, shutdown).await
+            }
+        }
+    }
+
+    async fn run_at_least_once(
+        &self,
+        mut committer: OffsetCommitter,
+        mut shutdown: Shutdown,
+    ) -> anyhow::Result<()> {
         loop {
-            match self.consumer.recv().await {
-                Ok(message) => {
-                    if let Some(payload) = message.payload() {
-                        match serde_json::from_slice::<ProcessedCDR>(payload) {
-                            Ok(cdr) => {
-                                info!("Received CDR from {} (country: {})", cdr.source_topic, cdr.country);
-                                
-                                match self.validator.validate(&cdr).await {
-                                    ValidationResult::Valid(valid_cdr) => {
-                                        if let Err(e) = self.producer.send_valid(&valid_cdr).await {
-                                            error!("Failed to send valid CDR: {}", e);
-                                        }
-                                    }
-                                    ValidationResult::Invalid(error) => {
-                                        warn!("CDR validation failed: {} - {}", error.error_type, error.message);
-                                        if let Err(e) = self.producer.send_rejected(&error).await {
-                                            error!("Failed to send rejected CDR: {}", e);
-                                        }
-                                    }
-                                }
+            let received = tokio::select! {
+                biased;
+                _ = shutdown.requested() => break,
+                received = tokio::time::timeout(committer.interval(), self.consumer.recv()) => received,
+            };
+            match received {
+                // Idle: flush offsets acknowledged since the last commit
+                Err(_) => {
+                    if let Err(e) = committer.commit(&self.consumer) {
+                        error!("Failed to commit offsets: {:?}", e);
+                    }
+                }
+                Ok(Ok(message)) => {
+                    match self.handle_message(&message).await {
+                        Ok(()) => {
+                            // Published and acknowledged by the broker
+                            self.dedup.commit();
+                            if let Err(e) = committer.ack(
+                                &self.consumer,
+                                message.topic(),
+                                message.partition(),
+                                message.offset(),
+                            ) {
+                                error!("Failed to store offset: {:?}", e);
                             }
-                            Err(e) => {
-                                error!("Failed to deserialize ProcessedCDR: {}", e);
+                        }
+                        Err(e) => {
+                            // Output not acknowledged: rewind so the message is redelivered
+                            error!("Failed to publish validation result, retrying: {}", e);
+                            tokio::time::sleep(Duration::from_secs(1)).await;
+                            self.rewind(message.topic(), message.partition(), message.offset());
+                        }
+                    }
+                }
+                Ok(Err(e)) => {
+                    error!("Kafka consumer error: {:?}", e);
+                    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                }
+            }
+        }
+
+        info!("Kafka consumer stopped, committing final offsets");
+        if let Err(e) = committer.commit_sync(&self.consumer) {
+            error!("Failed to commit final offsets: {:?}", e);
+        }
+        if let Err(e) = self.producer.flush(FLUSH_TIMEOUT) {
+            error!("Failed to flush producer: {:?}", e);
+        }
+        Ok(())
+    }
+
+    async fn run_exactly_once(
+        &self,
+        mut batch: TransactionBatch,
+        mut shutdown: Shutdown,
+    ) -> anyhow::Result<()> {
+        loop {
+            let received = tokio::select! {
+                biased;
+                _ = shutdown.requested() => break,
+                received = tokio::time::timeout(batch.interval(), self.consumer.recv()) => received,
+            };
+            match received {
+                // Idle: commit the transaction opened before the pause
+                Err(_) => {
+                    if batch.is_open() {
+                        self.commit_transaction(&mut batch).await?;
+                    }
+                }
+                Ok(Ok(message)) => {
+                    if !batch.is_open() {
+                        if let Err(e) = self.producer.begin_transaction() {
+                            error!("Failed to begin transaction: {:?}", e);
+                            tokio::time::sleep(Duration::from_secs(1)).await;
+                            self.rewind(message.topic(), message.partition(), message.offset());
+                            continue;
+                        }
+                        batch.open();
+                    }
+                    batch.record(message.topic(), message.partition(), message.offset());
+
+                    match self.handle_message(&message).await {
+                        Ok(()) => {
+                            if batch.should_commit() {
+                                self.commit_transaction(&mut batch).await?;
                             }
                         }
+                        Err(e) => {
+                            error!("Failed to publish validation result, aborting transaction: {}", e);
+                            self.abort_transaction(&mut batch).await?;
+                        }
                     }
                 }
-                Err(e) => {
+                Ok(Err(e)) => {
                     error!("Kafka consumer error: {:?}", e);
                     tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
                 }
             }
         }
+
+        info!("Kafka consumer stopped, committing the open transaction");
+        if batch.is_open() {
+            self.commit_transaction(&mut batch).await?;
+        }
+        Ok(())
+    }
+
+    /// Commit the outputs of the batch together with its input offsets.
+    /// A failed commit aborts the transaction so that the batch is replayed.
+    async fn commit_transaction(&self, batch: &mut TransactionBatch) -> anyhow::Result<()> {
+        let result = batch
+            .commit_offsets()
+            .map_err(anyhow::Error::from)
+            .and_then(|offsets| self.producer.commit_transaction(&self.consumer, &offsets));
+
+        match result {
+            Ok(()) => {
+                debug!("Committed transaction of {} messages", batch.messages());
+                self.dedup.commit();
+                batch.reset();
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to commit transaction: {}", e);
+                self.abort_transaction(batch).await
+            }
+        }
+    }
+
+    /// Abort the open transaction and rewind the consumer to replay it.
+    /// Fails only when the producer can no longer be used (e.g. fenced by
+    /// another instance with the same transactional id).
+    async fn abort_transaction(&self, batch: &mut TransactionBatch) -> anyhow::Result<()> {
+        self.producer
+            .abort_transaction()
+            .map_err(|e| anyhow::anyhow!("Failed to abort transaction: {:?}", e))?;
+        self.dedup.rollback();
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        for (topic, partition, offset) in batch.rewind_offsets() {
+            self.rewind(topic, partition, offset);
+        }
+        batch.reset();
+        Ok(())
+    }
+
+    fn rewind(&self, topic: &str, partition: i32, offset: i64) {
+        if let Err(e) = self.consumer.seek(
+            topic,
+            partition,
+            Offset::Offset(offset),
+            Duration::from_secs(5),
+        ) {
+            error!("Failed to rewind partition {}: {:?}", partition, e);
+        }
+    }
+
+    /// Validate one message and publish the result. Returns an error only
+    /// when the output could not be published; undecodable messages are
+    /// logged and considered handled.
+    async fn handle_message(&self, message: &BorrowedMessage<'_>) -> anyhow::Result<()> {
+        let Some(payload) = message.payload() else {
+            return Ok(());
+        };
+
+        let cdr = match orion_model::from_slice::<ProcessedCDR>(payload) {
+            Ok(cdr) => cdr,
+            Err(e) => {
+                error!("Failed to deserialize ProcessedCDR: {}", e);
+                return Ok(());
+            }
+        };
+
+        info!("Received CDR from {} (country: {})", cdr.source_topic, cdr.country);
+
+        match self.validator.validate(&cdr).await {
+            ValidationResult::Valid(valid_cdr) => {
+                if self.dedup.is_duplicate(&valid_cdr.cdr_id) {
+                    debug!("Dropping duplicate CDR {}", valid_cdr.cdr_id);
+                    metrics::increment_duplicates_total();
+                    return Ok(());
+                }
+                self.producer.send_valid(&valid_cdr).await?;
+                self.dedup.record(&valid_cdr.cdr_id);
+                Ok(())
+            }
+            ValidationResult::Invalid(error) => {
+                warn!("CDR validation failed: {} - {}", error.error_type, error.message);
+                self.producer.send_rejected(&error).await
+            }
+        }
     }
 }
@@ -1,10 +1,17 @@
 use rdkafka::config::ClientConfig;
-use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::consumer::Consumer;
+use rdkafka::error::KafkaResult;
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
 use rdkafka::util::Timeout;
+use rdkafka::TopicPartitionList;
+use crate::config::{DeliveryMode, KafkaConfig};
 use crate::service::model::{ValidatedCDR, ValidationError};
 use std::time::Duration;
 use tracing::{info, error};
 
+/// Timeout of the blocking transaction API calls
+const TRANSACTION_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct KafkaProducerService {
     producer: FutureProducer,
     output_topic: String,
@@ -12,21 +19,60 @@ pub struct KafkaProducerService {
 }
 
 impl KafkaProducerService {
-    pub fn new(brokers: &str, output_topic: String, rejected_topic: String) -> anyhow::Result<Self> {
-        let producer: FutureProducer = ClientConfig::new()
-            .set("bootstrap.servers", brokers)
-            .set("message.timeout.ms", "5000")
-            .create()?;
+    pub fn new(config: &KafkaConfig) -> anyhow::Result<Self> {
+        let mut client_config = ClientConfig::new();
+        client_config
+            .set("bootstrap.servers", &config.brokers)
+            .set("message.timeout.ms", "5000");
+
+        if config.delivery_mode == DeliveryMode::ExactlyOnce {
+            client_config
+                .set("transactional.id", &config.transactional_id)
+                .set("transaction.timeout.ms", config.transaction_timeout_ms.to_string());
+        }
+
+        let producer: FutureProducer = client_config.create()?;
+
+        if config.delivery_mode == DeliveryMode::ExactlyOnce {
+            // Fences any previous instance using the same transactional id
+            producer.init_transactions(TRANSACTION_TIMEOUT)?;
+            info!("Transactional producer initialized ({})", config.transactional_id);
+        }
 
         Ok(Self {
             producer,
-            output_topic,
-            rejected_topic,
+            output_topic: config.output_topic.clone(),
+            rejected_topic: config.rejected_topic.clone(),
         })
     }
 
+    pub fn begin_transaction(&self) -> KafkaResult<()> {
+        self.producer.begin_transaction()
+    }
+
+    /// Add the consumed offsets to the current transaction and commit it,
+    /// making its outputs visible to `read_committed` consumers
+    pub fn commit_transaction<C: Consumer>(
+        &self,
+        consumer: &C,
+        offsets: &TopicPartitionList,
+    ) -> anyhow::Result<()> {
+        let group_metadata = consumer
+            .group_metadata()
+            .ok_or_else(|| anyhow::anyhow!("Consumer group metadata unavailable"))?;
+
+        self.producer
+            .send_offsets_to_transaction(offsets, &group_metadata, TRANSACTION_TIMEOUT)?;
+        self.producer.commit_transaction(TRANSACTION_TIMEOUT)?;
+        Ok(())
+    }
+
+    pub fn abort_transaction(&self) -> KafkaResult<()> {
+        self.producer.abort_transaction(TRANSACTION_TIMEOUT)
+    }
+
     pub async fn send_valid(&self, cdr: &ValidatedCDR) -> anyhow::Result<()> {
-        let payload = serde_json::to_string(cdr)?;
+        let payload = orion_model::to_json(cdr)?;
         let key = cdr.cdr_id.as_bytes();
 
         let record = FutureRecord::to(&self.output_topic)
@@ -50,7 +96,7 @@ impl KafkaProducerService {
 
     pub async fn send_rejected(&self, error: &ValidationError) -> anyhow::Result<()> {
         let payload = serde_json::to_string(error)?;
-        let key = error.timestamp.as_bytes();
+        let key = error.rejection_id.as_bytes();
 
         let record = FutureRecord::to(&self.rejected_topic)
             .key(key)
@@ -70,4 +116,9 @@ impl KafkaProducerService {
             }
         }
     }
+
+    /// Wait for every queued record to be delivered, before shutdown
+    pub fn flush(&self, timeout: Duration) -> KafkaResult<()> {
+        self.producer.flush(Timeout::After(timeout))
+    }
 }
@@ -1,6 +1,24 @@
+mod cdr_id;
+mod dedup;
+mod event_time;
+mod identity;
 mod kafka_consumer;
 mod kafka_producer;
 mod model;
+mod rejection_consumer;
+mod rejection_store;
+mod resubmitter;
+mod rule_store;
+mod rules;
+mod semantic;
 mod validator;
 
+pub use dedup::Deduplicator;
+pub use event_time::EventTimePolicy;
 pub use kafka_consumer::KafkaConsumerService;
+pub use rejection_consumer::RejectionConsumer;
+pub use rejection_store::{RejectionQuery, RejectionStore};
+pub use resubmitter::{Fix, ResubmitError, Resubmitter};
+pub use rule_store::RuleStore;
+pub use semantic::SemanticChecker;
+pub use validator::Validator;
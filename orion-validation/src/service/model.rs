@@ -1,37 +1,8 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use chrono::Utc;
+use uuid::Uuid;
 
-/// CDR processed by ingestion service
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProcessedCDR {
-    pub raw_payload: String,
-    pub source_topic: String,
-    pub country: String,
-    pub ingestion_timestamp: String,
-}
-
-/// Validated CDR ready for normalization
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ValidatedCDR {
-    pub cdr_id: String,
-    pub event_type: EventType,
-    pub imsi: String,
-    pub msisdn: String,
-    pub timestamp: DateTime<Utc>,
-    pub country: String,
-    pub raw_data: serde_json::Value,
-    pub validation_timestamp: String,
-}
-
-/// Event type classification
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
-pub enum EventType {
-    Voice,
-    Data,
-    Sms,
-    Unknown,
-}
+pub use orion_model::{EventType, ProcessedCDR, Severity, ValidatedCDR, Violation};
 
 /// Validation result
 #[derive(Debug)]
@@ -40,12 +11,24 @@ pub enum ValidationResult {
     Invalid(ValidationError),
 }
 
-/// Validation error with details
+/// Validation error with details. `error_type`, `message` and `field`
+/// describe the first violation; `violations` lists all of them.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValidationError {
+    /// Identifies the rejection in the rejection store
+    #[serde(default)]
+    pub rejection_id: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub source_topic: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_system: Option<String>,
     pub error_type: String,
     pub message: String,
     pub field: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<Violation>,
     pub original_cdr: String,
     pub timestamp: String,
 }
@@ -53,13 +36,49 @@ pub struct ValidationError {
 impl ValidationError {
     pub fn new(error_type: &str, message: String, field: Option<String>, original_cdr: String) -> Self {
         Self {
+            rejection_id: Uuid::new_v4().to_string(),
+            country: String::new(),
+            source_topic: String::new(),
+            source_system: None,
             error_type: error_type.to_string(),
             message,
             field,
+            violations: Vec::new(),
+            original_cdr,
+            timestamp: Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Rejection of a CDR breaking one or more rules. The first error, not
+    /// warning, is the reason reported in `error_type`.
+    pub fn from_violations(violations: Vec<Violation>, original_cdr: String) -> Self {
+        let first = violations
+            .iter()
+            .find(|v| v.severity == Severity::Error)
+            .or(violations.first())
+            .cloned()
+            .unwrap_or_else(|| Violation::new("", "invalid", "CDR rejected".to_string(), Severity::Error));
+        Self {
+            rejection_id: Uuid::new_v4().to_string(),
+            country: String::new(),
+            source_topic: String::new(),
+            source_system: None,
+            error_type: first.error_type,
+            message: first.message,
+            field: Some(first.field).filter(|field| !field.is_empty()),
+            violations,
             original_cdr,
             timestamp: Utc::now().to_rfc3339(),
         }
     }
+
+    /// Origin of the rejected CDR, kept to resubmit it
+    pub fn with_source(mut self, cdr: &ProcessedCDR) -> Self {
+        self.country = cdr.country.clone();
+        self.source_topic = cdr.source_topic.clone();
+        self.source_system = cdr.source_system.clone();
+        self
+    }
 }
 
 #[cfg(test)]
@@ -86,4 +105,25 @@ mod tests {
         assert_eq!(error.message, "IMSI is required");
         assert_eq!(error.field, Some("imsi".to_string()));
     }
+
+    #[test]
+    fn test_validation_error_from_violations() {
+        let violation = |field: &str, error_type: &str| Violation {
+            field: field.to_string(),
+            error_type: error_type.to_string(),
+            message: format!("{} is wrong", field),
+            rule_set: Some("subscriber".to_string()),
+            severity: Severity::Error,
+        };
+        let error = ValidationError::from_violations(
+            vec![violation("imsi", "invalid_imsi"), violation("msisdn", "missing_field")],
+            "{}".to_string(),
+        );
+
+        assert_eq!(error.error_type, "invalid_imsi");
+        assert_eq!(error.field, Some("imsi".to_string()));
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["violations"].as_array().unwrap().len(), 2);
+        assert_eq!(json["violations"][1]["rule_set"], "subscriber");
+    }
 }
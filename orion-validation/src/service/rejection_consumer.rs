@@ -0,0 +1,117 @@
+use crate::config::KafkaConfig;
+use crate::metrics;
+use crate::service::model::ValidationError;
+use crate::service::rejection_store::RejectionStore;
+use orion_kafka::OffsetCommitter;
+use orion_shutdown::Shutdown;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::message::Message;
+use rdkafka::Offset;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info};
+
+/// Persists the records of the rejected topic into the rejection store
+pub struct RejectionConsumer {
+    consumer: StreamConsumer,
+    store: Arc<RejectionStore>,
+    committer: OffsetCommitter,
+}
+
+impl RejectionConsumer {
+    pub fn new(config: &KafkaConfig, group_id: &str, store: Arc<RejectionStore>) -> anyhow::Result<Self> {
+        // Only committed rejections, offsets committed once stored
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("group.id", group_id)
+            .set("enable.auto.commit", "false")
+            .set("enable.auto.offset.store", "false")
+            .set("isolation.level", "read_committed")
+            .set("auto.offset.reset", "earliest")
+            .create()?;
+
+        consumer.subscribe(&[&config.rejected_topic])?;
+        info!("Rejection store consumer subscribed to {}", config.rejected_topic);
+
+        Ok(Self {
+            consumer,
+            store,
+            committer: OffsetCommitter::new(
+                config.commit_batch_size,
+                Duration::from_millis(config.commit_interval_ms),
+            ),
+        })
+    }
+
+    pub async fn run(mut self, mut shutdown: Shutdown) -> anyhow::Result<()> {
+        loop {
+            let received = tokio::select! {
+                biased;
+                _ = shutdown.requested() => break,
+                received = tokio::time::timeout(self.committer.interval(), self.consumer.recv()) => received,
+            };
+            match received {
+                // Idle: flush offsets acknowledged since the last commit
+                Err(_) => {
+                    if let Err(e) = self.committer.commit(&self.consumer) {
+                        error!("Failed to commit rejection offsets: {:?}", e);
+                    }
+                }
+                Ok(Ok(message)) => {
+                    let rejection = message
+                        .payload()
+                        .map(serde_json::from_slice::<ValidationError>);
+                    let stored = match rejection {
+                        Some(Ok(rejection)) => self.store.append(&rejection).map(|()| {
+                            debug!("Stored rejection {}", rejection.rejection_id);
+                            metrics::increment_rejections_stored_total();
+                        }),
+                        // Not a rejection record: nothing to keep
+                        Some(Err(e)) => {
+                            error!("Failed to deserialize rejection: {}", e);
+                            Ok(())
+                        }
+                        None => Ok(()),
+                    };
+
+                    match stored {
+                        Ok(()) => {
+                            if let Err(e) = self.committer.ack(
+                                &self.consumer,
+                                message.topic(),
+                                message.partition(),
+                                message.offset(),
+                            ) {
+                                error!("Failed to store rejection offset: {:?}", e);
+                            }
+                        }
+                        Err(e) => {
+                            // Not stored: rewind so the message is redelivered
+                            error!("Failed to store rejection, retrying: {:?}", e);
+                            tokio::time::sleep(Duration::from_secs(1)).await;
+                            if let Err(e) = self.consumer.seek(
+                                message.topic(),
+                                message.partition(),
+                                Offset::Offset(message.offset()),
+                                Duration::from_secs(5),
+                            ) {
+                                error!("Failed to rewind partition {}: {:?}", message.partition(), e);
+                            }
+                        }
+                    }
+                }
+                Ok(Err(e)) => {
+                    error!("Rejection consumer error: {:?}", e);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+
+        info!("Rejection consumer stopped, committing final offsets");
+        if let Err(e) = self.committer.commit_sync(&self.consumer) {
+            error!("Failed to commit final rejection offsets: {:?}", e);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,532 @@
+//! Rejected CDRs, persisted to local files.
+//!
+//! Rejections are appended to one JSON Lines file per day
+//! (`rejections-YYYY-MM-DD.jsonl`); files older than the retention are
+//! deleted when a new day starts. Resubmissions are appended to
+//! `resubmissions.jsonl`, so the rejection files are never rewritten.
+//!
+//! The position of each rejection and the resubmission times are indexed
+//! in memory: a rejection is read by seeking to its line, and listings read
+//! the day files backwards, newest first, up to their limit. The index
+//! catches up with lines appended by other instances sharing the directory
+//! when an id is not found and before a listing. Reads block on the file
+//! system: async callers run them with `spawn_blocking`.
+
+use crate::service::model::ValidationError;
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+const RESUBMISSIONS_FILE: &str = "resubmissions.jsonl";
+
+/// Bytes read at once when reading a day file backwards
+const READ_CHUNK: usize = 64 * 1024;
+
+/// Rejection as listed by the API
+#[derive(Debug, Clone, Serialize)]
+pub struct Rejection {
+    #[serde(flatten)]
+    pub error: ValidationError,
+    /// Last time the record was fixed and resubmitted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resubmitted_at: Option<String>,
+}
+
+/// Filters of `GET /rejections`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RejectionQuery {
+    pub error_type: Option<String>,
+    pub country: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Only the records not resubmitted yet
+    #[serde(default)]
+    pub pending: bool,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Resubmission {
+    rejection_id: String,
+    resubmitted_at: String,
+}
+
+/// Id of a stored rejection, read without the rest of the record
+#[derive(Deserialize)]
+struct RejectionId {
+    rejection_id: String,
+}
+
+/// Line of a rejection: day file and byte offset
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    day: NaiveDate,
+    offset: u64,
+}
+
+#[derive(Default)]
+struct Index {
+    positions: HashMap<String, Position>,
+    /// Bytes of each day file already indexed
+    indexed: HashMap<NaiveDate, u64>,
+    /// Last resubmission time by rejection id
+    resubmissions: HashMap<String, String>,
+    /// Bytes of the resubmissions file already indexed
+    resubmissions_indexed: u64,
+    /// Day of the last append, to prune once per day
+    last_day: Option<NaiveDate>,
+}
+
+pub struct RejectionStore {
+    dir: PathBuf,
+    retention_days: u64,
+    index: Mutex<Index>,
+}
+
+impl RejectionStore {
+    /// Most records returned by a listing
+    pub const MAX_LIMIT: usize = 1000;
+
+    pub fn open(dir: impl Into<PathBuf>, retention_days: u64) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create rejection directory {}", dir.display()))?;
+        let store = Self {
+            dir,
+            retention_days,
+            index: Mutex::new(Index::default()),
+        };
+        store.refresh(&mut store.index())?;
+        Ok(store)
+    }
+
+    pub fn append(&self, rejection: &ValidationError) -> anyhow::Result<()> {
+        let day = rejected_at(rejection).unwrap_or_else(Utc::now).date_naive();
+        let mut index = self.index();
+        self.rotate(day, &mut index);
+
+        let path = self.day_file(day);
+        let offset = fs::metadata(&path).map_or(0, |metadata| metadata.len());
+        let written = append_line(&path, rejection)?;
+        index.positions.insert(rejection.rejection_id.clone(), Position { day, offset });
+        // Right after the indexed lines: no need to read it again
+        if index.indexed.get(&day).copied().unwrap_or(0) == offset {
+            index.indexed.insert(day, offset + written);
+        }
+        Ok(())
+    }
+
+    /// Rejections matching the query, newest first
+    pub fn list(&self, query: &RejectionQuery) -> anyhow::Result<Vec<Rejection>> {
+        let limit = query.limit.unwrap_or(100).min(Self::MAX_LIMIT);
+        self.refresh(&mut self.index())?;
+        let mut found = Vec::new();
+
+        for (day, path) in self.day_files()?.into_iter().rev() {
+            if query.from.is_some_and(|from| day < from.date_naive())
+                || query.to.is_some_and(|to| day > to.date_naive())
+            {
+                continue;
+            }
+
+            let file = fs::File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+            for line in ReverseLines::new(file, READ_CHUNK)? {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let error: ValidationError = match serde_json::from_str(&line) {
+                    Ok(error) => error,
+                    Err(e) => {
+                        warn!("Skipping unreadable line of {}: {}", path.display(), e);
+                        continue;
+                    }
+                };
+                let matches = query.error_type.as_ref().is_none_or(|t| error.error_type == *t
+                    || error.violations.iter().any(|v| v.error_type == *t))
+                    && query.country.as_ref().is_none_or(|c| error.country.eq_ignore_ascii_case(c))
+                    && within(&error, query.from, query.to);
+                if !matches {
+                    continue;
+                }
+                let resubmitted_at = self.index().resubmissions.get(&error.rejection_id).cloned();
+                if query.pending && resubmitted_at.is_some() {
+                    continue;
+                }
+                found.push(Rejection { error, resubmitted_at });
+                if found.len() >= limit {
+                    return Ok(found);
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    pub fn get(&self, rejection_id: &str) -> anyhow::Result<Option<Rejection>> {
+        let (position, resubmitted_at) = {
+            let mut index = self.index();
+            if index.positions.contains_key(rejection_id) {
+                self.refresh_resubmissions(&mut index)?;
+            } else {
+                self.refresh(&mut index)?;
+            }
+            let Some(position) = index.positions.get(rejection_id).copied() else {
+                return Ok(None);
+            };
+            (position, index.resubmissions.get(rejection_id).cloned())
+        };
+
+        let path = self.day_file(position.day);
+        let mut file = match fs::File::open(&path) {
+            Ok(file) => file,
+            // Deleted by the retention since it was indexed
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to open {}", path.display())),
+        };
+        file.seek(SeekFrom::Start(position.offset))?;
+        let mut line = String::new();
+        BufReader::new(file).read_line(&mut line)?;
+        let error: ValidationError = serde_json::from_str(&line)
+            .with_context(|| format!("Unreadable rejection {} in {}", rejection_id, path.display()))?;
+        Ok(Some(Rejection { error, resubmitted_at }))
+    }
+
+    pub fn mark_resubmitted(&self, rejection_id: &str) -> anyhow::Result<String> {
+        let resubmitted_at = Utc::now().to_rfc3339();
+        let mut index = self.index();
+        let path = self.dir.join(RESUBMISSIONS_FILE);
+        let offset = fs::metadata(&path).map_or(0, |metadata| metadata.len());
+        let written = append_line(
+            &path,
+            &Resubmission {
+                rejection_id: rejection_id.to_string(),
+                resubmitted_at: resubmitted_at.clone(),
+            },
+        )?;
+        index.resubmissions.insert(rejection_id.to_string(), resubmitted_at.clone());
+        if index.resubmissions_indexed == offset {
+            index.resubmissions_indexed = offset + written;
+        }
+        Ok(resubmitted_at)
+    }
+
+    /// Index the lines appended since the last refresh, by this instance or
+    /// another one
+    fn refresh(&self, index: &mut Index) -> anyhow::Result<()> {
+        for (day, path) in self.day_files()? {
+            let from = index.indexed.get(&day).copied().unwrap_or(0);
+            let positions = &mut index.positions;
+            let end = scan_lines(&path, from, |offset, line| {
+                if let Ok(record) = serde_json::from_str::<RejectionId>(line) {
+                    positions.insert(record.rejection_id, Position { day, offset });
+                }
+            })?;
+            index.indexed.insert(day, end);
+        }
+        self.refresh_resubmissions(index)
+    }
+
+    fn refresh_resubmissions(&self, index: &mut Index) -> anyhow::Result<()> {
+        let path = self.dir.join(RESUBMISSIONS_FILE);
+        if path.exists() {
+            let resubmissions = &mut index.resubmissions;
+            index.resubmissions_indexed = scan_lines(&path, index.resubmissions_indexed, |_, line| {
+                match serde_json::from_str::<Resubmission>(line) {
+                    Ok(r) => {
+                        resubmissions.insert(r.rejection_id, r.resubmitted_at);
+                    }
+                    Err(e) => warn!("Skipping unreadable line of {}: {}", path.display(), e),
+                }
+            })?;
+        }
+        Ok(())
+    }
+
+    fn index(&self) -> std::sync::MutexGuard<'_, Index> {
+        self.index.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn day_file(&self, day: NaiveDate) -> PathBuf {
+        self.dir.join(format!("rejections-{}.jsonl", day.format("%Y-%m-%d")))
+    }
+
+    /// Rejection files by day, oldest first
+    fn day_files(&self) -> anyhow::Result<Vec<(NaiveDate, PathBuf)>> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let day = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("rejections-")?.strip_suffix(".jsonl"))
+                .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok());
+            if let Some(day) = day {
+                files.push((day, path));
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Delete the day files past retention when the day changes
+    fn rotate(&self, day: NaiveDate, index: &mut Index) {
+        if index.last_day == Some(day) || self.retention_days == 0 {
+            index.last_day = Some(day);
+            return;
+        }
+        index.last_day = Some(day);
+
+        let Some(oldest) = day.checked_sub_days(chrono::Days::new(self.retention_days)) else {
+            return;
+        };
+        match self.day_files() {
+            Ok(files) => {
+                for (file_day, path) in files.into_iter().filter(|(file_day, _)| *file_day < oldest) {
+                    match fs::remove_file(&path) {
+                        Ok(()) => info!("Deleted rejections of {} (retention)", file_day),
+                        Err(e) => warn!("Failed to delete {}: {}", path.display(), e),
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to list rejection files: {:?}", e),
+        }
+        index.positions.retain(|_, position| position.day >= oldest);
+        index.indexed.retain(|indexed_day, _| *indexed_day >= oldest);
+        let Index { positions, resubmissions, .. } = index;
+        resubmissions.retain(|rejection_id, _| positions.contains_key(rejection_id));
+    }
+}
+
+/// Lines of a file from the last to the first, read backwards by chunks
+struct ReverseLines {
+    file: fs::File,
+    /// Start in the file of `pending`
+    position: u64,
+    /// Bytes read and not returned yet, from `position` on
+    pending: Vec<u8>,
+    chunk: usize,
+}
+
+impl ReverseLines {
+    fn new(mut file: fs::File, chunk: usize) -> io::Result<Self> {
+        let position = file.seek(SeekFrom::End(0))?;
+        Ok(Self { file, position, pending: Vec::new(), chunk })
+    }
+}
+
+impl Iterator for ReverseLines {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(newline) = self.pending.iter().rposition(|&b| b == b'\n') {
+                let line = String::from_utf8_lossy(&self.pending[newline + 1..]).into_owned();
+                self.pending.truncate(newline);
+                return Some(Ok(line));
+            }
+            if self.position == 0 {
+                if self.pending.is_empty() {
+                    return None;
+                }
+                let line = String::from_utf8_lossy(&self.pending).into_owned();
+                self.pending.clear();
+                return Some(Ok(line));
+            }
+
+            let size = self.chunk.min(self.position as usize);
+            self.position -= size as u64;
+            let mut chunk = vec![0; size];
+            let read = self
+                .file
+                .seek(SeekFrom::Start(self.position))
+                .and_then(|_| self.file.read_exact(&mut chunk));
+            if let Err(e) = read {
+                self.position = 0;
+                self.pending.clear();
+                return Some(Err(e));
+            }
+            chunk.append(&mut self.pending);
+            self.pending = chunk;
+        }
+    }
+}
+
+fn rejected_at(error: &ValidationError) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&error.timestamp)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+fn within(error: &ValidationError, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> bool {
+    if from.is_none() && to.is_none() {
+        return true;
+    }
+    let Some(at) = rejected_at(error) else { return false };
+    from.is_none_or(|from| at >= from) && to.is_none_or(|to| at <= to)
+}
+
+/// Append a JSON line, returning its length in bytes
+fn append_line<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<u64> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(&line)?;
+    Ok(line.len() as u64)
+}
+
+/// Complete lines of a file from byte `from` on, with their offset.
+/// Returns the offset after the last complete line: a line still being
+/// written is read by the next scan.
+fn scan_lines(path: &Path, from: u64, mut each: impl FnMut(u64, &str)) -> anyhow::Result<u64> {
+    let mut file = fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    file.seek(SeekFrom::Start(from))?;
+    let mut reader = BufReader::new(file);
+    let mut line = Vec::new();
+    let mut offset = from;
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 || line.last() != Some(&b'\n') {
+            return Ok(offset);
+        }
+        each(offset, &String::from_utf8_lossy(&line));
+        offset += read as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::model::ProcessedCDR;
+
+    fn rejection(error_type: &str, country: &str, timestamp: &str) -> ValidationError {
+        let cdr = ProcessedCDR {
+            raw_payload: r#"{"imsi":"123"}"#.to_string(),
+            source_topic: format!("cdr.raw.{}", country),
+            country: country.to_string(),
+            source_system: None,
+            origin: None,
+            ingestion_timestamp: timestamp.to_string(),
+        };
+        let mut error = ValidationError::new(error_type, "wrong".to_string(), None, cdr.raw_payload.clone())
+            .with_source(&cdr);
+        error.timestamp = timestamp.to_string();
+        error
+    }
+
+    #[test]
+    fn test_list_filters_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RejectionStore::open(dir.path(), 0).unwrap();
+        store.append(&rejection("invalid_imsi", "FR", "2026-01-28T10:00:00Z")).unwrap();
+        store.append(&rejection("invalid_imsi", "TN", "2026-01-29T09:00:00Z")).unwrap();
+        store.append(&rejection("missing_field", "FR", "2026-01-29T10:00:00Z")).unwrap();
+        store.append(&rejection("invalid_imsi", "FR", "2026-01-29T11:00:00Z")).unwrap();
+
+        let all = store.list(&RejectionQuery::default()).unwrap();
+        let times: Vec<&str> = all.iter().map(|r| r.error.timestamp.as_str()).collect();
+        assert_eq!(
+            times,
+            vec!["2026-01-29T11:00:00Z", "2026-01-29T10:00:00Z", "2026-01-29T09:00:00Z", "2026-01-28T10:00:00Z"]
+        );
+
+        let query = RejectionQuery {
+            error_type: Some("invalid_imsi".to_string()),
+            country: Some("fr".to_string()),
+            from: "2026-01-29T00:00:00Z".parse().ok(),
+            ..Default::default()
+        };
+        let found = store.list(&query).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].error.timestamp, "2026-01-29T11:00:00Z");
+    }
+
+    #[test]
+    fn test_resubmitted_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RejectionStore::open(dir.path(), 0).unwrap();
+        let first = rejection("invalid_imsi", "FR", "2026-01-29T09:00:00Z");
+        store.append(&first).unwrap();
+        store.append(&rejection("invalid_imsi", "FR", "2026-01-29T10:00:00Z")).unwrap();
+
+        assert!(store.get(&first.rejection_id).unwrap().unwrap().resubmitted_at.is_none());
+        store.mark_resubmitted(&first.rejection_id).unwrap();
+        assert!(store.get(&first.rejection_id).unwrap().unwrap().resubmitted_at.is_some());
+        assert!(store.get("unknown").unwrap().is_none());
+
+        let pending = store.list(&RejectionQuery { pending: true, ..Default::default() }).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_ne!(pending[0].error.rejection_id, first.rejection_id);
+    }
+
+    #[test]
+    fn test_index_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RejectionStore::open(dir.path(), 0).unwrap();
+        let first = rejection("invalid_imsi", "FR", "2026-01-28T10:00:00Z");
+        let second = rejection("missing_field", "TN", "2026-01-28T11:00:00Z");
+        store.append(&first).unwrap();
+        store.append(&second).unwrap();
+        store.mark_resubmitted(&second.rejection_id).unwrap();
+        drop(store);
+
+        let store = RejectionStore::open(dir.path(), 0).unwrap();
+        let found = store.get(&second.rejection_id).unwrap().unwrap();
+        assert_eq!(found.error.country, "TN");
+
+        // Appended by another instance sharing the directory
+        let other = RejectionStore::open(dir.path(), 0).unwrap();
+        let third = rejection("invalid_imsi", "CH", "2026-01-28T12:00:00Z");
+        other.append(&third).unwrap();
+        other.mark_resubmitted(&first.rejection_id).unwrap();
+        assert_eq!(store.get(&third.rejection_id).unwrap().unwrap().error.country, "CH");
+        assert!(store.get(&first.rejection_id).unwrap().unwrap().resubmitted_at.is_some());
+        assert!(found.resubmitted_at.is_some());
+        assert_eq!(store.get(&first.rejection_id).unwrap().unwrap().error.country, "FR");
+
+        // Listing stops at the limit, newest first
+        let query = RejectionQuery { limit: Some(2), ..Default::default() };
+        let listed = store.list(&query).unwrap();
+        let countries: Vec<&str> = listed.iter().map(|r| r.error.country.as_str()).collect();
+        assert_eq!(countries, vec!["CH", "TN"]);
+    }
+
+    #[test]
+    fn test_reverse_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lines");
+        for content in ["first\nsecond\nthird\n", "first\nsecond\nthird"] {
+            fs::write(&path, content).unwrap();
+            let lines: Vec<String> = ReverseLines::new(fs::File::open(&path).unwrap(), 4)
+                .unwrap()
+                .map(Result::unwrap)
+                .filter(|line| !line.is_empty())
+                .collect();
+            assert_eq!(lines, vec!["third", "second", "first"]);
+        }
+        fs::write(&path, "").unwrap();
+        assert_eq!(ReverseLines::new(fs::File::open(&path).unwrap(), 4).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_retention_deletes_old_days() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RejectionStore::open(dir.path(), 2).unwrap();
+        store.append(&rejection("invalid_imsi", "FR", "2026-01-20T10:00:00Z")).unwrap();
+        store.append(&rejection("invalid_imsi", "FR", "2026-01-29T10:00:00Z")).unwrap();
+
+        let days: Vec<NaiveDate> = store.day_files().unwrap().into_iter().map(|(day, _)| day).collect();
+        assert_eq!(days, vec![NaiveDate::from_ymd_opt(2026, 1, 29).unwrap()]);
+        assert_eq!(store.index().positions.len(), 1);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::metrics;
+use crate::service::model::{ProcessedCDR, ValidationError};
+use crate::service::rejection_store::RejectionStore;
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+/// Correction of a rejected CDR before it is resubmitted. Without either,
+/// the original payload is resubmitted as is (e.g. after a rules fix).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Fix {
+    /// Replacement payload, JSON or raw text
+    pub payload: Option<Value>,
+    /// Fields set on the payload, removed when `null`
+    #[serde(default)]
+    pub patch: Map<String, Value>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ResubmitError {
+    #[error("Rejection {0} not found")]
+    NotFound(String),
+    #[error("{0}")]
+    InvalidFix(String),
+    #[error(transparent)]
+    Failed(#[from] anyhow::Error),
+}
+
+/// Sends fixed rejected CDRs back to the validation input topic, so they
+/// go through the whole pipeline again
+pub struct Resubmitter {
+    producer: FutureProducer,
+    input_topic: String,
+    store: Arc<RejectionStore>,
+}
+
+impl Resubmitter {
+    pub fn new(brokers: &str, input_topic: &str, store: Arc<RejectionStore>) -> anyhow::Result<Self> {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("message.timeout.ms", "5000")
+            .create()?;
+
+        Ok(Self {
+            producer,
+            input_topic: input_topic.to_string(),
+            store,
+        })
+    }
+
+    /// Resubmit a rejected CDR, returning when it was resubmitted
+    pub async fn resubmit(&self, rejection_id: &str, fix: &Fix) -> Result<String, ResubmitError> {
+        let store = self.store.clone();
+        let id = rejection_id.to_string();
+        let rejection = tokio::task::spawn_blocking(move || store.get(&id))
+            .await
+            .map_err(anyhow::Error::from)??
+            .ok_or_else(|| ResubmitError::NotFound(rejection_id.to_string()))?;
+
+        let cdr = resubmitted_cdr(&rejection.error, fix)?;
+
+        let payload = orion_model::to_json(&cdr).map_err(anyhow::Error::from)?;
+        let record = FutureRecord::to(&self.input_topic)
+            .key(rejection_id)
+            .payload(&payload);
+        if let Err((e, _)) = self.producer.send(record, Timeout::After(Duration::from_secs(5))).await {
+            metrics::record_resubmission("error");
+            return Err(anyhow::anyhow!("Kafka send error: {:?}", e).into());
+        }
+
+        let store = self.store.clone();
+        let id = rejection_id.to_string();
+        let resubmitted_at = tokio::task::spawn_blocking(move || store.mark_resubmitted(&id))
+            .await
+            .map_err(anyhow::Error::from)??;
+        metrics::record_resubmission("success");
+        info!("Resubmitted rejection {} to {}", rejection_id, self.input_topic);
+        Ok(resubmitted_at)
+    }
+}
+
+/// Fixed CDR, with the origin of the rejected one
+fn resubmitted_cdr(error: &ValidationError, fix: &Fix) -> Result<ProcessedCDR, ResubmitError> {
+    Ok(ProcessedCDR::new(
+        apply_fix(&error.original_cdr, fix)?,
+        &error.source_topic,
+        &error.country,
+        error.source_system.clone(),
+    ))
+}
+
+/// Payload to resubmit: the replacement or original payload, patched
+fn apply_fix(original: &str, fix: &Fix) -> Result<String, ResubmitError> {
+    let base = match &fix.payload {
+        Some(Value::String(raw)) => raw.clone(),
+        Some(payload) => payload.to_string(),
+        None => original.to_string(),
+    };
+    if fix.patch.is_empty() {
+        return Ok(base);
+    }
+
+    let Ok(Value::Object(mut fields)) = serde_json::from_str::<Value>(&base) else {
+        return Err(ResubmitError::InvalidFix(
+            "The payload is not a JSON object and cannot be patched, provide a replacement payload".to_string(),
+        ));
+    };
+    for (field, value) in &fix.patch {
+        if value.is_null() {
+            fields.remove(field);
+        } else {
+            fields.insert(field.clone(), value.clone());
+        }
+    }
+    Ok(Value::Object(fields).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fix(value: Value) -> Fix {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_apply_fix() {
+        let original = r#"{"imsi":"123","msisdn":"+33612345678","apn":"wap"}"#;
+        assert_eq!(apply_fix(original, &Fix::default()).unwrap(), original);
+
+        let patched: Value = serde_json::from_str(
+            &apply_fix(original, &fix(json!({"patch": {"imsi": "208150123456789", "apn": null}}))).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(patched, json!({"imsi": "208150123456789", "msisdn": "+33612345678"}));
+
+        let replaced = apply_fix("not json", &fix(json!({"payload": {"imsi": "208150123456789"}}))).unwrap();
+        assert_eq!(replaced, r#"{"imsi":"208150123456789"}"#);
+
+        assert!(matches!(
+            apply_fix("not json", &fix(json!({"patch": {"imsi": "1"}}))),
+            Err(ResubmitError::InvalidFix(_))
+        ));
+    }
+
+    #[test]
+    fn test_resubmitted_cdr_keeps_its_origin() {
+        let rejected = ProcessedCDR::new(
+            r#"{"imsi":"123"}"#.to_string(),
+            "cdr.raw.FR",
+            "FR",
+            Some("fr-mediation".to_string()),
+        );
+        let error = ValidationError::new("invalid_imsi", "wrong".to_string(), None, rejected.raw_payload.clone())
+            .with_source(&rejected);
+
+        // Round trip through the rejection store
+        let dir = tempfile::tempdir().unwrap();
+        let store = RejectionStore::open(dir.path(), 0).unwrap();
+        store.append(&error).unwrap();
+        let stored = store.get(&error.rejection_id).unwrap().unwrap().error;
+
+        let cdr = resubmitted_cdr(&stored, &fix(json!({"patch": {"imsi": "208150123456789"}}))).unwrap();
+        assert_eq!(cdr.raw_payload, r#"{"imsi":"208150123456789"}"#);
+        assert_eq!(cdr.source_topic, "cdr.raw.FR");
+        assert_eq!(cdr.country, "FR");
+        assert_eq!(cdr.source_system.as_deref(), Some("fr-mediation"));
+    }
+}
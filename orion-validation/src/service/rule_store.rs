@@ -0,0 +1,137 @@
+use crate::metrics;
+use crate::service::rules::RuleEngine;
+use orion_shutdown::Shutdown;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use tracing::{error, info, warn};
+
+/// Current validation rules, swapped in place when the rules file changes.
+///
+/// A CDR is always checked against one consistent version of the rules; a
+/// file that fails to load is reported and the previous rules are kept.
+pub struct RuleStore {
+    path: Option<PathBuf>,
+    engine: RwLock<Arc<RuleEngine>>,
+    modified: Mutex<Option<SystemTime>>,
+}
+
+impl RuleStore {
+    /// Load the rules file, falling back to the bundled rules when it does
+    /// not exist (yet). An invalid file is an error.
+    pub fn open(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let modified = modified(&path);
+        let engine = if path.exists() {
+            RuleEngine::from_file(&path)?
+        } else {
+            warn!("No rules file at {}, using the bundled rules", path.display());
+            RuleEngine::bundled()
+        };
+
+        Ok(Self {
+            path: Some(path),
+            engine: RwLock::new(Arc::new(engine)),
+            modified: Mutex::new(modified),
+        })
+    }
+
+    /// Bundled rules, without a file to reload from
+    pub fn bundled() -> Self {
+        Self {
+            path: None,
+            engine: RwLock::new(Arc::new(RuleEngine::bundled())),
+            modified: Mutex::new(None),
+        }
+    }
+
+    pub fn current(&self) -> Arc<RuleEngine> {
+        Arc::clone(&self.engine.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Reload the rules file now
+    pub fn reload(&self) -> anyhow::Result<Arc<RuleEngine>> {
+        let Some(path) = &self.path else {
+            anyhow::bail!("No rules file configured");
+        };
+
+        let modified = modified(path);
+        let result = RuleEngine::from_file(path);
+        *self.modified.lock().unwrap_or_else(|e| e.into_inner()) = modified;
+
+        match result {
+            Ok(engine) => {
+                let engine = Arc::new(engine);
+                *self.engine.write().unwrap_or_else(|e| e.into_inner()) = Arc::clone(&engine);
+                metrics::record_rules_reload("success");
+                info!(
+                    "✅ Validation rules reloaded from {} ({} rule sets, {} rules)",
+                    path.display(),
+                    engine.rule_sets(),
+                    engine.rules()
+                );
+                Ok(engine)
+            }
+            Err(e) => {
+                metrics::record_rules_reload("error");
+                error!("Failed to reload validation rules, keeping the current ones: {:?}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Reload the rules file whenever its modification time changes, until
+    /// shutdown
+    pub async fn watch(self: Arc<Self>, interval: Duration, mut shutdown: Shutdown) {
+        let Some(path) = self.path.clone() else { return };
+        let mut timer = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = shutdown.requested() => break,
+                _ = timer.tick() => {}
+            }
+
+            let current = modified(&path);
+            let last = *self.modified.lock().unwrap_or_else(|e| e.into_inner());
+            if current.is_some() && current != last {
+                // Errors are logged and counted, the current rules stay active
+                let _ = self.reload();
+            }
+        }
+    }
+}
+
+fn modified(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = "rule_sets:\n  - name: subscriber\n    rules:\n      - {field: imsi, check: required}\n";
+
+    #[test]
+    fn test_reload_keeps_rules_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rules.yaml");
+        std::fs::write(&path, RULES).unwrap();
+
+        let store = RuleStore::open(&path).unwrap();
+        assert_eq!(store.current().rules(), 1);
+
+        std::fs::write(&path, format!("{}      - {{field: msisdn, check: required}}\n", RULES)).unwrap();
+        assert_eq!(store.reload().unwrap().rules(), 2);
+
+        std::fs::write(&path, "rule_sets: [").unwrap();
+        assert!(store.reload().is_err());
+        assert_eq!(store.current().rules(), 2);
+    }
+
+    #[test]
+    fn test_missing_file_falls_back_to_bundled_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RuleStore::open(dir.path().join("rules.yaml")).unwrap();
+        assert_eq!(store.current().rules(), RuleEngine::bundled().rules());
+    }
+}
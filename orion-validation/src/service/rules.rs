@@ -0,0 +1,467 @@
+//! Declarative validation rules.
+//!
+//! Rules are loaded from a YAML or TOML file and grouped in rule sets. A rule
+//! set applies to the CDRs of its countries and event types (all of them
+//! when omitted). Every applicable rule is checked, so a rejected CDR lists
+//! all of its violations rather than the first one.
+
+use crate::service::model::{Severity, Violation};
+use anyhow::Context;
+use chrono::DateTime;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::path::Path;
+
+/// Rules shipped with the service, used when no rules file is deployed
+const BUNDLED_RULES: &str = include_str!("../../config/rules.yaml");
+
+#[derive(Debug, Deserialize)]
+struct RuleFile {
+    #[serde(default)]
+    rule_sets: Vec<RuleSetSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuleSetSpec {
+    name: String,
+    #[serde(default)]
+    countries: Vec<String>,
+    #[serde(default)]
+    event_types: Vec<String>,
+    #[serde(default)]
+    rules: Vec<RuleSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuleSpec {
+    field: String,
+    #[serde(flatten)]
+    check: Check,
+    error_type: Option<String>,
+    message: Option<String>,
+    #[serde(default)]
+    severity: Severity,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "check", rename_all = "snake_case")]
+enum Check {
+    Required,
+    Regex {
+        pattern: String,
+        #[serde(skip)]
+        regex: Option<Regex>,
+    },
+    Range {
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    Enum {
+        values: Vec<String>,
+    },
+    NotBefore {
+        other: String,
+    },
+    OnlyForEventTypes {
+        event_types: Vec<String>,
+    },
+}
+
+impl Check {
+    fn default_error_type(&self) -> &'static str {
+        match self {
+            Check::Required => "missing_field",
+            Check::Regex { .. } => "invalid_format",
+            Check::Range { .. } => "out_of_range",
+            Check::Enum { .. } => "invalid_value",
+            Check::NotBefore { .. } => "inconsistent_fields",
+            Check::OnlyForEventTypes { .. } => "unexpected_field",
+        }
+    }
+}
+
+struct Rule {
+    field: String,
+    check: Check,
+    error_type: String,
+    message: Option<String>,
+    severity: Severity,
+}
+
+struct RuleSet {
+    name: String,
+    countries: Vec<String>,
+    event_types: Vec<String>,
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    fn applies_to(&self, country: &str, event_type: &str) -> bool {
+        (self.countries.is_empty() || self.countries.iter().any(|c| c.eq_ignore_ascii_case(country)))
+            && (self.event_types.is_empty()
+                || self.event_types.iter().any(|e| e.eq_ignore_ascii_case(event_type)))
+    }
+}
+
+/// Compiled rule sets
+pub struct RuleEngine {
+    rule_sets: Vec<RuleSet>,
+}
+
+impl RuleEngine {
+    /// Load a rules file, parsed as TOML for a `.toml` extension and as YAML
+    /// otherwise
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read rules file {}", path.display()))?;
+        let parsed = if path.extension().is_some_and(|ext| ext == "toml") {
+            Self::from_toml(&content)
+        } else {
+            Self::from_yaml(&content)
+        };
+        parsed.with_context(|| format!("Invalid rules file {}", path.display()))
+    }
+
+    pub fn from_yaml(content: &str) -> anyhow::Result<Self> {
+        Self::compile(serde_yaml::from_str(content)?)
+    }
+
+    pub fn from_toml(content: &str) -> anyhow::Result<Self> {
+        Self::compile(toml::from_str(content)?)
+    }
+
+    pub fn bundled() -> Self {
+        Self::from_yaml(BUNDLED_RULES).expect("bundled rules are valid")
+    }
+
+    fn compile(file: RuleFile) -> anyhow::Result<Self> {
+        let mut rule_sets = Vec::new();
+        for spec in file.rule_sets {
+            let mut rules = Vec::new();
+            for rule in spec.rules {
+                let mut check = rule.check;
+                match &mut check {
+                    Check::Regex { pattern, regex } => {
+                        *regex = Some(Regex::new(pattern).with_context(|| {
+                            format!("Invalid pattern for {} in rule set {}", rule.field, spec.name)
+                        })?);
+                    }
+                    Check::Range { min: Some(min), max: Some(max) } if *min > *max => {
+                        anyhow::bail!(
+                            "Empty range for {} in rule set {}: min {} > max {}",
+                            rule.field, spec.name, min, max
+                        );
+                    }
+                    _ => {}
+                }
+                rules.push(Rule {
+                    error_type: rule
+                        .error_type
+                        .unwrap_or_else(|| check.default_error_type().to_string()),
+                    field: rule.field,
+                    check,
+                    message: rule.message,
+                    severity: rule.severity,
+                });
+            }
+            rule_sets.push(RuleSet {
+                name: spec.name,
+                countries: spec.countries,
+                event_types: spec.event_types,
+                rules,
+            });
+        }
+        Ok(Self { rule_sets })
+    }
+
+    pub fn rule_sets(&self) -> usize {
+        self.rule_sets.len()
+    }
+
+    pub fn rules(&self) -> usize {
+        self.rule_sets.iter().map(|set| set.rules.len()).sum()
+    }
+
+    /// Summary of the rule sets, as served by `GET /rules`
+    pub fn describe(&self) -> Value {
+        Value::Array(
+            self.rule_sets
+                .iter()
+                .map(|set| {
+                    serde_json::json!({
+                        "name": set.name,
+                        "countries": set.countries,
+                        "event_types": set.event_types,
+                        "rules": set.rules.len(),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// Check a CDR against every applicable rule
+    pub fn check(&self, cdr: &Value, country: &str) -> Vec<Violation> {
+        let event_type = text(cdr.get("event_type")).unwrap_or_default().to_lowercase();
+        let mut violations = Vec::new();
+
+        for set in self.rule_sets.iter().filter(|set| set.applies_to(country, &event_type)) {
+            for rule in &set.rules {
+                if let Some(problem) = rule.evaluate(cdr, &event_type) {
+                    violations.push(Violation {
+                        field: rule.field.clone(),
+                        error_type: rule.error_type.clone(),
+                        message: problem,
+                        rule_set: Some(set.name.clone()),
+                        severity: rule.severity,
+                    });
+                }
+            }
+        }
+        violations
+    }
+}
+
+impl Rule {
+    /// Message of the violation, if the rule is broken
+    fn evaluate(&self, cdr: &Value, event_type: &str) -> Option<String> {
+        let value = cdr.get(&self.field).filter(|value| is_present(value));
+        let describe = |default: String| match &self.message {
+            Some(message) => match value {
+                Some(value) => format!("{}, got: {}", message, display(value)),
+                None => message.clone(),
+            },
+            None => default,
+        };
+
+        if let Check::Required = self.check {
+            return value
+                .is_none()
+                .then(|| describe(format!("{} field is required", self.field)));
+        }
+        let value = value?;
+
+        match &self.check {
+            Check::Required => None,
+            Check::Regex { pattern, regex } => {
+                let matched = text(Some(value))
+                    .zip(regex.as_ref())
+                    .is_some_and(|(text, regex)| regex.is_match(&text));
+                (!matched).then(|| describe(format!("{} must match {}, got: {}", self.field, pattern, display(value))))
+            }
+            Check::Range { min, max } => {
+                let Some(number) = number(value) else {
+                    return Some(describe(format!("{} must be a number, got: {}", self.field, display(value))));
+                };
+                let below = min.is_some_and(|min| number < min);
+                let above = max.is_some_and(|max| number > max);
+                (below || above).then(|| {
+                    describe(format!(
+                        "{} must be within [{}, {}], got: {}",
+                        self.field,
+                        min.map_or("-inf".to_string(), |min| min.to_string()),
+                        max.map_or("+inf".to_string(), |max| max.to_string()),
+                        display(value)
+                    ))
+                })
+            }
+            Check::Enum { values } => {
+                let allowed = text(Some(value))
+                    .is_some_and(|text| values.iter().any(|v| v.eq_ignore_ascii_case(&text)));
+                (!allowed).then(|| {
+                    describe(format!("{} must be one of {:?}, got: {}", self.field, values, display(value)))
+                })
+            }
+            Check::NotBefore { other } => {
+                let other_value = cdr.get(other).filter(|value| is_present(value))?;
+                match compare(value, other_value) {
+                    Some(Ordering::Less) => Some(describe(format!(
+                        "{} must not be before {}, got: {} < {}",
+                        self.field, other, display(value), display(other_value)
+                    ))),
+                    Some(_) => None,
+                    None => Some(describe(format!(
+                        "{} and {} cannot be compared: {} / {}",
+                        self.field, other, display(value), display(other_value)
+                    ))),
+                }
+            }
+            Check::OnlyForEventTypes { event_types } => {
+                let expected = event_types.iter().any(|e| e.eq_ignore_ascii_case(event_type));
+                let unset = number(value).is_some_and(|number| number == 0.0);
+                (!expected && !unset).then(|| {
+                    describe(format!(
+                        "{} is only expected for {:?} CDRs, got: {} on a {} CDR",
+                        self.field, event_types, display(value), event_type
+                    ))
+                })
+            }
+        }
+    }
+}
+
+pub(super) fn is_present(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::String(text) => !text.trim().is_empty(),
+        _ => true,
+    }
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+pub(super) fn display(value: &Value) -> String {
+    text(Some(value)).unwrap_or_else(|| value.to_string())
+}
+
+pub(super) fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Compare two values as RFC 3339 timestamps, or else as numbers
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    let timestamp = |value: &Value| {
+        value
+            .as_str()
+            .and_then(|text| DateTime::parse_from_rfc3339(text).ok())
+    };
+    if let (Some(left), Some(right)) = (timestamp(left), timestamp(right)) {
+        return Some(left.cmp(&right));
+    }
+    number(left)?.partial_cmp(&number(right)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_bundled_rules_report_every_violation() {
+        let engine = RuleEngine::bundled();
+        let cdr = json!({
+            "imsi": "123",
+            "event_type": "voice",
+            "duration": -5,
+            "bytes_up": 1024,
+            "start_timestamp": "2026-01-29T10:15:00Z",
+            "end_timestamp": "2026-01-29T10:14:00Z"
+        });
+
+        let violations = engine.check(&cdr, "FR");
+        let types: Vec<(&str, &str)> = violations
+            .iter()
+            .map(|v| (v.field.as_str(), v.error_type.as_str()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("imsi", "invalid_imsi"),
+                ("msisdn", "missing_field"),
+                ("duration", "out_of_range"),
+                ("end_timestamp", "inconsistent_fields"),
+                ("bytes_up", "unexpected_field"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bundled_rules_cover_field_aliases() {
+        let engine = RuleEngine::bundled();
+        let cdr = json!({
+            "imsi": "208150123456789",
+            "msisdn": "+33612345678",
+            "event_type": "voice",
+            "duration_seconds": -5,
+            "bytes_uploaded": 1024,
+            "bytes_downloaded": -1
+        });
+
+        let violations = engine.check(&cdr, "FR");
+        let types: Vec<(&str, &str)> = violations
+            .iter()
+            .map(|v| (v.field.as_str(), v.error_type.as_str()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("duration_seconds", "out_of_range"),
+                ("bytes_downloaded", "out_of_range"),
+                ("bytes_uploaded", "unexpected_field"),
+                ("bytes_downloaded", "unexpected_field"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rule_sets_scoped_by_country_and_event_type() {
+        let engine = RuleEngine::from_yaml(
+            r#"
+rule_sets:
+  - name: tn_data
+    countries: [TN]
+    event_types: [data]
+    rules:
+      - field: apn
+        check: enum
+        values: [internet, mms]
+        severity: warning
+"#,
+        )
+        .unwrap();
+
+        let cdr = json!({"event_type": "data", "apn": "wap"});
+        let violations = engine.check(&cdr, "TN");
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].severity, Severity::Warning);
+        assert!(engine.check(&cdr, "FR").is_empty());
+        assert!(engine.check(&json!({"event_type": "sms", "apn": "wap"}), "TN").is_empty());
+    }
+
+    #[test]
+    fn test_toml_rules() {
+        let engine = RuleEngine::from_toml(
+            r#"
+[[rule_sets]]
+name = "usage"
+
+[[rule_sets.rules]]
+field = "duration"
+check = "range"
+min = 0
+max = 3600
+error_type = "invalid_duration"
+"#,
+        )
+        .unwrap();
+
+        let violations = engine.check(&json!({"duration": "7200"}), "CH");
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].error_type, "invalid_duration");
+        assert!(engine.check(&json!({"duration": 60}), "CH").is_empty());
+    }
+
+    #[test]
+    fn test_invalid_rules_are_refused() {
+        let bad_regex = "rule_sets:\n  - name: x\n    rules:\n      - {field: imsi, check: regex, pattern: '(['}\n";
+        assert!(RuleEngine::from_yaml(bad_regex).is_err());
+
+        let empty_range = "rule_sets:\n  - name: x\n    rules:\n      - {field: duration, check: range, min: 10, max: 1}\n";
+        assert!(RuleEngine::from_yaml(empty_range).is_err());
+
+        let unknown_check = "rule_sets:\n  - name: x\n    rules:\n      - {field: imsi, check: luhn}\n";
+        assert!(RuleEngine::from_yaml(unknown_check).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+//! Semantic checks per event type.
+//!
+//! Beyond the declarative rules, a CDR must carry what its event type is
+//! about: a voice call a called number and a duration, a data session its
+//! byte counters and APN, an SMS a destination and a message length. Called
+//! numbers must be possible in the numbering plan of the CDR's country.
+//! Field aliases are those accepted by orion-normalization. Errors reject
+//! the CDR, warnings are attached to it.
+
+use crate::service::model::{EventType, Severity, Violation};
+use crate::service::rules::{display, is_present, number};
+use serde_json::Value;
+
+const CALLED_NUMBER: &[&str] = &["called_number", "destination", "destination_number"];
+const DURATION: &[&str] = &["duration", "duration_seconds"];
+const BYTES_UP: &[&str] = &["bytes_up", "bytes_uploaded"];
+const BYTES_DOWN: &[&str] = &["bytes_down", "bytes_downloaded"];
+const APN: &[&str] = &["apn"];
+const DESTINATION: &[&str] = &["destination", "destination_number", "called_number"];
+const MESSAGE_LENGTH: &[&str] = &["message_length", "length"];
+
+/// Longest SMS accepted, in characters
+pub const MAX_SMS_LENGTH: f64 = 160.0;
+
+pub struct SemanticChecker {
+    max_call_duration_secs: f64,
+}
+
+impl SemanticChecker {
+    pub fn new(max_call_duration_secs: u64) -> Self {
+        Self {
+            max_call_duration_secs: max_call_duration_secs as f64,
+        }
+    }
+
+    /// Check a CDR of `country` against the requirements of its event type
+    pub fn check(&self, event_type: &EventType, cdr: &Value, country: &str) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        match event_type {
+            EventType::Voice => {
+                dialled(cdr, CALLED_NUMBER, country, "Voice CDR requires a called number", &mut violations);
+                if let Some(duration) = counter(cdr, DURATION, "Voice CDR requires a duration", &mut violations) {
+                    if duration > self.max_call_duration_secs {
+                        violations.push(error(
+                            field(cdr, DURATION),
+                            "out_of_range",
+                            format!(
+                                "Call duration cannot exceed {}s, got: {}",
+                                self.max_call_duration_secs, duration
+                            ),
+                        ));
+                    } else if duration == 0.0 {
+                        violations.push(warning(
+                            field(cdr, DURATION),
+                            "zero_duration",
+                            "Voice CDR with a zero duration".to_string(),
+                        ));
+                    }
+                }
+            }
+            EventType::Data => {
+                let up = counter(cdr, BYTES_UP, "Data CDR requires an uplink byte counter", &mut violations);
+                let down = counter(cdr, BYTES_DOWN, "Data CDR requires a downlink byte counter", &mut violations);
+                required(cdr, APN, "Data CDR requires an APN", &mut violations);
+                if up == Some(0.0) && down == Some(0.0) {
+                    violations.push(warning(
+                        field(cdr, BYTES_DOWN),
+                        "zero_volume",
+                        "Data CDR without any byte transferred".to_string(),
+                    ));
+                }
+            }
+            EventType::Sms => {
+                dialled(cdr, DESTINATION, country, "SMS CDR requires a destination", &mut violations);
+                match lookup(cdr, MESSAGE_LENGTH) {
+                    Some((name, value)) => match number(value) {
+                        Some(length) if (0.0..=MAX_SMS_LENGTH).contains(&length) => {}
+                        Some(_) => violations.push(error(
+                            name,
+                            "out_of_range",
+                            format!(
+                                "SMS message length must be between 0 and {}, got: {}",
+                                MAX_SMS_LENGTH,
+                                display(value)
+                            ),
+                        )),
+                        None => violations.push(not_a_number(name, value)),
+                    },
+                    // Deliberately a warning: the SMS records of 3GPP TS
+                    // 32.298 decoded by orion-ingestion carry no message
+                    // length, and an SMS is rated per message, not by length
+                    None => violations.push(warning(
+                        MESSAGE_LENGTH[0],
+                        "missing_field",
+                        "SMS CDR without a message length".to_string(),
+                    )),
+                }
+            }
+            EventType::Unknown => {}
+        }
+        violations
+    }
+}
+
+impl Default for SemanticChecker {
+    fn default() -> Self {
+        Self::new(86_400)
+    }
+}
+
+/// First alias present in the CDR, with its value
+fn lookup<'a>(cdr: &'a Value, aliases: &[&'static str]) -> Option<(&'static str, &'a Value)> {
+    aliases
+        .iter()
+        .find_map(|&alias| cdr.get(alias).filter(|value| is_present(value)).map(|value| (alias, value)))
+}
+
+/// Name of the field as present in the CDR, the first alias otherwise
+fn field(cdr: &Value, aliases: &[&'static str]) -> &'static str {
+    lookup(cdr, aliases).map_or(aliases[0], |(name, _)| name)
+}
+
+fn required(cdr: &Value, aliases: &[&'static str], message: &str, violations: &mut Vec<Violation>) {
+    if lookup(cdr, aliases).is_none() {
+        violations.push(error(aliases[0], "missing_field", message.to_string()));
+    }
+}
+
+/// Dialled number of a required field, possible in the plan of `country`
+fn dialled(cdr: &Value, aliases: &[&'static str], country: &str, message: &str, violations: &mut Vec<Violation>) {
+    let Some((name, value)) = lookup(cdr, aliases) else {
+        violations.push(error(aliases[0], "missing_field", message.to_string()));
+        return;
+    };
+    let Some(number) = value.as_str() else {
+        violations.push(error(name, "invalid_format", format!("{} must be a string, got: {}", name, display(value))));
+        return;
+    };
+    if let Err(e) = orion_numbering::parse(number, country) {
+        violations.push(error(name, "invalid_number", format!("Impossible {}: {}", name, e)));
+    }
+}
+
+/// Non-negative numeric value of a required field
+fn counter(cdr: &Value, aliases: &[&'static str], message: &str, violations: &mut Vec<Violation>) -> Option<f64> {
+    let Some((name, value)) = lookup(cdr, aliases) else {
+        violations.push(error(aliases[0], "missing_field", message.to_string()));
+        return None;
+    };
+    match number(value) {
+        Some(count) if count >= 0.0 => Some(count),
+        Some(_) => {
+            violations.push(error(name, "out_of_range", format!("{} cannot be negative, got: {}", name, display(value))));
+            None
+        }
+        None => {
+            violations.push(not_a_number(name, value));
+            None
+        }
+    }
+}
+
+fn not_a_number(field: &str, value: &Value) -> Violation {
+    error(field, "invalid_format", format!("{} must be a number, got: {}", field, display(value)))
+}
+
+fn error(field: &str, error_type: &str, message: String) -> Violation {
+    Violation::new(field, error_type, message, Severity::Error)
+}
+
+fn warning(field: &str, error_type: &str, message: String) -> Violation {
+    Violation::new(field, error_type, message, Severity::Warning)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn summary(violations: &[Violation]) -> Vec<(&str, &str, Severity)> {
+        violations
+            .iter()
+            .map(|v| (v.field.as_str(), v.error_type.as_str(), v.severity))
+            .collect()
+    }
+
+    #[test]
+    fn test_voice_requires_called_number_and_bounded_duration() {
+        let checker = SemanticChecker::new(14_400);
+        assert!(checker
+            .check(&EventType::Voice, &json!({"called_number": "+33698765432", "duration": 120}), "FR")
+            .is_empty());
+        assert_eq!(
+            summary(&checker.check(&EventType::Voice, &json!({"duration": -3}), "FR")),
+            vec![
+                ("called_number", "missing_field", Severity::Error),
+                ("duration", "out_of_range", Severity::Error),
+            ]
+        );
+        assert_eq!(
+            summary(&checker.check(&EventType::Voice, &json!({"destination": "+21698765432", "duration_seconds": 20000}), "FR")),
+            vec![("duration_seconds", "out_of_range", Severity::Error)]
+        );
+        assert_eq!(
+            summary(&checker.check(&EventType::Voice, &json!({"called_number": "+33698765432", "duration": "0"}), "FR")),
+            vec![("duration", "zero_duration", Severity::Warning)]
+        );
+    }
+
+    #[test]
+    fn test_data_requires_counters_and_apn() {
+        let checker = SemanticChecker::default();
+        assert!(checker
+            .check(&EventType::Data, &json!({"bytes_uploaded": 1024, "bytes_downloaded": 4096, "apn": "internet"}), "FR")
+            .is_empty());
+        assert_eq!(
+            summary(&checker.check(&EventType::Data, &json!({"bytes_up": "lots", "bytes_down": -1}), "FR")),
+            vec![
+                ("bytes_up", "invalid_format", Severity::Error),
+                ("bytes_down", "out_of_range", Severity::Error),
+                ("apn", "missing_field", Severity::Error),
+            ]
+        );
+        assert_eq!(
+            summary(&checker.check(&EventType::Data, &json!({"bytes_up": 0, "bytes_down": 0, "apn": "internet"}), "FR")),
+            vec![("bytes_down", "zero_volume", Severity::Warning)]
+        );
+    }
+
+    #[test]
+    fn test_sms_requires_destination_and_length() {
+        let checker = SemanticChecker::default();
+        assert!(checker
+            .check(&EventType::Sms, &json!({"destination_number": "+33698765432", "message_length": 160}), "FR")
+            .is_empty());
+        assert_eq!(
+            summary(&checker.check(&EventType::Sms, &json!({"length": 161}), "FR")),
+            vec![
+                ("destination", "missing_field", Severity::Error),
+                ("length", "out_of_range", Severity::Error),
+            ]
+        );
+        assert_eq!(
+            summary(&checker.check(&EventType::Sms, &json!({"called_number": "+33698765432"}), "FR")),
+            vec![("message_length", "missing_field", Severity::Warning)]
+        );
+        assert!(checker.check(&EventType::Unknown, &json!({}), "FR").is_empty());
+    }
+
+    #[test]
+    fn test_called_numbers_follow_the_numbering_plan() {
+        let checker = SemanticChecker::default();
+        let voice = |called: Value, country: &str| {
+            summary(&checker.check(&EventType::Voice, &json!({"called_number": called, "duration": 60}), country))
+                .into_iter()
+                .map(|(field, error_type, _)| (field.to_string(), error_type.to_string()))
+                .collect::<Vec<_>>()
+        };
+        assert!(voice(json!("06 98 76 54 32"), "FR").is_empty());
+        assert!(voice(json!("112"), "FR").is_empty());
+        assert!(voice(json!("98765432"), "TN").is_empty());
+        assert_eq!(voice(json!("0698765"), "FR"), vec![("called_number".to_string(), "invalid_number".to_string())]);
+        assert_eq!(voice(json!("+999123456"), "FR"), vec![("called_number".to_string(), "invalid_number".to_string())]);
+        assert_eq!(voice(json!(33698765432u64), "FR"), vec![("called_number".to_string(), "invalid_format".to_string())]);
+    }
+}
@@ -1,23 +1,27 @@
 use crate::metrics;
-use crate::service::model::{ProcessedCDR, ValidatedCDR, ValidationResult, ValidationError, EventType};
-use regex::Regex;
+use crate::service::model::{ProcessedCDR, ValidatedCDR, ValidationResult, ValidationError, EventType, Severity, Violation};
+use crate::service::cdr_id::cdr_id;
+use crate::service::event_time::EventTimePolicy;
+use crate::service::identity;
+use crate::service::rule_store::RuleStore;
+use crate::service::semantic::SemanticChecker;
 use chrono::Utc;
+use orion_numbering::NumberType;
+use std::sync::Arc;
 use std::time::Instant;
-use uuid::Uuid;
 
+/// Validates CDRs against the declarative rules of a `RuleStore`, then
+/// against the MCC/MNC registry, the numbering plan and the semantic checks
+/// of their event type, and dates them with their event time
 pub struct Validator {
-    imsi_regex: Regex,
-    msisdn_regex: Regex,
+    rules: Arc<RuleStore>,
+    semantic: SemanticChecker,
+    event_time: EventTimePolicy,
 }
 
 impl Validator {
-    pub fn new() -> Self {
-        Self {
-            // IMSI: 14-15 digits
-            imsi_regex: Regex::new(r"^\d{14,15}$").unwrap(),
-            // MSISDN: + followed by 10-15 digits
-            msisdn_regex: Regex::new(r"^\+?\d{10,15}$").unwrap(),
-        }
+    pub fn new(rules: Arc<RuleStore>, semantic: SemanticChecker, event_time: EventTimePolicy) -> Self {
+        Self { rules, semantic, event_time }
     }
 
     pub async fn validate(&self, cdr: &ProcessedCDR) -> ValidationResult {
@@ -29,92 +33,68 @@ impl Validator {
             Ok(data) => data,
             Err(e) => {
                 metrics::increment_invalid_total();
+                metrics::record_violation("json_parse_error", Severity::Error.as_str());
                 metrics::record_latency(start.elapsed().as_secs_f64());
                 return ValidationResult::Invalid(ValidationError::new(
                     "json_parse_error",
                     format!("Failed to parse JSON: {}", e),
                     None,
                     cdr.raw_payload.clone(),
-                ));
+                ).with_source(cdr));
             }
         };
 
-        // Extract and validate IMSI
-        let imsi = match json_data.get("imsi").and_then(|v| v.as_str()) {
-            Some(val) => {
-                if !self.imsi_regex.is_match(val) {
-                    metrics::increment_invalid_total();
-                    metrics::record_latency(start.elapsed().as_secs_f64());
-                    return ValidationResult::Invalid(ValidationError::new(
-                        "invalid_imsi",
-                        format!("IMSI must be 14-15 digits, got: {}", val),
-                        Some("imsi".to_string()),
-                        cdr.raw_payload.clone(),
-                    ));
-                }
-                val.to_string()
-            }
-            None => {
-                metrics::increment_invalid_total();
-                metrics::record_latency(start.elapsed().as_secs_f64());
-                return ValidationResult::Invalid(ValidationError::new(
-                    "missing_field",
-                    "IMSI field is required".to_string(),
-                    Some("imsi".to_string()),
-                    cdr.raw_payload.clone(),
-                ));
-            }
-        };
+        let mut violations = self.rules.current().check(&json_data, &cdr.country);
 
-        // Extract and validate MSISDN
-        let msisdn = match json_data.get("msisdn").and_then(|v| v.as_str()) {
-            Some(val) => {
-                if !self.msisdn_regex.is_match(val) {
-                    metrics::increment_invalid_total();
-                    metrics::record_latency(start.elapsed().as_secs_f64());
-                    return ValidationResult::Invalid(ValidationError::new(
-                        "invalid_msisdn",
-                        format!("MSISDN must be 10-15 digits with optional +, got: {}", val),
-                        Some("msisdn".to_string()),
-                        cdr.raw_payload.clone(),
-                    ));
-                }
-                val.to_string()
-            }
-            None => {
-                metrics::increment_invalid_total();
-                metrics::record_latency(start.elapsed().as_secs_f64());
-                return ValidationResult::Invalid(ValidationError::new(
-                    "missing_field",
-                    "MSISDN field is required".to_string(),
-                    Some("msisdn".to_string()),
-                    cdr.raw_payload.clone(),
-                ));
-            }
-        };
+        // IMSI and MSISDN are part of the output contract, whatever the rules
+        let imsi = required_text(&json_data, "imsi", &mut violations);
+        if let Some(imsi) = &imsi {
+            identity::check_imsi(imsi, &mut violations);
+        }
+        let msisdn = required_text(&json_data, "msisdn", &mut violations)
+            .and_then(|msisdn| subscriber_number(&msisdn, &cdr.country, &mut violations));
+        identity::check_imei(&json_data, &mut violations);
+        let event_time = self.event_time.resolve(&json_data, Utc::now(), &mut violations);
 
         // Extract event type
         let event_type = json_data
             .get("event_type")
             .and_then(|v| v.as_str())
-            .map(|v| match v.to_lowercase().as_str() {
-                "voice" => EventType::Voice,
-                "data" => EventType::Data,
-                "sms" => EventType::Sms,
-                _ => EventType::Unknown,
-            })
-            .unwrap_or(EventType::Unknown);
+            .map_or(EventType::Unknown, EventType::parse);
+
+        // A field already reported by a rule is not reported twice
+        for violation in self.semantic.check(&event_type, &json_data, &cdr.country) {
+            if !violations.iter().any(|v| v.field == violation.field) {
+                violations.push(violation);
+            }
+        }
+
+        for violation in &violations {
+            metrics::record_violation(&violation.error_type, violation.severity.as_str());
+        }
+
+        let rejected = violations.iter().any(|v| v.severity == Severity::Error);
+        let (Some(imsi), Some(msisdn), false) = (imsi, msisdn, rejected) else {
+            metrics::increment_invalid_total();
+            metrics::record_latency(start.elapsed().as_secs_f64());
+            return ValidationResult::Invalid(ValidationError::from_violations(
+                violations,
+                cdr.raw_payload.clone(),
+            ).with_source(cdr));
+        };
 
         // Create validated CDR
         let validated = ValidatedCDR {
-            cdr_id: Uuid::new_v4().to_string(),
+            cdr_id: cdr_id(&imsi, event_type.as_str(), event_time, &json_data),
             event_type,
             imsi,
             msisdn,
-            timestamp: Utc::now(),
+            timestamp: event_time.unwrap_or_else(Utc::now),
             country: cdr.country.clone(),
+            source_system: cdr.source_system.clone(),
             raw_data: json_data,
             validation_timestamp: Utc::now().to_rfc3339(),
+            warnings: violations,
         };
 
         metrics::increment_valid_total();
@@ -124,9 +104,43 @@ impl Validator {
     }
 }
 
+/// Text value of a contract field, reported as missing unless a rule
+/// already did
+fn required_text(json: &serde_json::Value, field: &str, violations: &mut Vec<Violation>) -> Option<String> {
+    let value = json.get(field).and_then(|v| v.as_str()).filter(|v| !v.is_empty());
+    if value.is_none() && !violations.iter().any(|v| v.field == field) {
+        violations.push(Violation::new(
+            field,
+            "missing_field",
+            format!("{} field is required", field.to_uppercase()),
+            Severity::Error,
+        ));
+    }
+    value.map(|v| v.to_string())
+}
+
+/// E.164 form of the MSISDN, which must be a subscriber number possible in
+/// the numbering plan of the CDR's country (national forms are read with it)
+fn subscriber_number(msisdn: &str, country: &str, violations: &mut Vec<Violation>) -> Option<String> {
+    if violations.iter().any(|v| v.field == "msisdn") {
+        return None;
+    }
+    let message = match orion_numbering::parse(msisdn, country) {
+        Ok(number) if number.number_type != NumberType::ShortCode => return Some(number.canonical),
+        Ok(_) => format!("MSISDN cannot be a short code: {}", msisdn),
+        Err(e) => format!("Impossible MSISDN: {}", e),
+    };
+    violations.push(Violation::new("msisdn", "invalid_msisdn", message, Severity::Error));
+    None
+}
+
 impl Default for Validator {
     fn default() -> Self {
-        Self::new()
+        Self::new(
+            Arc::new(RuleStore::bundled()),
+            SemanticChecker::default(),
+            EventTimePolicy::default(),
+        )
     }
 }
 
@@ -136,25 +150,36 @@ mod tests {
 
     #[tokio::test]
     async fn test_validate_valid_cdr() {
-        let validator = Validator::new();
+        let validator = Validator::default();
+        let event_time = Utc::now().timestamp() - 60;
         let cdr = ProcessedCDR {
-            raw_payload: r#"{"imsi":"208150123456789","msisdn":"+33612345678","event_type":"voice"}"#.to_string(),
+            raw_payload: format!(
+                r#"{{"imsi":"208150123456789","msisdn":"+33612345678","event_type":"voice","called_number":"+33698765432","duration":120,"timestamp":{}}}"#,
+                event_time
+            ),
             source_topic: "cdr.raw.FR".to_string(),
             country: "FR".to_string(),
+            source_system: None,
+            origin: None,
             ingestion_timestamp: Utc::now().to_rfc3339(),
         };
 
-        let result = validator.validate(&cdr).await;
-        assert!(matches!(result, ValidationResult::Valid(_)));
+        let ValidationResult::Valid(validated) = validator.validate(&cdr).await else {
+            panic!("Expected Valid result");
+        };
+        assert!(validated.warnings.is_empty());
+        assert_eq!(validated.timestamp.timestamp(), event_time);
     }
 
     #[tokio::test]
     async fn test_validate_missing_imsi() {
-        let validator = Validator::new();
+        let validator = Validator::default();
         let cdr = ProcessedCDR {
             raw_payload: r#"{"msisdn":"+33612345678","event_type":"voice"}"#.to_string(),
             source_topic: "cdr.raw.FR".to_string(),
             country: "FR".to_string(),
+            source_system: None,
+            origin: None,
             ingestion_timestamp: Utc::now().to_rfc3339(),
         };
 
@@ -169,11 +194,13 @@ mod tests {
 
     #[tokio::test]
     async fn test_validate_invalid_imsi_format() {
-        let validator = Validator::new();
+        let validator = Validator::default();
         let cdr = ProcessedCDR {
             raw_payload: r#"{"imsi":"123","msisdn":"+33612345678","event_type":"voice"}"#.to_string(),
             source_topic: "cdr.raw.FR".to_string(),
             country: "FR".to_string(),
+            source_system: None,
+            origin: None,
             ingestion_timestamp: Utc::now().to_rfc3339(),
         };
 
@@ -184,4 +211,90 @@ mod tests {
             panic!("Expected Invalid result");
         }
     }
+
+    #[tokio::test]
+    async fn test_validate_reports_all_violations() {
+        let validator = Validator::default();
+        let cdr = ProcessedCDR {
+            raw_payload: r#"{"imsi":"123","msisdn":"WRONG","event_type":"data","bytes_down":-1}"#.to_string(),
+            source_topic: "cdr.raw.TN".to_string(),
+            country: "TN".to_string(),
+            source_system: None,
+            origin: None,
+            ingestion_timestamp: Utc::now().to_rfc3339(),
+        };
+
+        let ValidationResult::Invalid(err) = validator.validate(&cdr).await else {
+            panic!("Expected Invalid result");
+        };
+        let types: Vec<&str> = err.violations.iter().map(|v| v.error_type.as_str()).collect();
+        assert_eq!(
+            types,
+            vec!["invalid_imsi", "out_of_range", "invalid_msisdn", "missing_field", "missing_field", "missing_field"]
+        );
+        assert_eq!(err.error_type, "invalid_imsi");
+    }
+
+    #[tokio::test]
+    async fn test_validate_msisdn_numbering_plan() {
+        let validator = Validator::default();
+        let cdr = |msisdn: &str| ProcessedCDR {
+            raw_payload: format!(
+                r#"{{"imsi":"605010123456789","msisdn":"{}","event_type":"voice","called_number":"71123456","duration":60,"timestamp":"{}"}}"#,
+                msisdn,
+                Utc::now().to_rfc3339()
+            ),
+            source_topic: "cdr.raw.TN".to_string(),
+            country: "TN".to_string(),
+            source_system: None,
+            origin: None,
+            ingestion_timestamp: Utc::now().to_rfc3339(),
+        };
+
+        let ValidationResult::Valid(validated) = validator.validate(&cdr("20 123 456")).await else {
+            panic!("Expected Valid result");
+        };
+        assert_eq!(validated.msisdn, "+21620123456");
+
+        for msisdn in ["197", "+216 60123456", "0021620123"] {
+            let ValidationResult::Invalid(err) = validator.validate(&cdr(msisdn)).await else {
+                panic!("Expected Invalid result for {}", msisdn);
+            };
+            assert_eq!(err.error_type, "invalid_msisdn", "{}", msisdn);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_semantic_errors_and_warnings() {
+        let validator = Validator::default();
+        let cdr = |payload: &str| ProcessedCDR {
+            raw_payload: payload.to_string(),
+            source_topic: "cdr.raw.FR".to_string(),
+            country: "FR".to_string(),
+            source_system: None,
+            origin: None,
+            ingestion_timestamp: Utc::now().to_rfc3339(),
+        };
+
+        let result = validator
+            .validate(&cdr(r#"{"imsi":"208150123456789","msisdn":"+33612345678","event_type":"voice"}"#))
+            .await;
+        let ValidationResult::Invalid(err) = result else {
+            panic!("Expected Invalid result");
+        };
+        let fields: Vec<&str> = err.violations.iter().map(|v| v.field.as_str()).collect();
+        assert_eq!(fields, vec!["timestamp", "called_number", "duration"]);
+
+        let result = validator
+            .validate(&cdr(&format!(
+                r#"{{"imsi":"208150123456789","msisdn":"+33612345678","event_type":"sms","destination_number":"+33698765432","timestamp":"{}"}}"#,
+                Utc::now().to_rfc3339()
+            )))
+            .await;
+        let ValidationResult::Valid(validated) = result else {
+            panic!("Expected Valid result");
+        };
+        assert_eq!(validated.warnings.len(), 1);
+        assert_eq!(validated.warnings[0].severity, Severity::Warning);
+    }
 }
@@ -0,0 +1,54 @@
+//! Exactly-once integration tests, run by the shared `orion_kafka::testing`
+//! scenarios against a single-node Kafka broker in a container. They need a
+//! Docker daemon:
+//!
+//! ```bash
+//! cargo test --test exactly_once -- --ignored
+//! ```
+
+use orion_kafka::testing::{self, Service};
+
+/// 20 valid CDRs followed by one CDR without IMSI, rejected
+fn service() -> Service {
+    let mut payloads: Vec<String> = (0..20)
+        .map(|i| format!(r#"{{"imsi":"2081501234{:05}","msisdn":"+33612345678"}}"#, i))
+        .collect();
+    payloads.push(r#"{"msisdn":"+33612345678"}"#.to_string());
+
+    let inputs = payloads
+        .into_iter()
+        .map(|raw_payload| {
+            serde_json::json!({
+                "raw_payload": raw_payload,
+                "source_topic": "cdr.raw.FR",
+                "country": "FR",
+                "ingestion_timestamp": "2026-01-29T10:00:00Z",
+            })
+            .to_string()
+        })
+        .collect();
+
+    Service {
+        binary: env!("CARGO_BIN_EXE_orion-validation"),
+        name: "orion-validation-it",
+        env: vec![
+            ("KAFKA_OUTPUT_TOPIC", "cdr.normalized"),
+            ("KAFKA_REJECTED_TOPIC", "cdr.rejected"),
+        ],
+        input_topic: "cdr.validated",
+        inputs,
+        outputs: vec![("cdr.normalized", 20), ("cdr.rejected", 1)],
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires a Docker daemon"]
+async fn test_restart_does_not_duplicate_outputs() {
+    testing::restart_does_not_duplicate_outputs(&service()).await;
+}
+
+#[tokio::test]
+#[ignore = "requires a Docker daemon"]
+async fn test_crash_mid_transaction_replays_without_duplicates() {
+    testing::crash_mid_transaction_replays_without_duplicates(&service()).await;
+}